@@ -0,0 +1,329 @@
+//! Derive macros for the [`z3`](https://docs.rs/z3/) crate.
+//!
+//! Use them through the `derive` feature of `z3`, which re-exports them next
+//! to the traits they implement.
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields};
+
+/// Implement `z3::Z3Enum` for a fieldless enum.
+///
+/// The enumeration sort is named after the enum, and its constants after the
+/// variants.
+#[proc_macro_derive(Z3Enum)]
+pub fn derive_z3_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_z3_enum(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn expand_z3_enum(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "Z3Enum can only be derived for enums",
+            ))
+        }
+    };
+    if data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            input,
+            "Z3Enum needs at least one variant",
+        ));
+    }
+    if let Some(v) = data
+        .variants
+        .iter()
+        .find(|v| !matches!(v.fields, Fields::Unit))
+    {
+        return Err(syn::Error::new_spanned(
+            v,
+            "Z3Enum can only be derived for enums without fields",
+        ));
+    }
+
+    let ident = &input.ident;
+    let name = ident.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let variants: Vec<_> = data.variants.iter().map(|v| &v.ident).collect();
+    let names: Vec<_> = variants.iter().map(|v| v.to_string()).collect();
+    let indices: Vec<_> = (0..variants.len()).collect();
+
+    Ok(quote! {
+        impl #impl_generics ::z3::Z3Enum for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #name;
+            const VARIANT_NAMES: &'static [&'static str] = &[#(#names),*];
+
+            fn to_index(&self) -> usize {
+                match self {
+                    #(#ident::#variants => #indices,)*
+                }
+            }
+
+            fn from_index(index: usize) -> ::std::option::Option<Self> {
+                match index {
+                    #(#indices => ::std::option::Option::Some(#ident::#variants),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+    })
+}
+
+/// Implement `z3::Z3Value` for a struct with named fields, each of which
+/// implements `z3::Z3Value` too.
+///
+/// The struct is modelled by a datatype with a single constructor, both
+/// named after the struct, and an accessor `Struct.field` for every field.
+/// Symbolic values are of a generated type `SymStruct`, which implements
+/// `z3::ast::Ast` and has a getter for every field.
+#[proc_macro_derive(Z3Struct)]
+pub fn derive_z3_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_z3_struct(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn expand_z3_struct(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "Z3Struct can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "Z3Struct can only be derived for structs",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "Z3Struct cannot be derived for generic structs",
+        ));
+    }
+
+    let vis = &input.vis;
+    let ident = &input.ident;
+    let sym = format_ident!("Sym{}", ident);
+    let name = ident.to_string();
+    let names: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let accessors: Vec<_> = names.iter().map(|n| format!("{}.{}", name, n)).collect();
+    let indices: Vec<_> = (0..names.len()).collect();
+    let sym_doc = format!(
+        "Symbolic values of [`{}`], generated by `#[derive(Z3Struct)]`.",
+        name
+    );
+
+    Ok(quote! {
+        #[doc = #sym_doc]
+        #[derive(Clone, Debug)]
+        #vis struct #sym {
+            ast: ::z3::ast::Datatype,
+            datatype: ::std::rc::Rc<::z3::DatatypeSort>,
+        }
+
+        impl #sym {
+            /// Declare the datatype of the struct.
+            pub fn datatype(ctx: ::std::rc::Rc<::z3::Context>) -> ::z3::DatatypeSort {
+                ::z3::DatatypeBuilder::new(ctx.clone(), #name)
+                    .variant(
+                        #name,
+                        ::std::vec![#((
+                            #accessors,
+                            ::z3::DatatypeAccessor::Sort(
+                                <#types as ::z3::Z3Value>::sort(ctx.clone()),
+                            ),
+                        )),*],
+                    )
+                    .finish()
+            }
+
+            fn from_datatype(ast: ::z3::ast::Datatype) -> Self {
+                let datatype = ::std::rc::Rc::new(Self::datatype(::z3::ast::Ast::get_ctx(&ast)));
+                #sym { ast, datatype }
+            }
+
+            /// Create a constant of the struct's datatype.
+            pub fn new_const<S: ::std::convert::Into<::z3::Symbol>>(
+                ctx: ::std::rc::Rc<::z3::Context>,
+                name: S,
+            ) -> Self {
+                let datatype = Self::datatype(ctx.clone());
+                let ast = ::z3::ast::Datatype::new_const(ctx, name, &datatype.sort);
+                #sym { ast, datatype: ::std::rc::Rc::new(datatype) }
+            }
+
+            /// Create a fresh constant of the struct's datatype.
+            pub fn fresh_const(ctx: ::std::rc::Rc<::z3::Context>, prefix: &str) -> Self {
+                let datatype = Self::datatype(ctx.clone());
+                let ast = ::z3::ast::Dynamic::fresh_const(ctx, prefix, &datatype.sort)
+                    .as_datatype()
+                    .unwrap();
+                #sym { ast, datatype: ::std::rc::Rc::new(datatype) }
+            }
+
+            /// Apply the struct's constructor to the given fields.
+            pub fn new(
+                ctx: ::std::rc::Rc<::z3::Context>,
+                #(#names: &<#types as ::z3::Z3Value>::Symbolic),*
+            ) -> Self {
+                let datatype = Self::datatype(ctx);
+                let ast = datatype.variants[0]
+                    .constructor
+                    .apply(&[#(#names as &dyn ::z3::ast::Ast),*])
+                    .as_datatype()
+                    .unwrap();
+                #sym { ast, datatype: ::std::rc::Rc::new(datatype) }
+            }
+
+            #(
+                pub fn #names(&self) -> <#types as ::z3::Z3Value>::Symbolic {
+                    <#types as ::z3::Z3Value>::symbolic(
+                        self.datatype.variants[0].accessors[#indices].apply(&[&self.ast]),
+                    )
+                }
+            )*
+
+            /// The underlying datatype value.
+            pub fn as_datatype(&self) -> &::z3::ast::Datatype {
+                &self.ast
+            }
+
+            /// The Rust value of `self` in `model`, or `None` if it cannot
+            /// be decoded.
+            pub fn from_model(&self, model: &::z3::Model) -> ::std::option::Option<#ident> {
+                <#ident as ::z3::Z3Value>::from_model(model, self)
+            }
+        }
+
+        impl ::std::fmt::Display for #sym {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                ::std::fmt::Display::fmt(&self.ast, f)
+            }
+        }
+
+        impl ::z3::ast::Ast for #sym {
+            unsafe fn wrap(ctx: ::std::rc::Rc<::z3::Context>, ast: ::z3::__derive::Z3_ast) -> Self {
+                Self::from_datatype(::z3::ast::Datatype::wrap(ctx, ast))
+            }
+
+            fn get_ctx(&self) -> ::std::rc::Rc<::z3::Context> {
+                self.ast.get_ctx()
+            }
+
+            fn get_z3_ast(&self) -> ::z3::__derive::Z3_ast {
+                self.ast.get_z3_ast()
+            }
+        }
+
+        impl ::z3::Z3Value for #ident {
+            type Symbolic = #sym;
+
+            fn sort(ctx: ::std::rc::Rc<::z3::Context>) -> ::z3::Sort {
+                #sym::datatype(ctx).sort
+            }
+
+            fn symbolic(ast: ::z3::ast::Dynamic) -> #sym {
+                #sym::from_datatype(ast.as_datatype().unwrap())
+            }
+
+            fn to_ast(&self, ctx: ::std::rc::Rc<::z3::Context>) -> ::z3::ast::Dynamic {
+                let datatype = #sym::datatype(ctx.clone());
+                datatype.variants[0].constructor.apply(&[
+                    #(&::z3::Z3Value::to_ast(&self.#names, ctx.clone()) as &dyn ::z3::ast::Ast),*
+                ])
+            }
+
+            fn decode(value: &::z3::ast::Dynamic) -> ::std::option::Option<Self> {
+                use ::z3::ast::Ast;
+                let value = value.as_datatype()?;
+                let datatype = #sym::datatype(value.get_ctx());
+                if !value.is_app() || value.decl() != datatype.variants[0].constructor {
+                    return ::std::option::Option::None;
+                }
+                let args = value.children();
+                ::std::option::Option::Some(#ident {
+                    #(#names: <#types as ::z3::Z3Value>::decode(&args[#indices])?),*
+                })
+            }
+        }
+    })
+}
+
+/// Implement `z3::FromModel` for a struct with named fields, each of which
+/// implements `z3::Z3Value`.
+///
+/// Each field is read from the constant of the same name, or of the name
+/// given with `#[z3(name = "...")]`.
+#[proc_macro_derive(FromModel, attributes(z3))]
+pub fn derive_from_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_from_model(&input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn expand_from_model(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "FromModel can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "FromModel can only be derived for structs",
+            ))
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let names: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let constants = fields
+        .iter()
+        .map(|f| {
+            let mut constant = f.ident.as_ref().unwrap().to_string();
+            for attr in f.attrs.iter().filter(|a| a.path().is_ident("z3")) {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("name") {
+                        constant = meta.value()?.parse::<syn::LitStr>()?.value();
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `name = \"...\"`"))
+                    }
+                })?;
+            }
+            Ok(constant)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        impl #impl_generics ::z3::FromModel for #ident #ty_generics #where_clause {
+            fn from_model(model: &::z3::Model) -> ::std::option::Option<Self> {
+                ::std::option::Option::Some(#ident {
+                    #(#names: model.get_const_value::<#types, _>(#constants)?),*
+                })
+            }
+        }
+    })
+}
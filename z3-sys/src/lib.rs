@@ -222,6 +222,16 @@ pub struct _Z3_solver {
 /// tactic or logic.
 pub type Z3_solver = *mut _Z3_solver;
 
+#[doc(hidden)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _Z3_solver_callback {
+    _unused: [u8; 0],
+}
+/// Handle passed to user propagator callbacks, used to propagate
+/// consequences back to the solver.
+pub type Z3_solver_callback = *mut _Z3_solver_callback;
+
 #[doc(hidden)]
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
@@ -1552,6 +1562,54 @@ pub enum ErrorCode {
 pub type Z3_error_handler =
     ::std::option::Option<unsafe extern "C" fn(c: Z3_context, e: ErrorCode)>;
 
+/// User propagator callback invoked when the solver creates a
+/// backtracking point (See [`Z3_solver_propagate_init`]).
+pub type Z3_push_eh = ::std::option::Option<unsafe extern "C" fn(ctx: *mut ::std::os::raw::c_void)>;
+
+/// User propagator callback invoked when the solver backtracks
+/// `num_scopes` backtracking points (See [`Z3_solver_propagate_init`]).
+pub type Z3_pop_eh = ::std::option::Option<
+    unsafe extern "C" fn(ctx: *mut ::std::os::raw::c_void, num_scopes: ::std::os::raw::c_uint),
+>;
+
+/// User propagator callback invoked when the solver needs a copy of the
+/// user context for a new Z3 context (See [`Z3_solver_propagate_init`]).
+pub type Z3_fresh_eh = ::std::option::Option<
+    unsafe extern "C" fn(
+        ctx: *mut ::std::os::raw::c_void,
+        new_context: Z3_context,
+    ) -> *mut ::std::os::raw::c_void,
+>;
+
+/// User propagator callback invoked when a registered expression is
+/// assigned a fixed value (See [`Z3_solver_propagate_fixed`]).
+pub type Z3_fixed_eh = ::std::option::Option<
+    unsafe extern "C" fn(
+        ctx: *mut ::std::os::raw::c_void,
+        cb: Z3_solver_callback,
+        id: ::std::os::raw::c_uint,
+        value: Z3_ast,
+    ),
+>;
+
+/// User propagator callback invoked when two registered expressions
+/// are found equal or distinct (See [`Z3_solver_propagate_eq`] and
+/// [`Z3_solver_propagate_diseq`]).
+pub type Z3_eq_eh = ::std::option::Option<
+    unsafe extern "C" fn(
+        ctx: *mut ::std::os::raw::c_void,
+        cb: Z3_solver_callback,
+        x: ::std::os::raw::c_uint,
+        y: ::std::os::raw::c_uint,
+    ),
+>;
+
+/// User propagator callback invoked when the solver is about to
+/// report a model (See [`Z3_solver_propagate_final`]).
+pub type Z3_final_eh = ::std::option::Option<
+    unsafe extern "C" fn(ctx: *mut ::std::os::raw::c_void, cb: Z3_solver_callback),
+>;
+
 /// Precision of a given goal. Some goals can be transformed using over/under approximations.
 ///
 /// This corresponds to `Z3_goal_prec` in the C API.
@@ -3018,6 +3076,9 @@ extern "C" {
     /// Check if `s` is a sequence sort.
     pub fn Z3_is_seq_sort(c: Z3_context, s: Z3_sort) -> bool;
 
+    /// Retrieve basis sort for sequence sort.
+    pub fn Z3_get_seq_sort_basis(c: Z3_context, s: Z3_sort) -> Z3_sort;
+
     /// Create a regular expression sort out of a sequence sort.
     pub fn Z3_mk_re_sort(c: Z3_context, seq: Z3_sort) -> Z3_sort;
 
@@ -3097,6 +3158,10 @@ extern "C" {
     /// Retrieve from `s` the unit sequence positioned at position `index`.
     pub fn Z3_mk_seq_at(c: Z3_context, s: Z3_ast, index: Z3_ast) -> Z3_ast;
 
+    /// Retrieve from `s` the element positioned at position `index`.
+    /// The function is under-specified if the index is out of bounds.
+    pub fn Z3_mk_seq_nth(c: Z3_context, s: Z3_ast, index: Z3_ast) -> Z3_ast;
+
     /// Return the length of the sequence `s`.
     pub fn Z3_mk_seq_length(c: Z3_context, s: Z3_ast) -> Z3_ast;
 
@@ -5141,8 +5206,26 @@ extern "C" {
     /// converted to Booleans either, so the if caller intends to
     /// preserve satisfiability, it should apply bit-blasting tactics.
     /// Quantifiers and theory atoms will not be encoded.
+    ///
+    /// # See also:
+    ///
+    /// - [`Z3_goal_to_dimacs_string_with_names`]
     pub fn Z3_goal_to_dimacs_string(c: Z3_context, g: Z3_goal) -> Z3_string;
 
+    /// Convert a goal into a DIMACS formatted string, like
+    /// [`Z3_goal_to_dimacs_string`], with comment lines that give the
+    /// names of the variables if `include_names` is true.
+    ///
+    /// This is `Z3_goal_to_dimacs_string` with the signature of the Z3
+    /// releases that added the `include_names` parameter.
+    #[allow(clashing_extern_declarations)]
+    #[link_name = "Z3_goal_to_dimacs_string"]
+    pub fn Z3_goal_to_dimacs_string_with_names(
+        c: Z3_context,
+        g: Z3_goal,
+        include_names: bool,
+    ) -> Z3_string;
+
     /// Return a tactic associated with the given name.
     ///
     /// The complete list of tactics may be obtained using the procedures [`Z3_get_num_tactics`] and [`Z3_get_tactic_name`].
@@ -5595,6 +5678,88 @@ extern "C" {
     /// Return the set of non units in the solver state.
     pub fn Z3_solver_get_non_units(c: Z3_context, s: Z3_solver) -> Z3_ast_vector;
 
+    /// Register a user propagator with the solver.
+    ///
+    /// `user_context` is passed to every callback. `push_eh` and `pop_eh`
+    /// are invoked when the solver creates and removes backtracking
+    /// points, and `fresh_eh` when it needs a user context for a new
+    /// Z3 context.
+    ///
+    /// The user propagator declarations follow Z3 4.8.10 to 4.8.12, whose
+    /// callbacks refer to registered expressions by id. Z3 4.8.13 and later
+    /// changed the signatures of the callbacks and of
+    /// [`Z3_solver_propagate_register`] and
+    /// [`Z3_solver_propagate_consequence`], so calling these against a
+    /// later release is undefined behavior; check [`Z3_get_version`] first.
+    ///
+    /// Older releases do not have these functions, so they are only declared
+    /// with the `user-propagator` feature.
+    ///
+    /// # See also:
+    ///
+    /// - [`Z3_solver_propagate_fixed`]
+    /// - [`Z3_solver_propagate_final`]
+    /// - [`Z3_solver_propagate_eq`]
+    /// - [`Z3_solver_propagate_diseq`]
+    /// - [`Z3_solver_propagate_register`]
+    #[cfg(feature = "user-propagator")]
+    pub fn Z3_solver_propagate_init(
+        c: Z3_context,
+        s: Z3_solver,
+        user_context: *mut ::std::os::raw::c_void,
+        push_eh: Z3_push_eh,
+        pop_eh: Z3_pop_eh,
+        fresh_eh: Z3_fresh_eh,
+    );
+
+    /// Register a callback for when an expression is bound to a fixed
+    /// value. The supported expression types are Booleans and
+    /// bit-vectors.
+    #[cfg(feature = "user-propagator")]
+    pub fn Z3_solver_propagate_fixed(c: Z3_context, s: Z3_solver, fixed_eh: Z3_fixed_eh);
+
+    /// Register a callback on final check. This provides freedom to the
+    /// propagator to delay actions or implement a branch-and-bound
+    /// solver. The final check is invoked when all decision variables
+    /// have been assigned by the solver.
+    #[cfg(feature = "user-propagator")]
+    pub fn Z3_solver_propagate_final(c: Z3_context, s: Z3_solver, final_eh: Z3_final_eh);
+
+    /// Register a callback on expression equalities.
+    #[cfg(feature = "user-propagator")]
+    pub fn Z3_solver_propagate_eq(c: Z3_context, s: Z3_solver, eq_eh: Z3_eq_eh);
+
+    /// Register a callback on expression disequalities.
+    #[cfg(feature = "user-propagator")]
+    pub fn Z3_solver_propagate_diseq(c: Z3_context, s: Z3_solver, eq_eh: Z3_eq_eh);
+
+    /// Register an expression to propagate on with the solver. Returns
+    /// the id the callbacks use to refer to `e`.
+    #[cfg(feature = "user-propagator")]
+    pub fn Z3_solver_propagate_register(
+        c: Z3_context,
+        s: Z3_solver,
+        e: Z3_ast,
+    ) -> ::std::os::raw::c_uint;
+
+    /// Propagate a consequence based on fixed values and equalities.
+    ///
+    /// This is a callback to be invoked from the user propagator
+    /// callbacks: `conseq` is implied by the values of the registered
+    /// expressions `fixed_ids` and the equalities between `eq_lhs[i]` and
+    /// `eq_rhs[i]`.
+    #[cfg(feature = "user-propagator")]
+    pub fn Z3_solver_propagate_consequence(
+        c: Z3_context,
+        cb: Z3_solver_callback,
+        num_fixed: ::std::os::raw::c_uint,
+        fixed_ids: *const ::std::os::raw::c_uint,
+        num_eqs: ::std::os::raw::c_uint,
+        eq_lhs: *const ::std::os::raw::c_uint,
+        eq_rhs: *const ::std::os::raw::c_uint,
+        conseq: Z3_ast,
+    );
+
     /// Check whether the assertions in a given solver are consistent or not.
     ///
     /// The function [`Z3_solver_get_model`]
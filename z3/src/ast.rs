@@ -3,18 +3,25 @@
 use log::debug;
 use std::borrow::Borrow;
 use std::cmp::{Eq, PartialEq};
-use std::convert::{TryFrom, TryInto};
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::ffi::{CStr, CString};
 use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::rc::Rc;
 
+pub use crate::ast_node::{fold, visit, AstNode, QuantifierKind};
+pub use crate::quantifier::QuantifierBuilder;
 pub use z3_sys::AstKind;
 use z3_sys::*;
 
-use crate::{Context, FuncDecl, IsNotApp, Pattern, Sort, SortDiffers, Symbol};
+use crate::conversion::{c_len, cstring, try_cstring, ConversionError};
+use crate::Rc;
+use crate::{Context, FuncDecl, IsNotApp, Model, ParamDescrs, Pattern, Sort, SortDiffers, Symbol};
 
-use num::{bigint::BigInt, rational::BigRational};
+use num::{
+    bigint::{BigInt, BigUint},
+    rational::BigRational,
+};
 
 /// [`Ast`] node representing a boolean value.
 pub struct Bool {
@@ -46,6 +53,13 @@ pub struct String {
     pub(crate) z3_ast: Z3_ast,
 }
 
+/// [`Ast`] node representing a sequence value, whose elements may be of any
+/// sort.
+pub struct Seq {
+    pub(crate) ctx: Rc<Context>,
+    pub(crate) z3_ast: Z3_ast,
+}
+
 /// [`Ast`] node representing a bitvector value.
 pub struct BV {
     pub(crate) ctx: Rc<Context>,
@@ -300,6 +314,40 @@ pub trait Ast: fmt::Debug {
         }
     }
 
+    /// Simplify the `Ast` like [`Ast::simplify()`], configured with
+    /// `params`, e.g. `som` to write polynomials as sums of monomials.
+    ///
+    /// See [`simplify_param_descrs()`] for the parameters the simplifier
+    /// accepts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Params};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let y = ast::Int::new_const(ctx.clone(), "y");
+    /// let product = (x.clone() + y.clone()) * x.clone();
+    ///
+    /// let mut params = Params::new(ctx.clone());
+    /// params.set_bool("som", true);
+    /// let expanded = product.simplify_params(&params);
+    /// assert_eq!(product.simplify().to_string(), "(* x (+ x y))");
+    /// assert_eq!(expanded.to_string(), "(+ (* x x) (* x y))");
+    /// ```
+    fn simplify_params(&self, params: &crate::Params) -> Self
+    where
+        Self: Sized,
+    {
+        unsafe {
+            Self::wrap(self.get_ctx(), {
+                Z3_simplify_ex(self.get_ctx().z3_ctx, self.get_z3_ast(), params.z3_params)
+            })
+        }
+    }
+
     /// Performs substitution on the `Ast`. The slice `substitutions` contains a
     /// list of pairs with a "from" `Ast` that will be substituted by a "to" `Ast`.
     fn substitute<T: Ast>(&self, substitutions: &[(&T, &T)]) -> Self
@@ -329,10 +377,84 @@ pub trait Ast: fmt::Debug {
         }
     }
 
+    /// Substitute the free variables of the `Ast`: the variable with de
+    /// Bruijn index `i`, printed as `(:var i)`, is replaced by `to[i]`.
+    ///
+    /// Free variables occur, e.g., in the body of a quantifier and in the
+    /// covers of a [`Fixedpoint`](crate::Fixedpoint).
+    fn substitute_vars(&self, to: &[&dyn Ast]) -> Self
+    where
+        Self: Sized,
+    {
+        let to: Vec<Z3_ast> = to.iter().map(|t| t.get_z3_ast()).collect();
+        unsafe {
+            Self::wrap(
+                self.get_ctx(),
+                Z3_substitute_vars(
+                    self.get_ctx().z3_ctx,
+                    self.get_z3_ast(),
+                    c_len(to.len()),
+                    to.as_ptr(),
+                ),
+            )
+        }
+    }
+
+    /// Replace every application `f(a_0, ..., a_n)` of a function `f` in
+    /// `substitutions` by its replacement, in which `(:var i)` stands for
+    /// the argument `a_i`. A constant is a function without arguments, so
+    /// its replacement has no variables.
+    ///
+    /// # Panics
+    ///
+    /// If a replacement does not have the sort of the function's range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, FuncDecl, Sort};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let int = Sort::int(ctx.clone());
+    /// let f = FuncDecl::new(ctx.clone(), "f", &[&int, &int], &int);
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let one = ast::Int::from_i64(ctx.clone(), 1);
+    /// let term = f.apply(&[&x, &f.apply(&[&one, &x])]);
+    ///
+    /// // f(a, b) := a - b
+    /// let a = ast::Dynamic::new_bound(ctx.clone(), 0, &int).as_int().unwrap();
+    /// let b = ast::Dynamic::new_bound(ctx.clone(), 1, &int).as_int().unwrap();
+    /// let difference = a - b;
+    /// let replaced = term.substitute_funs(&[(&f, &difference)]);
+    /// assert_eq!(replaced.to_string(), "(- x (- 1 x))");
+    /// ```
+    fn substitute_funs(&self, substitutions: &[(&FuncDecl, &dyn Ast)]) -> Self
+    where
+        Self: Sized,
+    {
+        for (f, to) in substitutions {
+            assert_eq!(
+                f.range(),
+                to.get_sort(),
+                "replacement of {} has the wrong sort",
+                f.name()
+            );
+        }
+        let ctx = self.get_ctx();
+        let substituted = substitute_funs_raw(&ctx, self.get_z3_ast(), substitutions);
+        unsafe { Self::wrap(ctx, substituted.get_z3_ast()) }
+    }
+
     /// Return the number of children of this `Ast`.
     ///
-    /// Leaf nodes (eg `Bool` consts) will return 0.
+    /// Leaf nodes (eg `Bool` consts) will return 0, and so will quantifiers
+    /// and bound variables, which are not applications; use [`Ast::node()`]
+    /// to get at the body of a quantifier.
     fn num_children(&self) -> usize {
+        if !self.is_app() {
+            return 0;
+        }
         let this_ctx = self.get_ctx().z3_ctx;
         unsafe {
             let this_app = Z3_to_app(this_ctx, self.get_z3_ast());
@@ -371,6 +493,40 @@ pub trait Ast: fmt::Debug {
         }
     }
 
+    /// Decompose the top level of this `Ast`, see [`fold()`] and [`visit()`]
+    /// for walking whole terms.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::{Ast, AstNode, QuantifierKind}, Config, Context, Symbol};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let body = x.ge(&ast::Int::from_i64(ctx.clone(), 0));
+    /// let forall = ast::forall_const(ctx.clone(), &[&x], &[], &body);
+    ///
+    /// match forall.node() {
+    ///     AstNode::Quantifier { kind, bound, body, .. } => {
+    ///         assert_eq!(kind, QuantifierKind::Forall);
+    ///         assert_eq!(bound[0].0, Symbol::String("x".to_owned()));
+    ///         match body.node() {
+    ///             AstNode::App { decl, args } => {
+    ///                 assert_eq!(decl.name(), ">=");
+    ///                 assert!(matches!(args[0].node(), AstNode::Var { index: 0, .. }));
+    ///                 assert!(matches!(&args[1].node(), AstNode::Numeral { value, .. } if value == "0"));
+    ///             }
+    ///             _ => unreachable!(),
+    ///         }
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    fn node(&self) -> AstNode {
+        AstNode::new(self.get_ctx(), self.get_z3_ast())
+    }
+
     /// Return `true` if this is a Z3 function application.
     ///
     /// Note that constants are function applications with 0 arguments.
@@ -386,6 +542,14 @@ pub trait Ast: fmt::Debug {
         self.is_app() && self.num_children() == 0
     }
 
+    /// Return `true` if this is a numeral of an arithmetic, bitvector or
+    /// floating-point sort.
+    ///
+    /// Numerals are constants, so [`Ast::is_const()`] holds for them too.
+    fn is_numeral(&self) -> bool {
+        self.kind() == AstKind::Numeral
+    }
+
     /// Return the `FuncDecl` of the `Ast`.
     ///
     /// This will panic if the `Ast` is not an app, i.e. if [`AstKind`] is not
@@ -432,6 +596,7 @@ macro_rules! impl_ast {
                     Z3_inc_ref(ctx.z3_ctx, ast);
                     ast
                 };
+                crate::track_live(ctx.z3_ctx, concat!("ast::", stringify!($ast)), 1);
                 Self { ctx, z3_ast }
             }
 
@@ -477,6 +642,7 @@ macro_rules! impl_ast {
                     unsafe { Z3_get_ast_id(self.ctx.z3_ctx, self.z3_ast) },
                     self.z3_ast
                 );
+                crate::track_live(self.ctx.z3_ctx, concat!("ast::", stringify!($ast)), -1);
                 unsafe {
                     Z3_dec_ref(self.ctx.z3_ctx, self.z3_ast);
                 }
@@ -547,6 +713,8 @@ impl_ast!(Float);
 impl_from_try_into_dynamic!(Float, as_float);
 impl_ast!(String);
 impl_from_try_into_dynamic!(String, as_string);
+impl_ast!(Seq);
+impl_from_try_into_dynamic!(Seq, as_seq);
 impl_ast!(BV);
 impl_from_try_into_dynamic!(BV, as_bv);
 impl_ast!(Array);
@@ -563,7 +731,7 @@ impl Int {
     pub fn from_str(ctx: Rc<Context>, value: &str) -> Option<Int> {
         let sort = Sort::int(ctx.clone());
         let ast = unsafe {
-            let int_cstring = CString::new(value).unwrap();
+            let int_cstring = try_cstring(value).ok()?;
             let numeral_ptr = Z3_mk_numeral(ctx.z3_ctx, int_cstring.as_ptr(), sort.z3_sort);
             if numeral_ptr.is_null() {
                 return None;
@@ -585,7 +753,7 @@ impl Real {
     pub fn from_real_str(ctx: Rc<Context>, num: &str, den: &str) -> Option<Real> {
         let sort = Sort::real(ctx.clone());
         let ast = unsafe {
-            let fraction_cstring = CString::new(format!("{num:} / {den:}")).unwrap();
+            let fraction_cstring = try_cstring(format!("{num:} / {den:}")).ok()?;
             let numeral_ptr = Z3_mk_numeral(ctx.z3_ctx, fraction_cstring.as_ptr(), sort.z3_sort);
             if numeral_ptr.is_null() {
                 return None;
@@ -639,7 +807,7 @@ impl Bool {
         let sort = Sort::bool(ctx.clone());
         unsafe {
             let z3_ast = {
-                let pp = CString::new(prefix).unwrap();
+                let pp = cstring(prefix);
                 let p = pp.as_ptr();
                 Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
             };
@@ -764,7 +932,7 @@ impl Int {
         let sort = Sort::int(ctx.clone());
         unsafe {
             let z3_ast = {
-                let pp = CString::new(prefix).unwrap();
+                let pp = cstring(prefix);
                 let p = pp.as_ptr();
                 Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
             };
@@ -810,6 +978,26 @@ impl Int {
         }
     }
 
+    /// The value of this numeral as an arbitrary-precision integer, also
+    /// when it does not fit in an `i64`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use num::bigint::BigInt;
+    /// # use z3::{ast, Config, Context};
+    /// # let cfg = Config::new();
+    /// # let ctx = Rc::new(Context::new(&cfg));
+    /// let big: BigInt = "-123456789012345678901234567890".parse().unwrap();
+    /// let n = ast::Int::from_big_int(ctx.clone(), &big);
+    /// assert_eq!(n.as_i64(), None);
+    /// assert_eq!(n.as_big_int(), Some(big));
+    /// ```
+    pub fn as_big_int(&self) -> Option<BigInt> {
+        numeral_string(&self.ctx, self.z3_ast)?.parse().ok()
+    }
+
     pub fn from_real(ast: &Real) -> Int {
         unsafe { Self::wrap(ast.ctx.clone(), Z3_mk_real2int(ast.ctx.z3_ctx, ast.z3_ast)) }
     }
@@ -903,7 +1091,7 @@ impl Real {
         let sort = Sort::real(ctx.clone());
         unsafe {
             let z3_ast = {
-                let pp = CString::new(prefix).unwrap();
+                let pp = cstring(prefix);
                 let p = pp.as_ptr();
                 Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
             };
@@ -936,6 +1124,12 @@ impl Real {
         }
     }
 
+    /// The value of this numeral as an arbitrary-precision rational, also
+    /// when its numerator or denominator does not fit in an `i64`.
+    pub fn as_big_rational(&self) -> Option<BigRational> {
+        numeral_string(&self.ctx, self.z3_ast)?.parse().ok()
+    }
+
     pub fn from_int(ast: &Int) -> Real {
         unsafe { Self::wrap(ast.ctx.clone(), Z3_mk_int2real(ast.ctx.z3_ctx, ast.z3_ast)) }
     }
@@ -1000,7 +1194,7 @@ impl Float {
         let sort = Sort::float(ctx.clone(), ebits, sbits);
         unsafe {
             let z3_ast = {
-                let pp = CString::new(prefix).unwrap();
+                let pp = cstring(prefix);
                 let p = pp.as_ptr();
                 Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
             };
@@ -1012,7 +1206,7 @@ impl Float {
         let sort = Sort::float32(ctx.clone());
         unsafe {
             let z3_ast = {
-                let pp = CString::new(prefix).unwrap();
+                let pp = cstring(prefix);
                 let p = pp.as_ptr();
                 Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
             };
@@ -1024,7 +1218,7 @@ impl Float {
         let sort = Sort::double(ctx.clone());
         unsafe {
             let z3_ast = {
-                let pp = CString::new(prefix).unwrap();
+                let pp = cstring(prefix);
                 let p = pp.as_ptr();
                 Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
             };
@@ -1109,7 +1303,7 @@ impl String {
         let sort = Sort::string(ctx.clone());
         unsafe {
             let z3_ast = {
-                let pp = CString::new(prefix).unwrap();
+                let pp = cstring(prefix);
                 let p = pp.as_ptr();
                 Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
             };
@@ -1170,6 +1364,180 @@ impl String {
         /// Checks whether `Self` is a suffix of the argument
         suffix(Z3_mk_seq_suffix, Bool);
     }
+
+    unop! {
+        /// The length of `Self`
+        length(Z3_mk_seq_length, Int);
+    }
+
+    /// The string of the character at position `index`, which is empty if
+    /// `index` is out of bounds
+    pub fn at(&self, index: &Int) -> String {
+        assert!(self.ctx == index.ctx);
+        unsafe {
+            Self::wrap(self.get_ctx(), {
+                Z3_mk_seq_at(self.ctx.z3_ctx, self.z3_ast, index.z3_ast)
+            })
+        }
+    }
+}
+
+impl Seq {
+    /// Creates a new constant of the sort of sequences of `eltype`
+    pub fn new_const<S: Into<Symbol>>(ctx: Rc<Context>, name: S, eltype: &Sort) -> Seq {
+        let sort = Sort::seq(ctx.clone(), eltype);
+        unsafe {
+            let z3_ast = Z3_mk_const(ctx.z3_ctx, name.into().as_z3_symbol(&ctx), sort.z3_sort);
+            Self::wrap(ctx, z3_ast)
+        }
+    }
+
+    /// Creates a fresh constant of the sort of sequences of `eltype`
+    pub fn fresh_const(ctx: Rc<Context>, prefix: &str, eltype: &Sort) -> Seq {
+        let sort = Sort::seq(ctx.clone(), eltype);
+        unsafe {
+            let z3_ast = {
+                let pp = cstring(prefix);
+                let p = pp.as_ptr();
+                Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
+            };
+            Self::wrap(ctx, z3_ast)
+        }
+    }
+
+    /// Creates the empty sequence of `eltype`
+    pub fn empty(ctx: Rc<Context>, eltype: &Sort) -> Seq {
+        let sort = Sort::seq(ctx.clone(), eltype);
+        unsafe {
+            let z3_ast = Z3_mk_seq_empty(ctx.z3_ctx, sort.z3_sort);
+            Self::wrap(ctx, z3_ast)
+        }
+    }
+
+    /// Creates the sequence whose only element is `elem`
+    pub fn unit<A: Ast>(elem: &A) -> Seq {
+        unsafe {
+            let z3_ast = Z3_mk_seq_unit(elem.get_ctx().z3_ctx, elem.get_z3_ast());
+            Self::wrap(elem.get_ctx(), z3_ast)
+        }
+    }
+
+    /// Creates the sequence of the given elements, which must all have the
+    /// same sort
+    pub fn from_elements(ctx: Rc<Context>, eltype: &Sort, elems: &[&dyn Ast]) -> Seq {
+        let units: Vec<Seq> = elems
+            .iter()
+            .map(|e| unsafe { Self::wrap(ctx.clone(), Z3_mk_seq_unit(ctx.z3_ctx, e.get_z3_ast())) })
+            .collect();
+        match units.len() {
+            0 => Self::empty(ctx, eltype),
+            1 => units[0].clone(),
+            _ => Self::concat(ctx, &units),
+        }
+    }
+
+    /// The sort of the elements of this sequence
+    pub fn element_sort(&self) -> Sort {
+        self.get_sort().seq_basis().unwrap()
+    }
+
+    varop! {
+        /// Concatenates the argument sequences, which must be of the same sort
+        concat(Z3_mk_seq_concat, Seq);
+    }
+
+    unop! {
+        /// The length of `Self`
+        length(Z3_mk_seq_length, Int);
+    }
+
+    binop! {
+        /// Checks whether `Self` contains a subsequence
+        contains(Z3_mk_seq_contains, Bool);
+        /// Checks whether `Self` is a prefix of the argument
+        prefix(Z3_mk_seq_prefix, Bool);
+        /// Checks whether `Self` is a suffix of the argument
+        suffix(Z3_mk_seq_suffix, Bool);
+    }
+
+    /// The element at position `index`, which is unspecified if `index`
+    /// is out of bounds
+    pub fn nth(&self, index: &Int) -> Dynamic {
+        assert!(self.ctx == index.ctx);
+        unsafe {
+            Dynamic::wrap(self.get_ctx(), {
+                Z3_mk_seq_nth(self.ctx.z3_ctx, self.z3_ast, index.z3_ast)
+            })
+        }
+    }
+
+    /// The unit sequence of the element at position `index`, which is empty
+    /// if `index` is out of bounds
+    pub fn at(&self, index: &Int) -> Seq {
+        assert!(self.ctx == index.ctx);
+        unsafe {
+            Self::wrap(self.get_ctx(), {
+                Z3_mk_seq_at(self.ctx.z3_ctx, self.z3_ast, index.z3_ast)
+            })
+        }
+    }
+
+    /// The subsequence of at most `length` elements starting at `offset`
+    pub fn extract(&self, offset: &Int, length: &Int) -> Seq {
+        assert!((self.ctx == offset.ctx) && (offset.ctx == length.ctx));
+        unsafe {
+            Self::wrap(self.get_ctx(), {
+                Z3_mk_seq_extract(self.ctx.z3_ctx, self.z3_ast, offset.z3_ast, length.z3_ast)
+            })
+        }
+    }
+
+    /// Retrieves the elements of a sequence value, such as one obtained from
+    /// [`Model::eval()`](crate::Model::eval).
+    ///
+    /// If this is not built from the empty sequence, unit sequences and
+    /// concatenation, return `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver, Sort};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let int = Sort::int(ctx.clone());
+    /// let trace = ast::Seq::new_const(ctx.clone(), "trace", &int);
+    /// let zero = ast::Int::from_i64(ctx.clone(), 0);
+    /// let seven = ast::Int::from_i64(ctx.clone(), 7);
+    ///
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&trace.length()._eq(&ast::Int::from_i64(ctx.clone(), 2)));
+    /// solver.assert(&trace.nth(&zero)._eq(&ast::Dynamic::from_ast(&seven)));
+    /// solver.assert(&ast::Seq::unit(&seven).suffix(&trace));
+    /// assert_eq!(solver.check(), SatResult::Sat);
+    ///
+    /// let model = solver.get_model().unwrap();
+    /// let elements = model.eval(&trace, true).unwrap().elements().unwrap();
+    /// assert_eq!(elements.len(), 2);
+    /// assert_eq!(elements[0].as_int().unwrap().as_i64(), Some(7));
+    /// ```
+    pub fn elements(&self) -> Option<Vec<Dynamic>> {
+        let mut elements = vec![];
+        // Concatenations nest, so walk them with an explicit stack.
+        let mut stack = vec![Dynamic::from_ast(self)];
+        while let Some(s) = stack.pop() {
+            if !s.is_app() {
+                return None;
+            }
+            match s.decl().kind() {
+                DeclKind::SEQ_EMPTY => {}
+                DeclKind::SEQ_UNIT => elements.push(s.nth_child(0)?),
+                DeclKind::SEQ_CONCAT => stack.extend(s.children().into_iter().rev()),
+                _ => return None,
+            }
+        }
+        Some(elements)
+    }
 }
 
 macro_rules! bv_overflow_check_signed {
@@ -1195,7 +1563,7 @@ impl BV {
     pub fn from_str(ctx: Rc<Context>, sz: u32, value: &str) -> Option<BV> {
         let sort = Sort::bitvector(ctx.clone(), sz);
         let ast = unsafe {
-            let bv_cstring = CString::new(value).unwrap();
+            let bv_cstring = try_cstring(value).ok()?;
             let numeral_ptr = Z3_mk_numeral(ctx.z3_ctx, bv_cstring.as_ptr(), sort.z3_sort);
             if numeral_ptr.is_null() {
                 return None;
@@ -1206,6 +1574,14 @@ impl BV {
         Some(unsafe { Self::wrap(ctx, ast) })
     }
 
+    /// Create a bit vector of `sz` bits from an arbitrary-precision
+    /// integer, modulo `2^sz`.
+    pub fn from_big_int(ctx: Rc<Context>, value: &BigInt, sz: u32) -> BV {
+        let modulus = BigInt::from(1) << sz;
+        let value = ((value % &modulus) + &modulus) % &modulus;
+        BV::from_str(ctx, sz, &value.to_str_radix(10)).unwrap()
+    }
+
     pub fn new_const<S: Into<Symbol>>(ctx: Rc<Context>, name: S, sz: u32) -> BV {
         let sort = Sort::bitvector(ctx.clone(), sz);
         unsafe {
@@ -1218,7 +1594,7 @@ impl BV {
         let sort = Sort::bitvector(ctx.clone(), sz);
         unsafe {
             let z3_ast = {
-                let pp = CString::new(prefix).unwrap();
+                let pp = cstring(prefix);
                 let p = pp.as_ptr();
                 Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
             };
@@ -1264,6 +1640,38 @@ impl BV {
         }
     }
 
+    /// The value of this numeral as an unsigned arbitrary-precision
+    /// integer, also for bit vectors wider than 64 bits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use num::bigint::{BigInt, BigUint};
+    /// # use z3::{ast, Config, Context};
+    /// # let cfg = Config::new();
+    /// # let ctx = Rc::new(Context::new(&cfg));
+    /// let max = ast::BV::from_big_int(ctx.clone(), &BigInt::from(-1), 128);
+    /// assert_eq!(max.as_u64(), None);
+    /// assert_eq!(max.as_big_uint(), Some((BigUint::from(1u8) << 128) - 1u8));
+    /// assert_eq!(max.as_big_int(), Some(BigInt::from(-1)));
+    /// ```
+    pub fn as_big_uint(&self) -> Option<BigUint> {
+        numeral_string(&self.ctx, self.z3_ast)?.parse().ok()
+    }
+
+    /// The value of this numeral as a signed arbitrary-precision integer,
+    /// reading it in two's complement.
+    pub fn as_big_int(&self) -> Option<BigInt> {
+        let value = BigInt::from(self.as_big_uint()?);
+        let size = self.get_size();
+        if size > 0 && value.bit(u64::from(size - 1)) {
+            Some(value - (BigInt::from(1) << size))
+        } else {
+            Some(value)
+        }
+    }
+
     /// Create a bit vector from an integer.
     ///
     /// The bit vector will have width `sz`.
@@ -1465,7 +1873,7 @@ impl Array {
         let sort = Sort::array(ctx.clone(), domain, range);
         unsafe {
             let z3_ast = {
-                let pp = CString::new(prefix).unwrap();
+                let pp = cstring(prefix);
                 let p = pp.as_ptr();
                 Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
             };
@@ -1485,6 +1893,45 @@ impl Array {
         }
     }
 
+    /// Create the array `lambda bounds. body`, which maps each index to
+    /// `body` with the constants `bounds` replaced by the index.
+    ///
+    /// With several bounds, the array has several indices, one per bound.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver};
+    /// # let cfg = Config::new();
+    /// # let ctx = Rc::new(Context::new(&cfg));
+    /// let i = ast::Int::new_const(ctx.clone(), "i");
+    /// let doubled = ast::Array::lambda_const(ctx.clone(), &[&i], &(&i * 2i64));
+    ///
+    /// let solver = Solver::new(ctx.clone());
+    /// let seven = ast::Int::from_i64(ctx.clone(), 7);
+    /// solver.assert(&doubled.select(&seven)._eq(&ast::Int::from_i64(ctx.clone(), 14).into()).not());
+    /// assert_eq!(solver.check(), SatResult::Unsat);
+    /// ```
+    pub fn lambda_const(ctx: Rc<Context>, bounds: &[&dyn Ast], body: &dyn Ast) -> Array {
+        assert!(!bounds.is_empty(), "a lambda needs at least one bound");
+        assert!(bounds.iter().all(|a| a.get_ctx() == ctx));
+        assert_eq!(ctx, body.get_ctx());
+
+        let bounds: Vec<_> = bounds
+            .iter()
+            .map(|a| unsafe { Z3_to_app(ctx.z3_ctx, a.get_z3_ast()) })
+            .collect();
+        unsafe {
+            let z3_ast = Z3_mk_lambda_const(
+                ctx.z3_ctx,
+                c_len(bounds.len()),
+                bounds.as_ptr(),
+                body.get_z3_ast(),
+            );
+            Self::wrap(ctx, z3_ast)
+        }
+    }
+
     /// Get the value at a given index in the array.
     ///
     /// Note that the `index` _must be_ of the array's `domain` sort.
@@ -1553,6 +2000,99 @@ impl Array {
         // >> is_app(a) and a.decl().kind() == Z3_OP_CONST_ARRAY
         self.is_app() && matches!(self.decl().kind(), DeclKind::CONST_ARRAY)
     }
+
+    /// An index at which this array and `other` differ, if they do
+    /// (`array-ext` in Z3); `self == other` exactly when they agree at
+    /// that index.
+    ///
+    /// This is the extensionality index Z3 introduces itself to refute
+    /// the equality of two arrays.
+    pub fn ext(&self, other: &Array) -> Dynamic {
+        assert_eq!(self.ctx, other.ctx);
+        unsafe {
+            Dynamic::wrap(
+                self.get_ctx(),
+                Z3_mk_array_ext(self.ctx.z3_ctx, self.z3_ast, other.z3_ast),
+            )
+        }
+    }
+
+    /// A concrete index at which the arrays `a` and `b` have different
+    /// values in `model`, or `None` if they are equal in it.
+    ///
+    /// The index is the value of [`a.ext(b)`](Array::ext) in the model if
+    /// it is a witness, which it is when the solver refuted the equality of
+    /// `a` and `b`. Otherwise, it is one of the indices the values of `a`
+    /// and `b` in the model are given explicitly at.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::{Array, Ast}, Config, Context, SatResult, Solver, Sort};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let int = Sort::int(ctx.clone());
+    /// let a = Array::new_const(ctx.clone(), "a", &int, &int);
+    /// let i = ast::Int::new_const(ctx.clone(), "i");
+    /// let seven = ast::Int::from_i64(ctx.clone(), 7);
+    /// let b = a.store(&i, &seven);
+    ///
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&a._eq(&b).not());
+    /// assert_eq!(solver.check(), SatResult::Sat);
+    /// let model = solver.get_model().unwrap();
+    ///
+    /// // `a` and `b` can only differ at `i`.
+    /// let index = Array::counterexample_index(&a, &b, &model).unwrap();
+    /// assert_eq!(index.as_int(), model.eval(&i, true));
+    /// ```
+    pub fn counterexample_index(a: &Array, b: &Array, model: &Model) -> Option<Dynamic> {
+        let mut candidates = vec![];
+        candidates.extend(model.eval(&a.ext(b), true));
+        for array in &[a, b] {
+            if let Some(value) = model.eval(*array, true) {
+                candidates.extend(value.explicit_indices(model));
+            }
+        }
+        candidates.into_iter().find(|i| {
+            let differ = a.select(i)._eq(&b.select(i)).not();
+            model.eval(&differ, true).and_then(|d| d.as_bool()) == Some(true)
+        })
+    }
+
+    /// The indices this array value, of a model, is given explicitly at:
+    /// those of its `store`s, or of the entries of the function it is
+    /// `as-array` of.
+    fn explicit_indices(&self, model: &Model) -> Vec<Dynamic> {
+        let ctx = self.get_ctx();
+        if unsafe { Z3_is_as_array(ctx.z3_ctx, self.z3_ast) } {
+            let f = unsafe {
+                FuncDecl::wrap(
+                    ctx.clone(),
+                    Z3_get_as_array_func_decl(ctx.z3_ctx, self.z3_ast),
+                )
+            };
+            return model
+                .get_func_interp(&f)
+                .map(|interp| {
+                    interp
+                        .get_entries()
+                        .iter()
+                        .filter_map(|e| e.get_args().into_iter().next())
+                        .collect()
+                })
+                .unwrap_or_default();
+        }
+        let mut indices = vec![];
+        let mut array = self.clone();
+        while array.is_app() && array.decl().kind() == DeclKind::STORE {
+            let args = array.children();
+            indices.push(args[1].clone());
+            array = args[0].as_array().unwrap();
+        }
+        indices
+    }
 }
 
 impl Set {
@@ -1568,7 +2108,7 @@ impl Set {
         let sort = Sort::set(ctx.clone(), eltype);
         unsafe {
             let z3_ast = {
-                let pp = CString::new(prefix).unwrap();
+                let pp = cstring(prefix);
                 let p = pp.as_ptr();
                 Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
             };
@@ -1662,10 +2202,24 @@ impl Dynamic {
         }
     }
 
+    /// Like [`Dynamic::new_const()`], but return an error instead of
+    /// panicking if `name` contains a NUL byte.
+    pub fn try_new_const<S: Into<Symbol>>(
+        ctx: Rc<Context>,
+        name: S,
+        sort: &Sort,
+    ) -> Result<Self, ConversionError> {
+        let name = name.into().try_as_z3_symbol(&ctx)?;
+        unsafe {
+            let z3_ast = Z3_mk_const(ctx.z3_ctx, name, sort.z3_sort);
+            Ok(Self::wrap(ctx, z3_ast))
+        }
+    }
+
     pub fn fresh_const(ctx: Rc<Context>, prefix: &str, sort: &Sort) -> Self {
         unsafe {
             let z3_ast = {
-                let pp = CString::new(prefix).unwrap();
+                let pp = cstring(prefix);
                 let p = pp.as_ptr();
                 Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
             };
@@ -1673,6 +2227,29 @@ impl Dynamic {
         }
     }
 
+    /// Like [`Dynamic::fresh_const()`], but return an error instead of
+    /// panicking if `prefix` contains a NUL byte.
+    pub fn try_fresh_const(
+        ctx: Rc<Context>,
+        prefix: &str,
+        sort: &Sort,
+    ) -> Result<Self, ConversionError> {
+        let pp = try_cstring(prefix)?;
+        unsafe {
+            let z3_ast = Z3_mk_fresh_const(ctx.z3_ctx, pp.as_ptr(), sort.z3_sort);
+            Ok(Self::wrap(ctx, z3_ast))
+        }
+    }
+
+    /// Create the free variable `(:var index)` of the given sort, as used by
+    /// [`Ast::substitute_vars()`] and [`Ast::substitute_funs()`].
+    pub fn new_bound(ctx: Rc<Context>, index: u32, sort: &Sort) -> Self {
+        unsafe {
+            let z3_ast = Z3_mk_bound(ctx.z3_ctx, index, sort.z3_sort);
+            Self::wrap(ctx, z3_ast)
+        }
+    }
+
     pub fn sort_kind(&self) -> SortKind {
         unsafe { Z3_get_sort_kind(self.ctx.z3_ctx, Z3_get_sort(self.ctx.z3_ctx, self.z3_ast)) }
     }
@@ -1720,6 +2297,16 @@ impl Dynamic {
         }
     }
 
+    /// Returns `None` if the `Dynamic` is not actually a `Seq`
+    ///
+    /// Strings are sequences of characters, so this accepts them as well.
+    pub fn as_seq(&self) -> Option<Seq> {
+        match self.sort_kind() {
+            SortKind::Seq => Some(unsafe { Seq::wrap(self.get_ctx(), self.z3_ast) }),
+            _ => None,
+        }
+    }
+
     /// Returns `None` if the `Dynamic` is not actually a `BV`
     pub fn as_bv(&self) -> Option<BV> {
         match self.sort_kind() {
@@ -1783,7 +2370,7 @@ impl Datatype {
 
         unsafe {
             let z3_ast = {
-                let pp = CString::new(prefix).unwrap();
+                let pp = cstring(prefix);
                 let p = pp.as_ptr();
                 Z3_mk_fresh_const(ctx.z3_ctx, p, sort.z3_sort)
             };
@@ -1795,12 +2382,19 @@ impl Datatype {
 impl Regexp {
     /// Creates a regular expression that recognizes the string given as parameter
     pub fn literal(ctx: Rc<Context>, s: &str) -> Self {
+        match Self::try_literal(ctx, s) {
+            Ok(re) => re,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Like [`Regexp::literal()`], but return an error instead of panicking
+    /// if `s` contains a NUL byte.
+    pub fn try_literal(ctx: Rc<Context>, s: &str) -> Result<Self, ConversionError> {
+        let c_str = try_cstring(s)?;
         unsafe {
-            let z3_ast = {
-                let c_str = CString::new(s).unwrap();
-                Z3_mk_seq_to_re(ctx.z3_ctx, Z3_mk_string(ctx.z3_ctx, c_str.as_ptr()))
-            };
-            Self::wrap(ctx, z3_ast)
+            let z3_ast = Z3_mk_seq_to_re(ctx.z3_ctx, Z3_mk_string(ctx.z3_ctx, c_str.as_ptr()));
+            Ok(Self::wrap(ctx, z3_ast))
         }
     }
 
@@ -1809,8 +2403,8 @@ impl Regexp {
     pub fn range(ctx: Rc<Context>, lo: &char, hi: &char) -> Self {
         unsafe {
             let z3_ast = {
-                let lo_cs = CString::new(lo.to_string()).unwrap();
-                let hi_cs = CString::new(hi.to_string()).unwrap();
+                let lo_cs = cstring(lo.to_string());
+                let hi_cs = cstring(hi.to_string());
                 let lo_z3s = Z3_mk_string(ctx.z3_ctx, lo_cs.as_ptr());
                 Z3_inc_ref(ctx.z3_ctx, lo_z3s);
                 let hi_z3s = Z3_mk_string(ctx.z3_ctx, hi_cs.as_ptr());
@@ -1932,9 +2526,9 @@ pub fn forall_const(
         let z3_ast = Z3_mk_forall_const(
             ctx.z3_ctx,
             0,
-            bounds.len().try_into().unwrap(),
+            c_len(bounds.len()),
             bounds.as_ptr() as *const Z3_app,
-            patterns.len().try_into().unwrap(),
+            c_len(patterns.len()),
             patterns.as_ptr() as *const Z3_pattern,
             body.get_z3_ast(),
         );
@@ -1992,9 +2586,9 @@ pub fn exists_const(
         let z3_ast = Z3_mk_exists_const(
             ctx.z3_ctx,
             0,
-            bounds.len().try_into().unwrap(),
+            c_len(bounds.len()),
             bounds.as_ptr() as *const Z3_app,
-            patterns.len().try_into().unwrap(),
+            c_len(patterns.len()),
             patterns.as_ptr() as *const Z3_pattern,
             body.get_z3_ast(),
         );
@@ -2002,6 +2596,114 @@ pub fn exists_const(
     }
 }
 
+/// The decimal digits of the numeral `ast`, or of the fraction `n/d` for a
+/// rational, or `None` if `ast` is not a numeral.
+fn numeral_string(ctx: &Context, ast: Z3_ast) -> Option<std::string::String> {
+    unsafe {
+        if !Z3_is_numeral_ast(ctx.z3_ctx, ast) {
+            return None;
+        }
+        let s = Z3_get_numeral_string(ctx.z3_ctx, ast);
+        if s.is_null() {
+            return None;
+        }
+        CStr::from_ptr(s).to_str().ok().map(|s| s.to_owned())
+    }
+}
+
+/// Rewrite `root` bottom-up for [`Ast::substitute_funs()`].
+fn substitute_funs_raw(
+    ctx: &Rc<Context>,
+    root: Z3_ast,
+    substitutions: &[(&FuncDecl, &dyn Ast)],
+) -> Dynamic {
+    let z3_ctx = ctx.z3_ctx;
+    let mut done: HashMap<Z3_ast, Dynamic> = HashMap::new();
+    // Post-order walk with an explicit stack, so deep terms cannot overflow
+    // the call stack.
+    let mut stack = vec![(root, false)];
+    while let Some((a, expanded)) = stack.pop() {
+        if done.contains_key(&a) {
+            continue;
+        }
+        let children: Vec<Z3_ast> = unsafe {
+            match Z3_get_ast_kind(z3_ctx, a) {
+                AstKind::App => (0..Z3_get_app_num_args(z3_ctx, a as Z3_app))
+                    .map(|i| Z3_get_app_arg(z3_ctx, a as Z3_app, i))
+                    .collect(),
+                AstKind::Quantifier => vec![Z3_get_quantifier_body(z3_ctx, a)],
+                _ => vec![],
+            }
+        };
+        if !expanded {
+            stack.push((a, true));
+            stack.extend(children.iter().map(|&c| (c, false)));
+            continue;
+        }
+        let args: Vec<Z3_ast> = children.iter().map(|c| done[c].get_z3_ast()).collect();
+        let updated = unsafe {
+            let updated = if children.is_empty() {
+                a
+            } else {
+                Z3_update_term(z3_ctx, a, c_len(args.len()), args.as_ptr())
+            };
+            Dynamic::wrap(ctx.clone(), updated)
+        };
+        let replaced = if updated.is_app() {
+            let decl = updated.decl();
+            match substitutions.iter().find(|(f, _)| **f == decl) {
+                Some((_, to)) => unsafe {
+                    Dynamic::wrap(
+                        ctx.clone(),
+                        Z3_substitute_vars(
+                            z3_ctx,
+                            to.get_z3_ast(),
+                            c_len(args.len()),
+                            args.as_ptr(),
+                        ),
+                    )
+                },
+                None => updated,
+            }
+        } else {
+            updated
+        };
+        done.insert(a, replaced);
+    }
+    done[&root].clone()
+}
+
+/// The help text of the simplifier, which lists the parameters
+/// [`Ast::simplify_params()`] accepts.
+pub fn simplify_help(ctx: Rc<Context>) -> std::string::String {
+    let p = unsafe { Z3_simplify_get_help(ctx.z3_ctx) };
+    if p.is_null() {
+        return std::string::String::new();
+    }
+    unsafe { CStr::from_ptr(p) }.to_string_lossy().into_owned()
+}
+
+/// The descriptions of the parameters [`Ast::simplify_params()`] accepts.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, Config, Context, ParamKind};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let descrs = ast::simplify_param_descrs(ctx.clone());
+/// assert_eq!(descrs.kind("pull_cheap_ite"), Some(ParamKind::Bool));
+/// assert!(descrs.names().iter().any(|n| n == "elim_sign_ext"));
+/// assert!(ast::simplify_help(ctx).contains("som"));
+/// ```
+pub fn simplify_param_descrs(ctx: Rc<Context>) -> ParamDescrs {
+    unsafe {
+        let descrs = Z3_simplify_get_param_descrs(ctx.z3_ctx);
+        ParamDescrs::wrap(ctx, descrs)
+    }
+}
+
 impl IsNotApp {
     pub fn new(kind: AstKind) -> Self {
         Self { kind }
@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::CStr;
+
+use z3_sys::*;
+
+use crate::ast::{Ast, Dynamic};
+use crate::Rc;
+use crate::{Context, FuncDecl, Sort, Symbol};
+
+/// The binder of an [`AstNode::Quantifier`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuantifierKind {
+    Forall,
+    Exists,
+    Lambda,
+}
+
+/// The top level of an [`Ast`], decomposed by [`Ast::node()`].
+#[derive(Clone, Debug)]
+pub enum AstNode {
+    /// A numeral of an arithmetic, bitvector or floating-point sort. The
+    /// value is in decimal, and rationals are printed as `p/q`.
+    Numeral { value: String, sort: Sort },
+    /// The application of `decl` to `args`. Constants are applications
+    /// without arguments.
+    App { decl: FuncDecl, args: Vec<Dynamic> },
+    /// The free variable `(:var index)`, which in the body of a quantifier
+    /// refers to its bound variables: index 0 is the last one.
+    Var { index: u32, sort: Sort },
+    /// A quantifier or lambda binding `bound` in `body`.
+    Quantifier {
+        kind: QuantifierKind,
+        bound: Vec<(Symbol, Sort)>,
+        weight: u32,
+        body: Dynamic,
+    },
+}
+
+impl AstNode {
+    pub(crate) fn new(ctx: Rc<Context>, a: Z3_ast) -> AstNode {
+        let z3_ctx = ctx.z3_ctx;
+        unsafe {
+            match Z3_get_ast_kind(z3_ctx, a) {
+                AstKind::Numeral => AstNode::Numeral {
+                    value: CStr::from_ptr(Z3_get_numeral_string(z3_ctx, a))
+                        .to_string_lossy()
+                        .into_owned(),
+                    sort: Sort::wrap(ctx.clone(), Z3_get_sort(z3_ctx, a)),
+                },
+                AstKind::App => {
+                    let app = Z3_to_app(z3_ctx, a);
+                    AstNode::App {
+                        decl: FuncDecl::wrap(ctx.clone(), Z3_get_app_decl(z3_ctx, app)),
+                        args: (0..Z3_get_app_num_args(z3_ctx, app))
+                            .map(|i| Dynamic::wrap(ctx.clone(), Z3_get_app_arg(z3_ctx, app, i)))
+                            .collect(),
+                    }
+                }
+                AstKind::Var => AstNode::Var {
+                    index: Z3_get_index_value(z3_ctx, a),
+                    sort: Sort::wrap(ctx.clone(), Z3_get_sort(z3_ctx, a)),
+                },
+                AstKind::Quantifier => AstNode::Quantifier {
+                    kind: if Z3_is_lambda(z3_ctx, a) {
+                        QuantifierKind::Lambda
+                    } else if Z3_is_quantifier_forall(z3_ctx, a) {
+                        QuantifierKind::Forall
+                    } else {
+                        QuantifierKind::Exists
+                    },
+                    bound: (0..Z3_get_quantifier_num_bound(z3_ctx, a))
+                        .map(|i| {
+                            (
+                                Symbol::from_z3_symbol(
+                                    &ctx,
+                                    Z3_get_quantifier_bound_name(z3_ctx, a, i),
+                                ),
+                                Sort::wrap(ctx.clone(), Z3_get_quantifier_bound_sort(z3_ctx, a, i)),
+                            )
+                        })
+                        .collect(),
+                    weight: Z3_get_quantifier_weight(z3_ctx, a),
+                    body: Dynamic::wrap(ctx.clone(), Z3_get_quantifier_body(z3_ctx, a)),
+                },
+                kind => unreachable!("expressions are never of kind {:?}", kind),
+            }
+        }
+    }
+
+    /// The subterms of this node: the arguments of an application and the
+    /// body of a quantifier.
+    pub fn children(&self) -> Vec<Dynamic> {
+        match self {
+            AstNode::App { args, .. } => args.clone(),
+            AstNode::Quantifier { body, .. } => vec![body.clone()],
+            AstNode::Numeral { .. } | AstNode::Var { .. } => vec![],
+        }
+    }
+}
+
+/// Fold `root` bottom-up.
+///
+/// `f` is called once for every distinct subterm, after it has been called
+/// for the subterm's [children](AstNode::children), whose results it is
+/// passed in order. Shared subterms are folded once, and the walk does not
+/// recurse, so neither large nor deep terms are a problem.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::{Ast, AstNode}, Config, Context};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let y = ast::Int::new_const(ctx.clone(), "y");
+/// let sum = &x + &y;
+/// let term = &sum * &sum;
+///
+/// // The depth of the term, counting shared subterms once.
+/// let depth = ast::fold(&term, |_, _, children: Vec<usize>| {
+///     1 + children.into_iter().max().unwrap_or(0)
+/// });
+/// assert_eq!(depth, 3);
+///
+/// // The number of numeral leaves.
+/// let numerals = ast::fold(&(&x + 2i64 + 3i64), |_, node, children: Vec<usize>| {
+///     matches!(node, AstNode::Numeral { .. }) as usize + children.iter().sum::<usize>()
+/// });
+/// assert_eq!(numerals, 2);
+/// ```
+pub fn fold<T, F>(root: &dyn Ast, mut f: F) -> T
+where
+    T: Clone,
+    F: FnMut(&Dynamic, &AstNode, Vec<T>) -> T,
+{
+    let root = Dynamic::from_ast(root);
+    let mut done: HashMap<Z3_ast, T> = HashMap::new();
+    let mut stack = vec![(root.clone(), false)];
+    while let Some((a, expanded)) = stack.pop() {
+        if done.contains_key(&a.z3_ast) {
+            continue;
+        }
+        let node = a.node();
+        let children = node.children();
+        if !expanded {
+            stack.push((a, true));
+            stack.extend(children.into_iter().map(|c| (c, false)));
+            continue;
+        }
+        let results = children.iter().map(|c| done[&c.z3_ast].clone()).collect();
+        let result = f(&a, &node, results);
+        done.insert(a.z3_ast, result);
+    }
+    done.remove(&root.z3_ast).unwrap()
+}
+
+/// Visit the subterms of `root` top-down.
+///
+/// `f` is called once for every distinct subterm reached, and the walk
+/// descends into the [children](AstNode::children) of a subterm only if
+/// `f` returns `true` for it.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::{Ast, AstNode}, Config, Context};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let y = ast::Int::new_const(ctx.clone(), "y");
+/// let formula = x.gt(&y) & (&x + 1i64)._eq(&y);
+///
+/// let mut constants = vec![];
+/// ast::visit(&formula, |a, node| {
+///     if let AstNode::App { args, .. } = node {
+///         if args.is_empty() {
+///             constants.push(a.to_string());
+///         }
+///     }
+///     true
+/// });
+/// constants.sort();
+/// assert_eq!(constants, ["x", "y"]);
+/// ```
+pub fn visit<F>(root: &dyn Ast, mut f: F)
+where
+    F: FnMut(&Dynamic, &AstNode) -> bool,
+{
+    let mut seen = HashSet::new();
+    let mut stack = vec![Dynamic::from_ast(root)];
+    while let Some(a) = stack.pop() {
+        if !seen.insert(a.z3_ast) {
+            continue;
+        }
+        let node = a.node();
+        if f(&a, &node) {
+            // Reversed, so that children are visited in order.
+            stack.extend(node.children().into_iter().rev());
+        }
+    }
+}
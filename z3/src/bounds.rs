@@ -0,0 +1,83 @@
+use z3_sys::*;
+
+use crate::{ast, ast::Ast, Optimize, Priority, SatResult, Solver};
+
+/// The tightest bounds of an integer term, see [`infer_bounds()`].
+///
+/// A bound is `None` if the term is unbounded in that direction.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Bounds {
+    /// The smallest value the term can take.
+    pub lower: Option<ast::Int>,
+    /// The largest value the term can take.
+    pub upper: Option<ast::Int>,
+}
+
+impl Bounds {
+    /// Both bounds as `i64`, if they exist and fit.
+    pub fn as_i64(&self) -> (Option<i64>, Option<i64>) {
+        (
+            self.lower.as_ref().and_then(ast::Int::as_i64),
+            self.upper.as_ref().and_then(ast::Int::as_i64),
+        )
+    }
+}
+
+/// Compute the tightest lower and upper bounds each of `vars` can take in
+/// the models of the assertions of `solver`.
+///
+/// The `i`-th entry of the result holds the bounds of `vars[i]`; the
+/// terms do not have to be constants. Return `None` if the assertions are
+/// unsatisfiable, or Z3 could not optimize them.
+///
+/// All bounds are computed with a single [`Optimize`], which optimizes
+/// every objective independently ([`Priority::Box`]).
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, Solver};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let y = ast::Int::new_const(ctx.clone(), "y");
+/// let int = |i| ast::Int::from_i64(ctx.clone(), i);
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&x.ge(&int(0)));
+/// solver.assert(&(x.clone() + y.clone())._eq(&int(10)));
+/// solver.assert(&y.ge(&int(3)));
+///
+/// let bounds = z3::infer_bounds(&solver, &[x, y]).unwrap();
+/// assert_eq!(bounds[0].as_i64(), (Some(0), Some(7)));
+/// assert_eq!(bounds[1].as_i64(), (Some(3), Some(10)));
+/// ```
+pub fn infer_bounds(solver: &Solver, vars: &[ast::Int]) -> Option<Vec<Bounds>> {
+    let ctx = solver.get_context();
+    let opt = Optimize::new(ctx.clone());
+    opt.set_priority(Priority::Box);
+    for assertion in solver.get_assertions() {
+        opt.assert(&assertion);
+    }
+    let objectives: Vec<_> = vars
+        .iter()
+        .map(|v| (opt.minimize(v), opt.maximize(v)))
+        .collect();
+    if opt.check(&[]) != SatResult::Sat {
+        return None;
+    }
+    // An unbounded objective has a bound involving infinity.
+    let numeral = |b: Option<ast::Dynamic>| {
+        b?.as_int()
+            .filter(|i| unsafe { Z3_is_numeral_ast(ctx.z3_ctx, i.get_z3_ast()) })
+    };
+    Some(
+        objectives
+            .iter()
+            .map(|(min, max)| Bounds {
+                lower: numeral(opt.lower(min)),
+                upper: numeral(opt.upper(max)),
+            })
+            .collect(),
+    )
+}
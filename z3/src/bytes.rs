@@ -0,0 +1,144 @@
+use crate::ast::{self, Ast, Bool, Int, Seq, BV};
+use crate::Rc;
+use crate::{Context, FuncDecl, Sort};
+
+/// The declarations of `str.to_code` and `str.from_code`, which the C API
+/// has no constructors for, taken from a parsed formula.
+fn code_decls(ctx: &Rc<Context>) -> (FuncDecl, FuncDecl) {
+    let parsed = Context::parse_smtlib2_string(
+        ctx.clone(),
+        "(assert (= (str.to_code (str.from_code 0)) 0))",
+        &[],
+        &[],
+    )
+    .expect("Z3 supports str.to_code and str.from_code");
+    let to_code = parsed[0].nth_child(0).unwrap();
+    let from_code = to_code.nth_child(0).unwrap();
+    (to_code.decl(), from_code.decl())
+}
+
+/// The bytes of `bv`, from the most significant one.
+fn bytes(bv: &BV) -> Vec<BV> {
+    let size = bv.get_size();
+    assert!(
+        size > 0 && size.is_multiple_of(8),
+        "a bit vector of {} bits is not made of bytes",
+        size
+    );
+    (0..size / 8)
+        .rev()
+        .map(|i| bv.extract(i * 8 + 7, i * 8))
+        .collect()
+}
+
+/// Concatenate `bytes`, the first being the most significant one, and
+/// return the bit vector with the condition that the sequence or string
+/// `length` has exactly as many elements.
+fn concat_bytes(ctx: Rc<Context>, bytes: Vec<BV>, length: Int) -> (BV, Bool) {
+    let len = Int::from_u64(ctx, bytes.len() as u64);
+    let mut bytes = bytes.into_iter();
+    let first = bytes.next().expect("no bytes to concatenate");
+    (bytes.fold(first, |bv, b| bv.concat(&b)), length._eq(&len))
+}
+
+impl BV {
+    /// The sequence of the bytes of this bit vector, from the most
+    /// significant one, as bit vectors of 8 bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of this bit vector is not a multiple of 8.
+    pub fn to_byte_seq(&self) -> Seq {
+        let units: Vec<Seq> = bytes(self).iter().map(Seq::unit).collect();
+        Seq::concat(self.get_ctx(), &units)
+    }
+
+    /// The string of the bytes of this bit vector, from the most
+    /// significant one, as the characters of codes 0 to 255.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of this bit vector is not a multiple of 8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let magic = ast::BV::from_u64(ctx.clone(), 0x4745_5420, 32);
+    /// let s = magic.to_byte_string().simplify();
+    /// assert_eq!(s.as_string().as_deref(), Some("GET "));
+    ///
+    /// let (bv, exact_length) = s.bytes_to_bv(4);
+    /// assert_eq!(bv.simplify().as_u64(), Some(0x4745_5420));
+    /// assert_eq!(exact_length.simplify().as_bool(), Some(true));
+    /// ```
+    pub fn to_byte_string(&self) -> ast::String {
+        let ctx = self.get_ctx();
+        let (_, from_code) = code_decls(&ctx);
+        let chars: Vec<ast::String> = bytes(self)
+            .iter()
+            .map(|b| {
+                let code = b.to_int(false);
+                from_code.apply(&[&code]).as_string().unwrap()
+            })
+            .collect();
+        ast::String::concat(ctx, &chars)
+    }
+}
+
+impl Seq {
+    /// The concatenation of the first `len` elements of this sequence of
+    /// bit vectors of 8 bits, the first being the most significant byte,
+    /// with the condition that the sequence has exactly `len` elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the elements are not bit vectors of 8 bits, or if `len`
+    /// is 0.
+    pub fn bytes_to_bv(&self, len: u32) -> (BV, Bool) {
+        let ctx = self.get_ctx();
+        assert_eq!(
+            self.element_sort(),
+            Sort::bitvector(ctx.clone(), 8),
+            "not a sequence of bytes"
+        );
+        let bytes = (0..len)
+            .map(|i| {
+                let i = Int::from_u64(ctx.clone(), i.into());
+                self.nth(&i).as_bv().unwrap()
+            })
+            .collect();
+        concat_bytes(ctx, bytes, self.length())
+    }
+}
+
+impl ast::String {
+    /// The concatenation of the codes of the first `len` characters of
+    /// this string, as bytes, the first being the most significant byte,
+    /// with the condition that the string has exactly `len` characters
+    /// and that their codes are below 256.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is 0.
+    pub fn bytes_to_bv(&self, len: u32) -> (BV, Bool) {
+        let ctx = self.get_ctx();
+        let (to_code, _) = code_decls(&ctx);
+        let below = Int::from_u64(ctx.clone(), 256);
+        let mut are_bytes = vec![];
+        let bytes = (0..len)
+            .map(|i| {
+                let i = Int::from_u64(ctx.clone(), i.into());
+                let code = to_code.apply(&[&self.at(&i)]).as_int().unwrap();
+                are_bytes.push(code.lt(&below));
+                BV::from_int(&code, 8)
+            })
+            .collect();
+        let (bv, exact_length) = concat_bytes(ctx.clone(), bytes, self.length());
+        are_bytes.push(exact_length);
+        (bv, Bool::and(ctx, &are_bytes))
+    }
+}
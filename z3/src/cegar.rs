@@ -0,0 +1,154 @@
+use crate::ast::{Ast, AstNode, Bool, Dynamic};
+use crate::{FuncDecl, Model, SatResult, Solver};
+
+/// The result of [`cegar()`].
+#[derive(Debug)]
+pub struct CegarOutcome {
+    /// [`SatResult::Sat`] if a model agrees with the oracle,
+    /// [`SatResult::Unsat`] if the assertions are unsatisfiable with the
+    /// lemmas learned from it, and [`SatResult::Unknown`] if the solver gave
+    /// up or the rounds ran out.
+    pub result: SatResult,
+    /// The number of checks made.
+    pub rounds: usize,
+    /// The model, if `result` is [`SatResult::Sat`].
+    pub model: Option<Model>,
+    /// The lemmas `f(a) = v` added to the solver, in order.
+    pub lemmas: Vec<Bool>,
+}
+
+/// Check the assertions of `solver`, in which `abstracted` is left
+/// uninterpreted, refining it with a concrete Rust implementation of it
+/// until a model agrees with the implementation.
+///
+/// Each round checks the solver. If it is satisfiable, every application
+/// `f(args)` of `abstracted` in the assertions is evaluated in the model,
+/// and `oracle` is called with the values of `args`. Where the model's
+/// value of `f(args)` is not the value the oracle returns, the lemma
+/// `f(args) = value` is asserted, which eliminates that counterexample,
+/// and the next round begins. A model in which every application agrees
+/// with the oracle ends the loop, as does an unsatisfiable round, since
+/// the lemmas hold of the concrete function. After `max_rounds` checks,
+/// the result is [`SatResult::Unknown`].
+///
+/// The lemmas stay asserted, so that later checks of `solver` can use
+/// them; [`Solver::push()`] before calling this to be able to remove them.
+/// Applications under a quantifier whose arguments depend on the bound
+/// variables have no values to call the oracle with, and are not refined.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, cegar, Config, Context, FuncDecl, SatResult, Solver, Sort};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let byte = Sort::bitvector(ctx.clone(), 8);
+/// let hash = FuncDecl::new(ctx.clone(), "hash", &[&byte], &byte);
+/// fn concrete_hash(x: u64) -> u64 {
+///     (x * 167 + 13) % 256
+/// }
+///
+/// // A preimage of 0x3a below 16, if there is one.
+/// let x = ast::BV::new_const(ctx.clone(), "x", 8);
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&x.bvult(&ast::BV::from_u64(ctx.clone(), 16, 8)));
+/// let target = ast::BV::from_u64(ctx.clone(), 0x3a, 8);
+/// solver.assert(&hash.apply(&[&x])._eq(&target.into()));
+///
+/// let outcome = cegar(&solver, &hash, 17, |args| {
+///     let x = args[0].as_bv().unwrap().as_u64().unwrap();
+///     ast::BV::from_u64(args[0].get_ctx(), concrete_hash(x), 8).into()
+/// });
+/// assert_eq!(outcome.result, SatResult::Sat);
+/// let x = outcome.model.unwrap().eval(&x, true).unwrap().as_u64().unwrap();
+/// assert_eq!(concrete_hash(x), 0x3a);
+/// ```
+pub fn cegar<F>(
+    solver: &Solver,
+    abstracted: &FuncDecl,
+    max_rounds: usize,
+    mut oracle: F,
+) -> CegarOutcome
+where
+    F: FnMut(&[Dynamic]) -> Dynamic,
+{
+    let mut lemmas = vec![];
+    for round in 1..=max_rounds {
+        let result = solver.check();
+        if result != SatResult::Sat {
+            return CegarOutcome {
+                result,
+                rounds: round,
+                model: None,
+                lemmas,
+            };
+        }
+        let model = solver.get_model().unwrap();
+        let new_lemmas = refine(solver, &model, abstracted, &mut oracle);
+        if new_lemmas.is_empty() {
+            return CegarOutcome {
+                result,
+                rounds: round,
+                model: Some(model),
+                lemmas,
+            };
+        }
+        for lemma in &new_lemmas {
+            solver.assert(lemma);
+        }
+        lemmas.extend(new_lemmas);
+    }
+    CegarOutcome {
+        result: SatResult::Unknown,
+        rounds: max_rounds,
+        model: None,
+        lemmas,
+    }
+}
+
+/// The lemmas eliminating the applications of `abstracted` in the
+/// assertions of `solver` on which `model` disagrees with `oracle`.
+fn refine<F>(solver: &Solver, model: &Model, abstracted: &FuncDecl, oracle: &mut F) -> Vec<Bool>
+where
+    F: FnMut(&[Dynamic]) -> Dynamic,
+{
+    let mut points: Vec<Vec<Dynamic>> = vec![];
+    let mut lemmas = vec![];
+    for assertion in solver.get_assertions() {
+        crate::ast::visit(&assertion, |_, node| {
+            if let AstNode::App { decl, args } = node {
+                if decl != abstracted {
+                    return true;
+                }
+                let args: Option<Vec<Dynamic>> = args
+                    .iter()
+                    .map(|a| model.eval(a, true).filter(|v| !has_vars(v)))
+                    .collect();
+                if let Some(args) = args {
+                    if !points.contains(&args) {
+                        let refs: Vec<&dyn Ast> = args.iter().map(|a| a as &dyn Ast).collect();
+                        let point = abstracted.apply(&refs);
+                        let value = oracle(&args);
+                        if model.eval(&point, true).as_ref() != Some(&value) {
+                            lemmas.push(point._eq(&value));
+                        }
+                        points.push(args);
+                    }
+                }
+            }
+            true
+        });
+    }
+    lemmas
+}
+
+/// Whether `a` has free variables, bound by an enclosing quantifier.
+fn has_vars(a: &Dynamic) -> bool {
+    let mut vars = false;
+    crate::ast::visit(a, |_, node| {
+        vars |= matches!(node, AstNode::Var { .. });
+        !vars
+    });
+    vars
+}
@@ -0,0 +1,177 @@
+use z3_sys::*;
+
+use crate::ast::{Ast, Regexp};
+use crate::conversion::cstring;
+use crate::Context;
+use crate::Rc;
+
+/// The largest character Z3 supports in strings.
+pub(crate) const MAX_CHAR: u32 = 0x2ffff;
+
+/// A common class of characters, see [`Regexp::ascii_class()`] and
+/// [`Regexp::unicode_class()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CharClass {
+    /// Decimal digits.
+    Digit,
+    /// Letters.
+    Alpha,
+    /// Letters and decimal digits.
+    Alnum,
+    /// Whitespace.
+    Whitespace,
+    /// Uppercase letters.
+    Upper,
+    /// Lowercase letters.
+    Lower,
+    /// Word characters, as matched by `\w`: letters, digits and `_` (and,
+    /// outside of ASCII, marks and connector punctuation).
+    Word,
+}
+
+impl CharClass {
+    /// The ranges of ASCII characters in this class, as used by
+    /// [`Regexp::ascii_class()`].
+    pub fn ascii_ranges(self) -> &'static [(char, char)] {
+        match self {
+            CharClass::Digit => &[('0', '9')],
+            CharClass::Alpha => &[('A', 'Z'), ('a', 'z')],
+            CharClass::Alnum => &[('0', '9'), ('A', 'Z'), ('a', 'z')],
+            CharClass::Whitespace => &[('\t', '\r'), (' ', ' ')],
+            CharClass::Upper => &[('A', 'Z')],
+            CharClass::Lower => &[('a', 'z')],
+            CharClass::Word => &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')],
+        }
+    }
+
+    /// The ranges of Unicode characters in this class, as used by
+    /// [`Regexp::unicode_class()`].
+    ///
+    /// The classes follow the definitions of the [`regex`] crate, e.g.
+    /// [`CharClass::Digit`] is the general category `Nd` and
+    /// [`CharClass::Alpha`] the property `Alphabetic`.
+    ///
+    /// This requires the `regex` feature.
+    ///
+    /// [`regex`]: https://docs.rs/regex
+    #[cfg(feature = "regex")]
+    pub fn unicode_ranges(self) -> Vec<(char, char)> {
+        use regex_syntax::hir::{Class, HirKind};
+
+        let pattern = match self {
+            CharClass::Digit => r"\d",
+            CharClass::Alpha => r"\p{Alphabetic}",
+            CharClass::Alnum => r"[\p{Alphabetic}\d]",
+            CharClass::Whitespace => r"\s",
+            CharClass::Upper => r"\p{Uppercase}",
+            CharClass::Lower => r"\p{Lowercase}",
+            CharClass::Word => r"\w",
+        };
+        match regex_syntax::parse(pattern).unwrap().into_kind() {
+            HirKind::Class(Class::Unicode(class)) => {
+                class.iter().map(|r| (r.start(), r.end())).collect()
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Regexp {
+    /// Create a regular expression that recognizes a single character in
+    /// any of the inclusive `ranges`.
+    ///
+    /// Unlike [`Regexp::range()`], this accepts any characters as bounds,
+    /// not only ASCII ones. Characters beyond the range Z3 supports
+    /// (`U+2FFFF`) are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, Config, Context};
+    /// # let cfg = Config::new();
+    /// # let ctx = Rc::new(Context::new(&cfg));
+    /// let hex_digit = ast::Regexp::ranges(ctx, &[('0', '9'), ('a', 'f'), ('A', 'F')]);
+    /// ```
+    pub fn ranges(ctx: Rc<Context>, ranges: &[(char, char)]) -> Regexp {
+        let mut res: Vec<Regexp> = ranges
+            .iter()
+            .filter(|(lo, _)| *lo as u32 <= MAX_CHAR)
+            .map(|&(lo, hi)| {
+                let hi = char::from_u32((hi as u32).min(MAX_CHAR)).unwrap();
+                char_range(&ctx, lo, hi)
+            })
+            .collect();
+        match res.len() {
+            0 => Regexp::empty(ctx),
+            1 => res.pop().unwrap(),
+            _ => Regexp::union(ctx, &res),
+        }
+    }
+
+    /// Create a regular expression that recognizes a single ASCII
+    /// character of `class`, like `[[:digit:]]` does in POSIX regular
+    /// expressions.
+    ///
+    /// # See also:
+    ///
+    /// - [`Regexp::unicode_class()`]
+    pub fn ascii_class(ctx: Rc<Context>, class: CharClass) -> Regexp {
+        Regexp::ranges(ctx, class.ascii_ranges())
+    }
+
+    /// Create a regular expression that recognizes a single Unicode
+    /// character of `class`, see [`CharClass::unicode_ranges()`].
+    ///
+    /// This requires the `regex` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, Config, CharClass, Context, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let digits = ast::Regexp::unicode_class(ctx.clone(), CharClass::Digit).plus();
+    /// // Arabic-Indic digits.
+    /// let s = ast::String::from_str(ctx.clone(), "\\u{661}\\u{662}").unwrap();
+    ///
+    /// let solver = Solver::new(ctx);
+    /// solver.assert(&s.regex_matches(&digits));
+    /// assert_eq!(solver.check(), SatResult::Sat);
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn unicode_class(ctx: Rc<Context>, class: CharClass) -> Regexp {
+        Regexp::ranges(ctx, &class.unicode_ranges())
+    }
+}
+
+/// A regular expression recognizing the characters from `lo` to `hi`.
+pub(crate) fn char_range(ctx: &Rc<Context>, lo: char, hi: char) -> Regexp {
+    if lo == hi {
+        return Regexp::literal(ctx.clone(), &escape(lo));
+    }
+    // Unlike `Regexp::range()`, escape the bounds, so that characters
+    // outside of ASCII are not read as several bytes.
+    let lo = cstring(escape(lo));
+    let hi = cstring(escape(hi));
+    unsafe {
+        let lo = Z3_mk_string(ctx.z3_ctx, lo.as_ptr());
+        Z3_inc_ref(ctx.z3_ctx, lo);
+        let hi = Z3_mk_string(ctx.z3_ctx, hi.as_ptr());
+        Z3_inc_ref(ctx.z3_ctx, hi);
+        let range = Z3_mk_re_range(ctx.z3_ctx, lo, hi);
+        Z3_dec_ref(ctx.z3_ctx, lo);
+        Z3_dec_ref(ctx.z3_ctx, hi);
+        Regexp::wrap(ctx.clone(), range)
+    }
+}
+
+/// Escape `c` so that Z3 reads it back as the single character `c`.
+fn escape(c: char) -> String {
+    if c.is_ascii_graphic() && c != '\\' || c == ' ' {
+        c.to_string()
+    } else {
+        format!("\\u{{{:x}}}", c as u32)
+    }
+}
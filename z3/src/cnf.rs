@@ -0,0 +1,270 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+use z3_sys::*;
+
+use crate::{ast, ast::Ast, DeclKind, Goal, Tactic};
+
+/// A Boolean formula in conjunctive normal form over numbered variables,
+/// for model counters and other external SAT tools.
+///
+/// The formula is converted with Z3's `tseitin-cnf` tactic, which adds
+/// auxiliary variables. They are defined by the original variables, so
+/// counting the models projected onto the original variables gives the
+/// number of models of the formula; [`Cnf::to_dimacs()`] lists these
+/// variables in the `c ind` lines that projected model counters such as
+/// ganak read.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, Cnf, Config, Context};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let a = ast::Bool::new_const(ctx.clone(), "a");
+/// let b = ast::Bool::new_const(ctx.clone(), "b");
+/// let c = ast::Bool::new_const(ctx.clone(), "c");
+///
+/// let cnf = Cnf::new(&[a.iff(&(b.clone() & !c.clone()))], &[a.clone(), b, c]).unwrap();
+/// assert_eq!(cnf.var_index(&a), Some(1));
+/// let dimacs = cnf.to_dimacs();
+/// assert!(dimacs.contains("c ind 1 2 3 0\n"));
+/// assert!(dimacs.contains("c 1 a\n"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Cnf {
+    // `vars[i]` is DIMACS variable `i + 1`; the first `projection` of them
+    // are the variables to count over.
+    vars: Vec<ast::Bool>,
+    projection: usize,
+    clauses: Vec<Vec<i32>>,
+    // Whether `vars[i]` was introduced by `tseitin-cnf`; shorter than
+    // `vars` when the remaining variables are not.
+    auxiliary: Vec<bool>,
+}
+
+impl Cnf {
+    /// Convert the conjunction of `formulas` to CNF.
+    ///
+    /// The Boolean constants in `projection` are the variables to count
+    /// over; they are numbered `1..=projection.len()` in order, whether or
+    /// not they occur in the formulas. Other atoms, like `x < 3`, become
+    /// variables of their own, in the normal form Z3 rewrites them to.
+    ///
+    /// Return an error if the tactic fails.
+    pub fn new(formulas: &[ast::Bool], projection: &[ast::Bool]) -> Result<Cnf, String> {
+        let ctx = match formulas.first().or_else(|| projection.first()) {
+            Some(f) => f.get_ctx(),
+            None => {
+                return Ok(Cnf {
+                    vars: vec![],
+                    projection: 0,
+                    clauses: vec![],
+                    auxiliary: vec![],
+                })
+            }
+        };
+        let goal = Goal::new(ctx.clone(), false, false, false);
+        for f in formulas {
+            goal.assert(f);
+        }
+        let subgoals: Vec<Goal> = Tactic::new(ctx, "tseitin-cnf")
+            .apply(&goal, None)?
+            .list_subgoals()
+            .collect();
+        let goal = match <[Goal; 1]>::try_from(subgoals) {
+            Ok([goal]) => goal,
+            Err(_) => return Err("tseitin-cnf did not produce a single goal".to_owned()),
+        };
+        let mut cnf = Cnf::from_goal(&goal, projection)?;
+        let inputs = constants(formulas);
+        cnf.auxiliary = cnf
+            .vars
+            .iter()
+            .map(|v| is_constant(v) && !inputs.contains(&v.get_z3_ast()))
+            .collect();
+        Ok(cnf)
+    }
+
+    /// Read the clauses of `goal`, numbering the variables in `projection`
+    /// first and the other atoms in the order they occur.
+    ///
+    /// Return an error if a formula of the goal is not a clause.
+    pub(crate) fn from_goal(goal: &Goal, projection: &[ast::Bool]) -> Result<Cnf, String> {
+        let mut cnf = Cnf {
+            vars: vec![],
+            projection: projection.len(),
+            clauses: vec![],
+            auxiliary: vec![],
+        };
+        let mut index: HashMap<Z3_ast, i32> = HashMap::new();
+        for v in projection {
+            cnf.var(&mut index, v);
+        }
+        for f in goal.iter_formulas::<ast::Bool>() {
+            let clause = match kind(&f) {
+                Some(DeclKind::TRUE) => continue,
+                Some(DeclKind::FALSE) => Some(vec![]),
+                Some(DeclKind::OR) => f
+                    .children()
+                    .iter()
+                    .map(|l| cnf.literal(&mut index, &l.as_bool().unwrap()))
+                    .collect::<Option<_>>(),
+                _ => cnf.literal(&mut index, &f).map(|l| vec![l]),
+            }
+            .ok_or_else(|| format!("{} is not a clause", f))?;
+            cnf.clauses.push(clause);
+        }
+        Ok(cnf)
+    }
+
+    fn var(&mut self, index: &mut HashMap<Z3_ast, i32>, atom: &ast::Bool) -> i32 {
+        let vars = &mut self.vars;
+        *index.entry(atom.get_z3_ast()).or_insert_with(|| {
+            vars.push(atom.clone());
+            vars.len() as i32
+        })
+    }
+
+    /// The literal `l` stands for, or `None` if it is neither an atom nor
+    /// the negation of one.
+    fn literal(&mut self, index: &mut HashMap<Z3_ast, i32>, l: &ast::Bool) -> Option<i32> {
+        if kind(l) == Some(DeclKind::NOT) {
+            let atom = l.nth_child(0).unwrap().as_bool().unwrap();
+            is_atom(&atom).then(|| -self.var(index, &atom))
+        } else {
+            is_atom(l).then(|| self.var(index, l))
+        }
+    }
+
+    /// The terms of the variables, variable `i` being the `i - 1`-th one.
+    pub(crate) fn vars(&self) -> &[ast::Bool] {
+        &self.vars
+    }
+
+    /// The number of variables, including the auxiliary ones.
+    pub fn num_vars(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// The clauses, with variable `i` as literal `i` and its negation as
+    /// `-i`, like in DIMACS.
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
+
+    /// The term DIMACS variable `index` stands for, with `None` if there
+    /// is no such variable.
+    pub fn var_term(&self, index: i32) -> Option<&ast::Bool> {
+        let i = usize::try_from(index).ok()?.checked_sub(1)?;
+        self.vars.get(i)
+    }
+
+    /// The DIMACS variable that stands for `atom`, with `None` if it does
+    /// not occur in the formula.
+    pub fn var_index(&self, atom: &ast::Bool) -> Option<i32> {
+        self.vars
+            .iter()
+            .position(|v| v == atom)
+            .map(|i| i as i32 + 1)
+    }
+
+    /// The variables to count over, see [`Cnf::new()`].
+    pub fn projection(&self) -> &[ast::Bool] {
+        &self.vars[..self.projection]
+    }
+
+    /// Render the formula in DIMACS format.
+    ///
+    /// The problem line is followed by a `c ind` line with the projection
+    /// variables, and a `c <index> <term>` line for every variable that is
+    /// not auxiliary, with the term on a single line.
+    pub fn to_dimacs(&self) -> String {
+        let mut comments = String::new();
+        if self.projection > 0 {
+            comments.push_str("c ind");
+            for i in 1..=self.projection {
+                write!(comments, " {}", i).unwrap();
+            }
+            comments.push_str(" 0\n");
+        }
+        for (i, v) in self.vars.iter().enumerate() {
+            if i < self.projection || self.auxiliary.get(i) != Some(&true) {
+                let term = v
+                    .to_string()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                writeln!(comments, "c {} {}", i + 1, term).unwrap();
+            }
+        }
+        self.render(&comments)
+    }
+
+    /// The DIMACS rendering of the formula, with the comment lines
+    /// `comments` after the problem line.
+    pub(crate) fn render(&self, comments: &str) -> String {
+        let mut s = String::new();
+        writeln!(s, "p cnf {} {}", self.vars.len(), self.clauses.len()).unwrap();
+        s.push_str(comments);
+        for clause in &self.clauses {
+            for l in clause {
+                write!(s, "{} ", l).unwrap();
+            }
+            s.push_str("0\n");
+        }
+        s
+    }
+}
+
+fn kind(a: &ast::Bool) -> Option<DeclKind> {
+    a.safe_decl().ok().map(|d| d.kind())
+}
+
+/// Whether `a` is an atom of a clause: an application that is not a
+/// Boolean connective, like a Boolean constant or `x < 3`.
+fn is_atom(a: &ast::Bool) -> bool {
+    match kind(a) {
+        None => false,
+        Some(
+            DeclKind::TRUE
+            | DeclKind::FALSE
+            | DeclKind::AND
+            | DeclKind::OR
+            | DeclKind::NOT
+            | DeclKind::IMPLIES
+            | DeclKind::IFF
+            | DeclKind::XOR
+            | DeclKind::ITE,
+        ) => false,
+        Some(DeclKind::EQ | DeclKind::DISTINCT) => {
+            a.children()[0].get_sort().kind() != SortKind::Bool
+        }
+        Some(_) => true,
+    }
+}
+
+/// Whether `a` is an uninterpreted constant.
+fn is_constant(a: &impl Ast) -> bool {
+    a.kind() == AstKind::App && a.num_children() == 0 && a.decl().kind() == DeclKind::UNINTERPRETED
+}
+
+/// The uninterpreted constants occurring in `formulas`.
+fn constants(formulas: &[ast::Bool]) -> HashSet<Z3_ast> {
+    let mut seen = HashSet::new();
+    let mut todo: Vec<ast::Dynamic> = formulas.iter().map(|f| ast::Dynamic::from_ast(f)).collect();
+    let mut consts = HashSet::new();
+    while let Some(a) = todo.pop() {
+        if a.kind() != AstKind::App || !seen.insert(a.get_z3_ast()) {
+            continue;
+        }
+        if is_constant(&a) {
+            consts.insert(a.get_z3_ast());
+        } else {
+            todo.extend(a.children());
+        }
+    }
+    consts
+}
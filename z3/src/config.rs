@@ -1,8 +1,10 @@
+use std::path::Path;
+
 use log::debug;
-use std::ffi::CString;
 
 use z3_sys::*;
 
+use crate::conversion::cstring;
 use crate::Config;
 
 impl Config {
@@ -39,8 +41,8 @@ impl Config {
     ///
     /// - [`Config::set_bool_param_value()`]
     pub fn set_param_value(&mut self, k: &str, v: &str) {
-        let ks = CString::new(k).unwrap();
-        let vs = CString::new(v).unwrap();
+        let ks = cstring(k);
+        let vs = cstring(v);
         self.kvs.push((ks, vs));
         unsafe {
             Z3_set_param_value(
@@ -82,6 +84,18 @@ impl Config {
         self.set_bool_param_value("model", b);
     }
 
+    /// Write a trace of the terms Z3 creates and of the quantifier
+    /// instantiations it makes to the file `path`, which
+    /// [`QuantifierInstantiations::read_trace()`](crate::QuantifierInstantiations::read_trace)
+    /// sums up by quantifier.
+    ///
+    /// This sets the `trace` and `trace_file_name` parameters. The trace is
+    /// complete once the context is dropped.
+    pub fn set_quantifier_trace<P: AsRef<Path>>(&mut self, path: P) {
+        self.set_bool_param_value("trace", true);
+        self.set_param_value("trace_file_name", &path.as_ref().to_string_lossy());
+    }
+
     pub fn set_debug_ref_count(&mut self, b: bool) {
         self.set_bool_param_value("debug_ref_count", b);
     }
@@ -1,10 +1,14 @@
-use log::debug;
-use std::ffi::CString;
-use std::rc::Rc;
+use log::{debug, warn};
+#[cfg(debug_assertions)]
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use z3_sys::*;
 
-use crate::{Config, Context, ContextHandle};
+use crate::conversion::cstring;
+use crate::Rc;
+use crate::{ast, Config, Context, ContextHandle, FuncDecl, ParseError, Parser, Sort};
 
 impl Context {
     pub fn new(cfg: &Config) -> Context {
@@ -15,6 +19,7 @@ impl Context {
                 Z3_set_error_handler(p, None);
                 p
             },
+            alive: Arc::new(Mutex::new(true)),
         }
     }
 
@@ -30,7 +35,66 @@ impl Context {
     /// - [`ContextHandle`]
     /// - [`ContextHandle::interrupt()`]
     pub fn handle(ctx: Rc<Self>) -> ContextHandle {
-        ContextHandle { ctx }
+        ContextHandle {
+            z3_ctx: ctx.z3_ctx,
+            alive: ctx.alive.clone(),
+        }
+    }
+
+    /// Parse the SMT-LIB2 commands in `source`, and return the assertions
+    /// in scope at the end.
+    ///
+    /// `source` can refer to the given `sorts` and `decls` by their names
+    /// without declaring them.
+    ///
+    /// # See also:
+    ///
+    /// - [`Context::parse_smtlib2_file()`]
+    /// - [`Parser`], for more control over parsing
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Sort, Symbol};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let node = Sort::uninterpreted(ctx.clone(), Symbol::String("Node".to_owned()));
+    /// let root = ast::Dynamic::new_const(ctx.clone(), "root", &node);
+    ///
+    /// let assertions = Context::parse_smtlib2_string(
+    ///     ctx,
+    ///     "(declare-const leaf Node) (assert (not (= root leaf)))",
+    ///     &[node],
+    ///     &[root.decl()],
+    /// )
+    /// .unwrap();
+    /// assert_eq!(assertions.len(), 1);
+    /// ```
+    pub fn parse_smtlib2_string(
+        ctx: Rc<Self>,
+        source: &str,
+        sorts: &[Sort],
+        decls: &[FuncDecl],
+    ) -> Result<Vec<ast::Bool>, ParseError> {
+        Parser::new(ctx)
+            .sorts(sorts)
+            .decls(decls)
+            .parse_string_raw(source)
+    }
+
+    /// Like [`Context::parse_smtlib2_string()`], but reads the commands
+    /// from the file at `path`.
+    pub fn parse_smtlib2_file<P: AsRef<Path>>(
+        ctx: Rc<Self>,
+        path: P,
+        sorts: &[Sort],
+        decls: &[FuncDecl],
+    ) -> Result<Vec<ast::Bool>, ParseError> {
+        Parser::new(ctx)
+            .sorts(sorts)
+            .decls(decls)
+            .parse_file_raw(path.as_ref())
     }
 
     /// Update a global parameter.
@@ -39,8 +103,8 @@ impl Context {
     ///
     /// - [`Context::update_bool_param_value()`]
     pub fn update_param_value(&mut self, k: &str, v: &str) {
-        let ks = CString::new(k).unwrap();
-        let vs = CString::new(v).unwrap();
+        let ks = cstring(k);
+        let vs = cstring(v);
         unsafe { Z3_update_param_value(self.z3_ctx, ks.as_ptr(), vs.as_ptr()) };
     }
 
@@ -56,20 +120,94 @@ impl Context {
     }
 }
 
+impl PartialEq for Context {
+    fn eq(&self, other: &Context) -> bool {
+        self.z3_ctx == other.z3_ctx
+    }
+}
+
+impl Eq for Context {}
+
 impl ContextHandle {
     /// Interrupt a solver performing a satisfiability test, a tactic processing a goal, or simplify functions.
+    ///
+    /// Does nothing if the context of this handle has already been dropped.
     pub fn interrupt(&self) {
-        unsafe {
-            Z3_interrupt(self.ctx.z3_ctx);
+        let alive = lock_ignoring_poison(&self.alive);
+        if *alive {
+            unsafe {
+                Z3_interrupt(self.z3_ctx);
+            }
+        } else {
+            warn!(
+                "interrupt() called on a handle to dropped context {:p}",
+                self.z3_ctx
+            );
         }
     }
+
+    /// Return `true` while the context of this handle has not been dropped.
+    pub fn is_alive(&self) -> bool {
+        *lock_ignoring_poison(&self.alive)
+    }
 }
 
+impl PartialEq for ContextHandle {
+    fn eq(&self, other: &ContextHandle) -> bool {
+        self.z3_ctx == other.z3_ctx
+    }
+}
+
+impl Eq for ContextHandle {}
+
+// The raw context is only ever used while holding the `alive` lock, and
+// only for `Z3_interrupt`, which Z3 allows to be called from any thread.
 unsafe impl Sync for ContextHandle {}
 unsafe impl Send for ContextHandle {}
 
+// A panic on another thread while it held the lock must not turn into a
+// panic here: this is called from `Drop`.
+fn lock_ignoring_poison(alive: &Mutex<bool>) -> MutexGuard<'_, bool> {
+    alive.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// The number of live wrappers by context, in debug builds. Wrappers hold
+// their context through an `Rc`, so a context can only be dropped under
+// live wrappers when one of them got hold of it in some other way, which
+// is a bug that would otherwise show up as a crash somewhere far away.
+#[cfg(debug_assertions)]
+static CHILDREN: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+#[cfg(debug_assertions)]
+pub(crate) fn track_child(z3_ctx: Z3_context, delta: isize) {
+    let mut children = CHILDREN.lock().unwrap_or_else(|e| e.into_inner());
+    let count = children.entry(z3_ctx as usize).or_insert(0);
+    *count = count.wrapping_add(delta as usize);
+    if *count == 0 {
+        children.remove(&(z3_ctx as usize));
+    }
+}
+
 impl Drop for Context {
     fn drop(&mut self) {
+        let mut alive = lock_ignoring_poison(&self.alive);
+        *alive = false;
+        debug!("drop context {:p}", self.z3_ctx);
+        #[cfg(debug_assertions)]
+        {
+            let children = CHILDREN
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&(self.z3_ctx as usize))
+                .unwrap_or(0);
+            if children > 0 {
+                eprintln!(
+                    "WARNING: z3 context {:p} dropped while {} of its objects are still alive; \
+                     using any of them is undefined behavior",
+                    self.z3_ctx, children
+                );
+            }
+        }
         unsafe { Z3_del_context(self.z3_ctx) };
     }
 }
@@ -0,0 +1,132 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::{c_char, c_uint};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+/// A Rust value that cannot be converted losslessly into what the Z3 C API
+/// expects.
+///
+/// Returned by the `try_` variants of the functions that panic on such
+/// values, e.g. [`Symbol::try_as_z3_symbol()`](crate::Symbol::try_as_z3_symbol)
+/// and [`ast::Dynamic::try_new_const()`](crate::ast::Dynamic::try_new_const).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConversionError {
+    /// A string contains a NUL byte at this position.
+    Nul(usize),
+    /// A length or count is larger than `u32::MAX`.
+    TooLong(usize),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            ConversionError::Nul(pos) => write!(f, "string contains a NUL byte at {}", pos),
+            ConversionError::TooLong(n) => write!(f, "{} elements are too many for Z3", n),
+        }
+    }
+}
+
+impl Error for ConversionError {}
+
+/// What the crate does with a timeout that is longer than Z3 accepts
+/// (`u32::MAX` milliseconds, about 49 days), e.g. in
+/// [`Tactic::try_for()`](crate::Tactic::try_for).
+///
+/// The policy only covers values that can be clamped without changing
+/// their meaning. Strings with a NUL byte and slices longer than
+/// `u32::MAX` elements cannot: functions that take them panic, and their
+/// `try_` variants return a [`ConversionError`] instead, regardless of the
+/// policy.
+///
+/// The policy is crate-wide; see [`set_conversion_policy()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ConversionPolicy {
+    /// Panic on timeouts that are too long.
+    Panic,
+    /// Clamp timeouts that are too long to the longest one Z3 accepts. This
+    /// is the default.
+    #[default]
+    Saturate,
+}
+
+static POLICY: AtomicU8 = AtomicU8::new(0);
+
+/// Set the crate-wide [`ConversionPolicy`].
+///
+/// # See also:
+///
+/// - [`conversion_policy()`]
+pub fn set_conversion_policy(policy: ConversionPolicy) {
+    let v = match policy {
+        ConversionPolicy::Saturate => 0,
+        ConversionPolicy::Panic => 1,
+    };
+    POLICY.store(v, Ordering::Relaxed);
+}
+
+/// Get the crate-wide [`ConversionPolicy`].
+///
+/// # See also:
+///
+/// - [`set_conversion_policy()`]
+pub fn conversion_policy() -> ConversionPolicy {
+    match POLICY.load(Ordering::Relaxed) {
+        0 => ConversionPolicy::Saturate,
+        _ => ConversionPolicy::Panic,
+    }
+}
+
+/// Convert `s` into a `CString`, panicking if it contains a NUL byte.
+pub(crate) fn cstring<S: Into<Vec<u8>>>(s: S) -> CString {
+    match try_cstring(s) {
+        Ok(cs) => cs,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Convert `s` into a `CString`.
+pub(crate) fn try_cstring<S: Into<Vec<u8>>>(s: S) -> Result<CString, ConversionError> {
+    CString::new(s).map_err(|e| ConversionError::Nul(e.nul_position()))
+}
+
+/// Convert a length or count into a `c_uint`, panicking if it is too large.
+pub(crate) fn c_len(n: usize) -> c_uint {
+    match try_c_len(n) {
+        Ok(n) => n,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Convert a length or count into a `c_uint`.
+pub(crate) fn try_c_len(n: usize) -> Result<c_uint, ConversionError> {
+    c_uint::try_from(n).map_err(|_| ConversionError::TooLong(n))
+}
+
+/// Convert a timeout into milliseconds according to the current policy.
+pub(crate) fn timeout_ms(timeout: Duration) -> c_uint {
+    match c_uint::try_from(timeout.as_millis()) {
+        Ok(ms) => ms,
+        Err(_) => match conversion_policy() {
+            ConversionPolicy::Saturate => c_uint::MAX,
+            ConversionPolicy::Panic => panic!("timeout of {:?} is too long for Z3", timeout),
+        },
+    }
+}
+
+/// Borrow a string returned by Z3, or the empty string if `p` is null.
+///
+/// # Safety
+///
+/// `p` must be null or point to a NUL-terminated string that outlives
+/// `'a`. Strings in Z3's per-context buffer only live until the next call
+/// into Z3 on that context that returns a string.
+pub(crate) unsafe fn z3_cstr<'a>(p: *const c_char) -> &'a CStr {
+    if p.is_null() {
+        Default::default()
+    } else {
+        CStr::from_ptr(p)
+    }
+}
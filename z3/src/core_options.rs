@@ -0,0 +1,161 @@
+use crate::ast::Bool;
+use crate::Rc;
+use crate::{Context, Params, SatResult, Solver};
+
+/// How [`Solver::check_assumptions_with()`] computes unsat cores, each
+/// option defaulting to the solver's own setting.
+///
+/// The options are Z3's `core.*` solver parameters, which apply to the
+/// cores of both the SMT and the SAT solvers. Without them, a core is
+/// whatever subset of the assumptions the solver used to reach the
+/// conflict, which need not be minimal.
+///
+/// # Examples
+///
+/// ```
+/// # use z3::CoreOptions;
+/// let options = CoreOptions::new().minimize(true).extend_patterns(true);
+/// assert_eq!(options, CoreOptions::minimal().extend_patterns(true));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CoreOptions {
+    minimize: Option<bool>,
+    minimize_partial: Option<bool>,
+    extend_patterns: Option<bool>,
+    extend_patterns_max_distance: Option<u32>,
+    extend_nonlocal_patterns: Option<bool>,
+}
+
+impl CoreOptions {
+    /// Options with the solver's own settings.
+    pub fn new() -> CoreOptions {
+        CoreOptions::default()
+    }
+
+    /// Options that minimize cores.
+    pub fn minimal() -> CoreOptions {
+        CoreOptions::new().minimize(true)
+    }
+
+    /// Whether to minimize cores, so that dropping any assumption from one
+    /// makes it satisfiable (`core.minimize`), at the cost of further
+    /// checks of the solver.
+    pub fn minimize(mut self, minimize: bool) -> CoreOptions {
+        self.minimize = Some(minimize);
+        self
+    }
+
+    /// Whether to drop the assumptions of cores that are cheaply found to
+    /// be unnecessary, without minimizing them (`core.minimize_partial`).
+    pub fn minimize_partial(mut self, minimize_partial: bool) -> CoreOptions {
+        self.minimize_partial = Some(minimize_partial);
+        self
+    }
+
+    /// Whether to extend cores with the assumptions that trigger the
+    /// patterns of instantiated quantifiers (`core.extend_patterns`).
+    pub fn extend_patterns(mut self, extend_patterns: bool) -> CoreOptions {
+        self.extend_patterns = Some(extend_patterns);
+        self
+    }
+
+    /// How far [`extend_patterns()`](CoreOptions::extend_patterns) looks
+    /// for triggering assumptions (`core.extend_patterns.max_distance`).
+    pub fn extend_patterns_max_distance(mut self, max_distance: u32) -> CoreOptions {
+        self.extend_patterns_max_distance = Some(max_distance);
+        self
+    }
+
+    /// Whether to extend cores with the assumptions of quantifiers whose
+    /// patterns have symbols that are not in their body
+    /// (`core.extend_nonlocal_patterns`).
+    pub fn extend_nonlocal_patterns(mut self, extend_nonlocal_patterns: bool) -> CoreOptions {
+        self.extend_nonlocal_patterns = Some(extend_nonlocal_patterns);
+        self
+    }
+
+    /// The parameters that are set, for [`Solver::set_params()`].
+    pub fn params(&self, ctx: Rc<Context>) -> Params {
+        let mut params = Params::new(ctx);
+        let flags = [
+            ("core.minimize", self.minimize),
+            ("core.minimize_partial", self.minimize_partial),
+            ("core.extend_patterns", self.extend_patterns),
+            (
+                "core.extend_nonlocal_patterns",
+                self.extend_nonlocal_patterns,
+            ),
+        ];
+        for (k, v) in flags.iter() {
+            if let Some(v) = v {
+                params.set_bool(*k, *v);
+            }
+        }
+        if let Some(max_distance) = self.extend_patterns_max_distance {
+            params.set_u32("core.extend_patterns.max_distance", max_distance);
+        }
+        params
+    }
+
+    /// Z3's defaults of the options, which they are restored to after
+    /// [`Solver::check_assumptions_with()`] unless they were set with
+    /// [`Solver::set_params()`].
+    fn defaults(ctx: Rc<Context>) -> Params {
+        CoreOptions {
+            minimize: Some(false),
+            minimize_partial: Some(false),
+            extend_patterns: Some(false),
+            extend_patterns_max_distance: Some(u32::MAX),
+            extend_nonlocal_patterns: Some(false),
+        }
+        .params(ctx)
+    }
+}
+
+impl Solver {
+    /// Check the assertions with `assumptions` like
+    /// [`Solver::check_assumptions()`], and return the result with the
+    /// unsat core computed with `options`, which is empty unless the result
+    /// is [`SatResult::Unsat`].
+    ///
+    /// The options only apply to this call, as with
+    /// [`Solver::check_with_params()`]. Z3 computes the core when it is
+    /// asked for, so [`Solver::get_unsat_core()`] afterwards gives it
+    /// without the options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, CoreOptions, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let positive = ast::Bool::new_const(ctx.clone(), "positive");
+    /// let negative = ast::Bool::new_const(ctx.clone(), "negative");
+    /// let zero = ast::Int::from_i64(ctx.clone(), 0);
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&positive.implies(&x.gt(&zero)));
+    /// solver.assert(&negative.implies(&x.lt(&zero)));
+    ///
+    /// let assumptions = [positive, negative];
+    /// let (result, core) = solver.check_assumptions_with(&assumptions, &CoreOptions::minimal());
+    /// assert_eq!(result, SatResult::Unsat);
+    /// assert_eq!(core.len(), 2);
+    /// ```
+    pub fn check_assumptions_with(
+        &self,
+        assumptions: &[Bool],
+        options: &CoreOptions,
+    ) -> (SatResult, Vec<Bool>) {
+        let params = options.params(self.get_context());
+        let defaults = CoreOptions::defaults(self.get_context());
+        self.with_params(&params, &defaults, || {
+            match self.check_assumptions(assumptions) {
+                SatResult::Unsat => (SatResult::Unsat, self.get_unsat_core()),
+                result => (result, vec![]),
+            }
+        })
+        .expect("the defaults cover every option")
+    }
+}
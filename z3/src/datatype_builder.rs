@@ -1,16 +1,19 @@
 //! Helpers for building custom [datatype sorts](DatatypeSort).
 
-use std::rc::Rc;
+use std::ffi::CStr;
 use std::{convert::TryInto, ptr::null_mut};
 
 use z3_sys::*;
 
+use crate::conversion::c_len;
+use crate::Rc;
 use crate::{
     Context, DatatypeAccessor, DatatypeBuilder, DatatypeSort, DatatypeVariant, FuncDecl, Sort,
     Symbol,
 };
 
 impl DatatypeBuilder {
+    /// Start declaring the datatype `name`, which has no variants yet.
     pub fn new<S: Into<Symbol>>(ctx: Rc<Context>, name: S) -> Self {
         Self {
             ctx,
@@ -19,6 +22,12 @@ impl DatatypeBuilder {
         }
     }
 
+    /// Add a variant with the constructor `name` and the tester `is-name`,
+    /// and an accessor for each of `fields`.
+    ///
+    /// A field of sort [`DatatypeAccessor::Datatype`] refers to a datatype
+    /// by name: to this one, for a recursive datatype, or to another one
+    /// declared together with it by [`create_datatypes()`].
     pub fn variant(mut self, name: &str, fields: Vec<(&str, DatatypeAccessor)>) -> Self {
         let mut accessor_vec: Vec<(String, DatatypeAccessor)> = Vec::new();
         for (accessor_name, accessor) in fields {
@@ -30,17 +39,69 @@ impl DatatypeBuilder {
         self
     }
 
+    /// Declare the datatype, which may refer to itself but to no other
+    /// datatype under construction.
+    ///
+    /// # Panics
+    ///
+    /// See [`create_datatypes()`].
     pub fn finish(self) -> DatatypeSort {
         let mut dtypes = create_datatypes(vec![self]);
         dtypes.remove(0)
     }
 }
 
+/// Declare several datatypes at once, which may refer to each other.
+///
+/// The sorts are returned in the order of `datatype_builders`.
+///
+/// # Panics
+///
+/// Panics if `datatype_builders` is empty, if a field refers to a datatype
+/// that is not declared exactly once among them, or if Z3 rejects the
+/// declaration, for example because a datatype has no finite values.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, DatatypeAccessor, DatatypeBuilder, Sort};
+/// # use z3::datatype_builder::create_datatypes;
+/// # let cfg = Config::new();
+/// # let ctx = Rc::new(Context::new(&cfg));
+/// // A tree is a value and a forest of children, and a forest is a list of
+/// // trees.
+/// let tree = DatatypeBuilder::new(ctx.clone(), "Tree").variant(
+///     "node",
+///     vec![
+///         ("value", DatatypeAccessor::Sort(Sort::int(ctx.clone()))),
+///         ("children", DatatypeAccessor::Datatype("Forest".into())),
+///     ],
+/// );
+/// let forest = DatatypeBuilder::new(ctx.clone(), "Forest")
+///     .variant("leaf", vec![])
+///     .variant(
+///         "grove",
+///         vec![
+///             ("first", DatatypeAccessor::Datatype("Tree".into())),
+///             ("rest", DatatypeAccessor::Datatype("Forest".into())),
+///         ],
+///     );
+/// let sorts = create_datatypes(vec![tree, forest]);
+/// let (tree, forest) = (&sorts[0], &sorts[1]);
+///
+/// let leaf = forest.variants[0].constructor.apply(&[]);
+/// let one = ast::Int::from_i64(ctx.clone(), 1);
+/// let t = tree.variants[0].constructor.apply(&[&one, &leaf]);
+/// assert_eq!(t.get_sort(), tree.sort);
+/// assert_eq!(tree.variants[0].accessors[1].apply(&[&t]).simplify(), leaf);
+/// ```
 pub fn create_datatypes(datatype_builders: Vec<DatatypeBuilder>) -> Vec<DatatypeSort> {
     let num = datatype_builders.len();
     assert!(num > 0, "At least one DatatypeBuilder must be specified");
 
     let ctx: Rc<Context> = datatype_builders[0].ctx.clone();
+    assert!(datatype_builders.iter().all(|d| d.ctx == ctx));
     let mut names: Vec<Z3_symbol> = Vec::with_capacity(num);
 
     let mut raw_sorts: Vec<Z3_sort> = Vec::with_capacity(num);
@@ -83,7 +144,10 @@ pub fn create_datatypes(datatype_builders: Vec<DatatypeBuilder>) -> Vec<Datatype
                         assert_eq!(
                             1,
                             matching_names.len(),
-                            "One and only one occurrence of each datatype is expected."
+                            "One and only one occurrence of each datatype is expected, \
+                             but {:?} is declared {} times.",
+                            dtype_name,
+                            matching_names.len()
                         );
 
                         let (sort_ref, _) = matching_names[0];
@@ -101,7 +165,7 @@ pub fn create_datatypes(datatype_builders: Vec<DatatypeBuilder>) -> Vec<Datatype
                     ctx.z3_ctx,
                     cname_symbol,
                     rname_symbol,
-                    num_fs.try_into().unwrap(),
+                    c_len(num_fs),
                     field_names.as_ptr(),
                     field_sorts.as_ptr(),
                     sort_refs.as_mut_ptr(),
@@ -111,9 +175,7 @@ pub fn create_datatypes(datatype_builders: Vec<DatatypeBuilder>) -> Vec<Datatype
         }
         assert!(!cs.is_empty());
 
-        let clist = unsafe {
-            Z3_mk_constructor_list(ctx.z3_ctx, num_cs.try_into().unwrap(), cs.as_mut_ptr())
-        };
+        let clist = unsafe { Z3_mk_constructor_list(ctx.z3_ctx, c_len(num_cs), cs.as_mut_ptr()) };
         clists.push(clist);
         ctors.extend(cs);
     }
@@ -121,17 +183,44 @@ pub fn create_datatypes(datatype_builders: Vec<DatatypeBuilder>) -> Vec<Datatype
     assert_eq!(num, names.len());
     assert_eq!(num, clists.len());
 
-    unsafe {
+    let error = unsafe {
+        Z3_set_error(ctx.z3_ctx, ErrorCode::OK);
         Z3_mk_datatypes(
             ctx.z3_ctx,
-            num.try_into().unwrap(),
+            c_len(num),
             names.as_ptr(),
             raw_sorts.as_mut_ptr(),
             clists.as_mut_ptr(),
         );
         raw_sorts.set_len(num);
+        match Z3_get_error_code(ctx.z3_ctx) {
+            ErrorCode::OK => None,
+            code => Some(
+                CStr::from_ptr(Z3_get_error_msg(ctx.z3_ctx, code))
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+        }
     };
 
+    // The constructors are not needed any more: the `FuncDecl`s are read
+    // off the sorts.
+    for ctor in ctors {
+        unsafe {
+            Z3_del_constructor(ctx.z3_ctx, ctor);
+        }
+    }
+
+    for clist in clists {
+        unsafe {
+            Z3_del_constructor_list(ctx.z3_ctx, clist);
+        }
+    }
+
+    if let Some(msg) = error {
+        panic!("invalid datatype declaration: {}", msg);
+    }
+
     let mut datatype_sorts: Vec<DatatypeSort> = Vec::with_capacity(raw_sorts.len());
     for (z3_sort, datatype_builder) in raw_sorts.into_iter().zip(&datatype_builders) {
         let num_cs = datatype_builder.constructors.len();
@@ -177,17 +266,5 @@ pub fn create_datatypes(datatype_builders: Vec<DatatypeBuilder>) -> Vec<Datatype
         datatype_sorts.push(DatatypeSort { sort, variants });
     }
 
-    for ctor in ctors {
-        unsafe {
-            Z3_del_constructor(ctx.z3_ctx, ctor);
-        }
-    }
-
-    for clist in clists {
-        unsafe {
-            Z3_del_constructor_list(ctx.z3_ctx, clist);
-        }
-    }
-
     datatype_sorts
 }
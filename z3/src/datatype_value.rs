@@ -0,0 +1,183 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::ast::{Ast, Bool, Datatype, Dynamic};
+use crate::{DatatypeVariant, Sort};
+
+/// An error returned by the helpers on [`Datatype`] values, such as
+/// [`Datatype::construct()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DatatypeError {
+    /// The sort is not a datatype.
+    NotADatatype(Sort),
+    /// The datatype has no constructor of this name.
+    UnknownConstructor { sort: Sort, name: String },
+    /// The datatype has no field of this name.
+    UnknownField { sort: Sort, name: String },
+    /// The constructor was applied to the wrong number of arguments.
+    Arity {
+        constructor: String,
+        expected: usize,
+        found: usize,
+    },
+    /// An argument of the constructor is of the wrong sort.
+    SortMismatch {
+        constructor: String,
+        field: String,
+        expected: Sort,
+        found: Sort,
+    },
+}
+
+impl fmt::Display for DatatypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            DatatypeError::NotADatatype(sort) => write!(f, "{} is not a datatype", sort),
+            DatatypeError::UnknownConstructor { sort, name } => {
+                write!(f, "datatype {} has no constructor {}", sort, name)?;
+                write_names(f, "constructors", sort, |v| vec![v.constructor.name()])
+            }
+            DatatypeError::UnknownField { sort, name } => {
+                write!(f, "datatype {} has no field {}", sort, name)?;
+                write_names(f, "fields", sort, |v| {
+                    v.accessors.iter().map(|a| a.name()).collect()
+                })
+            }
+            DatatypeError::Arity {
+                constructor,
+                expected,
+                found,
+            } => write!(
+                f,
+                "constructor {} takes {} arguments, not {}",
+                constructor, expected, found
+            ),
+            DatatypeError::SortMismatch {
+                constructor,
+                field,
+                expected,
+                found,
+            } => write!(
+                f,
+                "field {} of constructor {} is of sort {}, not {}",
+                field, constructor, expected, found
+            ),
+        }
+    }
+}
+
+/// Write the names `names` gives for the variants of `sort`, if it has any.
+fn write_names(
+    f: &mut fmt::Formatter,
+    what: &str,
+    sort: &Sort,
+    names: impl Fn(&DatatypeVariant) -> Vec<String>,
+) -> Result<(), fmt::Error> {
+    let names: Vec<String> = variants(sort)
+        .unwrap_or_default()
+        .iter()
+        .flat_map(names)
+        .collect();
+    if names.is_empty() {
+        Ok(())
+    } else {
+        write!(f, "; its {} are {}", what, names.join(", "))
+    }
+}
+
+impl Error for DatatypeError {}
+
+fn variants(sort: &Sort) -> Result<Vec<DatatypeVariant>, DatatypeError> {
+    sort.datatype_variants()
+        .ok_or_else(|| DatatypeError::NotADatatype(sort.clone()))
+}
+
+fn variant(sort: &Sort, constructor: &str) -> Result<DatatypeVariant, DatatypeError> {
+    variants(sort)?
+        .into_iter()
+        .find(|v| v.constructor.name() == constructor)
+        .ok_or_else(|| DatatypeError::UnknownConstructor {
+            sort: sort.clone(),
+            name: constructor.to_owned(),
+        })
+}
+
+impl Datatype {
+    /// Apply the constructor named `constructor` of the datatype `sort` to
+    /// `args`, after checking their number and sorts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::{Ast, Datatype}, Config, Context, DatatypeAccessor, DatatypeBuilder, Sort};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let shape = DatatypeBuilder::new(ctx.clone(), "Shape")
+    ///     .variant("Circle", vec![("radius", DatatypeAccessor::Sort(Sort::int(ctx.clone())))])
+    ///     .variant("Dot", vec![])
+    ///     .finish();
+    ///
+    /// let r = ast::Int::from_i64(ctx.clone(), 3);
+    /// let circle = Datatype::construct(&shape.sort, "Circle", &[&r]).unwrap();
+    /// assert_eq!(circle.field("radius").unwrap().simplify(), r.into());
+    /// assert_eq!(circle.is_variant("Dot").unwrap().simplify().as_bool(), Some(false));
+    ///
+    /// let err = Datatype::construct(&shape.sort, "Square", &[]).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "datatype Shape has no constructor Square; its constructors are Circle, Dot"
+    /// );
+    /// ```
+    pub fn construct(
+        sort: &Sort,
+        constructor: &str,
+        args: &[&dyn Ast],
+    ) -> Result<Datatype, DatatypeError> {
+        let variant = variant(sort, constructor)?;
+        let domain = variant.constructor.domain();
+        if domain.len() != args.len() {
+            return Err(DatatypeError::Arity {
+                constructor: constructor.to_owned(),
+                expected: domain.len(),
+                found: args.len(),
+            });
+        }
+        for ((expected, arg), accessor) in domain.into_iter().zip(args).zip(&variant.accessors) {
+            let found = arg.get_sort();
+            if found != expected {
+                return Err(DatatypeError::SortMismatch {
+                    constructor: constructor.to_owned(),
+                    field: accessor.name(),
+                    expected,
+                    found,
+                });
+            }
+        }
+        Ok(variant.constructor.apply(args).as_datatype().unwrap())
+    }
+
+    /// Whether this value was built with the constructor named
+    /// `constructor`.
+    pub fn is_variant(&self, constructor: &str) -> Result<Bool, DatatypeError> {
+        let variant = variant(&self.get_sort(), constructor)?;
+        Ok(variant.tester.apply(&[self]).as_bool().unwrap())
+    }
+
+    /// The field named `field` of this value.
+    ///
+    /// Like in SMT-LIB, the field of a value built with another
+    /// constructor than the field belongs to is unspecified.
+    pub fn field(&self, field: &str) -> Result<Dynamic, DatatypeError> {
+        let sort = self.get_sort();
+        variants(&sort)?
+            .iter()
+            .flat_map(|v| &v.accessors)
+            .find(|a| a.name() == field)
+            .map(|a| a.apply(&[self]))
+            .ok_or_else(|| DatatypeError::UnknownField {
+                sort: sort.clone(),
+                name: field.to_owned(),
+            })
+    }
+}
@@ -0,0 +1,95 @@
+//! Counts of the live wrappers of Z3 objects, to find leaks.
+//!
+//! Every wrapper, like a [`Model`](crate::Model) or an [`ast::Int`](crate::ast::Int),
+//! holds a reference to its Z3 object, and to its [`Context`], until it is
+//! dropped. A service that keeps wrappers around by mistake, say in a cache
+//! that is never cleared, leaks the memory of the Z3 objects and of their
+//! contexts. Comparing [`live_objects()`] between requests shows which
+//! wrappers pile up.
+//!
+//! This requires the `debug` feature. Counting takes a global lock for
+//! every wrapper that is created or dropped, so it is meant for
+//! diagnosing, not for production.
+//!
+//! # Examples
+//!
+//! ```
+//! # use std::rc::Rc;
+//! # use z3::{ast, Config, Context};
+//! use z3::debug;
+//!
+//! let cfg = Config::new();
+//! let ctx = Rc::new(Context::new(&cfg));
+//! let count = || {
+//!     debug::live_objects_in(&ctx)
+//!         .into_iter()
+//!         .find(|l| l.type_name == "ast::Int")
+//!         .map_or(0, |l| l.count)
+//! };
+//!
+//! let x = ast::Int::new_const(ctx.clone(), "x");
+//! let y = x.clone();
+//! assert_eq!(count(), 2);
+//! drop((x, y));
+//! assert_eq!(count(), 0);
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use z3_sys::Z3_context;
+
+use crate::Context;
+
+/// The number of live wrappers of one type in one context.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LiveObjects {
+    /// The context, as identified by [`context_id()`].
+    pub context: usize,
+    /// The type of the wrappers, like `"Model"`, or `"ast::Bool"` for
+    /// ASTs.
+    pub type_name: &'static str,
+    pub count: usize,
+}
+
+// The counts by context and type. Types without live wrappers are removed,
+// so that a context whose address is reused starts from nothing.
+static LIVE: Mutex<BTreeMap<(usize, &'static str), usize>> = Mutex::new(BTreeMap::new());
+
+pub(crate) fn track(z3_ctx: Z3_context, type_name: &'static str, delta: isize) {
+    let mut live = LIVE.lock().unwrap_or_else(|e| e.into_inner());
+    let key = (z3_ctx as usize, type_name);
+    let count = live.entry(key).or_insert(0);
+    *count = count.wrapping_add(delta as usize);
+    if *count == 0 {
+        live.remove(&key);
+    }
+}
+
+/// The live wrappers of all contexts, of all threads, by context and then
+/// by type name.
+pub fn live_objects() -> Vec<LiveObjects> {
+    let live = LIVE.lock().unwrap_or_else(|e| e.into_inner());
+    live.iter()
+        .map(|(&(context, type_name), &count)| LiveObjects {
+            context,
+            type_name,
+            count,
+        })
+        .collect()
+}
+
+/// The live wrappers of `ctx`, by type name.
+pub fn live_objects_in(ctx: &Context) -> Vec<LiveObjects> {
+    let id = context_id(ctx);
+    live_objects()
+        .into_iter()
+        .filter(|l| l.context == id)
+        .collect()
+}
+
+/// The identifier of `ctx` in [`LiveObjects::context`], which is unique
+/// among the contexts that exist at the same time.
+pub fn context_id(ctx: &Context) -> usize {
+    ctx.z3_ctx as usize
+}
@@ -0,0 +1,184 @@
+//! Differential checking of Z3 against another SMT solver.
+//!
+//! Formulas are exported to an SMT-LIB 2 script, which is piped to an
+//! external solver binary, such as cvc5 or Yices, whose answer is compared
+//! with Z3's. A definite answer of one solver that contradicts a definite
+//! answer of the other is a [`Discrepancy`]: a soundness bug in one of the
+//! solvers, or in the export. `unknown` never contradicts anything.
+//!
+//! # Examples
+//!
+//! ```
+//! # use std::rc::Rc;
+//! # use z3::{ast, ast::Ast, Config, Context, SatResult};
+//! use z3::diffcheck::{self, ExternalSolver};
+//!
+//! let cfg = Config::new();
+//! let ctx = Rc::new(Context::new(&cfg));
+//! let x = ast::Int::new_const(ctx.clone(), "x");
+//! let formulas = [x.gt(&ast::Int::from_i64(ctx.clone(), 0))];
+//!
+//! let script = diffcheck::to_smtlib(&formulas);
+//! assert!(script.contains("(declare-fun x () Int)"));
+//! assert!(script.ends_with("(check-sat)\n"));
+//!
+//! # #[cfg(unix)] {
+//! // A stand-in for another solver, which answers `unsat` to everything.
+//! let wrong = ExternalSolver::new("sh").arg("-c").arg("cat > /dev/null; echo unsat");
+//! let discrepancy = diffcheck::compare(&formulas, &wrong).unwrap().unwrap();
+//! assert_eq!(discrepancy.z3, SatResult::Sat);
+//! assert_eq!(discrepancy.other, SatResult::Unsat);
+//! # }
+//! ```
+
+use std::ffi::OsString;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::{ast, ast::Ast, Goal, SatResult, Solver};
+
+/// Export the conjunction of `formulas` to an SMT-LIB 2 script, with the
+/// declarations of the constants and functions they use and a final
+/// `(check-sat)`.
+pub fn to_smtlib(formulas: &[ast::Bool]) -> String {
+    let ctx = match formulas.first() {
+        Some(f) => f.get_ctx(),
+        None => return "(check-sat)\n".to_string(),
+    };
+    let solver = Solver::new(ctx);
+    for f in formulas {
+        solver.assert(f);
+    }
+    let mut script = solver.to_smt2();
+    if !script.trim_end().ends_with("(check-sat)") {
+        script.push_str("(check-sat)\n");
+    }
+    script
+}
+
+/// An SMT solver binary that reads an SMT-LIB 2 script on its standard
+/// input and prints `sat`, `unsat` or `unknown`.
+///
+/// The answer is awaited without a timeout, so pass the solver its own
+/// timeout option if it may run for long.
+#[derive(Clone, Debug)]
+pub struct ExternalSolver {
+    program: OsString,
+    args: Vec<OsString>,
+}
+
+impl ExternalSolver {
+    /// Run `program`, which is looked up in `PATH` if it is not a path.
+    pub fn new<S: Into<OsString>>(program: S) -> ExternalSolver {
+        ExternalSolver {
+            program: program.into(),
+            args: vec![],
+        }
+    }
+
+    /// Pass `arg` to the solver, after the arguments passed so far. Most
+    /// solvers need one to read the script from standard input, such as
+    /// `-in` for Z3 or `--lang=smt2` for cvc5.
+    pub fn arg<S: Into<OsString>>(mut self, arg: S) -> ExternalSolver {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Check the conjunction of `formulas`.
+    pub fn check(&self, formulas: &[ast::Bool]) -> Result<SatResult, String> {
+        self.check_smtlib(&to_smtlib(formulas))
+    }
+
+    /// Run the solver on `script`, and return its answer to the first
+    /// `(check-sat)`.
+    ///
+    /// Return an error if the solver cannot be run or gives no answer.
+    pub fn check_smtlib(&self, script: &str) -> Result<SatResult, String> {
+        let name = self.program.to_string_lossy();
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("cannot run {}: {}", name, e))?;
+        // The script is written from another thread, so that a solver that
+        // prints a lot before it has read all of it does not block on a
+        // full pipe. A solver may also exit before reading all of the
+        // script, for example after an error, which its output then
+        // reports.
+        let mut stdin = child.stdin.take().unwrap();
+        let script = script.to_owned();
+        let writer = thread::spawn(move || {
+            let _ = stdin.write_all(script.as_bytes());
+        });
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("cannot run {}: {}", name, e))?;
+        let _ = writer.join();
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let answer = stdout.lines().map(str::trim).find(|l| !l.is_empty());
+        match answer {
+            Some("sat") => Ok(SatResult::Sat),
+            Some("unsat") => Ok(SatResult::Unsat),
+            Some("unknown") => Ok(SatResult::Unknown),
+            _ => Err(format!(
+                "{} gave no answer ({}): {}{}",
+                name,
+                output.status,
+                stdout.trim(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        }
+    }
+}
+
+/// Contradicting answers of Z3 and an [`ExternalSolver`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Discrepancy {
+    /// The answer of Z3.
+    pub z3: SatResult,
+    /// The answer of the other solver.
+    pub other: SatResult,
+    /// The script the other solver was run on, to reproduce it.
+    pub smtlib: String,
+}
+
+/// Check the conjunction of `formulas` with Z3 and with `other`, and
+/// return the discrepancy if their answers contradict each other.
+///
+/// Return an error if `other` fails.
+pub fn compare(
+    formulas: &[ast::Bool],
+    other: &ExternalSolver,
+) -> Result<Option<Discrepancy>, String> {
+    let z3 = match formulas.first() {
+        Some(f) => {
+            let solver = Solver::new(f.get_ctx());
+            for f in formulas {
+                solver.assert(f);
+            }
+            solver.check()
+        }
+        None => SatResult::Sat,
+    };
+    let smtlib = to_smtlib(formulas);
+    let answer = other.check_smtlib(&smtlib)?;
+    Ok(match (z3, answer) {
+        (SatResult::Sat, SatResult::Unsat) | (SatResult::Unsat, SatResult::Sat) => {
+            Some(Discrepancy {
+                z3,
+                other: answer,
+                smtlib,
+            })
+        }
+        _ => None,
+    })
+}
+
+/// Like [`compare()`], for the formulas of `goal`.
+pub fn compare_goal(goal: &Goal, other: &ExternalSolver) -> Result<Option<Discrepancy>, String> {
+    compare(&goal.get_formulas::<ast::Bool>(), other)
+}
@@ -0,0 +1,246 @@
+use std::ffi::CStr;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use z3_sys::*;
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, Model, Symbol};
+
+/// A decimal fixed-point number with `SCALE` fractional digits, e.g. an
+/// amount of money in cents for `SCALE = 2`.
+///
+/// A `FixedPointNum` is an integer term (its [raw value](FixedPointNum::raw))
+/// that is implicitly divided by `10^SCALE`. The arithmetic operators keep
+/// track of that scaling, and cut products and quotients to `SCALE`
+/// fractional digits with Z3's integer division. That division is
+/// Euclidean: products, and quotients by a positive number, are rounded
+/// down, while quotients by a negative number are rounded up.
+///
+/// Only integer raw values are supported, not bit-vectors: the arithmetic
+/// never wraps around, so a fixed width has to be imposed with explicit
+/// bounds on the numbers.
+///
+/// With the `rust_decimal` feature, numbers convert from and to
+/// [`Decimal`](https://docs.rs/rust_decimal)s.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, Config, Context, FixedPointNum, SatResult, Solver};
+/// type Money = FixedPointNum<2>;
+///
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let price = Money::new_const(ctx.clone(), "price");
+/// let rate = Money::from_decimal_str(ctx.clone(), "0.19").unwrap();
+/// let total = price.clone() + price.clone() * rate;
+///
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&total._eq(&Money::from_decimal_str(ctx, "119.00").unwrap()));
+/// assert_eq!(solver.check(), SatResult::Sat);
+/// let model = solver.get_model().unwrap();
+/// let price = price.eval(&model).unwrap();
+/// assert_eq!(price.as_decimal_string().unwrap(), "100.00");
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct FixedPointNum<const SCALE: u32> {
+    raw: ast::Int,
+}
+
+impl<const SCALE: u32> FixedPointNum<SCALE> {
+    /// The fixed-point number `raw / 10^SCALE`.
+    pub fn from_raw(raw: ast::Int) -> FixedPointNum<SCALE> {
+        FixedPointNum { raw }
+    }
+
+    /// The integer this number is represented by, i.e. this number times
+    /// `10^SCALE`.
+    pub fn raw(&self) -> &ast::Int {
+        &self.raw
+    }
+
+    pub fn new_const<S: Into<Symbol>>(ctx: Rc<Context>, name: S) -> FixedPointNum<SCALE> {
+        FixedPointNum::from_raw(ast::Int::new_const(ctx, name))
+    }
+
+    pub fn from_i64(ctx: Rc<Context>, i: i64) -> FixedPointNum<SCALE> {
+        FixedPointNum::from_raw(ast::Int::from_i64(ctx.clone(), i) * Self::unit(ctx))
+    }
+
+    /// Parse a decimal number like `-12.5`. Return `None` if `value` is
+    /// not a decimal number, or has more than `SCALE` fractional digits.
+    pub fn from_decimal_str(ctx: Rc<Context>, value: &str) -> Option<FixedPointNum<SCALE>> {
+        let (negative, digits) = match value.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, value),
+        };
+        let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+        if int.is_empty() && frac.is_empty()
+            || frac.len() > SCALE as usize
+            || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let raw = format!(
+            "{}{}{}{}",
+            if negative { "-" } else { "" },
+            int,
+            frac,
+            "0".repeat(SCALE as usize - frac.len())
+        );
+        ast::Int::from_str(ctx, &raw).map(FixedPointNum::from_raw)
+    }
+
+    /// Render this number as a decimal string with exactly `SCALE`
+    /// fractional digits, if its raw value is a numeral (e.g. after
+    /// [`FixedPointNum::eval()`]).
+    pub fn as_decimal_string(&self) -> Option<String> {
+        let ctx = self.raw.get_ctx();
+        let raw = unsafe {
+            if !Z3_is_numeral_ast(ctx.z3_ctx, self.raw.get_z3_ast()) {
+                return None;
+            }
+            CStr::from_ptr(Z3_get_numeral_string(ctx.z3_ctx, self.raw.get_z3_ast()))
+                .to_str()
+                .ok()?
+                .to_owned()
+        };
+        let (sign, digits) = match raw.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", raw.as_str()),
+        };
+        let scale = SCALE as usize;
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        Some(if scale == 0 {
+            format!("{}{}", sign, int)
+        } else {
+            format!("{}{}.{}", sign, int, frac)
+        })
+    }
+
+    /// The number `value`, or `None` if it has more than `SCALE`
+    /// fractional digits other than trailing zeros.
+    ///
+    /// This requires the `rust_decimal` feature.
+    #[cfg(feature = "rust_decimal")]
+    pub fn from_decimal(
+        ctx: Rc<Context>,
+        value: &rust_decimal::Decimal,
+    ) -> Option<FixedPointNum<SCALE>> {
+        FixedPointNum::from_decimal_str(ctx, &value.normalize().to_string())
+    }
+
+    /// This number as a [`Decimal`](rust_decimal::Decimal), if its raw
+    /// value is a numeral that fits one.
+    ///
+    /// This requires the `rust_decimal` feature.
+    #[cfg(feature = "rust_decimal")]
+    pub fn as_decimal(&self) -> Option<rust_decimal::Decimal> {
+        rust_decimal::Decimal::from_str_exact(&self.as_decimal_string()?).ok()
+    }
+
+    /// Evaluate this number in `model`, see [`Model::eval()`].
+    pub fn eval(&self, model: &Model) -> Option<FixedPointNum<SCALE>> {
+        model.eval(&self.raw, true).map(FixedPointNum::from_raw)
+    }
+
+    pub fn _eq(&self, other: &FixedPointNum<SCALE>) -> ast::Bool {
+        self.raw._eq(&other.raw)
+    }
+
+    pub fn lt(&self, other: &FixedPointNum<SCALE>) -> ast::Bool {
+        self.raw.lt(&other.raw)
+    }
+
+    pub fn le(&self, other: &FixedPointNum<SCALE>) -> ast::Bool {
+        self.raw.le(&other.raw)
+    }
+
+    pub fn gt(&self, other: &FixedPointNum<SCALE>) -> ast::Bool {
+        self.raw.gt(&other.raw)
+    }
+
+    pub fn ge(&self, other: &FixedPointNum<SCALE>) -> ast::Bool {
+        self.raw.ge(&other.raw)
+    }
+
+    /// Convert to a number with a different scale, rounding down if
+    /// digits are dropped.
+    pub fn rescale<const TO: u32>(&self) -> FixedPointNum<TO> {
+        let ctx = self.raw.get_ctx();
+        let ten = |exp| ast::Int::from_str(ctx.clone(), &format!("1{}", "0".repeat(exp)));
+        FixedPointNum::from_raw(if TO >= SCALE {
+            self.raw.clone() * ten((TO - SCALE) as usize).unwrap()
+        } else {
+            ast::Int::div(&self.raw, &ten((SCALE - TO) as usize).unwrap())
+        })
+    }
+
+    /// `10^SCALE`, the raw value of `1`.
+    fn unit(ctx: Rc<Context>) -> ast::Int {
+        ast::Int::from_str(ctx, &format!("1{}", "0".repeat(SCALE as usize))).unwrap()
+    }
+}
+
+impl<const SCALE: u32> fmt::Debug for FixedPointNum<SCALE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self.as_decimal_string() {
+            Some(s) => write!(f, "{}", s),
+            None => write!(f, "{} / 10^{}", self.raw, SCALE),
+        }
+    }
+}
+
+impl<const SCALE: u32> fmt::Display for FixedPointNum<SCALE> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+impl<const SCALE: u32> Add for FixedPointNum<SCALE> {
+    type Output = FixedPointNum<SCALE>;
+
+    fn add(self, other: FixedPointNum<SCALE>) -> FixedPointNum<SCALE> {
+        FixedPointNum::from_raw(self.raw + other.raw)
+    }
+}
+
+impl<const SCALE: u32> Sub for FixedPointNum<SCALE> {
+    type Output = FixedPointNum<SCALE>;
+
+    fn sub(self, other: FixedPointNum<SCALE>) -> FixedPointNum<SCALE> {
+        FixedPointNum::from_raw(self.raw - other.raw)
+    }
+}
+
+impl<const SCALE: u32> Mul for FixedPointNum<SCALE> {
+    type Output = FixedPointNum<SCALE>;
+
+    fn mul(self, other: FixedPointNum<SCALE>) -> FixedPointNum<SCALE> {
+        let unit = Self::unit(self.raw.get_ctx());
+        FixedPointNum::from_raw(ast::Int::div(&(self.raw * other.raw), &unit))
+    }
+}
+
+impl<const SCALE: u32> Div for FixedPointNum<SCALE> {
+    type Output = FixedPointNum<SCALE>;
+
+    /// Like Z3's integer division, this rounds up if `other` is negative,
+    /// and is unspecified if `other` is zero.
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, other: FixedPointNum<SCALE>) -> FixedPointNum<SCALE> {
+        let unit = Self::unit(self.raw.get_ctx());
+        FixedPointNum::from_raw(ast::Int::div(&(self.raw * unit), &other.raw))
+    }
+}
+
+impl<const SCALE: u32> Neg for FixedPointNum<SCALE> {
+    type Output = FixedPointNum<SCALE>;
+
+    fn neg(self) -> FixedPointNum<SCALE> {
+        FixedPointNum::from_raw(-self.raw)
+    }
+}
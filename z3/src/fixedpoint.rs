@@ -0,0 +1,401 @@
+use std::ffi::CStr;
+use std::fmt;
+use std::ptr;
+
+use z3_sys::*;
+
+use crate::Rc;
+use crate::{
+    ast,
+    ast::{Ast, Bool},
+    Context, Fixedpoint, FuncDecl, Params, ParseError, SatResult, Statistics, Symbol,
+};
+
+use crate::conversion::{c_len, try_cstring};
+
+/// The engine a [`Fixedpoint`] solves queries with, see
+/// [`Fixedpoint::set_engine()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FixedpointEngine {
+    /// Bottom-up Datalog evaluation, for relations over finite sorts such
+    /// as bit-vectors and finite domains.
+    Datalog,
+    /// The Spacer engine for constrained Horn clauses over arithmetic,
+    /// arrays, and other theories.
+    Spacer,
+}
+
+impl Fixedpoint {
+    unsafe fn wrap(ctx: Rc<Context>, z3_fp: Z3_fixedpoint) -> Fixedpoint {
+        Z3_fixedpoint_inc_ref(ctx.z3_ctx, z3_fp);
+        crate::track_live(ctx.z3_ctx, "Fixedpoint", 1);
+        Fixedpoint { ctx, z3_fp }
+    }
+
+    /// Create a new fixedpoint context.
+    ///
+    /// # Examples
+    ///
+    /// Reachability in a graph, in Datalog:
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Fixedpoint, FixedpointEngine, FuncDecl, SatResult, Sort};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let node = Sort::bitvector(ctx.clone(), 4);
+    /// let bool_sort = Sort::bool(ctx.clone());
+    /// let edge = FuncDecl::new(ctx.clone(), "edge", &[&node, &node], &bool_sort);
+    /// let path = FuncDecl::new(ctx.clone(), "path", &[&node, &node], &bool_sort);
+    ///
+    /// let fp = Fixedpoint::new(ctx.clone());
+    /// fp.set_engine(FixedpointEngine::Datalog);
+    /// fp.register_relation(&edge);
+    /// fp.register_relation(&path);
+    /// for (a, b) in [(1, 2), (2, 3), (5, 6)] {
+    ///     fp.add_fact(&edge, &[a, b]);
+    /// }
+    /// let x = ast::BV::new_const(ctx.clone(), "x", 4);
+    /// let y = ast::BV::new_const(ctx.clone(), "y", 4);
+    /// let z = ast::BV::new_const(ctx.clone(), "z", 4);
+    /// let path_xy = path.apply(&[&x, &y]).as_bool().unwrap();
+    /// let base = edge.apply(&[&x, &y]).as_bool().unwrap().implies(&path_xy);
+    /// fp.add_rule(&ast::forall_const(ctx.clone(), &[&x, &y], &[], &base), None);
+    /// let step = ast::Bool::and(ctx.clone(), &[
+    ///     path.apply(&[&x, &z]).as_bool().unwrap(),
+    ///     edge.apply(&[&z, &y]).as_bool().unwrap(),
+    /// ])
+    /// .implies(&path_xy);
+    /// fp.add_rule(&ast::forall_const(ctx.clone(), &[&x, &y, &z], &[], &step), None);
+    ///
+    /// let bv = |i| ast::BV::from_u64(ctx.clone(), i, 4);
+    /// let reachable = |a, b| fp.query(&path.apply(&[&bv(a), &bv(b)]).as_bool().unwrap());
+    /// assert_eq!(reachable(1, 3), SatResult::Sat);
+    /// assert_eq!(reachable(1, 6), SatResult::Unsat);
+    /// ```
+    pub fn new(ctx: Rc<Context>) -> Fixedpoint {
+        unsafe {
+            let fp = Z3_mk_fixedpoint(ctx.z3_ctx);
+            Self::wrap(ctx, fp)
+        }
+    }
+
+    /// Get this fixedpoint context's context.
+    pub fn get_context(&self) -> Rc<Context> {
+        self.ctx.clone()
+    }
+
+    /// Add a Horn clause, optionally named `name`.
+    ///
+    /// A rule is an atom, or an implication `body => head` whose head is
+    /// an atom, universally quantified over its variables with
+    /// [`ast::forall_const()`](crate::ast::forall_const). Constants that
+    /// are not bound this way stay constants.
+    pub fn add_rule(&self, rule: &Bool, name: Option<Symbol>) {
+        let name = name.unwrap_or_else(|| Symbol::from(""));
+        unsafe {
+            Z3_fixedpoint_add_rule(
+                self.ctx.z3_ctx,
+                self.z3_fp,
+                rule.get_z3_ast(),
+                name.as_z3_symbol(&self.ctx),
+            )
+        };
+    }
+
+    /// Add the fact `relation(args)`, where the arguments are the numeric
+    /// values of the elements of finite sorts, such as bit-vectors.
+    ///
+    /// # Panics
+    ///
+    /// If there is not one argument per parameter of `relation`.
+    pub fn add_fact(&self, relation: &FuncDecl, args: &[u32]) {
+        assert_eq!(args.len(), relation.arity(), "wrong number of arguments");
+        let mut args = args.to_vec();
+        unsafe {
+            Z3_fixedpoint_add_fact(
+                self.ctx.z3_ctx,
+                self.z3_fp,
+                relation.z3_func_decl,
+                c_len(args.len()),
+                args.as_mut_ptr(),
+            )
+        };
+    }
+
+    /// Assert a background constraint. It is used by Spacer, and ignored by
+    /// the Datalog engine.
+    pub fn assert(&self, axiom: &Bool) {
+        unsafe { Z3_fixedpoint_assert(self.ctx.z3_ctx, self.z3_fp, axiom.get_z3_ast()) };
+    }
+
+    /// Declare `relation` as a relation to be computed by this context.
+    pub fn register_relation(&self, relation: &FuncDecl) {
+        unsafe {
+            Z3_fixedpoint_register_relation(self.ctx.z3_ctx, self.z3_fp, relation.z3_func_decl)
+        };
+    }
+
+    /// Check whether `query`, an atom or a conjunction of literals,
+    /// optionally existentially quantified with
+    /// [`ast::exists_const()`](crate::ast::exists_const), can be derived
+    /// from the rules.
+    ///
+    /// Return [`SatResult::Sat`] if it can, and [`SatResult::Unsat`] if
+    /// it cannot.
+    ///
+    /// # Examples
+    ///
+    /// Safety of a loop `x := 0; while * { x := x + 2 }`, as constrained
+    /// Horn clauses for Spacer:
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Fixedpoint, FixedpointEngine, FuncDecl, SatResult, Sort};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let int = |i| ast::Int::from_i64(ctx.clone(), i);
+    /// let inv = FuncDecl::new(ctx.clone(), "inv", &[&Sort::int(ctx.clone())], &Sort::bool(ctx.clone()));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let inv_x = inv.apply(&[&x]).as_bool().unwrap();
+    ///
+    /// let fp = Fixedpoint::new(ctx.clone());
+    /// fp.set_engine(FixedpointEngine::Spacer);
+    /// fp.register_relation(&inv);
+    /// fp.add_rule(&inv.apply(&[&int(0)]).as_bool().unwrap(), None);
+    /// let step = inv_x.implies(&inv.apply(&[&(&x + &int(2))]).as_bool().unwrap());
+    /// fp.add_rule(&ast::forall_const(ctx.clone(), &[&x], &[], &step), None);
+    ///
+    /// let odd = ast::Int::modulo(&x, &int(2))._eq(&int(1));
+    /// let query = ast::Bool::and(ctx.clone(), &[inv_x.clone(), odd]);
+    /// assert_eq!(fp.query(&ast::exists_const(ctx.clone(), &[&x], &[], &query)), SatResult::Unsat);
+    /// assert_eq!(fp.query(&inv.apply(&[&int(6)]).as_bool().unwrap()), SatResult::Sat);
+    /// ```
+    pub fn query(&self, query: &Bool) -> SatResult {
+        let result =
+            unsafe { Z3_fixedpoint_query(self.ctx.z3_ctx, self.z3_fp, query.get_z3_ast()) };
+        sat_result(result)
+    }
+
+    /// Check whether any of the `relations` is non-empty.
+    pub fn query_relations(&self, relations: &[&FuncDecl]) -> SatResult {
+        let relations: Vec<Z3_func_decl> = relations.iter().map(|r| r.z3_func_decl).collect();
+        let result = unsafe {
+            Z3_fixedpoint_query_relations(
+                self.ctx.z3_ctx,
+                self.z3_fp,
+                c_len(relations.len()),
+                relations.as_ptr(),
+            )
+        };
+        sat_result(result)
+    }
+
+    /// Retrieve the answer to the last [`Fixedpoint::query()`].
+    ///
+    /// In Datalog mode, after a satisfiable query, this is a disjunction
+    /// with one conjunction of values for the variables of the query per
+    /// derivation. With Spacer, after an unsatisfiable query, it is a
+    /// conjunction that defines every relation by an inductive invariant,
+    /// and after a satisfiable query, it is a derivation of the query.
+    ///
+    /// Return `None` if the last query has no answer, e.g. because it
+    /// returned [`SatResult::Unknown`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Fixedpoint, SatResult};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let fp = Fixedpoint::new(ctx.clone());
+    /// let queries = fp
+    ///     .from_string(
+    ///         "(declare-rel inv (Int)) (declare-rel err ()) (declare-var x Int)
+    ///          (rule (inv 0))
+    ///          (rule (=> (and (inv x) (< x 10)) (inv (+ x 1))))
+    ///          (rule (=> (and (inv x) (> x 10)) err))
+    ///          (query err)",
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(fp.query(&queries[0]), SatResult::Unsat);
+    /// let invariant = fp.get_answer().unwrap();
+    /// assert!(invariant.to_string().contains("inv"));
+    /// ```
+    pub fn get_answer(&self) -> Option<ast::Dynamic> {
+        unsafe {
+            Z3_set_error(self.ctx.z3_ctx, ErrorCode::OK);
+            let answer = Z3_fixedpoint_get_answer(self.ctx.z3_ctx, self.z3_fp);
+            if answer.is_null() || Z3_get_error_code(self.ctx.z3_ctx) != ErrorCode::OK {
+                return None;
+            }
+            Some(ast::Dynamic::wrap(self.ctx.clone(), answer))
+        }
+    }
+
+    /// Retrieve the reason why the last [`Fixedpoint::query()`] returned
+    /// [`SatResult::Unknown`].
+    pub fn get_reason_unknown(&self) -> Option<String> {
+        let p = unsafe { Z3_fixedpoint_get_reason_unknown(self.ctx.z3_ctx, self.z3_fp) };
+        if p.is_null() {
+            return None;
+        }
+        unsafe { CStr::from_ptr(p) }
+            .to_str()
+            .ok()
+            .map(|s| s.to_string())
+    }
+
+    /// The number of unfoldings of `relation` that Spacer has explored so
+    /// far, i.e. the levels with a [cover](Fixedpoint::get_cover_delta).
+    pub fn get_num_levels(&self, relation: &FuncDecl) -> u32 {
+        unsafe { Z3_fixedpoint_get_num_levels(self.ctx.z3_ctx, self.z3_fp, relation.z3_func_decl) }
+    }
+
+    /// Retrieve the properties of `relation` that Spacer learned at `level`
+    /// unfoldings, with `None` for the properties of the fixedpoint itself.
+    ///
+    /// Each level only holds the properties that are new at that level:
+    /// the full over-approximation of `relation` at level `k` is the
+    /// conjunction of the covers at levels `k`, `k + 1`, ..., and `None`.
+    /// The cover is a formula over the variables `(:var 0)`, `(:var 1)`,
+    /// ... for the arguments of `relation`.
+    pub fn get_cover_delta(&self, level: Option<u32>, relation: &FuncDecl) -> Option<Bool> {
+        let level = level.map_or(-1, |l| l as i32);
+        unsafe {
+            Z3_set_error(self.ctx.z3_ctx, ErrorCode::OK);
+            let cover = Z3_fixedpoint_get_cover_delta(
+                self.ctx.z3_ctx,
+                self.z3_fp,
+                level,
+                relation.z3_func_decl,
+            );
+            if cover.is_null() || Z3_get_error_code(self.ctx.z3_ctx) != ErrorCode::OK {
+                return None;
+            }
+            Some(Bool::wrap(self.ctx.clone(), cover))
+        }
+    }
+
+    /// Configure the fixedpoint context, e.g. with `engine` or the options
+    /// of Spacer (`spacer.*`) and of the Datalog engine (`datalog.*`).
+    pub fn set_params(&self, params: &Params) {
+        unsafe { Z3_fixedpoint_set_params(self.ctx.z3_ctx, self.z3_fp, params.z3_params) };
+    }
+
+    /// Select the engine queries are solved with. By default, Z3 picks
+    /// one from the rules.
+    pub fn set_engine(&self, engine: FixedpointEngine) {
+        let mut params = Params::new(self.ctx.clone());
+        let engine = match engine {
+            FixedpointEngine::Datalog => "datalog",
+            FixedpointEngine::Spacer => "spacer",
+        };
+        params.set_symbol("engine", engine);
+        self.set_params(&params);
+    }
+
+    /// Parse SMT-LIB2 commands with rules, like `(declare-rel ...)` and
+    /// `(rule ...)`, add the rules to this context, and return the queries.
+    ///
+    /// Return an error if `source` cannot be parsed.
+    pub fn from_string(&self, source: &str) -> Result<Vec<Bool>, ParseError> {
+        let source =
+            try_cstring(source).map_err(|_| ParseError::new("input contains a NUL byte"))?;
+        unsafe {
+            Z3_set_error(self.ctx.z3_ctx, ErrorCode::OK);
+            let queries = Z3_fixedpoint_from_string(self.ctx.z3_ctx, self.z3_fp, source.as_ptr());
+            let code = Z3_get_error_code(self.ctx.z3_ctx);
+            if code != ErrorCode::OK || queries.is_null() {
+                let msg = CStr::from_ptr(Z3_get_error_msg(self.ctx.z3_ctx, code));
+                return Err(ParseError::from_z3_message(&msg.to_string_lossy()));
+            }
+            Ok(self.collect_bools(queries))
+        }
+    }
+
+    /// The rules added to this context.
+    pub fn get_rules(&self) -> Vec<Bool> {
+        unsafe { self.collect_bools(Z3_fixedpoint_get_rules(self.ctx.z3_ctx, self.z3_fp)) }
+    }
+
+    /// The background constraints added with [`Fixedpoint::assert()`].
+    pub fn get_assertions(&self) -> Vec<Bool> {
+        unsafe { self.collect_bools(Z3_fixedpoint_get_assertions(self.ctx.z3_ctx, self.z3_fp)) }
+    }
+
+    unsafe fn collect_bools(&self, z3_vec: Z3_ast_vector) -> Vec<Bool> {
+        Z3_ast_vector_inc_ref(self.ctx.z3_ctx, z3_vec);
+        let bools = (0..Z3_ast_vector_size(self.ctx.z3_ctx, z3_vec))
+            .map(|i| {
+                Bool::wrap(
+                    self.ctx.clone(),
+                    Z3_ast_vector_get(self.ctx.z3_ctx, z3_vec, i),
+                )
+            })
+            .collect();
+        Z3_ast_vector_dec_ref(self.ctx.z3_ctx, z3_vec);
+        bools
+    }
+
+    /// Retrieve the statistics for the last [`Fixedpoint::query()`].
+    pub fn get_statistics(&self) -> Statistics {
+        unsafe {
+            Statistics::wrap(
+                self.ctx.clone(),
+                Z3_fixedpoint_get_statistics(self.ctx.z3_ctx, self.z3_fp),
+            )
+        }
+    }
+
+    /// Render the rules and `queries` as SMT-LIB2 commands that
+    /// [`Fixedpoint::from_string()`] reads back.
+    pub fn to_smtlib2(&self, queries: &[Bool]) -> String {
+        let mut queries: Vec<Z3_ast> = queries.iter().map(|q| q.get_z3_ast()).collect();
+        let p = unsafe {
+            Z3_fixedpoint_to_string(
+                self.ctx.z3_ctx,
+                self.z3_fp,
+                c_len(queries.len()),
+                if queries.is_empty() {
+                    ptr::null_mut()
+                } else {
+                    queries.as_mut_ptr()
+                },
+            )
+        };
+        if p.is_null() {
+            return String::new();
+        }
+        unsafe { CStr::from_ptr(p) }.to_string_lossy().into_owned()
+    }
+}
+
+fn sat_result(result: Z3_lbool) -> SatResult {
+    match result {
+        Z3_L_FALSE => SatResult::Unsat,
+        Z3_L_UNDEF => SatResult::Unknown,
+        Z3_L_TRUE => SatResult::Sat,
+        _ => unreachable!(),
+    }
+}
+
+impl fmt::Display for Fixedpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.to_smtlib2(&[]))
+    }
+}
+
+impl fmt::Debug for Fixedpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        <Self as fmt::Display>::fmt(self, f)
+    }
+}
+
+impl Drop for Fixedpoint {
+    fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "Fixedpoint", -1);
+        unsafe { Z3_fixedpoint_dec_ref(self.ctx.z3_ctx, self.z3_fp) };
+    }
+}
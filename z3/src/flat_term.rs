@@ -0,0 +1,540 @@
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use num::bigint::BigInt;
+use z3_sys::*;
+
+use crate::ast::{Ast, AstNode, Dynamic};
+use crate::conversion::{c_len, cstring};
+use crate::Rc;
+use crate::{Context, FuncDecl, Sort};
+
+/// A node of a [`FlatTerm`]: an operator applied to earlier nodes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FlatNode {
+    pub op: String,
+    pub children: Vec<usize>,
+}
+
+/// A term as a list of nodes, each of which refers to its children by
+/// their position in the list, so that children come before their parents
+/// and the last node is the root.
+///
+/// This is the layout of e-graph libraries' term representations, such
+/// as `egg`'s `RecExpr`. Like them, a `FlatTerm` prints as, and parses
+/// from, an s-expression without `let` bindings, and identical subterms
+/// are stored once.
+///
+/// Operators are the SMT-LIB names of function declarations, with the
+/// indices of indexed ones, as in `(_ extract 3 0)`, and numerals are atoms
+/// such as `-3`, `1/3`, `2.5` and `#x0f`. Terms of the Boolean, arithmetic
+/// and bitvector theories and uninterpreted functions convert back and
+/// forth without loss; quantifiers, bound variables, floating-point
+/// numerals and operators with parameters other than integers cannot be
+/// flattened.
+///
+/// With the `egg` feature, a `FlatTerm` converts from an
+/// [`egg::RecExpr`](https://docs.rs/egg) of any language whose nodes print
+/// as their operators, and into one of a language that parses them with
+/// `egg::FromOp`, such as `egg::SymbolLang`, node by node.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, FlatTerm, FuncDecl, Sort};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let sum = &x + ast::Int::from_i64(ctx.clone(), -3);
+/// let term = &sum * &sum;
+///
+/// let flat = FlatTerm::from_ast(&term).unwrap();
+/// assert_eq!(flat.nodes().len(), 4);
+/// assert_eq!(flat.to_string(), "(* (+ x -3) (+ x -3))");
+///
+/// // Parsed terms refer to constants and functions by name.
+/// let parsed: FlatTerm = "(* (+ x -3) (+ x -3))".parse().unwrap();
+/// assert_eq!(parsed, flat);
+/// let back = parsed.to_ast(ctx.clone(), &[x.decl()]).unwrap();
+/// assert_eq!(back, ast::Dynamic::from_ast(&term));
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct FlatTerm {
+    nodes: Vec<FlatNode>,
+    index: HashMap<FlatNode, usize>,
+}
+
+impl FlatTerm {
+    /// Create an empty `FlatTerm`.
+    pub fn new() -> FlatTerm {
+        FlatTerm::default()
+    }
+
+    /// Add a node, unless it is already present, and return its position.
+    ///
+    /// # Panics
+    ///
+    /// If a child does not refer to an earlier node.
+    pub fn add(&mut self, op: &str, children: &[usize]) -> usize {
+        assert!(
+            children.iter().all(|&c| c < self.nodes.len()),
+            "children must come before their parents"
+        );
+        let node = FlatNode {
+            op: op.to_owned(),
+            children: children.to_vec(),
+        };
+        if let Some(&i) = self.index.get(&node) {
+            return i;
+        }
+        self.nodes.push(node.clone());
+        self.index.insert(node, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    /// The nodes in order, so that the last one is the root.
+    pub fn nodes(&self) -> &[FlatNode] {
+        &self.nodes
+    }
+
+    /// Flatten `ast`.
+    ///
+    /// Return an error if `ast` contains a quantifier, a bound variable, a
+    /// floating-point numeral or an operator with parameters other than
+    /// integers, such as the `const` of a constant array.
+    pub fn from_ast(ast: &dyn Ast) -> Result<FlatTerm, String> {
+        let mut flat = FlatTerm::new();
+        let mut done: HashMap<Z3_ast, usize> = HashMap::new();
+        let mut stack = vec![(Dynamic::from_ast(ast), false)];
+        while let Some((a, expanded)) = stack.pop() {
+            if done.contains_key(&a.z3_ast) {
+                continue;
+            }
+            let (op, args) = match a.node() {
+                AstNode::Numeral { value, sort } => match sort.kind() {
+                    SortKind::Int | SortKind::Real => (value, vec![]),
+                    SortKind::BV => (a.to_string(), vec![]),
+                    _ => return Err(format!("cannot flatten the numeral {}", a)),
+                },
+                AstNode::App { decl, args } => match op_name(&decl) {
+                    Some(op) => (op, args),
+                    None => return Err(format!("cannot flatten the operator of {}", a)),
+                },
+                AstNode::Var { .. } | AstNode::Quantifier { .. } => {
+                    return Err(format!("cannot flatten {}", a));
+                }
+            };
+            if !expanded {
+                stack.push((a, true));
+                // Reversed, so that children are numbered from left to right.
+                stack.extend(args.into_iter().rev().map(|c| (c, false)));
+                continue;
+            }
+            let children: Vec<usize> = args.iter().map(|c| done[&c.z3_ast]).collect();
+            let i = flat.add(&op, &children);
+            done.insert(a.z3_ast, i);
+        }
+        Ok(flat)
+    }
+
+    /// Build the term in `ctx`.
+    ///
+    /// Atoms other than numerals, `true` and `false`, and operators other
+    /// than those of the Boolean, arithmetic and bitvector theories, are
+    /// looked up by name and arity in `decls`.
+    ///
+    /// Return an error if the term is empty, an atom or operator is
+    /// unknown, or the sorts of some arguments do not fit their operator.
+    pub fn to_ast(&self, ctx: Rc<Context>, decls: &[FuncDecl]) -> Result<Dynamic, String> {
+        if self.nodes.is_empty() {
+            return Err("the term is empty".to_owned());
+        }
+        let mut built: Vec<Dynamic> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let args: Vec<Z3_ast> = node.children.iter().map(|&c| built[c].z3_ast).collect();
+            let a = build(&ctx, &node.op, &args, decls)?;
+            built.push(a);
+        }
+        Ok(built.pop().unwrap())
+    }
+
+    fn fmt_node(&self, i: usize, f: &mut fmt::Formatter) -> fmt::Result {
+        let node = &self.nodes[i];
+        if node.children.is_empty() {
+            return write!(f, "{}", node.op);
+        }
+        write!(f, "({}", node.op)?;
+        for &c in &node.children {
+            write!(f, " ")?;
+            self.fmt_node(c, f)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Display for FlatTerm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.nodes.len() {
+            0 => Ok(()),
+            n => self.fmt_node(n - 1, f),
+        }
+    }
+}
+
+impl fmt::Debug for FlatTerm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FlatTerm")
+            .field("nodes", &self.nodes)
+            .finish()
+    }
+}
+
+/// Convert an `egg` term node by node, with the operator each node prints
+/// as. Its nodes must refer to earlier nodes, as those of a `RecExpr` built
+/// with `RecExpr::add()` do.
+///
+/// # Examples
+///
+/// ```
+/// # use std::convert::TryFrom;
+/// # use std::rc::Rc;
+/// # use egg::{RecExpr, SymbolLang};
+/// # use z3::{ast, ast::Ast, Config, Context, FlatTerm};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let term = &x * ast::Int::from_i64(ctx.clone(), 2);
+///
+/// let flat = FlatTerm::from_ast(&term).unwrap();
+/// let expr = RecExpr::<SymbolLang>::try_from(&flat).unwrap();
+/// assert_eq!(expr.to_string(), "(* x 2)");
+///
+/// let back = FlatTerm::from(&expr).to_ast(ctx, &[x.decl()]).unwrap();
+/// assert_eq!(back, ast::Dynamic::from_ast(&term));
+/// ```
+#[cfg(feature = "egg")]
+impl<L: egg::Language + fmt::Display> From<&egg::RecExpr<L>> for FlatTerm {
+    fn from(expr: &egg::RecExpr<L>) -> FlatTerm {
+        let mut flat = FlatTerm::new();
+        // `FlatTerm::add()` stores identical nodes once, so the positions
+        // can differ from those in `expr`.
+        let mut positions = Vec::with_capacity(expr.as_ref().len());
+        for node in expr.as_ref() {
+            let children: Vec<usize> = node
+                .children()
+                .iter()
+                .map(|&c| positions[usize::from(c)])
+                .collect();
+            positions.push(flat.add(&node.to_string(), &children));
+        }
+        flat
+    }
+}
+
+/// Build an `egg` term node by node, parsing each operator with
+/// `egg::FromOp`. See the `From<&RecExpr>` implementation for an example.
+#[cfg(feature = "egg")]
+impl<L: egg::FromOp> std::convert::TryFrom<&FlatTerm> for egg::RecExpr<L> {
+    type Error = L::Error;
+
+    fn try_from(flat: &FlatTerm) -> Result<egg::RecExpr<L>, L::Error> {
+        let mut expr = egg::RecExpr::default();
+        for node in flat.nodes() {
+            let children = node.children.iter().map(|&c| egg::Id::from(c)).collect();
+            expr.add(L::from_op(&node.op, children)?);
+        }
+        Ok(expr)
+    }
+}
+
+impl FromStr for FlatTerm {
+    type Err = String;
+
+    /// Parse an s-expression, such as the output of `egg`'s `RecExpr`.
+    fn from_str(s: &str) -> Result<FlatTerm, String> {
+        let mut flat = FlatTerm::new();
+        // Every open parenthesis starts a list of an operator and children.
+        let mut lists: Vec<(String, Vec<usize>)> = vec![];
+        let mut root = None;
+        let mut tokens = tokenize(s).into_iter();
+        while let Some(token) = tokens.next() {
+            if root.is_some() {
+                return Err(format!("unexpected `{}` after the term", token));
+            }
+            let done = match token {
+                "(" => {
+                    match tokens.next() {
+                        Some("(") => lists.push((indexed(&mut tokens)?, vec![])),
+                        Some(op) if op != ")" => lists.push((op.to_owned(), vec![])),
+                        _ => return Err("expected an operator after `(`".to_owned()),
+                    }
+                    continue;
+                }
+                ")" => match lists.pop() {
+                    Some((op, children)) => flat.add(&op, &children),
+                    None => return Err("unbalanced `)`".to_owned()),
+                },
+                atom => flat.add(atom, &[]),
+            };
+            match lists.last_mut() {
+                Some((_, children)) => children.push(done),
+                None => root = Some(done),
+            }
+        }
+        match root {
+            Some(_) if lists.is_empty() => Ok(flat),
+            _ => Err("unexpected end of input".to_owned()),
+        }
+    }
+}
+
+/// Read an indexed operator such as `(_ extract 3 0)` after its `(`.
+fn indexed<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<String, String> {
+    if tokens.next() != Some("_") {
+        return Err("expected `_` after `((`".to_owned());
+    }
+    let mut op = "(_".to_owned();
+    loop {
+        match tokens.next() {
+            Some(")") => break,
+            Some("(") | None => return Err("unterminated indexed operator".to_owned()),
+            Some(t) => {
+                op.push(' ');
+                op.push_str(t);
+            }
+        }
+    }
+    op.push(')');
+    Ok(op)
+}
+
+/// The operator of `decl` as it appears in a [`FlatTerm`], or `None` if it
+/// has parameters other than integers.
+fn op_name(decl: &FuncDecl) -> Option<String> {
+    let (z3_ctx, d) = (decl.ctx.z3_ctx, decl.z3_func_decl);
+    let n = unsafe { Z3_get_decl_num_parameters(z3_ctx, d) };
+    if n == 0 {
+        return Some(decl.name());
+    }
+    let mut op = format!("(_ {}", decl.name());
+    for i in 0..n {
+        if unsafe { Z3_get_decl_parameter_kind(z3_ctx, d, i) } != ParameterKind::Int {
+            return None;
+        }
+        let index = unsafe { Z3_get_decl_int_parameter(z3_ctx, d, i) };
+        write!(op, " {}", index).unwrap();
+    }
+    op.push(')');
+    Some(op)
+}
+
+fn tokenize(s: &str) -> Vec<&str> {
+    let mut tokens = vec![];
+    let mut start = None;
+    for (i, c) in s.char_indices() {
+        if c == '(' || c == ')' || c.is_whitespace() {
+            if let Some(j) = start.take() {
+                tokens.push(&s[j..i]);
+            }
+            if !c.is_whitespace() {
+                tokens.push(&s[i..i + 1]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(j) = start {
+        tokens.push(&s[j..]);
+    }
+    tokens
+}
+
+/// Build the application of `op` to `args`, catching the errors Z3 reports
+/// for ill-sorted arguments.
+fn build(
+    ctx: &Rc<Context>,
+    op: &str,
+    args: &[Z3_ast],
+    decls: &[FuncDecl],
+) -> Result<Dynamic, String> {
+    let z3_ctx = ctx.z3_ctx;
+    let n = c_len(args.len());
+    // The name and indices of an indexed operator.
+    let indexed = match op.strip_prefix("(_ ").and_then(|s| s.strip_suffix(')')) {
+        Some(inner) => {
+            let mut words = inner.split(' ');
+            let name = words.next().unwrap_or_default();
+            let indices = words
+                .map(|w| w.parse::<u32>())
+                .collect::<Result<Vec<u32>, _>>()
+                .map_err(|_| format!("bad indices in `{}`", op))?;
+            Some((name, indices))
+        }
+        None => None,
+    };
+    if let Some(k) = builtin_arity(op) {
+        if args.len() != k {
+            return Err(format!(
+                "`{}` takes {} arguments, not {}",
+                op,
+                k,
+                args.len()
+            ));
+        }
+    }
+    unsafe {
+        Z3_set_error(z3_ctx, ErrorCode::OK);
+        let a = match (op, args) {
+            ("true", []) => Z3_mk_true(z3_ctx),
+            ("false", []) => Z3_mk_false(z3_ctx),
+            (_, []) if numeral_sort(ctx, op).is_some() => {
+                let sort = numeral_sort(ctx, op).unwrap();
+                let value = match (op.strip_prefix("#x"), op.strip_prefix("#b")) {
+                    (Some(hex), _) => BigInt::parse_bytes(hex.as_bytes(), 16).unwrap().to_string(),
+                    (_, Some(bin)) => BigInt::parse_bytes(bin.as_bytes(), 2).unwrap().to_string(),
+                    _ => op.to_owned(),
+                };
+                let value = cstring(value);
+                Z3_mk_numeral(z3_ctx, value.as_ptr(), sort.z3_sort)
+            }
+            ("and", _) => Z3_mk_and(z3_ctx, n, args.as_ptr()),
+            ("or", _) => Z3_mk_or(z3_ctx, n, args.as_ptr()),
+            ("distinct", _) => Z3_mk_distinct(z3_ctx, n, args.as_ptr()),
+            ("+", _) => Z3_mk_add(z3_ctx, n, args.as_ptr()),
+            ("*", _) => Z3_mk_mul(z3_ctx, n, args.as_ptr()),
+            ("-", [a]) => Z3_mk_unary_minus(z3_ctx, *a),
+            ("-", [_, _, ..]) => Z3_mk_sub(z3_ctx, n, args.as_ptr()),
+            ("not", [a]) => Z3_mk_not(z3_ctx, *a),
+            ("xor", [a, b]) => Z3_mk_xor(z3_ctx, *a, *b),
+            ("=>", [a, b]) => Z3_mk_implies(z3_ctx, *a, *b),
+            ("=", [a, b]) => Z3_mk_eq(z3_ctx, *a, *b),
+            ("ite", [c, t, e]) => Z3_mk_ite(z3_ctx, *c, *t, *e),
+            ("/", [a, b]) | ("div", [a, b]) => Z3_mk_div(z3_ctx, *a, *b),
+            ("mod", [a, b]) => Z3_mk_mod(z3_ctx, *a, *b),
+            ("rem", [a, b]) => Z3_mk_rem(z3_ctx, *a, *b),
+            ("^", [a, b]) => Z3_mk_power(z3_ctx, *a, *b),
+            ("<", [a, b]) => Z3_mk_lt(z3_ctx, *a, *b),
+            ("<=", [a, b]) => Z3_mk_le(z3_ctx, *a, *b),
+            (">", [a, b]) => Z3_mk_gt(z3_ctx, *a, *b),
+            (">=", [a, b]) => Z3_mk_ge(z3_ctx, *a, *b),
+            ("to_real", [a]) => Z3_mk_int2real(z3_ctx, *a),
+            ("to_int", [a]) => Z3_mk_real2int(z3_ctx, *a),
+            ("is_int", [a]) => Z3_mk_is_int(z3_ctx, *a),
+            ("bvnot", [a]) => Z3_mk_bvnot(z3_ctx, *a),
+            ("bvneg", [a]) => Z3_mk_bvneg(z3_ctx, *a),
+            ("bvand", [a, b]) => Z3_mk_bvand(z3_ctx, *a, *b),
+            ("bvor", [a, b]) => Z3_mk_bvor(z3_ctx, *a, *b),
+            ("bvxor", [a, b]) => Z3_mk_bvxor(z3_ctx, *a, *b),
+            ("bvadd", [a, b]) => Z3_mk_bvadd(z3_ctx, *a, *b),
+            ("bvsub", [a, b]) => Z3_mk_bvsub(z3_ctx, *a, *b),
+            ("bvmul", [a, b]) => Z3_mk_bvmul(z3_ctx, *a, *b),
+            ("bvudiv", [a, b]) => Z3_mk_bvudiv(z3_ctx, *a, *b),
+            ("bvsdiv", [a, b]) => Z3_mk_bvsdiv(z3_ctx, *a, *b),
+            ("bvurem", [a, b]) => Z3_mk_bvurem(z3_ctx, *a, *b),
+            ("bvsrem", [a, b]) => Z3_mk_bvsrem(z3_ctx, *a, *b),
+            ("bvshl", [a, b]) => Z3_mk_bvshl(z3_ctx, *a, *b),
+            ("bvlshr", [a, b]) => Z3_mk_bvlshr(z3_ctx, *a, *b),
+            ("bvashr", [a, b]) => Z3_mk_bvashr(z3_ctx, *a, *b),
+            ("bvult", [a, b]) => Z3_mk_bvult(z3_ctx, *a, *b),
+            ("bvule", [a, b]) => Z3_mk_bvule(z3_ctx, *a, *b),
+            ("bvugt", [a, b]) => Z3_mk_bvugt(z3_ctx, *a, *b),
+            ("bvuge", [a, b]) => Z3_mk_bvuge(z3_ctx, *a, *b),
+            ("bvslt", [a, b]) => Z3_mk_bvslt(z3_ctx, *a, *b),
+            ("bvsle", [a, b]) => Z3_mk_bvsle(z3_ctx, *a, *b),
+            ("bvsgt", [a, b]) => Z3_mk_bvsgt(z3_ctx, *a, *b),
+            ("bvsge", [a, b]) => Z3_mk_bvsge(z3_ctx, *a, *b),
+            ("bvnand", [a, b]) => Z3_mk_bvnand(z3_ctx, *a, *b),
+            ("bvnor", [a, b]) => Z3_mk_bvnor(z3_ctx, *a, *b),
+            ("bvxnor", [a, b]) => Z3_mk_bvxnor(z3_ctx, *a, *b),
+            ("bvsmod", [a, b]) => Z3_mk_bvsmod(z3_ctx, *a, *b),
+            ("bv2int", [a]) => Z3_mk_bv2int(z3_ctx, *a, false),
+            ("concat", [a, b]) => Z3_mk_concat(z3_ctx, *a, *b),
+            _ if indexed.is_some() => {
+                let (f, indices) = indexed.as_ref().unwrap();
+                match (*f, &indices[..], args) {
+                    ("extract", [high, low], [a]) => Z3_mk_extract(z3_ctx, *high, *low, *a),
+                    ("zero_extend", [i], [a]) => Z3_mk_zero_ext(z3_ctx, *i, *a),
+                    ("sign_extend", [i], [a]) => Z3_mk_sign_ext(z3_ctx, *i, *a),
+                    ("repeat", [i], [a]) => Z3_mk_repeat(z3_ctx, *i, *a),
+                    ("rotate_left", [i], [a]) => Z3_mk_rotate_left(z3_ctx, *i, *a),
+                    ("rotate_right", [i], [a]) => Z3_mk_rotate_right(z3_ctx, *i, *a),
+                    ("int2bv", [n], [a]) => Z3_mk_int2bv(z3_ctx, *n, *a),
+                    _ => {
+                        return Err(format!(
+                            "unknown operator `{}` with {} arguments",
+                            op,
+                            args.len()
+                        ))
+                    }
+                }
+            }
+            _ => match decls
+                .iter()
+                .find(|d| d.arity() == args.len() && d.name() == op)
+            {
+                Some(d) => Z3_mk_app(z3_ctx, d.z3_func_decl, n, args.as_ptr()),
+                None => {
+                    return Err(format!(
+                        "unknown operator `{}` with {} arguments",
+                        op,
+                        args.len()
+                    ))
+                }
+            },
+        };
+        let code = Z3_get_error_code(z3_ctx);
+        if code != ErrorCode::OK || a.is_null() {
+            let msg = CStr::from_ptr(Z3_get_error_msg(z3_ctx, code));
+            return Err(format!("cannot apply `{}`: {}", op, msg.to_string_lossy()));
+        }
+        Ok(Dynamic::wrap(ctx.clone(), a))
+    }
+}
+
+/// The number of arguments of the built-in operator `op` if it is fixed.
+fn builtin_arity(op: &str) -> Option<usize> {
+    match op {
+        "not" | "to_real" | "to_int" | "is_int" | "bvnot" | "bvneg" | "bv2int" => Some(1),
+        "xor" | "=>" | "=" | "/" | "div" | "mod" | "rem" | "^" | "<" | "<=" | ">" | ">="
+        | "concat" => Some(2),
+        "ite" => Some(3),
+        _ if op.starts_with("bv")
+            && op.len() > 2
+            && op[2..].chars().all(|c| c.is_ascii_lowercase()) =>
+        {
+            Some(2)
+        }
+        _ => None,
+    }
+}
+
+/// The sort of the numeral atom `op`, if it is one.
+fn numeral_sort(ctx: &Rc<Context>, op: &str) -> Option<Sort> {
+    let digits = |s: &str, radix: u32| !s.is_empty() && s.chars().all(|c| c.is_digit(radix));
+    if let Some(hex) = op.strip_prefix("#x") {
+        return if digits(hex, 16) {
+            Some(Sort::bitvector(ctx.clone(), 4 * hex.len() as u32))
+        } else {
+            None
+        };
+    }
+    if let Some(bin) = op.strip_prefix("#b") {
+        return if digits(bin, 2) {
+            Some(Sort::bitvector(ctx.clone(), bin.len() as u32))
+        } else {
+            None
+        };
+    }
+    let unsigned = op.strip_prefix('-').unwrap_or(op);
+    if digits(unsigned, 10) {
+        return Some(Sort::int(ctx.clone()));
+    }
+    let mut parts = unsigned.splitn(2, ['.', '/']);
+    match (parts.next(), parts.next()) {
+        (Some(a), Some(b)) if digits(a, 10) && digits(b, 10) => Some(Sort::real(ctx.clone())),
+        _ => None,
+    }
+}
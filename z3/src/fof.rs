@@ -0,0 +1,175 @@
+//! First-order logic over a single sort, in the style of TPTP's FOF
+//! (first-order form).
+//!
+//! Everything lives in one uninterpreted sort `U`: a [`Fof`] declares
+//! predicates and functions over `U` the first time they are used, with
+//! the arity they are used with, so there are no sorts to declare.
+//! [`Fof::prove()`] then checks whether a conjecture follows from the
+//! axioms.
+//!
+//! # Examples
+//!
+//! ```
+//! # use std::rc::Rc;
+//! # use z3::{Config, Context};
+//! use z3::fof::{Fof, Status};
+//!
+//! let cfg = Config::new();
+//! let ctx = Rc::new(Context::new(&cfg));
+//! let fof = Fof::new(ctx.clone());
+//! let x = fof.var("X");
+//! let socrates = fof.constant("socrates");
+//!
+//! let axioms = [
+//!     fof.forall(&[&x], &fof.pred("human", &[&x]).implies(&fof.pred("mortal", &[&x]))),
+//!     fof.pred("human", &[&socrates]),
+//! ];
+//! let conjecture = fof.pred("mortal", &[&socrates]);
+//! assert!(matches!(fof.prove(&axioms, &conjecture), Status::Theorem));
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, FuncDecl, Model, SatResult, Solver, Sort, Symbol};
+
+/// A builder for first-order formulas over the sort `U`, see the
+/// [module documentation](self).
+#[derive(Debug)]
+pub struct Fof {
+    ctx: Rc<Context>,
+    universe: Sort,
+    // The predicates and functions used so far, and whether they are
+    // predicates.
+    symbols: RefCell<HashMap<String, (FuncDecl, bool)>>,
+}
+
+/// The result of [`Fof::prove()`], named after the SZS statuses that
+/// first-order provers report.
+#[derive(Debug)]
+pub enum Status {
+    /// The conjecture follows from the axioms.
+    Theorem,
+    /// The axioms and the negated conjecture have the given model.
+    CounterSatisfiable(Model),
+    /// Z3 could not decide the conjecture, e.g. because quantifier
+    /// instantiation did not terminate within the timeout.
+    Unknown,
+}
+
+impl Fof {
+    pub fn new(ctx: Rc<Context>) -> Fof {
+        Fof {
+            universe: Sort::uninterpreted(ctx.clone(), Symbol::from("U")),
+            ctx,
+            symbols: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The sort `U` of all terms.
+    pub fn universe(&self) -> &Sort {
+        &self.universe
+    }
+
+    /// A constant of sort `U`.
+    pub fn constant(&self, name: &str) -> ast::Dynamic {
+        ast::Dynamic::new_const(self.ctx.clone(), name, &self.universe)
+    }
+
+    /// A variable of sort `U`, to bind with [`Fof::forall()`] or
+    /// [`Fof::exists()`]. In Z3, this is the same term as a constant with
+    /// that name.
+    pub fn var(&self, name: &str) -> ast::Dynamic {
+        self.constant(name)
+    }
+
+    /// Apply the function `name` to `args`, declaring it with their number
+    /// on its first use.
+    ///
+    /// # Panics
+    ///
+    /// If `name` was used with a different number of arguments, or as a
+    /// predicate.
+    pub fn func(&self, name: &str, args: &[&ast::Dynamic]) -> ast::Dynamic {
+        self.apply(name, args, false)
+    }
+
+    /// Apply the predicate `name` to `args`, like [`Fof::func()`].
+    ///
+    /// # Panics
+    ///
+    /// If `name` was used with a different number of arguments, or as a
+    /// function.
+    pub fn pred(&self, name: &str, args: &[&ast::Dynamic]) -> ast::Bool {
+        self.apply(name, args, true).as_bool().unwrap()
+    }
+
+    fn apply(&self, name: &str, args: &[&ast::Dynamic], is_pred: bool) -> ast::Dynamic {
+        let mut symbols = self.symbols.borrow_mut();
+        let (decl, was_pred) = symbols.entry(name.to_owned()).or_insert_with(|| {
+            let domain = vec![&self.universe; args.len()];
+            let range = if is_pred {
+                Sort::bool(self.ctx.clone())
+            } else {
+                self.universe.clone()
+            };
+            (
+                FuncDecl::new(self.ctx.clone(), name, &domain, &range),
+                is_pred,
+            )
+        });
+        assert!(
+            *was_pred == is_pred && decl.arity() == args.len(),
+            "{} was used as a {} of arity {}",
+            name,
+            if *was_pred { "predicate" } else { "function" },
+            decl.arity()
+        );
+        let args: Vec<&dyn Ast> = args.iter().map(|a| *a as &dyn Ast).collect();
+        decl.apply(&args)
+    }
+
+    /// `∀ vars. body`.
+    pub fn forall(&self, vars: &[&ast::Dynamic], body: &ast::Bool) -> ast::Bool {
+        let vars: Vec<&dyn Ast> = vars.iter().map(|v| *v as &dyn Ast).collect();
+        ast::forall_const(self.ctx.clone(), &vars, &[], body)
+    }
+
+    /// `∃ vars. body`.
+    pub fn exists(&self, vars: &[&ast::Dynamic], body: &ast::Bool) -> ast::Bool {
+        let vars: Vec<&dyn Ast> = vars.iter().map(|v| *v as &dyn Ast).collect();
+        ast::exists_const(self.ctx.clone(), &vars, &[], body)
+    }
+
+    /// `f_1 => (f_2 => ... => f_n)`: the last formula holds if all the
+    /// others do.
+    ///
+    /// # Panics
+    ///
+    /// If `formulas` is empty.
+    pub fn implication_chain(&self, formulas: &[&ast::Bool]) -> ast::Bool {
+        let (last, premises) = formulas.split_last().expect("empty implication chain");
+        premises
+            .iter()
+            .rev()
+            .fold((*last).clone(), |rest, p| p.implies(&rest))
+    }
+
+    /// Check whether `conjecture` follows from `axioms`.
+    pub fn prove(&self, axioms: &[ast::Bool], conjecture: &ast::Bool) -> Status {
+        let solver = Solver::new(self.ctx.clone());
+        for a in axioms {
+            solver.assert(a);
+        }
+        solver.assert(&!conjecture.clone());
+        match solver.check() {
+            SatResult::Unsat => Status::Theorem,
+            SatResult::Sat => match solver.get_model() {
+                Some(model) => Status::CounterSatisfiable(model),
+                None => Status::Unknown,
+            },
+            SatResult::Unknown => Status::Unknown,
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::ast::{Ast, Dynamic};
+use crate::{Model, Symbol, Z3Value};
+
+/// A Rust type whose values can be read from the constants of a [`Model`].
+///
+/// With the `derive` feature, `#[derive(FromModel)]` implements it for
+/// structs with named fields of [`Z3Value`] types: each field is read from
+/// the constant of the same name, or of the name given with
+/// `#[z3(name = "...")]`, with [`Model::get_const_value()`].
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, FromModel, Model, SatResult, Solver};
+/// #[derive(Debug, PartialEq)]
+/// # #[cfg_attr(feature = "derive", derive(FromModel))]
+/// struct Plan {
+///     start: i64,
+///     end: i64,
+///     rushed: bool,
+/// }
+/// # #[cfg(not(feature = "derive"))]
+/// # impl FromModel for Plan {
+/// #     fn from_model(model: &Model) -> Option<Self> {
+/// #         Some(Plan {
+/// #             start: model.get_const_value("start")?,
+/// #             end: model.get_const_value("end")?,
+/// #             rushed: model.get_const_value("rushed")?,
+/// #         })
+/// #     }
+/// # }
+///
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let start = ast::Int::new_const(ctx.clone(), "start");
+/// let end = ast::Int::new_const(ctx.clone(), "end");
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&start._eq(&ast::Int::from_i64(ctx.clone(), 9)));
+/// solver.assert(&end._eq(&(start + 2i64)));
+/// assert_eq!(solver.check(), SatResult::Sat);
+///
+/// // `rushed` is not constrained, and gets a default value.
+/// let model = solver.get_model().unwrap();
+/// assert_eq!(
+///     Plan::from_model(&model),
+///     Some(Plan { start: 9, end: 11, rushed: false })
+/// );
+/// ```
+pub trait FromModel: Sized {
+    /// The value of `Self` in `model`, or `None` if a constant cannot be
+    /// decoded.
+    fn from_model(model: &Model) -> Option<Self>;
+}
+
+impl Model {
+    /// The Rust value of the constant `name` of the sort of `T` in this
+    /// model, evaluated with model completion, or `None` if it cannot be
+    /// decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&x._eq(&ast::Int::from_i64(ctx.clone(), 300)));
+    /// assert_eq!(solver.check(), SatResult::Sat);
+    ///
+    /// let model = solver.get_model().unwrap();
+    /// assert_eq!(model.get_const_value::<u16, _>("x"), Some(300));
+    /// assert_eq!(model.get_const_value::<u8, _>("x"), None);
+    /// ```
+    pub fn get_const_value<T: Z3Value, S: Into<Symbol>>(&self, name: S) -> Option<T> {
+        let sort = T::sort(self.ctx.clone());
+        let constant = Dynamic::new_const(self.ctx.clone(), name, &sort);
+        T::from_model(self, &constant as &dyn Ast)
+    }
+}
@@ -1,33 +1,65 @@
-use std::convert::TryInto;
 use std::ffi::CStr;
 use std::fmt;
-use std::rc::Rc;
 
 use z3_sys::*;
 
+use crate::conversion::{c_len, try_c_len, ConversionError};
+use crate::Rc;
 use crate::{ast, ast::Ast, Context, FuncDecl, Sort, Symbol};
 
 impl FuncDecl {
     pub(crate) unsafe fn wrap(ctx: Rc<Context>, z3_func_decl: Z3_func_decl) -> Self {
         Z3_inc_ref(ctx.z3_ctx, Z3_func_decl_to_ast(ctx.z3_ctx, z3_func_decl));
+        crate::track_live(ctx.z3_ctx, "FuncDecl", 1);
         Self { ctx, z3_func_decl }
     }
 
     pub fn new<S: Into<Symbol>>(ctx: Rc<Context>, name: S, domain: &[&Sort], range: &Sort) -> Self {
+        match Self::try_new(ctx, name, domain, range) {
+            Ok(f) => f,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Like [`FuncDecl::new()`], but return an error instead of panicking if
+    /// `name` contains a NUL byte or `domain` is too long for Z3.
+    pub fn try_new<S: Into<Symbol>>(
+        ctx: Rc<Context>,
+        name: S,
+        domain: &[&Sort],
+        range: &Sort,
+    ) -> Result<Self, ConversionError> {
         assert!(domain.iter().all(|s| s.ctx.z3_ctx == ctx.z3_ctx));
         assert_eq!(ctx.z3_ctx, range.ctx.z3_ctx);
 
         let domain: Vec<_> = domain.iter().map(|s| s.z3_sort).collect();
+        let name = name.into().try_as_z3_symbol(&ctx)?;
 
         unsafe {
             let func_decl = Z3_mk_func_decl(
                 ctx.z3_ctx,
-                name.into().as_z3_symbol(&ctx),
-                domain.len().try_into().unwrap(),
+                name,
+                try_c_len(domain.len())?,
                 domain.as_ptr(),
                 range.z3_sort,
             );
-            Self::wrap(ctx, func_decl)
+            Ok(Self::wrap(ctx, func_decl))
+        }
+    }
+
+    /// Translate this `FuncDecl` into the given [`Context`].
+    ///
+    /// # See also:
+    ///
+    /// - [`Translator`](crate::Translator)
+    pub fn translate(&self, dest: Rc<Context>) -> FuncDecl {
+        unsafe {
+            let ast = Z3_translate(
+                self.ctx.z3_ctx,
+                Z3_func_decl_to_ast(self.ctx.z3_ctx, self.z3_func_decl),
+                dest.z3_ctx,
+            );
+            Self::wrap(dest.clone(), Z3_to_func_decl(dest.z3_ctx, ast))
         }
     }
 
@@ -50,6 +82,18 @@ impl FuncDecl {
         unsafe { Z3_get_arity(self.ctx.z3_ctx, self.z3_func_decl) as usize }
     }
 
+    /// Return the sorts of the arguments of this `FuncDecl`.
+    pub fn domain(&self) -> Vec<Sort> {
+        (0..self.arity())
+            .map(|i| unsafe {
+                Sort::wrap(
+                    self.ctx.clone(),
+                    Z3_get_domain(self.ctx.z3_ctx, self.z3_func_decl, i as u32),
+                )
+            })
+            .collect()
+    }
+
     /// Create a constant (if `args` has length 0) or function application (otherwise).
     ///
     /// Note that `args` should have the types corresponding to the `domain` of the `FuncDecl`.
@@ -63,13 +107,23 @@ impl FuncDecl {
                 Z3_mk_app(
                     self.ctx.z3_ctx,
                     self.z3_func_decl,
-                    args.len().try_into().unwrap(),
+                    c_len(args.len()),
                     args.as_ptr(),
                 )
             })
         }
     }
 
+    /// Return the sort of the result of this `FuncDecl`.
+    pub fn range(&self) -> Sort {
+        unsafe {
+            Sort::wrap(
+                self.ctx.clone(),
+                Z3_get_range(self.ctx.z3_ctx, self.z3_func_decl),
+            )
+        }
+    }
+
     /// Return the `DeclKind` of this `FuncDecl`.
     pub fn kind(&self) -> DeclKind {
         unsafe { Z3_get_decl_kind(self.ctx.z3_ctx, self.z3_func_decl) }
@@ -93,6 +147,12 @@ impl FuncDecl {
     }
 }
 
+impl Clone for FuncDecl {
+    fn clone(&self) -> Self {
+        unsafe { Self::wrap(self.ctx.clone(), self.z3_func_decl) }
+    }
+}
+
 impl fmt::Display for FuncDecl {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         let p = unsafe { Z3_func_decl_to_string(self.ctx.z3_ctx, self.z3_func_decl) };
@@ -112,8 +172,17 @@ impl fmt::Debug for FuncDecl {
     }
 }
 
+impl PartialEq<FuncDecl> for FuncDecl {
+    fn eq(&self, other: &FuncDecl) -> bool {
+        unsafe { Z3_is_eq_func_decl(self.ctx.z3_ctx, self.z3_func_decl, other.z3_func_decl) }
+    }
+}
+
+impl Eq for FuncDecl {}
+
 impl Drop for FuncDecl {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "FuncDecl", -1);
         unsafe {
             Z3_dec_ref(
                 self.ctx.z3_ctx,
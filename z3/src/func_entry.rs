@@ -1,8 +1,8 @@
 use std::fmt;
-use std::rc::Rc;
 
 use z3_sys::*;
 
+use crate::Rc;
 use crate::{
     ast::{Ast, Dynamic},
     Context, FuncEntry,
@@ -11,6 +11,7 @@ use crate::{
 impl FuncEntry {
     pub(crate) unsafe fn wrap(ctx: Rc<Context>, z3_func_entry: Z3_func_entry) -> Self {
         Z3_func_entry_inc_ref(ctx.z3_ctx, z3_func_entry);
+        crate::track_live(ctx.z3_ctx, "FuncEntry", 1);
         Self { ctx, z3_func_entry }
     }
 
@@ -61,6 +62,7 @@ impl fmt::Debug for FuncEntry {
 
 impl Drop for FuncEntry {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "FuncEntry", -1);
         unsafe {
             Z3_func_entry_dec_ref(self.ctx.z3_ctx, self.z3_func_entry);
         }
@@ -1,8 +1,8 @@
 use std::fmt;
-use std::rc::Rc;
 
 use z3_sys::*;
 
+use crate::Rc;
 use crate::{
     ast::{Ast, Dynamic},
     Context, FuncEntry, FuncInterp,
@@ -11,6 +11,7 @@ use crate::{
 impl FuncInterp {
     pub(crate) unsafe fn wrap(ctx: Rc<Context>, z3_func_interp: Z3_func_interp) -> Self {
         Z3_func_interp_inc_ref(ctx.z3_ctx, z3_func_interp);
+        crate::track_live(ctx.z3_ctx, "FuncInterp", 1);
 
         Self {
             ctx,
@@ -52,15 +53,67 @@ impl FuncInterp {
             .collect()
     }
 
+    /// Returns the entries of the function interpretation, as the
+    /// arguments and the value of each.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, FuncDecl, SatResult, Solver, Sort};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let int = Sort::int(ctx.clone());
+    /// let f = FuncDecl::new(ctx.clone(), "f", &[&int, &int], &int);
+    /// let n = |i| ast::Int::from_i64(ctx.clone(), i);
+    ///
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&f.apply(&[&n(1), &n(2)])._eq(&n(3).into()));
+    /// solver.assert(&f.apply(&[&n(2), &n(1)])._eq(&n(4).into()));
+    /// assert_eq!(solver.check(), SatResult::Sat);
+    /// let interp = solver.get_model().unwrap().get_func_interp(&f).unwrap();
+    ///
+    /// let table: Vec<(Vec<i64>, i64)> = interp
+    ///     .entries()
+    ///     .map(|(args, value)| {
+    ///         let args = args.iter().map(|a| a.as_int().unwrap().as_i64().unwrap());
+    ///         (args.collect(), value.as_int().unwrap().as_i64().unwrap())
+    ///     })
+    ///     .collect();
+    /// assert!(table.contains(&(vec![2, 1], 4)));
+    /// assert!(interp.else_value().is_some());
+    /// ```
+    pub fn entries(&self) -> impl Iterator<Item = (Vec<Dynamic>, Dynamic)> + '_ {
+        (0..self.get_num_entries()).map(move |i| {
+            let entry = unsafe {
+                FuncEntry::wrap(
+                    self.ctx.clone(),
+                    Z3_func_interp_get_entry(self.ctx.z3_ctx, self.z3_func_interp, i),
+                )
+            };
+            (entry.get_args(), entry.get_value())
+        })
+    }
+
+    /// Returns the else value of the function interpretation, the value
+    /// for the arguments of no entry, or `None` if Z3 did not set it.
+    pub fn else_value(&self) -> Option<Dynamic> {
+        let z3_ast = unsafe { Z3_func_interp_get_else(self.ctx.z3_ctx, self.z3_func_interp) };
+        if z3_ast.is_null() {
+            None
+        } else {
+            Some(unsafe { Dynamic::wrap(self.ctx.clone(), z3_ast) })
+        }
+    }
+
     /// Returns the else value of the function interpretation.
-    /// Returns None if the else value is not set by Z3.
+    ///
+    /// # Panics
+    ///
+    /// If Z3 did not set the else value, see [`FuncInterp::else_value()`].
     pub fn get_else(&self) -> Dynamic {
-        unsafe {
-            Dynamic::wrap(
-                self.ctx.clone(),
-                Z3_func_interp_get_else(self.ctx.z3_ctx, self.z3_func_interp),
-            )
-        }
+        self.else_value()
+            .expect("function interpretation has no else value")
     }
 
     /// Sets the else value of the function interpretation.
@@ -91,7 +144,10 @@ impl fmt::Display for FuncInterp {
             }
             write!(f, " -> {}, ", e.get_value())
         })?;
-        write!(f, "else -> {}", self.get_else())?;
+        match self.else_value() {
+            Some(e) => write!(f, "else -> {}", e)?,
+            None => write!(f, "else -> ?")?,
+        }
         write!(f, "]")
     }
 }
@@ -104,6 +160,7 @@ impl fmt::Debug for FuncInterp {
 
 impl Drop for FuncInterp {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "FuncInterp", -1);
         unsafe {
             Z3_func_interp_dec_ref(self.ctx.z3_ctx, self.z3_func_interp);
         }
@@ -1,23 +1,21 @@
 use std::ffi::CStr;
 use std::fmt;
-use std::rc::Rc;
 
 use z3_sys::*;
 
-use crate::{ast, ast::Ast, Context, Goal};
+use crate::Rc;
+use crate::{ast, ast::Ast, Cnf, Context, Goal};
 
 impl Clone for Goal {
     fn clone(&self) -> Self {
-        Self {
-            ctx: self.ctx.clone(),
-            z3_goal: self.z3_goal,
-        }
+        unsafe { Self::wrap(self.ctx.clone(), self.z3_goal) }
     }
 }
 
 impl Goal {
     pub(crate) unsafe fn wrap(ctx: Rc<Context>, z3_goal: Z3_goal) -> Goal {
         Z3_goal_inc_ref(ctx.z3_ctx, z3_goal);
+        crate::track_live(ctx.z3_ctx, "Goal", 1);
         Goal { ctx, z3_goal }
     }
 
@@ -29,6 +27,22 @@ impl Goal {
         }
     }
 
+    /// Create a new goal holding the same formulas as this one, but with
+    /// the given model, unsat core and proof generation flags.
+    ///
+    /// The new goal starts out with a depth of `0` and is precise, like any
+    /// freshly created goal.
+    pub fn clone_with(&self, models: bool, unsat_cores: bool, proofs: bool) -> Goal {
+        let goal = Goal::new(self.ctx.clone(), models, unsat_cores, proofs);
+        for i in 0..self.get_size() {
+            unsafe {
+                let formula = Z3_goal_formula(self.ctx.z3_ctx, self.z3_goal, i);
+                Z3_goal_assert(self.ctx.z3_ctx, goal.z3_goal, formula);
+            }
+        }
+        goal
+    }
+
     /// Add a new formula `a` to the given goal.
     pub fn assert(&self, ast: &impl ast::Ast) {
         unsafe { Z3_goal_assert(self.ctx.z3_ctx, self.z3_goal, ast.get_z3_ast()) }
@@ -68,9 +82,8 @@ impl Goal {
         unsafe { Z3_goal_reset(self.ctx.z3_ctx, self.z3_goal) };
     }
 
-    /// Copy a goal `g` from the context `source` to the context `target`.
-    #[allow(clippy::needless_lifetimes)]
-    pub fn translate(self, ctx: Rc<Context>) -> Goal {
+    /// Copy this goal to the context `ctx`.
+    pub fn translate(&self, ctx: Rc<Context>) -> Goal {
         unsafe {
             let goal = Z3_goal_translate(self.ctx.z3_ctx, self.z3_goal, ctx.z3_ctx);
             Goal::wrap(ctx, goal)
@@ -82,6 +95,111 @@ impl Goal {
         unsafe { Z3_goal_precision(self.ctx.z3_ctx, self.z3_goal) }
     }
 
+    /// Render this goal in DIMACS format with Z3's
+    /// `Z3_goal_to_dimacs_string`, for an external SAT solver.
+    ///
+    /// With `include_names`, Z3 adds comment lines with the names of the
+    /// variables. See [`Goal::to_dimacs_with_vars()`] for the Boolean terms
+    /// the variables stand for, in its own numbering.
+    ///
+    /// Return Z3's error if the goal is not in CNF, as after the
+    /// `tseitin-cnf` tactic, or after `bit-blast` for bitvectors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Goal};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let a = ast::Bool::new_const(ctx.clone(), "a");
+    /// let b = ast::Bool::new_const(ctx.clone(), "b");
+    /// let goal = Goal::new(ctx.clone(), false, false, false);
+    /// goal.assert(&(b.clone() | !a.clone()));
+    /// goal.assert(&a);
+    ///
+    /// let dimacs = goal.to_dimacs(false).unwrap();
+    /// assert!(dimacs.starts_with("p cnf 2 2\n"));
+    /// assert!(!dimacs.lines().any(|l| l.starts_with('c')));
+    ///
+    /// goal.assert(&a._eq(&b));
+    /// assert!(goal.to_dimacs(false).is_err());
+    /// ```
+    pub fn to_dimacs(&self, include_names: bool) -> Result<String, String> {
+        unsafe {
+            Z3_set_error(self.ctx.z3_ctx, ErrorCode::OK);
+            let p =
+                Z3_goal_to_dimacs_string_with_names(self.ctx.z3_ctx, self.z3_goal, include_names);
+            let code = Z3_get_error_code(self.ctx.z3_ctx);
+            if p.is_null() || code != ErrorCode::OK {
+                let msg = CStr::from_ptr(Z3_get_error_msg(self.ctx.z3_ctx, code));
+                return Err(format!("goal is not in CNF: {}", msg.to_string_lossy()));
+            }
+            Ok(CStr::from_ptr(p).to_string_lossy().into_owned())
+        }
+    }
+
+    /// Render this goal in DIMACS format, with the Boolean term each
+    /// variable stands for: variable `i` is the `i - 1`-th term.
+    ///
+    /// The formulas of the goal must be clauses, as after the
+    /// `tseitin-cnf` tactic, or after `bit-blast` for bitvectors. The atoms
+    /// of the clauses are numbered in the order they occur; an atom that is
+    /// not a Boolean constant, like `x < 3`, is a variable of its own.
+    ///
+    /// Return an error if a formula is not a clause.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Goal};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let a = ast::Bool::new_const(ctx.clone(), "a");
+    /// let b = ast::Bool::new_const(ctx.clone(), "b");
+    /// let goal = Goal::new(ctx.clone(), false, false, false);
+    /// goal.assert(&(b.clone() | !a.clone()));
+    /// goal.assert(&a);
+    ///
+    /// let (dimacs, vars) = goal.to_dimacs_with_vars().unwrap();
+    /// assert_eq!(dimacs, "p cnf 2 2\n1 -2 0\n2 0\n");
+    /// assert_eq!(vars, [b.clone(), a.clone()]);
+    ///
+    /// goal.assert(&a._eq(&b));
+    /// assert!(goal.to_dimacs_with_vars().is_err());
+    /// ```
+    pub fn to_dimacs_with_vars(&self) -> Result<(String, Vec<ast::Bool>), String> {
+        let cnf = Cnf::from_goal(self, &[])?;
+        Ok((cnf.render(""), cnf.vars().to_vec()))
+    }
+
+    /// Iterate over the formulas of this goal, like
+    /// [`Goal::iter_formulas()`] for [`ast::Bool`]s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, Config, Context, Goal};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let a = ast::Bool::new_const(ctx.clone(), "a");
+    /// let b = ast::Bool::new_const(ctx.clone(), "b");
+    /// let goal = Goal::new(ctx.clone(), false, false, false);
+    /// goal.assert(&a);
+    /// goal.assert(&b);
+    ///
+    /// let names: Vec<_> = goal.iter().map(|f| f.to_string()).collect();
+    /// assert_eq!(names, ["a", "b"]);
+    /// for formula in &goal {
+    ///     assert!(formula == a || formula == b);
+    /// }
+    /// ```
+    pub fn iter(&self) -> GoalIter<'_> {
+        self.into_iter()
+    }
+
     pub fn iter_formulas<T>(&self) -> impl Iterator<Item = T> + '_
     where
         T: Ast,
@@ -130,8 +248,51 @@ impl fmt::Debug for Goal {
     }
 }
 
+/// An iterator over the formulas of a [`Goal`].
+#[derive(Debug)]
+pub struct GoalIter<'a> {
+    goal: &'a Goal,
+    idx: u32,
+    len: u32,
+}
+
+impl<'a> IntoIterator for &'a Goal {
+    type Item = ast::Bool;
+    type IntoIter = GoalIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        GoalIter {
+            goal: self,
+            idx: 0,
+            len: self.get_size(),
+        }
+    }
+}
+
+impl Iterator for GoalIter<'_> {
+    type Item = ast::Bool;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx >= self.len {
+            return None;
+        }
+        let goal = self.goal;
+        let formula = unsafe { Z3_goal_formula(goal.ctx.z3_ctx, goal.z3_goal, self.idx) };
+        self.idx += 1;
+        Some(unsafe { ast::Bool::wrap(goal.ctx.clone(), formula) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.len - self.idx) as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for GoalIter<'_> {}
+
 impl Drop for Goal {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "Goal", -1);
         unsafe {
             Z3_goal_dec_ref(self.ctx.z3_ctx, self.z3_goal);
         };
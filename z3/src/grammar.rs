@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use crate::ast::{self, Regexp};
+use crate::Context;
+use crate::Rc;
+
+/// A symbol on the right-hand side of a [`Grammar`] rule.
+#[derive(Clone, Debug)]
+pub enum Term {
+    /// A fixed string.
+    Literal(String),
+    /// Any string produced by the rules for this non-terminal.
+    NonTerminal(String),
+    /// Any string matched by this regular expression, e.g. a character
+    /// class.
+    Regexp(ast::Regexp),
+}
+
+impl Term {
+    /// Shorthand for [`Term::Literal`].
+    pub fn lit(s: &str) -> Term {
+        Term::Literal(s.to_owned())
+    }
+
+    /// Shorthand for [`Term::NonTerminal`].
+    pub fn nt(name: &str) -> Term {
+        Term::NonTerminal(name.to_owned())
+    }
+
+    /// Shorthand for [`Term::Regexp`].
+    pub fn re(re: &ast::Regexp) -> Term {
+        Term::Regexp(re.clone())
+    }
+}
+
+/// A context-free grammar, used to constrain strings to a bounded
+/// approximation of its language.
+///
+/// Z3 regular expressions can not express recursive rules, so a grammar
+/// is turned into one by unrolling its rules up to a given depth, see
+/// [`Grammar::to_regexp()`]. This is enough to generate structured test
+/// inputs of bounded size.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, Grammar, SatResult, Solver, Term};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let digit = ast::Regexp::range(ctx.clone(), &'0', &'9');
+/// // list ::= "[" "]" | "[" items "]"
+/// // items ::= item | item "," items
+/// // item ::= digit | list
+/// let grammar = Grammar::new(ctx.clone(), "list")
+///     .rule("list", vec![Term::lit("["), Term::lit("]")])
+///     .rule("list", vec![Term::lit("["), Term::nt("items"), Term::lit("]")])
+///     .rule("items", vec![Term::nt("item")])
+///     .rule("items", vec![Term::nt("item"), Term::lit(","), Term::nt("items")])
+///     .rule("item", vec![Term::re(&digit)])
+///     .rule("item", vec![Term::nt("list")]);
+///
+/// let s = ast::String::new_const(ctx.clone(), "s");
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&grammar.matches(&s, 6));
+/// solver.assert(&s.contains(&ast::String::from_str(ctx.clone(), "[[").unwrap()));
+/// assert_eq!(solver.check(), SatResult::Sat);
+/// let model = solver.get_model().unwrap();
+/// let s = model.eval(&s, true).unwrap().as_string().unwrap();
+/// assert!(s.starts_with('['));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Grammar {
+    ctx: Rc<Context>,
+    start: String,
+    rules: Vec<(String, Vec<Term>)>,
+}
+
+impl Grammar {
+    /// Create a grammar without rules, whose language is that of the
+    /// non-terminal `start`.
+    pub fn new(ctx: Rc<Context>, start: &str) -> Grammar {
+        Grammar {
+            ctx,
+            start: start.to_owned(),
+            rules: vec![],
+        }
+    }
+
+    /// Add the rule `name ::= terms`. Call this repeatedly with the same
+    /// `name` to give a non-terminal several alternatives.
+    pub fn rule(mut self, name: &str, terms: Vec<Term>) -> Grammar {
+        self.rules.push((name.to_owned(), terms));
+        self
+    }
+
+    /// Return a regular expression matching the strings that can be
+    /// derived from the start symbol with at most `depth` nested rule
+    /// applications.
+    ///
+    /// # Panics
+    ///
+    /// If a rule refers to a non-terminal that has no rules.
+    pub fn to_regexp(&self, depth: u32) -> ast::Regexp {
+        let used = self
+            .rules
+            .iter()
+            .flat_map(|(_, terms)| terms)
+            .filter_map(|t| match t {
+                Term::NonTerminal(n) => Some(n),
+                _ => None,
+            });
+        for name in std::iter::once(&self.start).chain(used) {
+            assert!(
+                self.rules.iter().any(|(n, _)| n == name),
+                "no rules for non-terminal {}",
+                name
+            );
+        }
+        let mut memo = HashMap::new();
+        self.expand(&self.start, depth, &mut memo)
+    }
+
+    /// Return a constraint that `s` is in the language of
+    /// [`Grammar::to_regexp(depth)`](Grammar::to_regexp).
+    pub fn matches(&self, s: &ast::String, depth: u32) -> ast::Bool {
+        s.regex_matches(&self.to_regexp(depth))
+    }
+
+    fn expand(
+        &self,
+        name: &str,
+        depth: u32,
+        memo: &mut HashMap<(String, u32), ast::Regexp>,
+    ) -> ast::Regexp {
+        if depth == 0 {
+            return Regexp::empty(self.ctx.clone());
+        }
+        if let Some(re) = memo.get(&(name.to_owned(), depth)) {
+            return re.clone();
+        }
+        let alternatives: Vec<ast::Regexp> = self
+            .rules
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, terms)| {
+                let parts: Vec<ast::Regexp> = terms
+                    .iter()
+                    .map(|t| match t {
+                        Term::Literal(s) => Regexp::literal(self.ctx.clone(), s),
+                        Term::NonTerminal(n) => self.expand(n, depth - 1, memo),
+                        Term::Regexp(re) => re.clone(),
+                    })
+                    .collect();
+                match parts.len() {
+                    0 => Regexp::literal(self.ctx.clone(), ""),
+                    1 => parts[0].clone(),
+                    _ => Regexp::concat(self.ctx.clone(), &parts),
+                }
+            })
+            .collect();
+        let re = match alternatives.len() {
+            1 => alternatives[0].clone(),
+            _ => Regexp::union(self.ctx.clone(), &alternatives),
+        };
+        memo.insert((name.to_owned(), depth), re.clone());
+        re
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, FuncDecl, Sort};
+
+/// A description of a [`Sort`] that is not tied to any [`Context`].
+///
+/// Unlike a `Sort`, a `SortSignature` can be stored once and turned into
+/// an equivalent `Sort` in as many contexts as needed.
+///
+/// # See also:
+///
+/// - [`SortSignature::to_sort()`]
+/// - [`Interner`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SortSignature {
+    Bool,
+    Int,
+    Real,
+    String,
+    BV(u32),
+    Float { ebits: u32, sbits: u32 },
+    Array(Box<SortSignature>, Box<SortSignature>),
+    Set(Box<SortSignature>),
+    Uninterpreted(String),
+}
+
+impl SortSignature {
+    /// Create the [`Sort`] described by this signature in `ctx`.
+    pub fn to_sort(&self, ctx: Rc<Context>) -> Sort {
+        match self {
+            SortSignature::Bool => Sort::bool(ctx),
+            SortSignature::Int => Sort::int(ctx),
+            SortSignature::Real => Sort::real(ctx),
+            SortSignature::String => Sort::string(ctx),
+            SortSignature::BV(sz) => Sort::bitvector(ctx, *sz),
+            SortSignature::Float { ebits, sbits } => Sort::float(ctx, *ebits, *sbits),
+            SortSignature::Array(domain, range) => {
+                let domain = domain.to_sort(ctx.clone());
+                let range = range.to_sort(ctx.clone());
+                Sort::array(ctx, &domain, &range)
+            }
+            SortSignature::Set(elt) => {
+                let elt = elt.to_sort(ctx.clone());
+                Sort::set(ctx, &elt)
+            }
+            SortSignature::Uninterpreted(name) => Sort::uninterpreted(ctx, name.as_str().into()),
+        }
+    }
+}
+
+/// A table of function and constant declarations, keyed by name, that can
+/// be materialized in any [`Context`] on demand.
+///
+/// This is useful when the same problem has to be built in several
+/// contexts (e.g. one per worker thread of a portfolio solver): each
+/// worker asks the interner for the declarations it needs and builds its
+/// terms directly, instead of building them once and translating them.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, Interner, SatResult, Solver, SortSignature};
+/// let mut interner = Interner::new();
+/// interner.declare("x", &[], SortSignature::Int);
+///
+/// let cfg = Config::new();
+/// let ctx1 = Rc::new(Context::new(&cfg));
+/// let ctx2 = Rc::new(Context::new(&cfg));
+///
+/// let x1 = interner.get_const(ctx1.clone(), "x").unwrap().as_int().unwrap();
+/// let x2 = interner.get_const(ctx2.clone(), "x").unwrap().as_int().unwrap();
+/// assert_eq!(x1.to_string(), x2.to_string());
+///
+/// let solver = Solver::new(ctx2.clone());
+/// solver.assert(&x2._eq(&ast::Int::from_i64(ctx2.clone(), 3)));
+/// assert_eq!(solver.check(), SatResult::Sat);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    decls: HashMap<String, (Vec<SortSignature>, SortSignature)>,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner {
+            decls: HashMap::new(),
+        }
+    }
+
+    /// Record a declaration named `name` with the given `domain` and
+    /// `range`. A declaration with an empty `domain` is a constant.
+    ///
+    /// If `name` was already declared, the previous signature is
+    /// replaced and returned.
+    pub fn declare<S: Into<String>>(
+        &mut self,
+        name: S,
+        domain: &[SortSignature],
+        range: SortSignature,
+    ) -> Option<(Vec<SortSignature>, SortSignature)> {
+        self.decls.insert(name.into(), (domain.to_vec(), range))
+    }
+
+    /// Return the signature recorded for `name`, if any.
+    pub fn signature(&self, name: &str) -> Option<(&[SortSignature], &SortSignature)> {
+        self.decls
+            .get(name)
+            .map(|(domain, range)| (domain.as_slice(), range))
+    }
+
+    /// Iterate over the names of all recorded declarations.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.decls.keys().map(|k| k.as_str())
+    }
+
+    /// Materialize the declaration named `name` in `ctx`.
+    ///
+    /// Returns `None` if `name` was never declared.
+    pub fn get_func_decl(&self, ctx: Rc<Context>, name: &str) -> Option<FuncDecl> {
+        let (domain, range) = self.decls.get(name)?;
+        let domain: Vec<Sort> = domain.iter().map(|s| s.to_sort(ctx.clone())).collect();
+        let domain: Vec<&Sort> = domain.iter().collect();
+        let range = range.to_sort(ctx.clone());
+        Some(FuncDecl::new(ctx, name, &domain, &range))
+    }
+
+    /// Materialize the constant named `name` in `ctx`.
+    ///
+    /// Returns `None` if `name` was never declared, or if it was declared
+    /// with a non-empty domain.
+    pub fn get_const(&self, ctx: Rc<Context>, name: &str) -> Option<ast::Dynamic> {
+        let (domain, range) = self.decls.get(name)?;
+        if !domain.is_empty() {
+            return None;
+        }
+        let range = range.to_sort(ctx.clone());
+        Some(ast::Dynamic::new_const(ctx, name, &range))
+    }
+
+    /// Apply the declaration named `name`, materialized in the context of
+    /// the `args`, to `args`.
+    ///
+    /// Returns `None` if `name` was never declared or if `args` is empty.
+    pub fn apply(&self, name: &str, args: &[&dyn Ast]) -> Option<ast::Dynamic> {
+        let ctx = args.first()?.get_ctx();
+        let f = self.get_func_decl(ctx, name)?;
+        Some(f.apply(args))
+    }
+}
@@ -6,34 +6,156 @@
 #![warn(clippy::doc_markdown)]
 #![deny(missing_debug_implementations)]
 
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::ffi::CString;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use z3_sys::*;
-pub use z3_sys::{AstKind, GoalPrec, SortKind};
+pub use z3_sys::{AstKind, GoalPrec, ParamKind, SortKind};
+
+// Objects hold their context through `Rc`, which is `Arc` with the `sync`
+// feature, see `sync.rs`.
+#[cfg(not(feature = "sync"))]
+pub(crate) use std::rc::Rc;
+#[cfg(feature = "sync")]
+pub(crate) use std::sync::Arc as Rc;
 
 pub mod ast;
+mod ast_node;
+mod bounds;
+mod bytes;
+mod cegar;
+mod char_class;
+mod cnf;
 mod config;
 mod context;
+mod conversion;
+mod core_options;
 pub mod datatype_builder;
+mod datatype_value;
+#[cfg(feature = "debug")]
+pub mod debug;
+pub mod diffcheck;
+mod fixed_point;
+mod fixedpoint;
+mod flat_term;
+pub mod fof;
+mod from_model;
 mod func_decl;
 mod func_entry;
 mod func_interp;
 mod goal;
+mod grammar;
+mod interner;
+mod linalg;
+mod linearize;
+mod mach_int;
+mod maxsat;
+pub mod mem;
+mod milp;
 mod model;
+mod model_diff;
+pub mod nnverify;
 mod ops;
 mod optimize;
+mod parallel;
 mod params;
+mod parser;
 mod pattern;
+mod piecewise;
+mod preprocess;
 mod probe;
+mod proof;
+#[cfg(feature = "user-propagator")]
+mod propagator;
+mod qe;
+mod quantifier;
+mod quantifier_trace;
 mod rec_func_decl;
+mod redundancy;
+#[cfg(feature = "regex")]
+mod regex;
+mod retract;
+mod retry;
+mod select;
+mod soft_groups;
 mod solver;
 mod sort;
 mod statistics;
 mod symbol;
+pub mod symexec;
+#[cfg(feature = "sync")]
+mod sync;
 mod tactic;
+pub mod taint;
+#[cfg(feature = "term-gen")]
+mod term_gen;
+pub mod testing;
+mod theory_mix;
+mod translator;
+mod version;
+mod z3_enum;
+mod z3_value;
 
+pub use crate::bounds::{infer_bounds, Bounds};
+pub use crate::cegar::{cegar, CegarOutcome};
+pub use crate::char_class::CharClass;
+pub use crate::cnf::Cnf;
+pub use crate::conversion::{
+    conversion_policy, set_conversion_policy, ConversionError, ConversionPolicy,
+};
+pub use crate::core_options::CoreOptions;
+pub use crate::datatype_value::DatatypeError;
+pub use crate::fixed_point::FixedPointNum;
+pub use crate::fixedpoint::FixedpointEngine;
+pub use crate::flat_term::{FlatNode, FlatTerm};
+pub use crate::from_model::FromModel;
+pub use crate::grammar::{Grammar, Term};
+pub use crate::interner::{Interner, SortSignature};
+pub use crate::linalg::{Numeric, SymMatrix, SymVector};
+pub use crate::linearize::{LemmaSchemata, Linearizer, Product};
+pub use crate::mach_int::{MachInt, MachineInt};
+pub use crate::maxsat::{MaxSatCore, MaxSatOutcome};
+pub use crate::milp::{indicator, Linear};
+pub use crate::model_diff::{DiffPoint, ModelDifference};
+pub use crate::optimize::{Objective, ObjectiveValue, ParetoModels};
+pub use crate::parallel::{check_all_parallel, check_all_parallel_until, DetachedSolver};
 pub use crate::params::{get_global_param, reset_all_global_params, set_global_param};
+pub use crate::parser::{ParseError, Parsed, Parser};
+pub use crate::piecewise::{piecewise_linear, PiecewiseLinear};
+pub use crate::preprocess::{preprocess_preserving_models, Preprocessed};
+pub use crate::proof::Proof;
+#[cfg(feature = "user-propagator")]
+pub use crate::propagator::{PropagatorCallback, UserPropagator};
+pub use crate::qe::{project, qe_lite};
+pub use crate::quantifier_trace::QuantifierInstantiations;
+pub use crate::redundancy::find_redundant_assertions;
+#[cfg(feature = "regex")]
+pub use crate::regex::RegexError;
+pub use crate::retract::{ConstraintId, RetractStats, RetractableSolver};
+pub use crate::retry::{Attempt, RetryOutcome, RetryPolicy};
+pub use crate::select::{select_by_index, SelectIndex, Selection};
+pub use crate::soft_groups::{SoftGroups, TierSummary};
+pub use crate::solver::{Cubes, Models, NotEuf};
 pub use crate::statistics::{StatisticsEntry, StatisticsValue};
+pub use crate::tactic::ApplyStats;
+#[cfg(feature = "term-gen")]
+pub use crate::term_gen::TermGenerator;
+pub use crate::theory_mix::TheoryMix;
+pub use crate::translator::Translator;
+pub use crate::version::{features, full_version, version, Features, Version};
+pub use crate::z3_enum::{EnumSort, Z3Enum};
+pub use crate::z3_value::Z3Value;
+#[cfg(feature = "derive")]
+pub use z3_derive::{FromModel, Z3Enum, Z3Struct};
+
+/// Items the code generated by the derive macros refers to.
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __derive {
+    pub use z3_sys::Z3_ast;
+}
 
 /// Configuration used to initialize [logical contexts](Context).
 ///
@@ -52,6 +174,16 @@ pub struct Config {
 /// cannot be used in another one. However, several objects may be "translated" from
 /// one context to another. It is not safe to access Z3 objects from multiple threads.
 ///
+/// Objects hold their context through an `Rc`. With the `sync` feature, that
+/// is an [`Arc`] instead, and the context and objects are [`Send`], so they
+/// can be moved to other threads; a context must still only be used by one
+/// thread at a time.
+///
+/// A context is only dropped after all of its objects, so an object that is
+/// leaked, as with [`std::mem::forget`], leaks its context too. Debug builds
+/// count the objects of each context and print a warning when a context is
+/// dropped while any are still alive.
+///
 /// # Examples:
 ///
 /// Creating a context with the default configuration:
@@ -66,21 +198,34 @@ pub struct Config {
 ///
 /// - [`Config`]
 /// - [`Context::new()`]
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Debug)]
 pub struct Context {
     z3_ctx: Z3_context,
+    // Shared with every `ContextHandle`; set to `false` (under the lock)
+    // right before the underlying Z3 context is deleted.
+    alive: Arc<Mutex<bool>>,
 }
 
 /// Handle that can be used to interrupt a computation from another thread.
 ///
+/// Unlike [`Context`], a handle is [`Send`] and [`Sync`], so it can be
+/// moved to a watchdog thread that aborts a long-running
+/// [`Solver::check()`], which then returns [`SatResult::Unknown`] with
+/// [`ReasonUnknown::Canceled`].
+///
+/// A handle does not keep its [`Context`] alive. Once the context has been
+/// dropped, [`ContextHandle::interrupt()`] does nothing (and logs a warning)
+/// instead of touching freed memory.
+///
 /// # See also:
 ///
 /// - [`Context::interrupt()`]
 /// - [`Context::handle()`]
 /// - [`ContextHandle::interrupt()`]
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Debug)]
 pub struct ContextHandle {
-    ctx: Rc<Context>,
+    z3_ctx: Z3_context,
+    alive: Arc<Mutex<bool>>,
 }
 
 /// Symbols are used to name several term and type constructors.
@@ -119,6 +264,27 @@ pub struct IsNotApp {
 pub struct Solver {
     ctx: Rc<Context>,
     z3_slv: Z3_solver,
+    // Set with `Solver::prefer()`.
+    preferences: RefCell<Vec<(ast::Bool, Polarity)>>,
+    // The latest values of the parameters set with `Solver::set_params()`,
+    // which `Solver::check_with_params()` restores.
+    params: RefCell<BTreeMap<String, params::ParamValue>>,
+    // Set with `Solver::propagator()`, from `Box::into_raw()`. Z3 holds
+    // the pointer too; `Solver::drop()` frees the state after releasing
+    // `z3_slv`.
+    #[cfg(feature = "user-propagator")]
+    propagator: std::cell::Cell<Option<std::ptr::NonNull<propagator::PropagatorState>>>,
+}
+
+/// The assertions of a [`Solver`] at some point in time, see
+/// [`Solver::snapshot()`].
+///
+/// A snapshot is independent of the solver it was taken from: it stays
+/// valid however that solver changes, and can be restored any number of
+/// times, in any order, and into any solver of the same context.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    assertions: Vec<ast::Bool>,
 }
 
 /// Model for the constraints inserted into the logical context.
@@ -137,6 +303,24 @@ pub struct Model {
 pub struct Optimize {
     ctx: Rc<Context>,
     z3_opt: Z3_optimize,
+    /// Identifies this optimizer to its [`Objective`]s: unlike `z3_opt`, it
+    /// is not reused after the optimizer is dropped.
+    id: usize,
+    /// The generation of the objective at each index, or `None` for
+    /// objectives added by parsing.
+    objectives: RefCell<Vec<Option<usize>>>,
+    /// The number of `objectives` at each [`Optimize::push()`].
+    scopes: RefCell<Vec<usize>>,
+}
+
+/// Context for solving recursive queries over Horn clauses, with Z3's
+/// Datalog and Spacer engines.
+//
+// Note for in-crate users: Never construct a `Fixedpoint` directly; only use
+// `Fixedpoint::new()` which handles Z3 refcounting properly.
+pub struct Fixedpoint {
+    ctx: Rc<Context>,
+    z3_fp: Z3_fixedpoint,
 }
 
 /// Function declaration. Every constant and function have an associated declaration.
@@ -252,10 +436,33 @@ pub struct DatatypeSort {
     pub variants: Vec<DatatypeVariant>,
 }
 
+/// A list sort created with [`Sort::list()`], with its declarations.
+#[derive(Debug)]
+pub struct ListSort {
+    pub sort: Sort,
+    /// The empty list.
+    pub nil: FuncDecl,
+    pub is_nil: FuncDecl,
+    /// The list with a head element in front of a tail list.
+    pub cons: FuncDecl,
+    pub is_cons: FuncDecl,
+    pub head: FuncDecl,
+    pub tail: FuncDecl,
+}
+
 /// Parameter set used to configure many components (simplifiers, tactics, solvers, etc).
 pub struct Params {
     ctx: Rc<Context>,
     z3_params: Z3_params,
+    // The values given to the setters, by parameter name.
+    values: BTreeMap<String, params::ParamValue>,
+}
+
+/// Descriptions of the parameters a component, such as the simplifier,
+/// accepts: their names, kinds, and documentation.
+pub struct ParamDescrs {
+    ctx: Rc<Context>,
+    z3_param_descrs: Z3_param_descrs,
 }
 
 /// Result of a satisfiability query.
@@ -269,6 +476,65 @@ pub enum SatResult {
     Sat,
 }
 
+/// The value a Boolean is preferably assigned, see [`Solver::prefer()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Polarity {
+    Positive,
+    Negative,
+}
+
+/// How [`Optimize`] combines several objectives, see
+/// [`Optimize::set_priority()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Priority {
+    /// Optimize the objectives in the order they were added, each one
+    /// without making the previous ones worse. This is the default.
+    Lexicographic,
+    /// Find the Pareto-optimal solutions: those where no objective can be
+    /// improved without making another one worse. Every
+    /// [`Optimize::check()`] produces another one.
+    Pareto,
+    /// Optimize every objective independently of the others.
+    Box,
+}
+
+/// Why a satisfiability query returned [`SatResult::Unknown`], as
+/// reconstructed from the justification Z3 gives in
+/// [`Solver::get_reason_unknown()`].
+///
+/// # See also:
+///
+/// - [`Solver::get_reason_unknown_kind()`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ReasonUnknown {
+    /// The query ran out of time.
+    Timeout,
+    /// The query was interrupted, e.g. by [`ContextHandle::interrupt()`].
+    Canceled,
+    /// The query ran out of memory.
+    MemoryLimit,
+    /// The query ran out of its resource limit (`rlimit`).
+    ResourceLimit,
+    /// A tactic failed; `tactic` is the name of the failing stage, e.g.
+    /// `"smt"` or `"nlsat"`, and `message` is Z3's full justification.
+    TacticFailed { tactic: String, message: String },
+    /// The solver gave up because the procedure it used is incomplete for
+    /// the query, e.g. because of quantifiers or non-linear arithmetic.
+    /// Holds Z3's justification.
+    Incomplete(String),
+    /// Any other justification.
+    Other(String),
+}
+
+/// A location in the Rust source code, recorded for an assertion made with
+/// [`Solver::assert_here()`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
 /// A pattern for quantifier instantiation, used to guide quantifier instantiation.
 pub struct Pattern {
     ctx: Rc<Context>,
@@ -276,10 +542,12 @@ pub struct Pattern {
 }
 
 /// Collection of subgoals resulting from applying of a tactic to a goal.
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct ApplyResult {
     ctx: Rc<Context>,
     z3_apply_result: Z3_apply_result,
+    elapsed: Duration,
+    stats: ApplyStats,
 }
 
 /// Basic building block for creating custom solvers for specific problem domains.
@@ -332,3 +600,17 @@ pub struct Statistics {
     ctx: Rc<Context>,
     z3_stats: Z3_stats,
 }
+
+/// Count that a wrapper of `type_name` in `z3_ctx` was created, with a
+/// `delta` of 1, or dropped, with -1, for `debug::live_objects()`. Debug
+/// builds also keep a total per context, so that dropping a context with
+/// live wrappers prints a warning. Otherwise, this does nothing.
+#[inline]
+pub(crate) fn track_live(z3_ctx: Z3_context, type_name: &'static str, delta: isize) {
+    #[cfg(debug_assertions)]
+    context::track_child(z3_ctx, delta);
+    #[cfg(feature = "debug")]
+    debug::track(z3_ctx, type_name, delta);
+    #[cfg(not(feature = "debug"))]
+    let _ = (z3_ctx, type_name, delta);
+}
@@ -0,0 +1,281 @@
+use std::ops::{Add, Mul, Sub};
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Context};
+
+/// An arithmetic sort that vectors and matrices can be built over: `Int`
+/// or `Real`.
+pub trait Numeric:
+    Ast + Clone + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    fn new_const(ctx: Rc<Context>, name: String) -> Self;
+    fn from_i64(ctx: Rc<Context>, i: i64) -> Self;
+}
+
+impl Numeric for ast::Int {
+    fn new_const(ctx: Rc<Context>, name: String) -> Self {
+        ast::Int::new_const(ctx, name)
+    }
+
+    fn from_i64(ctx: Rc<Context>, i: i64) -> Self {
+        ast::Int::from_i64(ctx, i)
+    }
+}
+
+impl Numeric for ast::Real {
+    fn new_const(ctx: Rc<Context>, name: String) -> Self {
+        ast::Real::new_const(ctx, name)
+    }
+
+    fn from_i64(ctx: Rc<Context>, i: i64) -> Self {
+        ast::Real::from_real_str(ctx, &i.to_string(), "1").unwrap()
+    }
+}
+
+/// A vector of `Int` or `Real` terms.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver, SymMatrix, SymVector};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// // Solve the system 2x + y = 5, x - y = 1.
+/// let a = SymMatrix::<ast::Int>::from_i64(ctx.clone(), &[&[2, 1], &[1, -1]]);
+/// let x = SymVector::<ast::Int>::new_const(ctx.clone(), "x", 2);
+/// let b = SymVector::from_i64(ctx.clone(), &[5, 1]);
+///
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&a.mul_vec(&x)._eq(&b));
+/// assert_eq!(solver.check(), SatResult::Sat);
+/// let model = solver.get_model().unwrap();
+/// let x: Vec<i64> = x.iter().map(|e| model.eval(e, true).unwrap().as_i64().unwrap()).collect();
+/// assert_eq!(x, [2, 1]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SymVector<T> {
+    elems: Vec<T>,
+}
+
+impl<T: Numeric> SymVector<T> {
+    pub fn new(elems: Vec<T>) -> SymVector<T> {
+        SymVector { elems }
+    }
+
+    /// Create a vector of `len` constants named `name_0`, `name_1`, ...
+    pub fn new_const(ctx: Rc<Context>, name: &str, len: usize) -> SymVector<T> {
+        SymVector::new(
+            (0..len)
+                .map(|i| T::new_const(ctx.clone(), format!("{}_{}", name, i)))
+                .collect(),
+        )
+    }
+
+    pub fn from_i64(ctx: Rc<Context>, values: &[i64]) -> SymVector<T> {
+        SymVector::new(
+            values
+                .iter()
+                .map(|&v| T::from_i64(ctx.clone(), v))
+                .collect(),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.elems.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elems.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> &T {
+        &self.elems[i]
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.elems.iter()
+    }
+
+    /// The dot product of `self` and `other`.
+    ///
+    /// # Panics
+    ///
+    /// If the vectors have different lengths, or are empty.
+    pub fn dot(&self, other: &SymVector<T>) -> T {
+        self.check_len(other);
+        self.zip_with(other, |a, b| a * b)
+            .elems
+            .into_iter()
+            .reduce(|sum, x| sum + x)
+            .expect("dot product of empty vectors")
+    }
+
+    /// Multiply every element by `factor`.
+    pub fn scale(&self, factor: &T) -> SymVector<T> {
+        SymVector::new(self.iter().map(|e| factor.clone() * e.clone()).collect())
+    }
+
+    /// Return the constraint that `self` and `other` are equal element by
+    /// element.
+    ///
+    /// # Panics
+    ///
+    /// If the vectors have different lengths, or are empty.
+    pub fn _eq(&self, other: &SymVector<T>) -> ast::Bool {
+        self.check_len(other);
+        let eqs: Vec<ast::Bool> = self
+            .iter()
+            .zip(other.iter())
+            .map(|(a, b)| a._eq(b))
+            .collect();
+        let ctx = eqs.first().expect("comparison of empty vectors").get_ctx();
+        ast::Bool::and(ctx, &eqs)
+    }
+
+    fn check_len(&self, other: &SymVector<T>) {
+        assert_eq!(self.len(), other.len(), "vectors have different lengths");
+    }
+
+    fn zip_with(&self, other: &SymVector<T>, f: impl Fn(T, T) -> T) -> SymVector<T> {
+        self.check_len(other);
+        SymVector::new(
+            self.iter()
+                .zip(other.iter())
+                .map(|(a, b)| f(a.clone(), b.clone()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: Numeric> Add for &SymVector<T> {
+    type Output = SymVector<T>;
+
+    fn add(self, other: &SymVector<T>) -> SymVector<T> {
+        self.zip_with(other, |a, b| a + b)
+    }
+}
+
+impl<T: Numeric> Sub for &SymVector<T> {
+    type Output = SymVector<T>;
+
+    fn sub(self, other: &SymVector<T>) -> SymVector<T> {
+        self.zip_with(other, |a, b| a - b)
+    }
+}
+
+/// A matrix of `Int` or `Real` terms, see [`SymVector`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SymMatrix<T> {
+    rows: Vec<SymVector<T>>,
+    cols: usize,
+}
+
+impl<T: Numeric> SymMatrix<T> {
+    /// Create the matrix with the given rows.
+    ///
+    /// # Panics
+    ///
+    /// If the rows have different lengths.
+    pub fn new(rows: Vec<SymVector<T>>) -> SymMatrix<T> {
+        let cols = rows.first().map_or(0, SymVector::len);
+        assert!(
+            rows.iter().all(|r| r.len() == cols),
+            "rows have different lengths"
+        );
+        SymMatrix { rows, cols }
+    }
+
+    /// Create a `rows` by `cols` matrix of constants named `name_0_0`,
+    /// `name_0_1`, ...
+    pub fn new_const(ctx: Rc<Context>, name: &str, rows: usize, cols: usize) -> SymMatrix<T> {
+        SymMatrix::new(
+            (0..rows)
+                .map(|i| SymVector::new_const(ctx.clone(), &format!("{}_{}", name, i), cols))
+                .collect(),
+        )
+    }
+
+    pub fn from_i64(ctx: Rc<Context>, rows: &[&[i64]]) -> SymMatrix<T> {
+        SymMatrix::new(
+            rows.iter()
+                .map(|r| SymVector::from_i64(ctx.clone(), r))
+                .collect(),
+        )
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> &T {
+        self.rows[row].get(col)
+    }
+
+    pub fn row(&self, i: usize) -> &SymVector<T> {
+        &self.rows[i]
+    }
+
+    pub fn col(&self, j: usize) -> SymVector<T> {
+        SymVector::new(self.rows.iter().map(|r| r.get(j).clone()).collect())
+    }
+
+    pub fn transpose(&self) -> SymMatrix<T> {
+        SymMatrix::new((0..self.cols).map(|j| self.col(j)).collect())
+    }
+
+    /// The product of this matrix and the column vector `v`.
+    ///
+    /// # Panics
+    ///
+    /// If `v` does not have as many elements as this matrix has columns.
+    pub fn mul_vec(&self, v: &SymVector<T>) -> SymVector<T> {
+        SymVector::new(self.rows.iter().map(|r| r.dot(v)).collect())
+    }
+
+    /// The product of `self` and `other`.
+    ///
+    /// # Panics
+    ///
+    /// If `other` does not have as many rows as `self` has columns.
+    pub fn mul(&self, other: &SymMatrix<T>) -> SymMatrix<T> {
+        assert_eq!(
+            self.cols,
+            other.num_rows(),
+            "matrix dimensions do not match"
+        );
+        let other_cols: Vec<SymVector<T>> = (0..other.cols).map(|j| other.col(j)).collect();
+        SymMatrix::new(
+            self.rows
+                .iter()
+                .map(|r| SymVector::new(other_cols.iter().map(|c| r.dot(c)).collect()))
+                .collect(),
+        )
+    }
+
+    /// Return the constraint that `self` and `other` are equal element by
+    /// element.
+    ///
+    /// # Panics
+    ///
+    /// If the matrices have different dimensions, or are empty.
+    pub fn _eq(&self, other: &SymMatrix<T>) -> ast::Bool {
+        assert_eq!(
+            self.num_rows(),
+            other.num_rows(),
+            "matrices have different dimensions"
+        );
+        let eqs: Vec<ast::Bool> = self
+            .rows
+            .iter()
+            .zip(&other.rows)
+            .map(|(a, b)| a._eq(b))
+            .collect();
+        let ctx = eqs.first().expect("comparison of empty matrices").get_ctx();
+        ast::Bool::and(ctx, &eqs)
+    }
+}
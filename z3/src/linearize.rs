@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::c_uint;
+
+use z3_sys::*;
+
+use crate::Rc;
+use crate::{ast, ast::Ast, ast::AstNode, Context, DeclKind, Model, SatResult, Solver, SortKind};
+
+/// The lemmas [`Linearizer`] refines a violated [`Product`] with. All of
+/// them are enabled by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LemmaSchemata {
+    /// `m = 0` exactly when `x = 0` or `y = 0`.
+    pub zero: bool,
+    /// The sign of `m` follows from the signs of `x` and `y`.
+    pub sign: bool,
+    /// The tangent planes of `x * y` at the point of the spurious model,
+    /// which cut that point off.
+    pub tangent: bool,
+}
+
+impl Default for LemmaSchemata {
+    fn default() -> Self {
+        LemmaSchemata {
+            zero: true,
+            sign: true,
+            tangent: true,
+        }
+    }
+}
+
+/// A non-linear product `x * y`, abstracted by the fresh constant `m`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Product {
+    pub m: ast::Dynamic,
+    pub x: ast::Dynamic,
+    pub y: ast::Dynamic,
+}
+
+type LemmaHook = Box<dyn FnMut(&Product, &Model) -> Vec<ast::Bool>>;
+
+/// Incremental linearization of non-linear integer and real arithmetic.
+///
+/// Every product of two non-numeral terms in an asserted formula is
+/// replaced by a fresh constant, so that the solver only sees linear
+/// arithmetic. Where a model of this abstraction gives a product a wrong
+/// value, lemmas about the product are added, enabled by
+/// [`LemmaSchemata`] and a [hook](Linearizer::lemma_hook), and the
+/// abstraction is checked again. An unsatisfiable abstraction proves the
+/// formulas unsatisfiable, and a model of it that satisfies the formulas
+/// is a model of them.
+///
+/// This can decide problems on which the non-linear procedures time out,
+/// but it is incomplete: after
+/// [`max_rounds`](Linearizer::check) refinements, or if no lemma applies,
+/// the result is [`SatResult::Unknown`]. Quantified subformulas are kept
+/// as they are, products and all.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, Linearizer, SatResult};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let y = ast::Int::new_const(ctx.clone(), "y");
+/// let int = |i| ast::Int::from_i64(ctx.clone(), i);
+///
+/// let mut lin = Linearizer::new(ctx.clone());
+/// lin.assert(&(&x * &y)._eq(&int(12)));
+/// lin.assert(&x.gt(&int(2)));
+/// lin.assert(&y.gt(&x));
+/// assert_eq!(lin.check(20), SatResult::Sat);
+///
+/// let model = lin.get_model().unwrap();
+/// let x = model.eval(&x, true).unwrap().as_i64().unwrap();
+/// let y = model.eval(&y, true).unwrap().as_i64().unwrap();
+/// assert_eq!((x, y), (3, 4));
+/// ```
+pub struct Linearizer {
+    ctx: Rc<Context>,
+    solver: Solver,
+    formulas: Vec<ast::Bool>,
+    products: Vec<Product>,
+    // The index in `products` of each abstracted `x * y`, keyed by the
+    // ASTs of `x` and `y`.
+    index: HashMap<(Z3_ast, Z3_ast), usize>,
+    // Whether the point-independent lemmas of a product are asserted.
+    refined: Vec<bool>,
+    schemata: LemmaSchemata,
+    hook: Option<LemmaHook>,
+    model: Option<Model>,
+}
+
+impl Linearizer {
+    pub fn new(ctx: Rc<Context>) -> Linearizer {
+        Linearizer {
+            solver: Solver::new(ctx.clone()),
+            ctx,
+            formulas: vec![],
+            products: vec![],
+            index: HashMap::new(),
+            refined: vec![],
+            schemata: LemmaSchemata::default(),
+            hook: None,
+            model: None,
+        }
+    }
+
+    /// The solver the abstraction is checked with, for example to set its
+    /// parameters.
+    pub fn solver(&self) -> &Solver {
+        &self.solver
+    }
+
+    /// Choose the built-in lemmas.
+    pub fn set_schemata(&mut self, schemata: LemmaSchemata) {
+        self.schemata = schemata;
+    }
+
+    /// Add lemmas of your own: `hook` is called for every product that a
+    /// spurious model violates, after the built-in lemmas are generated.
+    /// The lemmas it returns must be valid for the product.
+    pub fn lemma_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(&Product, &Model) -> Vec<ast::Bool> + 'static,
+    {
+        self.hook = Some(Box::new(hook));
+    }
+
+    /// Assert `formula`, with its products abstracted.
+    pub fn assert(&mut self, formula: &ast::Bool) {
+        assert_eq!(formula.get_ctx(), self.ctx);
+        let (abstracted, _) = ast::fold(formula, |a, node, children| {
+            self.abstract_node(a, node, children)
+        });
+        self.solver.assert(&abstracted.as_bool().unwrap());
+        self.formulas.push(formula.clone());
+    }
+
+    /// The products abstracted so far.
+    pub fn products(&self) -> &[Product] {
+        &self.products
+    }
+
+    /// Check the asserted formulas, with at most `max_rounds` refinements
+    /// of the abstraction.
+    pub fn check(&mut self, max_rounds: u32) -> SatResult {
+        self.model = None;
+        for round in 0..=max_rounds {
+            match self.solver.check() {
+                SatResult::Sat => {}
+                result => return result,
+            }
+            let model = self.solver.get_model().unwrap();
+            let genuine = self
+                .formulas
+                .iter()
+                .all(|f| model.eval(f, true).and_then(|v| v.as_bool()) == Some(true));
+            if genuine {
+                self.model = Some(model);
+                return SatResult::Sat;
+            }
+            if round == max_rounds {
+                break;
+            }
+            let lemmas = self.lemmas(&model);
+            if lemmas.is_empty() {
+                break;
+            }
+            for lemma in &lemmas {
+                self.solver.assert(lemma);
+            }
+        }
+        SatResult::Unknown
+    }
+
+    /// The model of the last [`Linearizer::check()`], if it was
+    /// satisfiable. It also interprets the constants of the
+    /// [products](Linearizer::products).
+    pub fn get_model(&self) -> Option<&Model> {
+        self.model.as_ref()
+    }
+
+    // Abstract the products of `a`, given its abstracted children, and
+    // tell whether it may contain bound variables. Quantifiers are kept as
+    // they are, and so are products of terms with bound variables.
+    fn abstract_node(
+        &mut self,
+        a: &ast::Dynamic,
+        node: &AstNode,
+        children: Vec<(ast::Dynamic, bool)>,
+    ) -> (ast::Dynamic, bool) {
+        let bound = children.iter().any(|(_, bound)| *bound);
+        let (decl, args) = match node {
+            AstNode::App { decl, args } => (decl, args),
+            AstNode::Numeral { .. } => return (a.clone(), false),
+            AstNode::Var { .. } | AstNode::Quantifier { .. } => return (a.clone(), true),
+        };
+        let children: Vec<_> = children.into_iter().map(|(c, _)| c).collect();
+        let arithmetic = matches!(a.sort_kind(), SortKind::Int | SortKind::Real);
+        if decl.kind() == DeclKind::MUL && arithmetic && !bound {
+            let (numerals, factors): (Vec<_>, Vec<_>) =
+                children.iter().cloned().partition(|c| c.is_numeral());
+            if factors.len() >= 2 {
+                let mut product = factors[0].clone();
+                for y in &factors[1..] {
+                    product = self.product(&product, y);
+                }
+                let mut args: Vec<_> = numerals.iter().collect();
+                args.push(&product);
+                return (apply(&self.ctx, Z3_mk_mul, &args), false);
+            }
+        }
+        if children.iter().zip(args).all(|(c, a)| c == a) {
+            return (a.clone(), bound);
+        }
+        let children: Vec<&dyn Ast> = children.iter().map(|c| c as &dyn Ast).collect();
+        (decl.apply(&children), bound)
+    }
+
+    fn product(&mut self, x: &ast::Dynamic, y: &ast::Dynamic) -> ast::Dynamic {
+        let key = (x.z3_ast, y.z3_ast);
+        let i = match self.index.get(&key) {
+            Some(&i) => i,
+            None => {
+                let mul = apply(&self.ctx, Z3_mk_mul, &[x, y]);
+                let m = ast::Dynamic::fresh_const(self.ctx.clone(), "nl", &mul.get_sort());
+                self.products.push(Product {
+                    m,
+                    x: x.clone(),
+                    y: y.clone(),
+                });
+                self.refined.push(false);
+                self.index.insert(key, self.products.len() - 1);
+                self.products.len() - 1
+            }
+        };
+        self.products[i].m.clone()
+    }
+
+    fn lemmas(&mut self, model: &Model) -> Vec<ast::Bool> {
+        let mut lemmas = vec![];
+        for i in 0..self.products.len() {
+            let p = self.products[i].clone();
+            let value = |a: &ast::Dynamic| model.eval(a, true).unwrap();
+            let (xv, yv, mv) = (value(&p.x), value(&p.y), value(&p.m));
+            let xy = apply(&self.ctx, Z3_mk_mul, &[&xv, &yv]).simplify();
+            if xy == mv.simplify() {
+                continue;
+            }
+
+            let zero = zero(&p.m);
+            if !self.refined[i] {
+                self.refined[i] = true;
+                if self.schemata.zero {
+                    let factor_zero = p.x._eq(&zero) | p.y._eq(&zero);
+                    lemmas.push(p.m._eq(&zero).iff(&factor_zero));
+                }
+                if self.schemata.sign {
+                    let (xp, xn) = (cmp(Z3_mk_gt, &p.x, &zero), cmp(Z3_mk_lt, &p.x, &zero));
+                    let (yp, yn) = (cmp(Z3_mk_gt, &p.y, &zero), cmp(Z3_mk_lt, &p.y, &zero));
+                    let same = (xp.clone() & yp.clone()) | (xn.clone() & yn.clone());
+                    let opposite = (xp & yn) | (xn & yp);
+                    lemmas.push(same.implies(&cmp(Z3_mk_gt, &p.m, &zero)));
+                    lemmas.push(opposite.implies(&cmp(Z3_mk_lt, &p.m, &zero)));
+                }
+            }
+            if self.schemata.tangent {
+                // m - t = (x - xv) * (y - yv) for the tangent plane t at
+                // (xv, yv).
+                let ctx = &self.ctx;
+                let t = apply(
+                    ctx,
+                    Z3_mk_sub,
+                    &[
+                        &apply(
+                            ctx,
+                            Z3_mk_add,
+                            &[
+                                &apply(ctx, Z3_mk_mul, &[&yv, &p.x]),
+                                &apply(ctx, Z3_mk_mul, &[&xv, &p.y]),
+                            ],
+                        ),
+                        &xy,
+                    ],
+                );
+                lemmas.push(
+                    p.x._eq(&xv)
+                        .implies(&p.m._eq(&apply(ctx, Z3_mk_mul, &[&xv, &p.y]))),
+                );
+                lemmas.push(
+                    p.y._eq(&yv)
+                        .implies(&p.m._eq(&apply(ctx, Z3_mk_mul, &[&yv, &p.x]))),
+                );
+                let (xa, xb) = (cmp(Z3_mk_gt, &p.x, &xv), cmp(Z3_mk_lt, &p.x, &xv));
+                let (ya, yb) = (cmp(Z3_mk_gt, &p.y, &yv), cmp(Z3_mk_lt, &p.y, &yv));
+                let same = (xa.clone() & ya.clone()) | (xb.clone() & yb.clone());
+                let opposite = (xa & yb) | (xb & ya);
+                lemmas.push(same.implies(&cmp(Z3_mk_gt, &p.m, &t)));
+                lemmas.push(opposite.implies(&cmp(Z3_mk_lt, &p.m, &t)));
+            }
+            if let Some(hook) = &mut self.hook {
+                lemmas.extend(hook(&p, model));
+            }
+        }
+        lemmas
+    }
+}
+
+impl fmt::Debug for Linearizer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Linearizer")
+            .field("formulas", &self.formulas)
+            .field("products", &self.products)
+            .field("schemata", &self.schemata)
+            .finish()
+    }
+}
+
+fn apply(
+    ctx: &Rc<Context>,
+    f: unsafe extern "C" fn(Z3_context, c_uint, *const Z3_ast) -> Z3_ast,
+    args: &[&ast::Dynamic],
+) -> ast::Dynamic {
+    let args: Vec<_> = args.iter().map(|a| a.z3_ast).collect();
+    unsafe {
+        ast::Dynamic::wrap(
+            ctx.clone(),
+            f(ctx.z3_ctx, args.len() as c_uint, args.as_ptr()),
+        )
+    }
+}
+
+fn cmp(
+    f: unsafe extern "C" fn(Z3_context, Z3_ast, Z3_ast) -> Z3_ast,
+    a: &ast::Dynamic,
+    b: &ast::Dynamic,
+) -> ast::Bool {
+    let ctx = a.get_ctx();
+    unsafe { ast::Bool::wrap(ctx.clone(), f(ctx.z3_ctx, a.z3_ast, b.z3_ast)) }
+}
+
+fn zero(a: &ast::Dynamic) -> ast::Dynamic {
+    let ctx = a.get_ctx();
+    unsafe {
+        let sort = Z3_get_sort(ctx.z3_ctx, a.z3_ast);
+        ast::Dynamic::wrap(ctx.clone(), Z3_mk_int(ctx.z3_ctx, 0, sort))
+    }
+}
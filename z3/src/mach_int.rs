@@ -0,0 +1,329 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Neg, Not, Rem, Shl, Shr, Sub};
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, Model, Symbol};
+
+/// A Rust primitive integer type that can be modelled by a [`MachInt`].
+pub trait MachineInt: Copy + fmt::Debug {
+    /// The width of the type in bits.
+    const BITS: u32;
+    /// Whether the type is signed, i.e. uses two's complement.
+    const SIGNED: bool;
+
+    /// The bits of `self`, zero-extended to 64 bits.
+    fn to_bits(self) -> u64;
+
+    /// The value with the lowest [`Self::BITS`](MachineInt::BITS) bits of
+    /// `bits`.
+    fn from_bits(bits: u64) -> Self;
+}
+
+macro_rules! impl_machine_int {
+    ($($t:ty, $u:ty, $signed:expr;)*) => {
+        $(
+            impl MachineInt for $t {
+                const BITS: u32 = <$t>::BITS;
+                const SIGNED: bool = $signed;
+
+                fn to_bits(self) -> u64 {
+                    self as $u as u64
+                }
+
+                fn from_bits(bits: u64) -> Self {
+                    bits as $u as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_machine_int! {
+    i8, u8, true;
+    i16, u16, true;
+    i32, u32, true;
+    i64, u64, true;
+    isize, usize, true;
+    u8, u8, false;
+    u16, u16, false;
+    u32, u32, false;
+    u64, u64, false;
+    usize, usize, false;
+}
+
+/// A bit-vector term with the semantics of the Rust integer type `T`.
+///
+/// The operators behave like Rust's `wrapping_*` methods (i.e. like
+/// release builds): `/` and `%` truncate towards zero, `>>` is arithmetic
+/// for signed types, and the shift amount is taken modulo the width of
+/// `T`. Comparisons use the signedness of `T`. The conditions under which
+/// an operation would panic in a debug build, or return `None` from the
+/// `checked_*` methods, are available as separate terms, e.g.
+/// [`MachInt::add_overflows()`], to be asserted as side conditions.
+///
+/// Division and remainder by zero are left unspecified by the operators;
+/// see [`MachInt::is_zero()`].
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, Config, Context, MachInt, SatResult, Solver};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = MachInt::<i32>::new_const(ctx.clone(), "x");
+/// let one = MachInt::from_value(ctx.clone(), 1);
+///
+/// // `x + 1 < x` only holds if the addition overflows.
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&(x.clone() + one.clone()).lt(&x));
+/// solver.assert(&!x.add_overflows(&one));
+/// assert_eq!(solver.check(), SatResult::Unsat);
+/// ```
+pub struct MachInt<T: MachineInt> {
+    bv: ast::BV,
+    ty: PhantomData<T>,
+}
+
+impl<T: MachineInt> MachInt<T> {
+    /// Interpret `bv` as a `T`.
+    ///
+    /// # Panics
+    ///
+    /// If `bv` does not have [`T::BITS`](MachineInt::BITS) bits.
+    pub fn from_bv(bv: ast::BV) -> MachInt<T> {
+        assert_eq!(bv.get_size(), T::BITS, "bit-vector has the wrong width");
+        MachInt {
+            bv,
+            ty: PhantomData,
+        }
+    }
+
+    /// The bit-vector term of this integer.
+    pub fn bv(&self) -> &ast::BV {
+        &self.bv
+    }
+
+    pub fn new_const<S: Into<Symbol>>(ctx: Rc<Context>, name: S) -> MachInt<T> {
+        MachInt::from_bv(ast::BV::new_const(ctx, name, T::BITS))
+    }
+
+    pub fn from_value(ctx: Rc<Context>, value: T) -> MachInt<T> {
+        MachInt::from_bv(ast::BV::from_u64(ctx, value.to_bits(), T::BITS))
+    }
+
+    /// Return the value of this integer, if it is a numeral (e.g. after
+    /// [`MachInt::eval()`]).
+    pub fn as_value(&self) -> Option<T> {
+        self.bv.as_u64().map(T::from_bits)
+    }
+
+    /// Evaluate this integer in `model`, see [`Model::eval()`].
+    pub fn eval(&self, model: &Model) -> Option<T> {
+        model.eval(&self.bv, true)?.as_u64().map(T::from_bits)
+    }
+
+    pub fn _eq(&self, other: &MachInt<T>) -> ast::Bool {
+        self.bv._eq(&other.bv)
+    }
+
+    pub fn lt(&self, other: &MachInt<T>) -> ast::Bool {
+        if T::SIGNED {
+            self.bv.bvslt(&other.bv)
+        } else {
+            self.bv.bvult(&other.bv)
+        }
+    }
+
+    pub fn le(&self, other: &MachInt<T>) -> ast::Bool {
+        if T::SIGNED {
+            self.bv.bvsle(&other.bv)
+        } else {
+            self.bv.bvule(&other.bv)
+        }
+    }
+
+    pub fn gt(&self, other: &MachInt<T>) -> ast::Bool {
+        other.lt(self)
+    }
+
+    pub fn ge(&self, other: &MachInt<T>) -> ast::Bool {
+        other.le(self)
+    }
+
+    /// Whether this integer is zero, i.e. whether dividing by it panics.
+    pub fn is_zero(&self) -> ast::Bool {
+        self.bv
+            ._eq(&ast::BV::from_u64(self.bv.get_ctx(), 0, T::BITS))
+    }
+
+    /// Whether `self + other` overflows.
+    pub fn add_overflows(&self, other: &MachInt<T>) -> ast::Bool {
+        let ok = self.bv.bvadd_no_overflow(&other.bv, T::SIGNED);
+        if T::SIGNED {
+            !(ok & self.bv.bvadd_no_underflow(&other.bv))
+        } else {
+            !ok
+        }
+    }
+
+    /// Whether `self - other` overflows.
+    pub fn sub_overflows(&self, other: &MachInt<T>) -> ast::Bool {
+        let ok = self.bv.bvsub_no_underflow(&other.bv, T::SIGNED);
+        if T::SIGNED {
+            !(ok & self.bv.bvsub_no_overflow(&other.bv))
+        } else {
+            !ok
+        }
+    }
+
+    /// Whether `self * other` overflows.
+    pub fn mul_overflows(&self, other: &MachInt<T>) -> ast::Bool {
+        // Compute the exact product at twice the width, rather than using
+        // `bvmul_no_overflow()`, which some Z3 versions get wrong for
+        // signed operands.
+        let ext = |bv: &ast::BV| {
+            if T::SIGNED {
+                bv.sign_ext(T::BITS)
+            } else {
+                bv.zero_ext(T::BITS)
+            }
+        };
+        let wide = ext(&self.bv).bvmul(&ext(&other.bv));
+        !wide._eq(&ext(&wide.extract(T::BITS - 1, 0)))
+    }
+
+    /// Whether `self / other` (or `self % other`) overflows, which only
+    /// happens for `MIN / -1` of a signed type. Division by zero is
+    /// checked by [`MachInt::is_zero()`].
+    pub fn div_overflows(&self, other: &MachInt<T>) -> ast::Bool {
+        if T::SIGNED {
+            !self.bv.bvsdiv_no_overflow(&other.bv)
+        } else {
+            ast::Bool::from_bool(self.bv.get_ctx(), false)
+        }
+    }
+
+    /// Whether `-self` overflows. For unsigned types, that is whenever
+    /// `self` is not zero.
+    pub fn neg_overflows(&self) -> ast::Bool {
+        if T::SIGNED {
+            !self.bv.bvneg_no_overflow()
+        } else {
+            !self.is_zero()
+        }
+    }
+
+    /// Whether shifting by `amount` overflows, i.e. whether `amount` is at
+    /// least the width of `T`.
+    pub fn shift_overflows(amount: &MachInt<u32>) -> ast::Bool {
+        let bits = ast::BV::from_u64(amount.bv.get_ctx(), T::BITS as u64, 32);
+        amount.bv.bvuge(&bits)
+    }
+
+    /// The shift amount `amount mod T::BITS`, at the width of `T`.
+    fn shift_amount(&self, amount: &MachInt<u32>) -> ast::BV {
+        let ctx = self.bv.get_ctx();
+        let masked = amount
+            .bv
+            .bvand(&ast::BV::from_u64(ctx, (T::BITS - 1) as u64, 32));
+        match T::BITS {
+            32 => masked,
+            n if n < 32 => masked.extract(n - 1, 0),
+            n => masked.zero_ext(n - 32),
+        }
+    }
+}
+
+impl<T: MachineInt> Clone for MachInt<T> {
+    fn clone(&self) -> Self {
+        MachInt::from_bv(self.bv.clone())
+    }
+}
+
+impl<T: MachineInt> PartialEq for MachInt<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bv == other.bv
+    }
+}
+
+impl<T: MachineInt> Eq for MachInt<T> {}
+
+impl<T: MachineInt> fmt::Debug for MachInt<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self.as_value() {
+            Some(v) => write!(f, "{:?}", v),
+            None => write!(f, "{}", self.bv),
+        }
+    }
+}
+
+impl<T: MachineInt> fmt::Display for MachInt<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        <Self as fmt::Debug>::fmt(self, f)
+    }
+}
+
+macro_rules! impl_mach_int_binop {
+    ($($trait:ident, $f:ident, |$a:ident, $b:ident| $body:expr;)*) => {
+        $(
+            impl<T: MachineInt> $trait for MachInt<T> {
+                type Output = MachInt<T>;
+
+                fn $f(self, other: MachInt<T>) -> MachInt<T> {
+                    let ($a, $b) = (&self.bv, &other.bv);
+                    MachInt::from_bv($body)
+                }
+            }
+        )*
+    };
+}
+
+impl_mach_int_binop! {
+    Add, add, |a, b| a.bvadd(b);
+    Sub, sub, |a, b| a.bvsub(b);
+    Mul, mul, |a, b| a.bvmul(b);
+    Div, div, |a, b| if T::SIGNED { a.bvsdiv(b) } else { a.bvudiv(b) };
+    Rem, rem, |a, b| if T::SIGNED { a.bvsrem(b) } else { a.bvurem(b) };
+    BitAnd, bitand, |a, b| a.bvand(b);
+    BitOr, bitor, |a, b| a.bvor(b);
+    BitXor, bitxor, |a, b| a.bvxor(b);
+}
+
+impl<T: MachineInt> Shl<MachInt<u32>> for MachInt<T> {
+    type Output = MachInt<T>;
+
+    fn shl(self, amount: MachInt<u32>) -> MachInt<T> {
+        MachInt::from_bv(self.bv.bvshl(&self.shift_amount(&amount)))
+    }
+}
+
+impl<T: MachineInt> Shr<MachInt<u32>> for MachInt<T> {
+    type Output = MachInt<T>;
+
+    fn shr(self, amount: MachInt<u32>) -> MachInt<T> {
+        let amount = self.shift_amount(&amount);
+        MachInt::from_bv(if T::SIGNED {
+            self.bv.bvashr(&amount)
+        } else {
+            self.bv.bvlshr(&amount)
+        })
+    }
+}
+
+impl<T: MachineInt> Neg for MachInt<T> {
+    type Output = MachInt<T>;
+
+    fn neg(self) -> MachInt<T> {
+        MachInt::from_bv(self.bv.bvneg())
+    }
+}
+
+impl<T: MachineInt> Not for MachInt<T> {
+    type Output = MachInt<T>;
+
+    fn not(self) -> MachInt<T> {
+        MachInt::from_bv(self.bv.bvnot())
+    }
+}
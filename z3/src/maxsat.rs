@@ -0,0 +1,175 @@
+use crate::ast::Bool;
+use crate::{Model, SatResult, Solver};
+
+/// An unsat core found by [`Solver::check_maxsat_with()`], passed to its
+/// callback.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaxSatCore {
+    /// The number of cores found so far, counting this one.
+    pub iteration: usize,
+    /// The indices of the soft constraints in the core, in increasing
+    /// order.
+    pub core: Vec<usize>,
+    /// A lower bound on the weight of the violated soft constraints,
+    /// taking this core into account.
+    pub lower_bound: u64,
+}
+
+/// The result of [`Solver::check_maxsat()`].
+#[derive(Debug)]
+pub struct MaxSatOutcome {
+    /// [`SatResult::Sat`] if an optimal model was found,
+    /// [`SatResult::Unsat`] if the hard constraints are unsatisfiable, and
+    /// [`SatResult::Unknown`] if the solver gave up or the callback stopped
+    /// the search.
+    pub result: SatResult,
+    /// The weight of the violated soft constraints if `result` is
+    /// [`SatResult::Sat`], and otherwise the best lower bound on it.
+    pub cost: u64,
+    /// The optimal model, if `result` is [`SatResult::Sat`].
+    pub model: Option<Model>,
+    /// The indices of the soft constraints the model violates, in
+    /// increasing order, or none if there is no model.
+    pub violated: Vec<usize>,
+    /// The number of unsat cores found.
+    pub cores: usize,
+}
+
+impl Solver {
+    /// Find a model of the assertions that minimizes the total weight of
+    /// the violated `soft` constraints, with [`Solver::check_maxsat_with()`]
+    /// and no callback.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, Config, Context, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let a = ast::Bool::new_const(ctx.clone(), "a");
+    /// let b = ast::Bool::new_const(ctx.clone(), "b");
+    /// let c = ast::Bool::new_const(ctx.clone(), "c");
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&!(a.clone() & b.clone()));
+    /// solver.assert(&!(b.clone() & c.clone()));
+    ///
+    /// // Either `b`, or both `a` and `c`.
+    /// let outcome = solver.check_maxsat(&[(&a, 2), (&b, 3), (&c, 2)]);
+    /// assert_eq!(outcome.result, SatResult::Sat);
+    /// assert_eq!(outcome.cost, 3);
+    /// assert_eq!(outcome.violated, [1]);
+    /// // The relaxation of the soft constraints is undone.
+    /// assert_eq!(solver.get_assertions().len(), 2);
+    /// ```
+    pub fn check_maxsat(&self, soft: &[(&Bool, u64)]) -> MaxSatOutcome {
+        self.check_maxsat_with(soft, |_| true)
+    }
+
+    /// Find a model of the assertions that minimizes the total weight of
+    /// the violated `soft` constraints, calling `on_core` for each unsat
+    /// core on the way. The search stops with [`SatResult::Unknown`] when
+    /// `on_core` returns `false`.
+    ///
+    /// This is the WPM1 algorithm, the weighted version of Fu and Malik's:
+    /// each soft constraint is checked as an assumption, and each unsat
+    /// core of them is relaxed, with a fresh literal added to every
+    /// constraint in it, of which exactly one may be true. Constraints of
+    /// a greater weight than the least one in the core are split first, and
+    /// the least weight adds to the lower bound on the cost. With no
+    /// arithmetic on the weights in the solver, this is often faster than
+    /// [`Optimize`](crate::Optimize) for Boolean soft constraints.
+    ///
+    /// The relaxation is asserted in a [scope](Solver::push), which is
+    /// popped before returning. Soft constraints of weight `0` are ignored.
+    pub fn check_maxsat_with<F>(&self, soft: &[(&Bool, u64)], mut on_core: F) -> MaxSatOutcome
+    where
+        F: FnMut(&MaxSatCore) -> bool,
+    {
+        let ctx = self.get_context();
+        let select = |f: &Bool| {
+            let selector = Bool::fresh_const(ctx.clone(), "maxsat");
+            self.assert(&selector.implies(f));
+            selector
+        };
+        let finish = |result, cost, model, violated, cores| MaxSatOutcome {
+            result,
+            cost,
+            model,
+            violated,
+            cores,
+        };
+
+        self.push();
+        // The working soft constraints: the index of the original one, the
+        // relaxed formula, the assumption standing for it, and the weight.
+        let mut working: Vec<(usize, Bool, Bool, u64)> = soft
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, w))| *w > 0)
+            .map(|(i, (f, w))| (i, (*f).clone(), select(f), *w))
+            .collect();
+        let mut lower_bound = 0;
+        let mut cores = 0;
+        let result = loop {
+            let selectors: Vec<Bool> = working.iter().map(|(_, _, s, _)| s.clone()).collect();
+            match self.check_assumptions(&selectors) {
+                SatResult::Sat => {
+                    let model = self.get_model().unwrap();
+                    let violated: Vec<usize> = soft
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, (f, _))| model.eval(*f, true).unwrap().as_bool() != Some(true))
+                        .map(|(i, _)| i)
+                        .collect();
+                    let cost = violated.iter().map(|&i| soft[i].1).sum();
+                    break finish(SatResult::Sat, cost, Some(model), violated, cores);
+                }
+                SatResult::Unknown => {
+                    break finish(SatResult::Unknown, lower_bound, None, vec![], cores);
+                }
+                SatResult::Unsat => {}
+            }
+
+            let core: Vec<usize> = self
+                .get_unsat_core()
+                .iter()
+                .filter_map(|c| selectors.iter().position(|s| s == c))
+                .collect();
+            if core.is_empty() {
+                break finish(SatResult::Unsat, lower_bound, None, vec![], cores);
+            }
+            cores += 1;
+            let least = core.iter().map(|&i| working[i].3).min().unwrap();
+            lower_bound += least;
+
+            let mut relaxations = vec![];
+            for &i in &core {
+                let (original, f, _, w) = working[i].clone();
+                if w > least {
+                    working.push((original, f.clone(), select(&f), w - least));
+                }
+                let relax = Bool::fresh_const(ctx.clone(), "relax");
+                let relaxed = Bool::or(ctx.clone(), &[&f, &relax]);
+                working[i] = (original, relaxed.clone(), select(&relaxed), least);
+                relaxations.push(relax);
+            }
+            let relaxations: Vec<(&Bool, i32)> = relaxations.iter().map(|r| (r, 1)).collect();
+            self.assert(&Bool::pb_eq(ctx.clone(), &relaxations, 1));
+
+            let mut originals: Vec<usize> = core.iter().map(|&i| working[i].0).collect();
+            originals.sort_unstable();
+            originals.dedup();
+            let progress = MaxSatCore {
+                iteration: cores,
+                core: originals,
+                lower_bound,
+            };
+            if !on_core(&progress) {
+                break finish(SatResult::Unknown, lower_bound, None, vec![], cores);
+            }
+        };
+        self.pop(1);
+        result
+    }
+}
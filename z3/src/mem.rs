@@ -0,0 +1,168 @@
+//! A byte-addressable memory model, as used by binary analysis.
+//!
+//! A [`Memory`] is an array from bit-vector addresses to bytes. Multi-byte
+//! values are loaded and stored byte by byte in a chosen [`Endian`]
+//! order, and [`is_aligned()`] gives the side condition of architectures
+//! that require aligned accesses.
+//!
+//! # Examples
+//!
+//! ```
+//! # use std::rc::Rc;
+//! # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver};
+//! use z3::mem::{Endian, Memory};
+//!
+//! let cfg = Config::new();
+//! let ctx = Rc::new(Context::new(&cfg));
+//! let mem = Memory::new_const(ctx.clone(), "mem", 32);
+//! let addr = ast::BV::new_const(ctx.clone(), "addr", 32);
+//! let value = ast::BV::from_u64(ctx.clone(), 0x1234_5678, 32);
+//!
+//! let mem = mem.store(&addr, &value, Endian::Little);
+//! let solver = Solver::new(ctx.clone());
+//! // The least significant byte is stored first.
+//! let first = mem.load(&addr, 1, Endian::Little);
+//! solver.assert(&!first._eq(&ast::BV::from_u64(ctx.clone(), 0x78, 8)));
+//! assert_eq!(solver.check(), SatResult::Unsat);
+//! ```
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, Sort, Symbol};
+
+/// The order of the bytes of a multi-byte value in a [`Memory`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endian {
+    /// The least significant byte is at the lowest address, like on x86.
+    Little,
+    /// The most significant byte is at the lowest address.
+    Big,
+}
+
+/// A byte-addressable memory: an array from `addr_bits`-wide bit-vector
+/// addresses to 8-bit bit-vectors.
+///
+/// Memories are immutable terms; [`Memory::store()`] returns the updated
+/// memory. Accesses wrap around at the end of the address space.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Memory {
+    bytes: ast::Array,
+    addr_bits: u32,
+}
+
+impl Memory {
+    /// Create a memory of unconstrained bytes.
+    pub fn new_const<S: Into<Symbol>>(ctx: Rc<Context>, name: S, addr_bits: u32) -> Memory {
+        let domain = Sort::bitvector(ctx.clone(), addr_bits);
+        let range = Sort::bitvector(ctx.clone(), 8);
+        Memory {
+            bytes: ast::Array::new_const(ctx, name, &domain, &range),
+            addr_bits,
+        }
+    }
+
+    /// Create a memory in which every byte is zero.
+    pub fn zeroed(ctx: Rc<Context>, addr_bits: u32) -> Memory {
+        let domain = Sort::bitvector(ctx.clone(), addr_bits);
+        let zero = ast::BV::from_u64(ctx.clone(), 0, 8);
+        Memory {
+            bytes: ast::Array::const_array(ctx, &domain, &zero),
+            addr_bits,
+        }
+    }
+
+    /// Interpret `bytes` as a memory.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is not an array from bit-vectors to 8-bit bit-vectors.
+    pub fn from_array(bytes: ast::Array) -> Memory {
+        let sort = bytes.get_sort();
+        let (domain, range) = (sort.array_domain().unwrap(), sort.array_range().unwrap());
+        match (domain.bv_size(), range.bv_size()) {
+            (Some(addr_bits), Some(8)) => Memory { bytes, addr_bits },
+            _ => panic!("not an array from bit-vectors to bytes"),
+        }
+    }
+
+    /// The array of bytes of this memory.
+    pub fn array(&self) -> &ast::Array {
+        &self.bytes
+    }
+
+    /// The width of the addresses of this memory.
+    pub fn addr_bits(&self) -> u32 {
+        self.addr_bits
+    }
+
+    /// Load the `size` bytes at `addr` as a `size * 8`-bit value.
+    ///
+    /// # Panics
+    ///
+    /// If `size` is zero, or `addr` is not `addr_bits` wide.
+    pub fn load(&self, addr: &ast::BV, size: u32, endian: Endian) -> ast::BV {
+        assert!(size > 0, "can not load zero bytes");
+        // In address order, i.e. most significant first for big endian.
+        let mut bytes: Vec<ast::BV> = (0..size)
+            .map(|i| self.bytes.select(&self.offset(addr, i)).as_bv().unwrap())
+            .collect();
+        if endian == Endian::Little {
+            bytes.reverse();
+        }
+        bytes.into_iter().reduce(|hi, lo| hi.concat(&lo)).unwrap()
+    }
+
+    /// Store `value` at `addr`, and return the updated memory.
+    ///
+    /// # Panics
+    ///
+    /// If the width of `value` is not a positive multiple of 8, or `addr`
+    /// is not `addr_bits` wide.
+    pub fn store(&self, addr: &ast::BV, value: &ast::BV, endian: Endian) -> Memory {
+        let bits = value.get_size();
+        assert!(
+            bits > 0 && bits.is_multiple_of(8),
+            "value is not a whole number of bytes"
+        );
+        let size = bits / 8;
+        let bytes = (0..size).fold(self.bytes.clone(), |bytes, i| {
+            let byte = match endian {
+                Endian::Little => i,
+                Endian::Big => size - 1 - i,
+            };
+            let value = value.extract(byte * 8 + 7, byte * 8);
+            bytes.store(&self.offset(addr, i), &value)
+        });
+        Memory {
+            bytes,
+            addr_bits: self.addr_bits,
+        }
+    }
+
+    /// `addr + i`.
+    fn offset(&self, addr: &ast::BV, i: u32) -> ast::BV {
+        assert_eq!(
+            addr.get_size(),
+            self.addr_bits,
+            "address has the wrong width"
+        );
+        if i == 0 {
+            addr.clone()
+        } else {
+            addr.bvadd(&ast::BV::from_u64(addr.get_ctx(), i as u64, self.addr_bits))
+        }
+    }
+}
+
+/// Whether `addr` is a multiple of `align`, i.e. whether an access of
+/// `align` bytes at `addr` is aligned.
+///
+/// # Panics
+///
+/// If `align` is not a power of two.
+pub fn is_aligned(addr: &ast::BV, align: u64) -> ast::Bool {
+    assert!(align.is_power_of_two(), "alignment is not a power of two");
+    let ctx = addr.get_ctx();
+    let bits = addr.get_size();
+    let mask = ast::BV::from_u64(ctx.clone(), align - 1, bits);
+    addr.bvand(&mask)._eq(&ast::BV::from_u64(ctx, 0, bits))
+}
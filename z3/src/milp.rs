@@ -0,0 +1,140 @@
+use crate::Rc;
+use crate::{ast, Context};
+
+/// Return the indicator constraint `b => constraint`: `constraint` has to
+/// hold whenever `b` does, and is relaxed otherwise.
+///
+/// Z3 handles such implications directly; [`Linear::big_m_le()`] and
+/// friends give the linear big-M form that MILP encodings use instead.
+pub fn indicator(b: &ast::Bool, constraint: &ast::Bool) -> ast::Bool {
+    b.implies(constraint)
+}
+
+/// A linear expression `c_1 * x_1 + ... + c_n * x_n + c` over bounded real
+/// variables, for generating big-M constraints.
+///
+/// Every variable is added with the bounds it is declared to have, from
+/// which [`Linear::big_m_le()`] and [`Linear::big_m_ge()`] compute the
+/// smallest valid M. The generated constraints are only equivalent to
+/// their indicator constraints if the bounds hold, so they should be
+/// asserted together with [`Linear::bound_constraints()`].
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, Linear, SatResult, Solver};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Real::new_const(ctx.clone(), "x");
+/// let y = ast::Real::new_const(ctx.clone(), "y");
+/// let open = ast::Bool::new_const(ctx.clone(), "open");
+///
+/// // open => 2x + y <= 10, with 0 <= x <= 100 and 0 <= y <= 50.
+/// let expr = Linear::new(ctx.clone()).term(2, &x, 0, 100).term(1, &y, 0, 50);
+/// assert_eq!(expr.bounds(), (0, 250));
+///
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&expr.bound_constraints());
+/// solver.assert(&expr.big_m_le(&open, 10));
+/// solver.assert(&open);
+/// solver.assert(&x.gt(&ast::Real::from_real(ctx.clone(), 5, 1)));
+/// assert_eq!(solver.check(), SatResult::Unsat);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Linear {
+    ctx: Rc<Context>,
+    terms: Vec<(i64, ast::Real, i64, i64)>,
+    constant: i64,
+}
+
+impl Linear {
+    /// The expression `0`.
+    pub fn new(ctx: Rc<Context>) -> Linear {
+        Linear {
+            ctx,
+            terms: vec![],
+            constant: 0,
+        }
+    }
+
+    /// Add `coef * var`, where `lower <= var <= upper`.
+    ///
+    /// # Panics
+    ///
+    /// If `lower > upper`.
+    pub fn term(mut self, coef: i64, var: &ast::Real, lower: i64, upper: i64) -> Linear {
+        assert!(lower <= upper, "empty bounds");
+        self.terms.push((coef, var.clone(), lower, upper));
+        self
+    }
+
+    /// Add the constant `c`.
+    pub fn constant(mut self, c: i64) -> Linear {
+        self.constant += c;
+        self
+    }
+
+    /// The smallest and largest value of this expression within the
+    /// declared bounds of its variables.
+    pub fn bounds(&self) -> (i128, i128) {
+        self.terms.iter().fold(
+            (self.constant as i128, self.constant as i128),
+            |(lo, hi), &(c, _, l, u)| {
+                let (a, b) = (c as i128 * l as i128, c as i128 * u as i128);
+                (lo + a.min(b), hi + a.max(b))
+            },
+        )
+    }
+
+    /// The declared bounds of the variables, as a constraint.
+    pub fn bound_constraints(&self) -> ast::Bool {
+        let bounds: Vec<ast::Bool> = self
+            .terms
+            .iter()
+            .map(|(_, v, l, u)| v.ge(&self.real(*l as i128)) & v.le(&self.real(*u as i128)))
+            .collect();
+        ast::Bool::and(self.ctx.clone(), &bounds)
+    }
+
+    /// This expression as a Z3 term.
+    pub fn to_real(&self) -> ast::Real {
+        self.terms
+            .iter()
+            .fold(self.real(self.constant as i128), |sum, (c, v, _, _)| {
+                sum + self.real(*c as i128) * v.clone()
+            })
+    }
+
+    /// Encode `b => self <= rhs` as `self <= rhs + M * (1 - b)`, with the
+    /// smallest M for which the constraint is relaxed whenever `b` is
+    /// false.
+    pub fn big_m_le(&self, b: &ast::Bool, rhs: i64) -> ast::Bool {
+        let m = (self.bounds().1 - rhs as i128).max(0);
+        let slack = self.real(m) * self.not_indicator(b);
+        self.to_real().le(&(self.real(rhs as i128) + slack))
+    }
+
+    /// Encode `b => self >= rhs` as `self >= rhs - M * (1 - b)`, with the
+    /// smallest valid M, like [`Linear::big_m_le()`].
+    pub fn big_m_ge(&self, b: &ast::Bool, rhs: i64) -> ast::Bool {
+        let m = (rhs as i128 - self.bounds().0).max(0);
+        let slack = self.real(m) * self.not_indicator(b);
+        self.to_real().ge(&(self.real(rhs as i128) - slack))
+    }
+
+    /// Encode `b => self == rhs` with both [`Linear::big_m_le()`] and
+    /// [`Linear::big_m_ge()`].
+    pub fn big_m_eq(&self, b: &ast::Bool, rhs: i64) -> ast::Bool {
+        self.big_m_le(b, rhs) & self.big_m_ge(b, rhs)
+    }
+
+    /// `1 - b` as a real number.
+    fn not_indicator(&self, b: &ast::Bool) -> ast::Real {
+        b.ite(&self.real(0), &self.real(1))
+    }
+
+    fn real(&self, v: i128) -> ast::Real {
+        ast::Real::from_real_str(self.ctx.clone(), &v.to_string(), "1").unwrap()
+    }
+}
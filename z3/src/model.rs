@@ -1,17 +1,57 @@
 use std::ffi::CStr;
 use std::fmt;
-use std::rc::Rc;
 
 use z3_sys::*;
 
-use crate::{ast::Ast, Context, FuncDecl, FuncInterp, Model, Optimize, Solver};
+use crate::conversion::c_len;
+use crate::Rc;
+use crate::{
+    ast::Ast, ast::Dynamic, Context, FuncDecl, FuncInterp, Model, Optimize, Solver, Sort, Symbol,
+};
 
 impl Model {
-    unsafe fn wrap(ctx: Rc<Context>, z3_mdl: Z3_model) -> Model {
+    pub(crate) unsafe fn wrap(ctx: Rc<Context>, z3_mdl: Z3_model) -> Model {
         Z3_model_inc_ref(ctx.z3_ctx, z3_mdl);
+        crate::track_live(ctx.z3_ctx, "Model", 1);
         Model { ctx, z3_mdl }
     }
 
+    /// Create an empty model, to be filled in with
+    /// [`Model::add_const_interp()`] and [`Model::add_func_interp()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, FuncDecl, Model, Sort};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let f = FuncDecl::new(ctx.clone(), "f", &[&Sort::int(ctx.clone())], &Sort::int(ctx.clone()));
+    ///
+    /// let model = Model::new(ctx.clone());
+    /// model.add_const_interp(&x.decl(), &ast::Int::from_i64(ctx.clone(), 3));
+    /// let interp = model.add_func_interp(&f, &ast::Int::from_i64(ctx.clone(), 0));
+    /// interp.add_entry(&[x.clone().into()], &ast::Int::from_i64(ctx.clone(), 7).into());
+    ///
+    /// // The candidate invariant f(x) > x holds in this model.
+    /// let f_x = f.apply(&[&x]).as_int().unwrap();
+    /// let inv = f_x.gt(&x);
+    /// assert_eq!(model.eval(&inv, false).unwrap().as_bool(), Some(true));
+    /// ```
+    pub fn new(ctx: Rc<Context>) -> Model {
+        unsafe {
+            let m = Z3_mk_model(ctx.z3_ctx);
+            Self::wrap(ctx, m)
+        }
+    }
+
+    /// A copy of this model, which can be changed without changing this
+    /// one.
+    pub fn copy(&self) -> Model {
+        self.translate(self.ctx.clone())
+    }
+
     pub fn of_solver(slv: &Solver) -> Option<Model> {
         unsafe {
             let m = Z3_solver_get_model(slv.ctx.z3_ctx, slv.z3_slv);
@@ -115,24 +155,188 @@ impl Model {
         }
     }
 
+    /// Interpret the constant `decl` as `value`, replacing any
+    /// interpretation it had.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `decl` takes arguments, or if `value` is not of the sort
+    /// of `decl`.
+    pub fn add_const_interp(&self, decl: &FuncDecl, value: &dyn Ast) {
+        assert_eq!(self.ctx.z3_ctx, decl.ctx.z3_ctx);
+        assert_eq!(self.ctx.z3_ctx, value.get_ctx().z3_ctx);
+        assert_eq!(decl.arity(), 0, "{} is not a constant", decl.name());
+        assert_eq!(
+            decl.range(),
+            value.get_sort(),
+            "value of {} has the wrong sort",
+            decl.name()
+        );
+        unsafe {
+            Z3_add_const_interp(
+                self.ctx.z3_ctx,
+                self.z3_mdl,
+                decl.z3_func_decl,
+                value.get_z3_ast(),
+            )
+        };
+    }
+
+    /// Interpret the function `decl` as `default` on all arguments, and
+    /// return the interpretation, to add entries with
+    /// [`FuncInterp::add_entry()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `default` is not of the range sort of `decl`.
+    pub fn add_func_interp(&self, decl: &FuncDecl, default: &dyn Ast) -> FuncInterp {
+        assert_eq!(self.ctx.z3_ctx, decl.ctx.z3_ctx);
+        assert_eq!(self.ctx.z3_ctx, default.get_ctx().z3_ctx);
+        assert_eq!(
+            decl.range(),
+            default.get_sort(),
+            "default of {} has the wrong sort",
+            decl.name()
+        );
+        unsafe {
+            let interp = Z3_add_func_interp(
+                self.ctx.z3_ctx,
+                self.z3_mdl,
+                decl.z3_func_decl,
+                default.get_z3_ast(),
+            );
+            FuncInterp::wrap(self.ctx.clone(), interp)
+        }
+    }
+
     pub fn eval<T>(&self, ast: &T, model_completion: bool) -> Option<T>
     where
         T: Ast,
     {
-        let mut tmp: Z3_ast = ast.get_z3_ast();
-        let res = {
+        self.eval_z3_ast(ast.get_z3_ast(), model_completion)
+            .map(|v| unsafe { T::wrap(self.ctx.clone(), v) })
+    }
+
+    /// Evaluate each of the given `asts` in the `Model`, in order.
+    ///
+    /// The `i`-th entry of the result is what [`Model::eval()`] returns for
+    /// `asts[i]`, but all of them are evaluated in one call into Z3, which
+    /// also evaluates the subterms they share only once.
+    ///
+    /// See [`Model::eval_many_dyn()`] for ASTs of different sorts.
+    pub fn eval_many<T>(&self, asts: &[T], model_completion: bool) -> Vec<Option<T>>
+    where
+        T: Ast,
+    {
+        let asts: Vec<Z3_ast> = asts.iter().map(|a| a.get_z3_ast()).collect();
+        self.eval_z3_asts(&asts, model_completion, |v| unsafe {
+            T::wrap(self.ctx.clone(), v)
+        })
+    }
+
+    /// Evaluate each of the given `asts`, which can be of different sorts,
+    /// in the `Model`, in order, like [`Model::eval_many()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let b = ast::Bool::new_const(ctx.clone(), "b");
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&x._eq(&ast::Int::from_i64(ctx.clone(), 5)));
+    /// solver.assert(&b);
+    /// assert_eq!(solver.check(), SatResult::Sat);
+    /// let model = solver.get_model().unwrap();
+    ///
+    /// let values = model.eval_many_dyn(&[&x, &b], true);
+    /// assert_eq!(values[0].as_ref().unwrap().as_int().unwrap().as_i64(), Some(5));
+    /// assert_eq!(values[1].as_ref().unwrap().as_bool().unwrap().as_bool(), Some(true));
+    /// ```
+    pub fn eval_many_dyn(&self, asts: &[&dyn Ast], model_completion: bool) -> Vec<Option<Dynamic>> {
+        let asts: Vec<Z3_ast> = asts.iter().map(|a| a.get_z3_ast()).collect();
+        self.eval_z3_asts(&asts, model_completion, |v| unsafe {
+            Dynamic::wrap(self.ctx.clone(), v)
+        })
+    }
+
+    /// Evaluate `asts` in one call to [`Z3_model_eval`], as the fields of a
+    /// tuple, and `wrap` the value of each of them.
+    ///
+    /// If the tuple does not evaluate to a tuple of values, every AST is
+    /// evaluated on its own, so that only the ones that fail are `None`.
+    fn eval_z3_asts<R>(
+        &self,
+        asts: &[Z3_ast],
+        model_completion: bool,
+        wrap: impl Fn(Z3_ast) -> R,
+    ) -> Vec<Option<R>> {
+        let z3_ctx = self.ctx.z3_ctx;
+        if asts.len() > 1 {
             unsafe {
-                Z3_model_eval(
-                    self.ctx.z3_ctx,
-                    self.z3_mdl,
-                    ast.get_z3_ast(),
-                    model_completion,
-                    &mut tmp,
-                )
+                let n = c_len(asts.len());
+                let names: Vec<Z3_symbol> =
+                    (0..n).map(|i| Z3_mk_int_symbol(z3_ctx, i as _)).collect();
+                let sorts: Vec<Z3_sort> = asts.iter().map(|&a| Z3_get_sort(z3_ctx, a)).collect();
+                let mut mk_tuple = std::ptr::null_mut();
+                let mut projections = vec![std::ptr::null_mut(); asts.len()];
+                let _sort = Sort::wrap(
+                    self.ctx.clone(),
+                    Z3_mk_tuple_sort(
+                        z3_ctx,
+                        Symbol::from("eval_many!").as_z3_symbol(&self.ctx),
+                        n,
+                        names.as_ptr(),
+                        sorts.as_ptr(),
+                        &mut mk_tuple,
+                        projections.as_mut_ptr(),
+                    ),
+                );
+                let mk_tuple = FuncDecl::wrap(self.ctx.clone(), mk_tuple);
+                let tuple = Dynamic::wrap(
+                    self.ctx.clone(),
+                    Z3_mk_app(z3_ctx, mk_tuple.z3_func_decl, n, asts.as_ptr()),
+                );
+                if let Some(value) = self.eval_z3_ast(tuple.z3_ast, model_completion) {
+                    // Keep the tuple alive while its fields are wrapped.
+                    let value = Dynamic::wrap(self.ctx.clone(), value);
+                    if Z3_is_app(z3_ctx, value.z3_ast) {
+                        let app = Z3_to_app(z3_ctx, value.z3_ast);
+                        if Z3_is_eq_func_decl(
+                            z3_ctx,
+                            Z3_get_app_decl(z3_ctx, app),
+                            mk_tuple.z3_func_decl,
+                        ) && Z3_get_app_num_args(z3_ctx, app) == n
+                        {
+                            return (0..n)
+                                .map(|i| Some(wrap(Z3_get_app_arg(z3_ctx, app, i))))
+                                .collect();
+                        }
+                    }
+                }
             }
+        }
+        asts.iter()
+            .map(|&a| self.eval_z3_ast(a, model_completion).map(&wrap))
+            .collect()
+    }
+
+    fn eval_z3_ast(&self, ast: Z3_ast, model_completion: bool) -> Option<Z3_ast> {
+        let mut tmp: Z3_ast = ast;
+        let res = unsafe {
+            Z3_model_eval(
+                self.ctx.z3_ctx,
+                self.z3_mdl,
+                ast,
+                model_completion,
+                &mut tmp,
+            )
         };
         if res {
-            Some(unsafe { T::wrap(self.ctx.clone(), tmp) })
+            Some(tmp)
         } else {
             None
         }
@@ -171,6 +375,7 @@ impl fmt::Debug for Model {
 
 impl Drop for Model {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "Model", -1);
         unsafe { Z3_model_dec_ref(self.ctx.z3_ctx, self.z3_mdl) };
     }
 }
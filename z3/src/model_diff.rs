@@ -0,0 +1,169 @@
+use crate::{ast::Ast, ast::Dynamic, FuncDecl, FuncInterp, Model};
+
+/// How two models interpret a declaration differently, as found by
+/// [`Model::diff()`].
+#[derive(Debug)]
+pub enum ModelDifference {
+    /// Only the first model interprets the declaration.
+    OnlyInFirst(FuncDecl),
+    /// Only the second model interprets the declaration.
+    OnlyInSecond(FuncDecl),
+    /// The models give a constant different values.
+    Const {
+        decl: FuncDecl,
+        first: Dynamic,
+        second: Dynamic,
+    },
+    /// The models give a function different values.
+    Func {
+        decl: FuncDecl,
+        /// The arguments, among those listed by either interpretation,
+        /// on which the values differ.
+        points: Vec<DiffPoint>,
+        /// The default values, if they differ syntactically.
+        else_values: Option<(Dynamic, Dynamic)>,
+    },
+}
+
+impl ModelDifference {
+    /// The declaration that is interpreted differently.
+    pub fn decl(&self) -> &FuncDecl {
+        match self {
+            ModelDifference::OnlyInFirst(decl)
+            | ModelDifference::OnlyInSecond(decl)
+            | ModelDifference::Const { decl, .. }
+            | ModelDifference::Func { decl, .. } => decl,
+        }
+    }
+}
+
+/// Arguments on which two models give a function different values.
+#[derive(Debug)]
+pub struct DiffPoint {
+    pub args: Vec<Dynamic>,
+    pub first: Dynamic,
+    pub second: Dynamic,
+}
+
+impl Model {
+    /// Compare the interpretations of `symbols` in this model and in
+    /// `other`, and return how they differ, in the order of `symbols`.
+    ///
+    /// Values are compared syntactically, which is exact for the numerals,
+    /// bitvector literals and datatype values Z3 puts in its models. A
+    /// function is compared on the arguments its interpretation in either
+    /// model lists, and by its default value. To compare all declarations,
+    /// pass those of both models, e.g. with [`Model::iter()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, ModelDifference, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let y = ast::Int::new_const(ctx.clone(), "y");
+    /// let model_with = |v: i64| {
+    ///     let solver = Solver::new(ctx.clone());
+    ///     solver.assert(&x._eq(&ast::Int::from_i64(ctx.clone(), v)));
+    ///     solver.assert(&y._eq(&ast::Int::from_i64(ctx.clone(), 0)));
+    ///     assert_eq!(solver.check(), SatResult::Sat);
+    ///     solver.get_model().unwrap()
+    /// };
+    ///
+    /// let (a, b) = (model_with(1), model_with(2));
+    /// let symbols: Vec<_> = a.iter().collect();
+    /// let diff = a.diff(&b, &symbols);
+    /// assert_eq!(diff.len(), 1);
+    /// match &diff[0] {
+    ///     ModelDifference::Const { decl, first, second } => {
+    ///         assert_eq!(decl.name(), "x");
+    ///         assert_eq!(first.as_int().unwrap().as_i64(), Some(1));
+    ///         assert_eq!(second.as_int().unwrap().as_i64(), Some(2));
+    ///     }
+    ///     d => panic!("unexpected difference {:?}", d),
+    /// }
+    /// ```
+    pub fn diff(&self, other: &Model, symbols: &[FuncDecl]) -> Vec<ModelDifference> {
+        assert_eq!(self.ctx.z3_ctx, other.ctx.z3_ctx);
+        let mut diff = vec![];
+        for decl in symbols {
+            assert_eq!(self.ctx.z3_ctx, decl.ctx.z3_ctx);
+            if decl.arity() == 0 {
+                let first = self.get_func_interp_as_const::<Dynamic>(decl);
+                let second = other.get_func_interp_as_const::<Dynamic>(decl);
+                match (first, second) {
+                    (Some(first), Some(second)) if first != second => {
+                        diff.push(ModelDifference::Const {
+                            decl: decl.clone(),
+                            first,
+                            second,
+                        })
+                    }
+                    (Some(_), None) => diff.push(ModelDifference::OnlyInFirst(decl.clone())),
+                    (None, Some(_)) => diff.push(ModelDifference::OnlyInSecond(decl.clone())),
+                    _ => {}
+                }
+            } else {
+                match (self.get_func_interp(decl), other.get_func_interp(decl)) {
+                    (Some(first), Some(second)) => {
+                        if let Some(d) = self.diff_func(other, decl, &first, &second) {
+                            diff.push(d);
+                        }
+                    }
+                    (Some(_), None) => diff.push(ModelDifference::OnlyInFirst(decl.clone())),
+                    (None, Some(_)) => diff.push(ModelDifference::OnlyInSecond(decl.clone())),
+                    (None, None) => {}
+                }
+            }
+        }
+        diff
+    }
+
+    fn diff_func(
+        &self,
+        other: &Model,
+        decl: &FuncDecl,
+        first: &FuncInterp,
+        second: &FuncInterp,
+    ) -> Option<ModelDifference> {
+        let mut args: Vec<Vec<Dynamic>> = vec![];
+        for (a, _) in first.entries().chain(second.entries()) {
+            if !args.contains(&a) {
+                args.push(a);
+            }
+        }
+
+        let mut points = vec![];
+        for args in args {
+            let refs: Vec<&dyn Ast> = args.iter().map(|a| a as &dyn Ast).collect();
+            let app = decl.apply(&refs);
+            let values = (self.eval(&app, true), other.eval(&app, true));
+            if let (Some(first), Some(second)) = values {
+                if first != second {
+                    points.push(DiffPoint {
+                        args,
+                        first,
+                        second,
+                    });
+                }
+            }
+        }
+
+        let else_values = match (first.else_value(), second.else_value()) {
+            (Some(first), Some(second)) if first != second => Some((first, second)),
+            _ => None,
+        };
+
+        if points.is_empty() && else_values.is_none() {
+            None
+        } else {
+            Some(ModelDifference::Func {
+                decl: decl.clone(),
+                points,
+                else_values,
+            })
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Verification of small feed-forward neural networks.
+//!
+//! A [`Network`] is a sequence of fully connected [`Layer`]s. Its weights
+//! are converted to exact rationals, and [`Network::encode()`] builds the
+//! real-valued outputs of the network as Z3 terms, with an `ite` for
+//! every rectified linear unit. [`Network::check_robust()`] uses that
+//! encoding to check local robustness: whether every input within an L∞
+//! ball around a given point is classified the same.
+//!
+//! # Examples
+//!
+//! ```
+//! # use std::rc::Rc;
+//! # use z3::{Config, Context};
+//! use z3::nnverify::{Activation, Layer, Network, Robustness};
+//!
+//! let cfg = Config::new();
+//! let ctx = Rc::new(Context::new(&cfg));
+//! // out_0 = relu(x - y), out_1 = relu(y - x)
+//! let net = Network::new(vec![Layer::new(
+//!     &[vec![1.0, -1.0], vec![-1.0, 1.0]],
+//!     &[0.0, 0.0],
+//!     Activation::Relu,
+//! )]);
+//! let robust = net.check_robust(ctx.clone(), &[1.0, 0.0], 0.25, 0);
+//! assert_eq!(robust, Robustness::Robust);
+//! let robust = net.check_robust(ctx.clone(), &[1.0, 0.0], 0.5, 0);
+//! assert!(matches!(robust, Robustness::Counterexample(_)));
+//! ```
+
+use num::rational::BigRational;
+use num::Zero;
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, SatResult, Solver};
+
+/// The activation function applied to the outputs of a [`Layer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Activation {
+    /// `max(x, 0)`.
+    Relu,
+    /// No activation, as in the last layer of most classifiers.
+    Identity,
+}
+
+/// A fully connected layer computing `activation(weights * x + biases)`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Layer {
+    weights: Vec<Vec<BigRational>>,
+    biases: Vec<BigRational>,
+    activation: Activation,
+}
+
+impl Layer {
+    /// Create a layer from its weight matrix, given as one row per output,
+    /// and its biases. The values are converted to rationals exactly.
+    ///
+    /// # Panics
+    ///
+    /// If the rows have different lengths, there is not one bias per row,
+    /// or a value is not finite.
+    pub fn new(weights: &[Vec<f64>], biases: &[f64], activation: Activation) -> Layer {
+        let exact = |v: &f64| BigRational::from_float(*v).expect("weight is not finite");
+        Layer::from_rationals(
+            weights
+                .iter()
+                .map(|row| row.iter().map(exact).collect())
+                .collect(),
+            biases.iter().map(exact).collect(),
+            activation,
+        )
+    }
+
+    /// Create a layer with rational weights, like [`Layer::new()`].
+    pub fn from_rationals(
+        weights: Vec<Vec<BigRational>>,
+        biases: Vec<BigRational>,
+        activation: Activation,
+    ) -> Layer {
+        assert_eq!(weights.len(), biases.len(), "need one bias per output");
+        assert!(
+            weights.iter().all(|row| row.len() == weights[0].len()),
+            "rows have different lengths"
+        );
+        Layer {
+            weights,
+            biases,
+            activation,
+        }
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.weights.first().map_or(0, Vec::len)
+    }
+
+    pub fn num_outputs(&self) -> usize {
+        self.biases.len()
+    }
+
+    pub fn activation(&self) -> Activation {
+        self.activation
+    }
+
+    /// The outputs of this layer for `inputs`.
+    ///
+    /// # Panics
+    ///
+    /// If there is not one input per column of the weight matrix.
+    pub fn encode(&self, inputs: &[ast::Real]) -> Vec<ast::Real> {
+        assert_eq!(inputs.len(), self.num_inputs(), "wrong number of inputs");
+        let ctx = inputs[0].get_ctx();
+        let real = |v: &BigRational| ast::Real::from_big_rational(ctx.clone(), v);
+        let zero = real(&BigRational::zero());
+        self.weights
+            .iter()
+            .zip(&self.biases)
+            .map(|(row, bias)| {
+                let sum = row
+                    .iter()
+                    .zip(inputs)
+                    .filter(|(w, _)| !w.is_zero())
+                    .fold(real(bias), |sum, (w, x)| sum + real(w) * x.clone());
+                match self.activation {
+                    Activation::Relu => sum.gt(&zero).ite(&sum, &zero),
+                    Activation::Identity => sum,
+                }
+            })
+            .collect()
+    }
+}
+
+/// A feed-forward network, see the [module documentation](self).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Network {
+    layers: Vec<Layer>,
+}
+
+/// The result of [`Network::check_robust()`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Robustness {
+    /// Every input in the region is classified as the expected label.
+    Robust,
+    /// An input in the region that is not classified as the expected label.
+    Counterexample(Vec<ast::Real>),
+    /// Z3 could not decide robustness, e.g. because of a timeout.
+    Unknown,
+}
+
+impl Network {
+    /// # Panics
+    ///
+    /// If there are no layers, or a layer does not have as many inputs as
+    /// the previous one has outputs.
+    pub fn new(layers: Vec<Layer>) -> Network {
+        assert!(!layers.is_empty(), "network has no layers");
+        assert!(
+            layers
+                .windows(2)
+                .all(|w| w[0].num_outputs() == w[1].num_inputs()),
+            "layer dimensions do not match"
+        );
+        Network { layers }
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.layers[0].num_inputs()
+    }
+
+    pub fn num_outputs(&self) -> usize {
+        self.layers[self.layers.len() - 1].num_outputs()
+    }
+
+    /// The outputs of the network for `inputs`.
+    pub fn encode(&self, inputs: &[ast::Real]) -> Vec<ast::Real> {
+        self.layers
+            .iter()
+            .fold(inputs.to_vec(), |xs, layer| layer.encode(&xs))
+    }
+
+    /// Return the constraint that `outputs[label]` is strictly larger than
+    /// every other output.
+    pub fn classifies_as(outputs: &[ast::Real], label: usize) -> ast::Bool {
+        let wins: Vec<ast::Bool> = outputs
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != label)
+            .map(|(_, o)| outputs[label].gt(o))
+            .collect();
+        ast::Bool::and(outputs[label].get_ctx(), &wins)
+    }
+
+    /// Check whether every input `x` with `|x_i - point_i| <= epsilon`
+    /// [is classified as](Network::classifies_as) `label`.
+    ///
+    /// # Panics
+    ///
+    /// If `point` does not have one value per input, `label` is not an
+    /// output, or a value is not finite.
+    pub fn check_robust(
+        &self,
+        ctx: Rc<Context>,
+        point: &[f64],
+        epsilon: f64,
+        label: usize,
+    ) -> Robustness {
+        assert_eq!(point.len(), self.num_inputs(), "wrong number of inputs");
+        assert!(label < self.num_outputs(), "no such output");
+        let exact = |v: f64| {
+            ast::Real::from_big_rational(
+                ctx.clone(),
+                &BigRational::from_float(v).expect("value is not finite"),
+            )
+        };
+        let inputs: Vec<ast::Real> = (0..point.len())
+            .map(|i| ast::Real::new_const(ctx.clone(), format!("x_{}", i)))
+            .collect();
+        let epsilon = exact(epsilon);
+        let solver = Solver::new(ctx.clone());
+        for (x, &p) in inputs.iter().zip(point) {
+            solver.assert(&x.ge(&(exact(p) - epsilon.clone())));
+            solver.assert(&x.le(&(exact(p) + epsilon.clone())));
+        }
+        let outputs = self.encode(&inputs);
+        solver.assert(&!Network::classifies_as(&outputs, label));
+        match solver.check() {
+            SatResult::Unsat => Robustness::Robust,
+            SatResult::Sat => {
+                let model = solver.get_model().unwrap();
+                Robustness::Counterexample(
+                    inputs
+                        .iter()
+                        .map(|x| model.eval(x, true).unwrap())
+                        .collect(),
+                )
+            }
+            SatResult::Unknown => Robustness::Unknown,
+        }
+    }
+}
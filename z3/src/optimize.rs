@@ -1,24 +1,42 @@
-use std::convert::TryInto;
-use std::ffi::{CStr, CString};
+use std::convert::TryFrom;
+use std::ffi::CStr;
 use std::fmt;
-use std::rc::Rc;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use z3_sys::*;
 
+use crate::Rc;
 use crate::{
     ast::{Ast, Bool, Dynamic},
-    Context, Model, Optimize, Params, SatResult, Statistics, Symbol,
+    Context, Model, Optimize, Params, ParseError, Priority, SatResult, Statistics, Symbol,
 };
 
+use crate::conversion::{c_len, cstring, try_cstring, z3_cstr};
 use num::{
     bigint::{BigInt, BigUint, Sign},
     rational::BigRational,
+    Signed, Zero,
 };
 
+/// The source of the ids of optimizers and the generations of objectives.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+fn next_id() -> usize {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 impl Optimize {
     unsafe fn wrap(ctx: Rc<Context>, z3_opt: Z3_optimize) -> Optimize {
         Z3_optimize_inc_ref(ctx.z3_ctx, z3_opt);
-        Optimize { ctx, z3_opt }
+        crate::track_live(ctx.z3_ctx, "Optimize", 1);
+        Optimize {
+            ctx,
+            z3_opt,
+            id: next_id(),
+            objectives: Default::default(),
+            scopes: Default::default(),
+        }
     }
 
     /// Create a new optimize context.
@@ -32,12 +50,58 @@ impl Optimize {
     /// Parse an SMT-LIB2 string with assertions, soft constraints and optimization objectives.
     /// Add the parsed constraints and objectives to the optimizer.
     pub fn from_string<T: Into<Vec<u8>>>(&self, source_string: T) {
-        let source_cstring = CString::new(source_string).unwrap();
+        let source_cstring = cstring(source_string);
         unsafe {
             Z3_optimize_from_string(self.ctx.z3_ctx, self.z3_opt, source_cstring.as_ptr());
         }
     }
 
+    /// Parse the SMT-LIB2 file at `path` like [`Optimize::from_string()`],
+    /// and add its constraints and objectives to the optimizer.
+    ///
+    /// Together with the [`Display`](fmt::Display) implementation, which
+    /// prints the constraints and objectives in the same format, this
+    /// round-trips optimization problems through files.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{Config, Context, Optimize, SatResult};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let optimize = Optimize::new(ctx.clone());
+    /// optimize.from_string("(declare-const x Int) (assert (< x 10)) (maximize x)");
+    ///
+    /// let path = std::env::temp_dir().join("optimize_from_file_doctest.smt2");
+    /// std::fs::write(&path, optimize.to_string()).unwrap();
+    /// let copy = Optimize::new(ctx.clone());
+    /// copy.from_file(&path).unwrap();
+    /// std::fs::remove_file(&path).unwrap();
+    ///
+    /// assert_eq!(copy.check(&[]), SatResult::Sat);
+    /// assert_eq!(copy.get_model().unwrap().to_string().trim(), "x -> 9");
+    /// ```
+    ///
+    /// Return an error if the file cannot be read or parsed.
+    pub fn from_file<P: AsRef<Path>>(&self, path: P) -> Result<(), ParseError> {
+        let path = path.as_ref();
+        let path_cstring = path
+            .to_str()
+            .and_then(|p| try_cstring(p).ok())
+            .ok_or_else(|| ParseError::new(format!("invalid file name {}", path.display())))?;
+        unsafe {
+            Z3_set_error(self.ctx.z3_ctx, ErrorCode::OK);
+            Z3_optimize_from_file(self.ctx.z3_ctx, self.z3_opt, path_cstring.as_ptr());
+            let code = Z3_get_error_code(self.ctx.z3_ctx);
+            if code != ErrorCode::OK {
+                let msg = CStr::from_ptr(Z3_get_error_msg(self.ctx.z3_ctx, code));
+                return Err(ParseError::from_z3_message(&msg.to_string_lossy()));
+            }
+        }
+        Ok(())
+    }
+
     /// Get this optimizers 's context.
     pub fn get_context(&self) -> Rc<Context> {
         self.ctx.clone()
@@ -58,6 +122,31 @@ impl Optimize {
     /// Weight is a positive, rational penalty for violating the constraint.
     /// Group is an optional identifier to group soft constraints.
     ///
+    /// Z3 minimizes the total weight of the violated soft constraints of
+    /// each group, as one objective per group; soft constraints without a
+    /// group form a single default group. Weights can be positive
+    /// integers, `(numerator, denominator)` pairs, or [`BigRational`]s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, Config, Context, Optimize, SatResult, Symbol};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let a = ast::Bool::new_const(ctx.clone(), "a");
+    /// let b = ast::Bool::new_const(ctx.clone(), "b");
+    ///
+    /// let opt = Optimize::new(ctx.clone());
+    /// opt.assert(&!(a.clone() & b.clone()));
+    /// // Violating `b` costs 3/2, violating `a` only 1.
+    /// opt.assert_soft(&a, 1, Some(Symbol::from("goals")));
+    /// opt.assert_soft(&b, (3, 2), Some(Symbol::from("goals")));
+    /// assert_eq!(opt.check(&[]), SatResult::Sat);
+    /// let model = opt.get_model().unwrap();
+    /// assert_eq!(model.eval(&b, true).unwrap().as_bool(), Some(true));
+    /// ```
+    ///
     /// # See also:
     ///
     /// - [`Optimize::assert()`]
@@ -65,7 +154,7 @@ impl Optimize {
     /// - [`Optimize::minimize()`]
     pub fn assert_soft(&self, ast: &impl Ast, weight: impl Weight, group: Option<Symbol>) {
         let weight_string = weight.to_string();
-        let weight_cstring = CString::new(weight_string).unwrap();
+        let weight_cstring = cstring(weight_string);
         let group = group
             .map(|g| g.as_z3_symbol(&self.ctx))
             .unwrap_or_else(std::ptr::null_mut);
@@ -82,31 +171,206 @@ impl Optimize {
 
     /// Add a maximization constraint.
     ///
+    /// The returned handle gives access to the bounds Z3 finds for the
+    /// objective, see [`Optimize::lower()`] and [`Optimize::upper()`].
+    ///
     /// # See also:
     ///
     /// - [`Optimize::assert()`]
     /// - [`Optimize::minimize()`]
-    pub fn maximize(&self, ast: &impl Ast) {
+    pub fn maximize<A: Ast + Clone>(&self, ast: &A) -> Objective<A> {
         // https://github.com/Z3Prover/z3/blob/09f911d8a84cd91988e5b96b69485b2a9a2edba3/src/opt/opt_context.cpp#L118-L120
         assert!(matches!(
             ast.get_sort().kind(),
             SortKind::Int | SortKind::Real | SortKind::BV
         ));
-        unsafe { Z3_optimize_maximize(self.ctx.z3_ctx, self.z3_opt, ast.get_z3_ast()) };
+        let index = unsafe { Z3_optimize_maximize(self.ctx.z3_ctx, self.z3_opt, ast.get_z3_ast()) };
+        self.objective(index, ast, true)
     }
 
-    /// Add a minimization constraint.
+    /// Add a minimization constraint, like [`Optimize::maximize()`].
     ///
     /// # See also:
     ///
     /// - [`Optimize::assert()`]
     /// - [`Optimize::maximize()`]
-    pub fn minimize(&self, ast: &impl Ast) {
+    pub fn minimize<A: Ast + Clone>(&self, ast: &A) -> Objective<A> {
         assert!(matches!(
             ast.get_sort().kind(),
             SortKind::Int | SortKind::Real | SortKind::BV
         ));
-        unsafe { Z3_optimize_minimize(self.ctx.z3_ctx, self.z3_opt, ast.get_z3_ast()) };
+        let index = unsafe { Z3_optimize_minimize(self.ctx.z3_ctx, self.z3_opt, ast.get_z3_ast()) };
+        self.objective(index, ast, false)
+    }
+
+    /// Record a new generation of the objective at `index`, and return a
+    /// handle to it.
+    fn objective<A: Ast + Clone>(&self, index: u32, ast: &A, maximize: bool) -> Objective<A> {
+        let generation = next_id();
+        let mut objectives = self.objectives.borrow_mut();
+        let i = index as usize;
+        if objectives.len() <= i {
+            objectives.resize(i + 1, None);
+        }
+        objectives[i] = Some(generation);
+        Objective {
+            optimizer: self.id,
+            index,
+            generation,
+            term: ast.clone(),
+            maximize,
+        }
+    }
+
+    /// Return the lower bound Z3 found for `objective` in the last
+    /// [`Optimize::check()`].
+    ///
+    /// The bound is a numeral if it is finite and attained; otherwise it
+    /// involves the symbols `oo` (infinity) and `epsilon` (an infinitesimal,
+    /// for strict inequalities over the reals). What the optimum is
+    /// depends on the [priority](Optimize::set_priority) of the objectives.
+    ///
+    /// Return `None` if `objective` is not an objective of this optimizer,
+    /// because it was added to another one or removed by
+    /// [`Optimize::pop()`], or if it was added after the last check.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Optimize, SatResult};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let start = ast::Int::new_const(ctx.clone(), "start");
+    /// let end = ast::Int::new_const(ctx.clone(), "end");
+    /// let int = |i| ast::Int::from_i64(ctx.clone(), i);
+    ///
+    /// let opt = Optimize::new(ctx.clone());
+    /// opt.assert(&start.ge(&int(9)));
+    /// opt.assert(&(end.clone() - start.clone()).ge(&int(3)));
+    /// let makespan = opt.minimize(&end);
+    /// assert_eq!(opt.check(&[]), SatResult::Sat);
+    /// assert_eq!(opt.lower(&makespan).unwrap().as_int().unwrap().as_i64(), Some(12));
+    /// let model = opt.get_model().unwrap();
+    /// assert_eq!(makespan.value(&model).unwrap().as_i64(), Some(12));
+    /// ```
+    pub fn lower<A>(&self, objective: &Objective<A>) -> Option<Dynamic> {
+        self.bound(Z3_optimize_get_lower, objective)
+    }
+
+    /// Return the upper bound Z3 found for `objective` in the last
+    /// [`Optimize::check()`], like [`Optimize::lower()`].
+    pub fn upper<A>(&self, objective: &Objective<A>) -> Option<Dynamic> {
+        self.bound(Z3_optimize_get_upper, objective)
+    }
+
+    /// The bound `get` returns for `objective`, or `None` if it is not an
+    /// objective of this optimizer or Z3 has no bound for it.
+    fn bound<A>(
+        &self,
+        get: unsafe extern "C" fn(Z3_context, Z3_optimize, u32) -> Z3_ast,
+        objective: &Objective<A>,
+    ) -> Option<Dynamic> {
+        if !self.has_objective(objective) {
+            return None;
+        }
+        unsafe {
+            Z3_set_error(self.ctx.z3_ctx, ErrorCode::OK);
+            let z3_ast = get(self.ctx.z3_ctx, self.z3_opt, objective.index);
+            if z3_ast.is_null() || Z3_get_error_code(self.ctx.z3_ctx) != ErrorCode::OK {
+                return None;
+            }
+            Some(Dynamic::wrap(self.ctx.clone(), z3_ast))
+        }
+    }
+
+    /// Whether `objective` was added to this optimizer, and has not been
+    /// removed by [`Optimize::pop()`].
+    fn has_objective<A>(&self, objective: &Objective<A>) -> bool {
+        objective.optimizer == self.id
+            && self.objectives.borrow().get(objective.index as usize)
+                == Some(&Some(objective.generation))
+    }
+
+    /// Return the lower bound of `objective` like [`Optimize::lower()`],
+    /// decoded into a finite, infinite or infinitesimally shifted value.
+    ///
+    /// Return `None` if `objective` is not an objective of this optimizer,
+    /// like [`Optimize::lower()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use num::{bigint::Sign, rational::BigRational, FromPrimitive};
+    /// # use z3::{ast, ast::Ast, Config, Context, ObjectiveValue, Optimize, SatResult};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let y = ast::Int::new_const(ctx.clone(), "y");
+    /// let opt = Optimize::new(ctx.clone());
+    /// opt.assert(&x.le(&ast::Int::from_i64(ctx.clone(), 7)));
+    /// let bounded = opt.maximize(&x);
+    /// let unbounded = opt.minimize(&y);
+    /// assert_eq!(opt.check(&[]), SatResult::Sat);
+    ///
+    /// let seven = BigRational::from_i64(7).unwrap();
+    /// assert_eq!(opt.upper_value(&bounded), Some(ObjectiveValue::Finite(seven)));
+    /// assert_eq!(
+    ///     opt.lower_value(&unbounded),
+    ///     Some(ObjectiveValue::Infinite(Sign::Minus))
+    /// );
+    /// ```
+    pub fn lower_value<A>(&self, objective: &Objective<A>) -> Option<ObjectiveValue> {
+        self.objective_value(Z3_optimize_get_lower_as_vector, objective)
+    }
+
+    /// Return the upper bound of `objective` like [`Optimize::upper()`],
+    /// decoded like [`Optimize::lower_value()`].
+    pub fn upper_value<A>(&self, objective: &Objective<A>) -> Option<ObjectiveValue> {
+        self.objective_value(Z3_optimize_get_upper_as_vector, objective)
+    }
+
+    /// Decode the coefficients `[a, b, c]` of a bound `a * oo + b + c *
+    /// epsilon`, as returned by `get` for `objective`.
+    fn objective_value<A>(
+        &self,
+        get: unsafe extern "C" fn(Z3_context, Z3_optimize, u32) -> Z3_ast_vector,
+        objective: &Objective<A>,
+    ) -> Option<ObjectiveValue> {
+        if !self.has_objective(objective) {
+            return None;
+        }
+        let coefficients: Option<Vec<BigRational>> = unsafe {
+            Z3_set_error(self.ctx.z3_ctx, ErrorCode::OK);
+            let z3_vec = get(self.ctx.z3_ctx, self.z3_opt, objective.index);
+            if z3_vec.is_null() || Z3_get_error_code(self.ctx.z3_ctx) != ErrorCode::OK {
+                return None;
+            }
+            Z3_ast_vector_inc_ref(self.ctx.z3_ctx, z3_vec);
+            let coefficients = (0..Z3_ast_vector_size(self.ctx.z3_ctx, z3_vec))
+                .map(|i| {
+                    let numeral = Z3_get_numeral_string(
+                        self.ctx.z3_ctx,
+                        Z3_ast_vector_get(self.ctx.z3_ctx, z3_vec, i),
+                    );
+                    z3_cstr(numeral).to_str().ok()?.parse().ok()
+                })
+                .collect();
+            Z3_ast_vector_dec_ref(self.ctx.z3_ctx, z3_vec);
+            coefficients
+        };
+        match <[BigRational; 3]>::try_from(coefficients?).ok()? {
+            [infinite, _, _] if !infinite.is_zero() => {
+                Some(ObjectiveValue::Infinite(if infinite.is_positive() {
+                    Sign::Plus
+                } else {
+                    Sign::Minus
+                }))
+            }
+            [_, base, epsilon] if epsilon.is_zero() => Some(ObjectiveValue::Finite(base)),
+            [_, base, epsilon] => Some(ObjectiveValue::Epsilon(base, epsilon)),
+        }
     }
 
     /// Create a backtracking point.
@@ -120,6 +384,8 @@ impl Optimize {
     /// - [`Optimize::pop()`]
     pub fn push(&self) {
         unsafe { Z3_optimize_push(self.ctx.z3_ctx, self.z3_opt) };
+        let n = self.objectives.borrow().len();
+        self.scopes.borrow_mut().push(n);
     }
 
     /// Backtrack one level.
@@ -134,6 +400,9 @@ impl Optimize {
     /// - [`Optimize::push()`]
     pub fn pop(&self) {
         unsafe { Z3_optimize_pop(self.ctx.z3_ctx, self.z3_opt) };
+        if let Some(n) = self.scopes.borrow_mut().pop() {
+            self.objectives.borrow_mut().truncate(n);
+        }
     }
 
     /// Check consistency and produce optimal values.
@@ -147,7 +416,7 @@ impl Optimize {
             Z3_optimize_check(
                 self.ctx.z3_ctx,
                 self.z3_opt,
-                assumptions.len().try_into().unwrap(),
+                c_len(assumptions.len()),
                 assumptions.as_ptr(),
             )
         } {
@@ -171,21 +440,164 @@ impl Optimize {
     ///
     /// This contains maximize/minimize objectives and grouped soft constraints.
     pub fn get_objectives(&self) -> Vec<Dynamic> {
-        let (z3_objectives, len) = unsafe {
-            let objectives = Z3_optimize_get_objectives(self.ctx.z3_ctx, self.z3_opt);
-            let len = Z3_ast_vector_size(self.ctx.z3_ctx, objectives);
-            (objectives, len)
-        };
+        unsafe {
+            let z3_vec = Z3_optimize_get_objectives(self.ctx.z3_ctx, self.z3_opt);
+            Z3_ast_vector_inc_ref(self.ctx.z3_ctx, z3_vec);
+            let objectives = (0..Z3_ast_vector_size(self.ctx.z3_ctx, z3_vec))
+                .map(|i| {
+                    Dynamic::wrap(
+                        self.ctx.clone(),
+                        Z3_ast_vector_get(self.ctx.z3_ctx, z3_vec, i),
+                    )
+                })
+                .collect();
+            Z3_ast_vector_dec_ref(self.ctx.z3_ctx, z3_vec);
+            objectives
+        }
+    }
 
-        let mut objectives = Vec::with_capacity(len as usize);
+    /// Return the hard constraints of this optimizer, see
+    /// [`Optimize::assert()`].
+    pub fn get_assertions(&self) -> Vec<Bool> {
+        unsafe {
+            let z3_vec = Z3_optimize_get_assertions(self.ctx.z3_ctx, self.z3_opt);
+            Z3_ast_vector_inc_ref(self.ctx.z3_ctx, z3_vec);
+            let assertions = (0..Z3_ast_vector_size(self.ctx.z3_ctx, z3_vec))
+                .map(|i| {
+                    Bool::wrap(
+                        self.ctx.clone(),
+                        Z3_ast_vector_get(self.ctx.z3_ctx, z3_vec, i),
+                    )
+                })
+                .collect();
+            Z3_ast_vector_dec_ref(self.ctx.z3_ctx, z3_vec);
+            assertions
+        }
+    }
 
-        for i in 0..len {
-            let elem = unsafe { Z3_ast_vector_get(self.ctx.z3_ctx, z3_objectives, i) };
-            let elem = unsafe { Dynamic::wrap(self.ctx.clone(), elem) };
-            objectives.push(elem);
+    /// Start the search from a known solution: assert that `objective`
+    /// is at least as good as its value in `model`.
+    ///
+    /// This prunes every candidate that is worse than `model` (e.g. the
+    /// result of a heuristic) in `objective`, without changing its
+    /// optimum. A solution that improves one objective may be worse in
+    /// the others, so with several objectives only prime the one that is
+    /// optimized first under [`Priority::Lexicographic`], the default.
+    /// Bit-vector objectives are compared as unsigned numbers, like Z3
+    /// optimizes them.
+    ///
+    /// Return `false`, and assert nothing, if `objective` is not an
+    /// objective of this optimizer (see [`Optimize::lower()`]), or `model`
+    /// does not satisfy the hard constraints.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Optimize, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let constraint = x.lt(&ast::Int::from_i64(ctx.clone(), 100));
+    ///
+    /// // A quick, not necessarily optimal, solution.
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&constraint);
+    /// solver.assert(&x._eq(&ast::Int::from_i64(ctx.clone(), 42)));
+    /// assert_eq!(solver.check(), SatResult::Sat);
+    /// let heuristic = solver.get_model().unwrap();
+    ///
+    /// let opt = Optimize::new(ctx.clone());
+    /// opt.assert(&constraint);
+    /// let objective = opt.maximize(&x);
+    /// assert!(opt.prime_with(&objective, &heuristic));
+    /// assert_eq!(opt.check(&[]), SatResult::Sat);
+    /// let model = opt.get_model().unwrap();
+    /// assert_eq!(model.eval(&x, true).unwrap().as_i64(), Some(99));
+    /// ```
+    pub fn prime_with<A: Ast>(&self, objective: &Objective<A>, model: &Model) -> bool {
+        let satisfied = self
+            .get_assertions()
+            .iter()
+            .all(|a| model.eval(a, true).and_then(|v| v.as_bool()) == Some(true));
+        if !satisfied || !self.has_objective(objective) {
+            return false;
         }
+        let value = match objective.value(model) {
+            Some(v) => v,
+            None => return false,
+        };
+        let bound = unsafe {
+            let (t, v) = (objective.term.get_z3_ast(), value.get_z3_ast());
+            let mk: unsafe extern "C" fn(Z3_context, Z3_ast, Z3_ast) -> Z3_ast =
+                match (objective.term.get_sort().kind(), objective.maximize) {
+                    (SortKind::BV, true) => Z3_mk_bvuge,
+                    (SortKind::BV, false) => Z3_mk_bvule,
+                    (_, true) => Z3_mk_ge,
+                    (_, false) => Z3_mk_le,
+                };
+            Bool::wrap(self.ctx.clone(), mk(self.ctx.z3_ctx, t, v))
+        };
+        self.assert(&bound);
+        true
+    }
 
-        objectives
+    /// Set how several objectives are combined, Z3's `opt.priority`
+    /// parameter.
+    pub fn set_priority(&self, priority: Priority) {
+        let mut params = Params::new(self.ctx.clone());
+        let value = match priority {
+            Priority::Lexicographic => "lex",
+            Priority::Pareto => "pareto",
+            Priority::Box => "box",
+        };
+        params.set_symbol("priority", value);
+        self.set_params(&params);
+    }
+
+    /// Iterate over the Pareto-optimal models of the objectives, see
+    /// [`Priority::Pareto`].
+    ///
+    /// This sets the priority to [`Priority::Pareto`]. Every step runs
+    /// [`Optimize::check()`], and the iterator ends with the first result
+    /// that is not [`SatResult::Sat`]: there are no more optimal models, or
+    /// Z3 could not find one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Optimize};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let y = ast::Int::new_const(ctx.clone(), "y");
+    /// let int = |i| ast::Int::from_i64(ctx.clone(), i);
+    ///
+    /// // Share a budget of 2 between `x` and `y`.
+    /// let opt = Optimize::new(ctx.clone());
+    /// opt.assert(&x.ge(&int(0)));
+    /// opt.assert(&y.ge(&int(0)));
+    /// opt.assert(&(x.clone() + y.clone()).le(&int(2)));
+    /// opt.maximize(&x);
+    /// opt.maximize(&y);
+    ///
+    /// let mut front: Vec<(i64, i64)> = opt
+    ///     .pareto_models()
+    ///     .map(|m| {
+    ///         let eval = |v: &ast::Int| m.eval(v, true).unwrap().as_i64().unwrap();
+    ///         (eval(&x), eval(&y))
+    ///     })
+    ///     .collect();
+    /// front.sort();
+    /// assert_eq!(front, [(0, 2), (1, 1), (2, 0)]);
+    /// ```
+    pub fn pareto_models(&self) -> ParetoModels<'_> {
+        self.set_priority(Priority::Pareto);
+        ParetoModels {
+            opt: self,
+            done: false,
+        }
     }
 
     /// Retrieve a string that describes the last status returned by [`Optimize::check()`].
@@ -218,6 +630,81 @@ impl Optimize {
     }
 }
 
+/// Iterator over the Pareto-optimal models of an [`Optimize`], see
+/// [`Optimize::pareto_models()`].
+#[derive(Debug)]
+pub struct ParetoModels<'a> {
+    opt: &'a Optimize,
+    done: bool,
+}
+
+impl<'a> Iterator for ParetoModels<'a> {
+    type Item = Model;
+
+    fn next(&mut self) -> Option<Model> {
+        if self.done {
+            return None;
+        }
+        let model = match self.opt.check(&[]) {
+            SatResult::Sat => self.opt.get_model(),
+            _ => None,
+        };
+        self.done = model.is_none();
+        model
+    }
+}
+
+/// An objective added with [`Optimize::maximize()`] or
+/// [`Optimize::minimize()`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Objective<A> {
+    /// The id of the optimizer the objective was added to.
+    optimizer: usize,
+    index: u32,
+    /// Tells this objective apart from one added at the same index after
+    /// it was removed by [`Optimize::pop()`].
+    generation: usize,
+    term: A,
+    maximize: bool,
+}
+
+impl<A: Ast> Objective<A> {
+    /// The index of this objective among the objectives of its
+    /// optimizer, in the order they were added.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The term this objective optimizes.
+    pub fn term(&self) -> &A {
+        &self.term
+    }
+
+    /// Whether this objective is maximized, rather than minimized.
+    pub fn is_maximize(&self) -> bool {
+        self.maximize
+    }
+
+    /// The value of this objective in `model`.
+    pub fn value(&self, model: &Model) -> Option<A> {
+        model.eval(&self.term, true)
+    }
+}
+
+/// The value of a bound of an objective, see [`Optimize::lower_value()`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ObjectiveValue {
+    /// The objective is bounded by this number, and attains it.
+    Finite(BigRational),
+    /// The objective is unbounded, with `Sign::Plus` for `+oo` and
+    /// `Sign::Minus` for `-oo`.
+    Infinite(Sign),
+    /// The bound `base + coeff * epsilon`, for an infinitesimal `epsilon`:
+    /// the objective gets arbitrarily close to `base`, as in `x < 1/2`
+    /// for a maximized real `x`, but never attains it.
+    Epsilon(BigRational, BigRational),
+}
+
 impl fmt::Display for Optimize {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         let p = unsafe { Z3_optimize_to_string(self.ctx.z3_ctx, self.z3_opt) };
@@ -239,6 +726,7 @@ impl fmt::Debug for Optimize {
 
 impl Drop for Optimize {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "Optimize", -1);
         unsafe { Z3_optimize_dec_ref(self.ctx.z3_ctx, self.z3_opt) };
     }
 }
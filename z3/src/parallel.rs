@@ -0,0 +1,224 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use z3_sys::*;
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Config, Context, ContextHandle, SatResult, Solver};
+
+/// The formulas one worker thread checks, translated into that worker's own
+/// context.
+struct Batch {
+    z3_ctx: Z3_context,
+    formulas: Vec<(usize, Z3_ast)>,
+}
+
+// A `Batch` is only ever used by the one worker thread it is handed to,
+// while the thread that created it does not touch the context until the
+// worker is joined.
+unsafe impl Send for Batch {}
+
+/// A [`Solver`] in a [`Context`] of its own, which can be sent to another
+/// thread (e.g. a worker thread, or a blocking task of an async runtime).
+///
+/// Z3 objects share their context, which may only be used by one thread at
+/// a time, so none of them is [`Send`] (unless the `sync` feature is
+/// enabled, see [`Context`]). A detached solver is the only
+/// object of its context, so it can be moved as a whole; see
+/// [`Solver::detach()`]. On the receiving thread, [`DetachedSolver::attach()`]
+/// turns it back into a `Solver`, and results can travel back the same
+/// way, or as plain Rust values.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&x.gt(&ast::Int::from_i64(ctx.clone(), 41)));
+///
+/// let detached = solver.detach(&cfg);
+/// let worker = std::thread::spawn(move || {
+///     let solver = detached.attach();
+///     assert_eq!(solver.check(), SatResult::Sat);
+///     solver.detach(&Config::new())
+/// });
+/// let solver = worker.join().unwrap().attach();
+/// assert_eq!(solver.get_assertions().len(), 1);
+/// ```
+#[derive(Debug)]
+pub struct DetachedSolver {
+    // The only reference to its context.
+    solver: Solver,
+}
+
+// The context of `solver` is not shared with any other object, so all of
+// it moves to the other thread together.
+unsafe impl Send for DetachedSolver {}
+
+impl DetachedSolver {
+    /// Turn this back into a [`Solver`], to be used on the current thread.
+    pub fn attach(self) -> Solver {
+        self.solver
+    }
+
+    /// Obtain a handle to interrupt the solver, e.g. while another thread
+    /// checks it.
+    pub fn handle(&self) -> ContextHandle {
+        Context::handle(self.solver.get_context())
+    }
+}
+
+impl Solver {
+    /// Translate this solver into a new context created from `cfg`, to be
+    /// sent to another thread; see [`DetachedSolver`].
+    ///
+    /// Like [`Solver::translate()`], this copies the assertions, but not
+    /// the [preferences](Solver::prefer) or the user propagator.
+    pub fn detach(&self, cfg: &Config) -> DetachedSolver {
+        DetachedSolver {
+            solver: self.translate(Rc::new(Context::new(cfg))),
+        }
+    }
+}
+
+/// Check each of `formulas` for satisfiability, independently of each
+/// other, using up to `max_threads` threads.
+///
+/// Every thread gets its own [`Context`], created from `cfg`, into which
+/// the formulas it checks are translated. The `i`-th entry of the result
+/// is the result for `formulas[i]`.
+///
+/// # See also:
+///
+/// - [`check_all_parallel_until()`]
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, SatResult};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let formulas: Vec<ast::Bool> = (0..8)
+///     .map(|i| x.gt(&ast::Int::from_i64(ctx.clone(), i)) & x.lt(&ast::Int::from_i64(ctx.clone(), 3)))
+///     .collect();
+///
+/// let results = z3::check_all_parallel(&cfg, &formulas, 4);
+/// assert_eq!(results[0], SatResult::Sat);
+/// assert_eq!(results[2], SatResult::Unsat);
+/// ```
+pub fn check_all_parallel(
+    cfg: &Config,
+    formulas: &[ast::Bool],
+    max_threads: usize,
+) -> Vec<SatResult> {
+    check_all_parallel_until(cfg, formulas, max_threads, |_, _| false)
+}
+
+/// Like [`check_all_parallel()`], but stops as soon as `stop` returns `true`
+/// for the index and result of a checked formula.
+///
+/// Checks still running at that point are interrupted, and formulas that
+/// were not checked yet are skipped; both are reported as
+/// [`SatResult::Unknown`]. This is useful to, e.g., stop at the first
+/// counterexample:
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, SatResult};
+/// # let cfg = Config::new();
+/// # let ctx = Rc::new(Context::new(&cfg));
+/// # let formulas = vec![ast::Bool::from_bool(ctx.clone(), false)];
+/// let results = z3::check_all_parallel_until(&cfg, &formulas, 4, |_, r| r == SatResult::Sat);
+/// ```
+pub fn check_all_parallel_until<F>(
+    cfg: &Config,
+    formulas: &[ast::Bool],
+    max_threads: usize,
+    stop: F,
+) -> Vec<SatResult>
+where
+    F: Fn(usize, SatResult) -> bool + Sync,
+{
+    if formulas.is_empty() {
+        return vec![];
+    }
+    let num_threads = max_threads.clamp(1, formulas.len());
+    let contexts: Vec<Rc<Context>> = (0..num_threads)
+        .map(|_| Rc::new(Context::new(cfg)))
+        .collect();
+    // Keep the translated formulas alive until every worker is done.
+    let translated: Vec<ast::Bool> = formulas
+        .iter()
+        .enumerate()
+        .map(|(i, f)| f.translate(contexts[i % num_threads].clone()))
+        .collect();
+    let handles: Vec<ContextHandle> = contexts
+        .iter()
+        .map(|c| Context::handle(c.clone()))
+        .collect();
+    let mut batches: Vec<Batch> = contexts
+        .iter()
+        .map(|c| Batch {
+            z3_ctx: c.z3_ctx,
+            formulas: vec![],
+        })
+        .collect();
+    for (i, f) in translated.iter().enumerate() {
+        batches[i % num_threads].formulas.push((i, f.get_z3_ast()));
+    }
+
+    let stopped = AtomicBool::new(false);
+    let mut results = vec![SatResult::Unknown; formulas.len()];
+    thread::scope(|s| {
+        let workers: Vec<_> = batches
+            .into_iter()
+            .map(|batch| {
+                let (stopped, stop, handles) = (&stopped, &stop, &handles);
+                s.spawn(move || {
+                    let mut results = Vec::with_capacity(batch.formulas.len());
+                    for (i, f) in batch.formulas {
+                        if stopped.load(Ordering::SeqCst) {
+                            break;
+                        }
+                        let result = unsafe { check_one(batch.z3_ctx, f) };
+                        results.push((i, result));
+                        if stop(i, result) && !stopped.swap(true, Ordering::SeqCst) {
+                            handles.iter().for_each(ContextHandle::interrupt);
+                        }
+                    }
+                    results
+                })
+            })
+            .collect();
+        for worker in workers {
+            // Re-raise a worker's panic with its own payload.
+            let worker_results = match worker.join() {
+                Ok(worker_results) => worker_results,
+                Err(payload) => std::panic::resume_unwind(payload),
+            };
+            for (i, result) in worker_results {
+                results[i] = result;
+            }
+        }
+    });
+    results
+}
+
+unsafe fn check_one(z3_ctx: Z3_context, formula: Z3_ast) -> SatResult {
+    let z3_slv = Z3_mk_solver(z3_ctx);
+    Z3_solver_inc_ref(z3_ctx, z3_slv);
+    Z3_solver_assert(z3_ctx, z3_slv, formula);
+    let result = match Z3_solver_check(z3_ctx, z3_slv) {
+        Z3_L_FALSE => SatResult::Unsat,
+        Z3_L_TRUE => SatResult::Sat,
+        _ => SatResult::Unknown,
+    };
+    Z3_solver_dec_ref(z3_ctx, z3_slv);
+    result
+}
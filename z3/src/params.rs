@@ -1,15 +1,22 @@
-use std::ffi::{CStr, CString};
+use std::collections::BTreeMap;
+use std::ffi::CStr;
 use std::fmt;
-use std::rc::Rc;
 
 use z3_sys::*;
 
-use crate::{Context, Params, Symbol};
+use crate::conversion::cstring;
+use crate::Rc;
+use crate::{Context, ParamDescrs, Params, Symbol};
 
 impl Params {
     unsafe fn wrap(ctx: Rc<Context>, z3_params: Z3_params) -> Params {
         Z3_params_inc_ref(ctx.z3_ctx, z3_params);
-        Params { ctx, z3_params }
+        crate::track_live(ctx.z3_ctx, "Params", 1);
+        Params {
+            ctx,
+            z3_params,
+            values: BTreeMap::new(),
+        }
     }
 
     pub fn new(ctx: Rc<Context>) -> Params {
@@ -20,47 +27,198 @@ impl Params {
     }
 
     pub fn set_symbol<K: Into<Symbol>, V: Into<Symbol>>(&mut self, k: K, v: V) {
+        let (k, v) = (k.into(), v.into());
         unsafe {
             Z3_params_set_symbol(
                 self.ctx.z3_ctx,
                 self.z3_params,
-                k.into().as_z3_symbol(&self.ctx),
-                v.into().as_z3_symbol(&self.ctx),
+                k.as_z3_symbol(&self.ctx),
+                v.as_z3_symbol(&self.ctx),
             );
         };
+        self.values.insert(name(&k), ParamValue::Symbol(v));
     }
 
     pub fn set_bool<K: Into<Symbol>>(&mut self, k: K, v: bool) {
+        let k = k.into();
         unsafe {
             Z3_params_set_bool(
                 self.ctx.z3_ctx,
                 self.z3_params,
-                k.into().as_z3_symbol(&self.ctx),
+                k.as_z3_symbol(&self.ctx),
                 v,
             );
         };
+        self.values.insert(name(&k), ParamValue::Bool(v));
     }
 
     pub fn set_f64<K: Into<Symbol>>(&mut self, k: K, v: f64) {
+        let k = k.into();
         unsafe {
             Z3_params_set_double(
                 self.ctx.z3_ctx,
                 self.z3_params,
-                k.into().as_z3_symbol(&self.ctx),
+                k.as_z3_symbol(&self.ctx),
                 v,
             );
         };
+        self.values.insert(name(&k), ParamValue::F64(v));
     }
 
     pub fn set_u32<K: Into<Symbol>>(&mut self, k: K, v: u32) {
+        let k = k.into();
         unsafe {
             Z3_params_set_uint(
                 self.ctx.z3_ctx,
                 self.z3_params,
-                k.into().as_z3_symbol(&self.ctx),
+                k.as_z3_symbol(&self.ctx),
                 v,
             );
         };
+        self.values.insert(name(&k), ParamValue::U32(v));
+    }
+
+    /// The parameters in this set and their values, as given to the
+    /// setters. Z3 cannot report them back.
+    pub(crate) fn values(&self) -> &BTreeMap<String, ParamValue> {
+        &self.values
+    }
+
+    /// Set parameter `k` to `v` with the setter of its type.
+    pub(crate) fn set(&mut self, k: &str, v: &ParamValue) {
+        match v {
+            ParamValue::Symbol(v) => self.set_symbol(k, v.clone()),
+            ParamValue::Bool(v) => self.set_bool(k, *v),
+            ParamValue::F64(v) => self.set_f64(k, *v),
+            ParamValue::U32(v) => self.set_u32(k, *v),
+        }
+    }
+
+    /// Set parameter `k` of the given kind to the value `v`, in SMT-LIB
+    /// syntax, or return an error if `v` is not a value of that kind.
+    pub(crate) fn set_parsed(&mut self, kind: ParamKind, k: &str, v: &str) -> Result<(), String> {
+        let invalid = || format!("invalid value {:?} for parameter {}", v, k);
+        match kind {
+            ParamKind::UInt => self.set_u32(k, v.parse().map_err(|_| invalid())?),
+            ParamKind::Bool => match v {
+                "true" => self.set_bool(k, true),
+                "false" => self.set_bool(k, false),
+                _ => return Err(invalid()),
+            },
+            ParamKind::Double => self.set_f64(k, v.parse().map_err(|_| invalid())?),
+            _ => self.set_symbol(k, v),
+        }
+        Ok(())
+    }
+}
+
+/// The value of a parameter, as given to one of the setters of [`Params`].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ParamValue {
+    Symbol(Symbol),
+    Bool(bool),
+    F64(f64),
+    U32(u32),
+}
+
+impl ParamValue {
+    /// The kind of parameter this is a value of.
+    pub(crate) fn kind(&self) -> ParamKind {
+        match self {
+            ParamValue::Symbol(_) => ParamKind::Symbol,
+            ParamValue::Bool(_) => ParamKind::Bool,
+            ParamValue::F64(_) => ParamKind::Double,
+            ParamValue::U32(_) => ParamKind::UInt,
+        }
+    }
+}
+
+fn name(k: &Symbol) -> String {
+    match k {
+        Symbol::Int(i) => i.to_string(),
+        Symbol::String(s) => s.clone(),
+    }
+}
+
+impl ParamDescrs {
+    pub(crate) unsafe fn wrap(ctx: Rc<Context>, z3_param_descrs: Z3_param_descrs) -> ParamDescrs {
+        Z3_param_descrs_inc_ref(ctx.z3_ctx, z3_param_descrs);
+        crate::track_live(ctx.z3_ctx, "ParamDescrs", 1);
+        ParamDescrs {
+            ctx,
+            z3_param_descrs,
+        }
+    }
+
+    /// The number of parameters.
+    pub fn len(&self) -> usize {
+        unsafe { Z3_param_descrs_size(self.ctx.z3_ctx, self.z3_param_descrs) as usize }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The names of the parameters.
+    pub fn names(&self) -> Vec<String> {
+        (0..self.len() as u32)
+            .map(|i| unsafe {
+                let name = Z3_param_descrs_get_name(self.ctx.z3_ctx, self.z3_param_descrs, i);
+                CStr::from_ptr(Z3_get_symbol_string(self.ctx.z3_ctx, name))
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    /// The kind of the parameter `name`, with `None` if there is no such
+    /// parameter.
+    pub fn kind(&self, name: &str) -> Option<ParamKind> {
+        let name = Symbol::from(name).as_z3_symbol(&self.ctx);
+        match unsafe { Z3_param_descrs_get_kind(self.ctx.z3_ctx, self.z3_param_descrs, name) } {
+            ParamKind::Invalid => None,
+            kind => Some(kind),
+        }
+    }
+
+    /// The documentation of the parameter `name`, with `None` if there is
+    /// no such parameter.
+    pub fn documentation(&self, name: &str) -> Option<String> {
+        self.kind(name)?;
+        let name = Symbol::from(name).as_z3_symbol(&self.ctx);
+        let p = unsafe {
+            Z3_param_descrs_get_documentation(self.ctx.z3_ctx, self.z3_param_descrs, name)
+        };
+        if p.is_null() {
+            return None;
+        }
+        Some(unsafe { CStr::from_ptr(p) }.to_string_lossy().into_owned())
+    }
+}
+
+impl fmt::Display for ParamDescrs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let p = unsafe { Z3_param_descrs_to_string(self.ctx.z3_ctx, self.z3_param_descrs) };
+        if p.is_null() {
+            return Result::Err(fmt::Error);
+        }
+        match unsafe { CStr::from_ptr(p) }.to_str() {
+            Ok(s) => write!(f, "{}", s),
+            Err(_) => Result::Err(fmt::Error),
+        }
+    }
+}
+
+impl fmt::Debug for ParamDescrs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        <Self as fmt::Display>::fmt(self, f)
+    }
+}
+
+impl Drop for ParamDescrs {
+    fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "ParamDescrs", -1);
+        unsafe { Z3_param_descrs_dec_ref(self.ctx.z3_ctx, self.z3_param_descrs) };
     }
 }
 
@@ -71,7 +229,7 @@ impl Params {
 /// - [`set_global_param()`]
 /// - [`reset_all_global_params()`]
 pub fn get_global_param(k: &str) -> Option<String> {
-    let ks = CString::new(k).unwrap();
+    let ks = cstring(k);
     let mut ptr = std::ptr::null();
     if unsafe { Z3_global_param_get(ks.as_ptr(), &mut ptr as Z3_string_ptr) } {
         let vs = unsafe { CStr::from_ptr(ptr) };
@@ -88,8 +246,8 @@ pub fn get_global_param(k: &str) -> Option<String> {
 /// - [`get_global_param()`]
 /// - [`reset_all_global_params()`]
 pub fn set_global_param(k: &str, v: &str) {
-    let ks = CString::new(k).unwrap();
-    let vs = CString::new(v).unwrap();
+    let ks = cstring(k);
+    let vs = cstring(v);
     unsafe { Z3_global_param_set(ks.as_ptr(), vs.as_ptr()) };
 }
 
@@ -124,6 +282,7 @@ impl fmt::Debug for Params {
 
 impl Drop for Params {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "Params", -1);
         unsafe { Z3_params_dec_ref(self.ctx.z3_ctx, self.z3_params) };
     }
 }
@@ -0,0 +1,602 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::ffi::CStr;
+use std::fmt;
+use std::path::Path;
+
+use z3_sys::*;
+
+use crate::conversion::{c_len, try_cstring};
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, FuncDecl, Sort};
+
+/// An error reported by Z3's SMT-LIB2 parser.
+///
+/// Z3 reports the position of most parse errors; when it does, it is
+/// available through [`ParseError::line()`] and [`ParseError::column()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+    line: Option<u32>,
+    column: Option<u32>,
+}
+
+impl ParseError {
+    pub(crate) fn new<S: Into<String>>(message: S) -> ParseError {
+        ParseError {
+            message: message.into(),
+            line: None,
+            column: None,
+        }
+    }
+
+    /// Build a `ParseError` out of a raw Z3 error message, which looks like
+    /// `(error "line 3 column 11: unknown constant y")`. Z3 may report more
+    /// than one error at once; only the first one is kept.
+    pub(crate) fn from_z3_message(raw: &str) -> ParseError {
+        Self::from_z3_messages(raw).remove(0)
+    }
+
+    /// Build a `ParseError` for each of the errors in a raw Z3 error
+    /// message, like [`ParseError::from_z3_message()`].
+    fn from_z3_messages(raw: &str) -> Vec<ParseError> {
+        let raw = raw.trim();
+        if !raw.starts_with("(error \"") {
+            return vec![Self::from_message(raw)];
+        }
+        raw.split("(error \"")
+            .skip(1)
+            .map(|m| Self::from_message(m.split("\")").next().unwrap_or(m)))
+            .collect()
+    }
+
+    /// Build a `ParseError` out of a message that may start with its
+    /// location, like `line 3 column 11: unknown constant y`.
+    fn from_message(msg: &str) -> ParseError {
+        let location = msg.strip_prefix("line ").and_then(|rest| {
+            let (line, rest) = rest.split_once(" column ")?;
+            let (column, rest) = rest.split_once(':')?;
+            Some((line.parse().ok()?, column.parse().ok()?, rest.trim_start()))
+        });
+        match location {
+            Some((line, column, message)) => ParseError {
+                message: message.to_owned(),
+                line: Some(line),
+                column: Some(column),
+            },
+            None => ParseError::new(msg),
+        }
+    }
+
+    /// The error message, without location information.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The (1-based) line the error was reported at, if known.
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// The column the error was reported at, if known.
+    pub fn column(&self) -> Option<u32> {
+        self.column
+    }
+
+    /// The byte offset the error was reported at, in the input whose lines
+    /// start at `line_starts`.
+    fn offset(&self, line_starts: &[usize]) -> Option<usize> {
+        let line_start = line_starts.get(self.line?.checked_sub(1)? as usize)?;
+        Some(line_start + self.column? as usize)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(f, "line {} column {}: {}", line, column, self.message)
+            }
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// The result of parsing SMT-LIB2 input with a [`Parser`].
+#[derive(Debug)]
+pub struct Parsed {
+    /// The assertions in scope at the end of the input.
+    pub assertions: Vec<ast::Bool>,
+    /// The sorts declared with `declare-sort`, and the uninterpreted and
+    /// datatype sorts used by the assertions, by name.
+    pub sorts: HashMap<String, Sort>,
+    /// The functions and constants declared with `declare-fun` and
+    /// `declare-const`, whether the assertions use them or not, and the
+    /// other uninterpreted declarations used by the assertions (e.g. the
+    /// ones made known with [`Parser::decls()`]), by name.
+    ///
+    /// Building terms from these declarations (instead of creating new ones
+    /// with the same name) guarantees that they refer to the same symbols
+    /// as the parsed assertions. Declarations that went out of scope with
+    /// `pop`, and `define-fun` macros, which the parser expands, are not
+    /// included.
+    pub decls: HashMap<String, FuncDecl>,
+    /// The commands that were skipped because they failed to parse. This
+    /// is always empty unless the [`Parser`] is lenient.
+    pub errors: Vec<ParseError>,
+}
+
+impl Parsed {
+    /// Collect the symbol tables of `assertions`, on top of the `declared`
+    /// sorts and declarations.
+    fn new(
+        assertions: Vec<ast::Bool>,
+        errors: Vec<ParseError>,
+        declared: (Vec<Sort>, Vec<FuncDecl>),
+    ) -> Parsed {
+        let (declared_sorts, declared_decls) = declared;
+        let mut sorts: HashMap<String, Sort> = declared_sorts
+            .into_iter()
+            .map(|s| (s.to_string(), s))
+            .collect();
+        let mut decls: HashMap<String, FuncDecl> =
+            declared_decls.into_iter().map(|d| (d.name(), d)).collect();
+        let mut seen = HashSet::new();
+        let mut stack: Vec<ast::Dynamic> = assertions.iter().map(ast::Dynamic::from).collect();
+        while let Some(node) = stack.pop() {
+            let ctx = node.get_ctx();
+            if !seen.insert(unsafe { Z3_get_ast_id(ctx.z3_ctx, node.get_z3_ast()) }) {
+                continue;
+            }
+            let sort = node.get_sort();
+            if let SortKind::Uninterpreted | SortKind::Datatype = sort.kind() {
+                sorts.entry(sort.to_string()).or_insert(sort);
+            }
+            match node.kind() {
+                AstKind::App => {
+                    let decl = node.decl();
+                    if decl.kind() == DeclKind::UNINTERPRETED {
+                        decls.entry(decl.name()).or_insert(decl);
+                    }
+                    stack.extend(node.children());
+                }
+                AstKind::Quantifier => unsafe {
+                    let body = Z3_get_quantifier_body(ctx.z3_ctx, node.get_z3_ast());
+                    stack.push(ast::Dynamic::wrap(ctx, body));
+                },
+                _ => {}
+            }
+        }
+        Parsed {
+            assertions,
+            sorts,
+            decls,
+            errors,
+        }
+    }
+}
+
+/// A parser for SMT-LIB2 input.
+///
+/// By default, parsing fails on the first error. A lenient parser (see
+/// [`Parser::lenient()`]) instead skips every top-level command that fails
+/// to parse, records why, and returns the assertions of the remaining
+/// commands. This is useful for tools like editors, which want to make
+/// sense of input that is still being written.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{Config, Context, Parser};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let source = "(declare-const x Int)\n(assert (> y 2))\n(assert (> x 1))";
+///
+/// let err = Parser::new(ctx.clone()).parse_string(source).unwrap_err();
+/// assert_eq!(err.line(), Some(2));
+///
+/// let parsed = Parser::new(ctx).lenient(true).parse_string(source).unwrap();
+/// assert_eq!(parsed.assertions.len(), 1);
+/// assert_eq!(parsed.errors.len(), 1);
+/// ```
+///
+/// The input can refer to sorts and declarations that were created in Rust,
+/// without declaring them itself, once they are made known to the parser
+/// with [`Parser::sorts()`] and [`Parser::decls()`]:
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, Parser};
+/// # let cfg = Config::new();
+/// # let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let parsed = Parser::new(ctx)
+///     .decls(&[x.decl()])
+///     .parse_string("(assert (> x 1))")
+///     .unwrap();
+/// assert_eq!(parsed.assertions[0], x.gt(&ast::Int::from_i64(x.get_ctx(), 1)));
+/// ```
+#[derive(Debug)]
+pub struct Parser {
+    ctx: Rc<Context>,
+    lenient: bool,
+    sorts: Vec<Sort>,
+    decls: Vec<FuncDecl>,
+}
+
+/// Where the input of [`Parser::parse_raw()`] comes from.
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    String(&'a str),
+    File(&'a Path),
+}
+
+impl Parser {
+    pub fn new(ctx: Rc<Context>) -> Parser {
+        Parser {
+            ctx,
+            lenient: false,
+            sorts: vec![],
+            decls: vec![],
+        }
+    }
+
+    /// Make `sorts` known to the parser, under their own names.
+    pub fn sorts(mut self, sorts: &[Sort]) -> Parser {
+        self.sorts.extend_from_slice(sorts);
+        self
+    }
+
+    /// Make `decls` known to the parser, under their own names.
+    pub fn decls(mut self, decls: &[FuncDecl]) -> Parser {
+        self.decls.extend_from_slice(decls);
+        self
+    }
+
+    /// Set whether commands that fail to parse are skipped instead of
+    /// failing the whole parse.
+    pub fn lenient(mut self, lenient: bool) -> Parser {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Parse the SMT-LIB2 commands in `source`.
+    ///
+    /// A lenient parser only returns `Err` if `source` cannot be handed to
+    /// Z3 at all (e.g. because it contains a NUL byte).
+    pub fn parse_string(&self, source: &str) -> Result<Parsed, ParseError> {
+        if !self.lenient {
+            let assertions = self.parse_raw(Source::String(source))?;
+            return Ok(Parsed::new(assertions, vec![], self.declared(source, &[])));
+        }
+        try_cstring(source).map_err(|_| ParseError::new("input contains a NUL byte"))?;
+        let (assertions, skipped) = self.parse_skipping_errors(source);
+        let skipped_commands: Vec<usize> = skipped.iter().map(|(i, _)| *i).collect();
+        let declared = self.declared(source, &skipped_commands);
+        let errors = skipped.into_iter().map(|(_, e)| e).collect();
+        Ok(Parsed::new(assertions, errors, declared))
+    }
+
+    /// The sorts and declarations that the `declare-sort`, `declare-fun`
+    /// and `declare-const` commands of `source` (except the `skipped` ones,
+    /// by index) declare, and that are still in scope at its end.
+    ///
+    /// Z3 only returns the assertions of a parse, so the declaration
+    /// commands are parsed again, followed by an assertion that mentions
+    /// each declared symbol, to look them up. Z3 shares equal declarations,
+    /// so these are the ones the assertions of `source` use.
+    fn declared(&self, source: &str, skipped: &[usize]) -> (Vec<Sort>, Vec<FuncDecl>) {
+        let mut script = String::new();
+        let mut probes = vec![];
+        for (i, &(start, stop)) in commands(source).iter().enumerate() {
+            if skipped.binary_search(&i).is_ok() {
+                continue;
+            }
+            let command = &source[start..stop];
+            match elements(command).as_slice() {
+                ["declare-sort", name, arity @ ..] if arity.iter().all(|a| *a == "0") => {
+                    probes.push(format!(
+                        "(assert (forall ((|z3.rs!0| {})) (= |z3.rs!0| |z3.rs!0|)))",
+                        name
+                    ));
+                }
+                ["declare-const", name, _] => probes.push(format!("(assert (= {0} {0}))", name)),
+                ["declare-fun", name, domain, _] => {
+                    let domain = elements(domain);
+                    if domain.is_empty() {
+                        probes.push(format!("(assert (= {0} {0}))", name));
+                    } else {
+                        let vars: Vec<String> = (0..domain.len())
+                            .map(|j| format!("|z3.rs!{}|", j))
+                            .collect();
+                        let bound: Vec<String> = vars
+                            .iter()
+                            .zip(&domain)
+                            .map(|(v, s)| format!("({} {})", v, s))
+                            .collect();
+                        let app = format!("({} {})", name, vars.join(" "));
+                        probes.push(format!(
+                            "(assert (forall ({}) (= {1} {1})))",
+                            bound.join(" "),
+                            app
+                        ));
+                    }
+                }
+                ["declare-sort" | "define-sort" | "declare-datatype" | "declare-datatypes"
+                | "push" | "pop" | "reset", ..] => {}
+                _ => continue,
+            }
+            script.push_str(command);
+            script.push('\n');
+        }
+        for probe in probes {
+            script.push_str(&probe);
+            script.push('\n');
+        }
+
+        // Probes for symbols that went out of scope fail, and are skipped.
+        let (assertions, _) = self.parse_skipping_errors(&script);
+        let mut sorts = vec![];
+        let mut decls = vec![];
+        for probe in assertions {
+            let ctx = probe.get_ctx();
+            let mut node = ast::Dynamic::from(&probe);
+            if node.kind() == AstKind::Quantifier {
+                node = unsafe {
+                    let z3_ast = node.get_z3_ast();
+                    if Z3_get_quantifier_num_bound(ctx.z3_ctx, z3_ast) == 1 {
+                        let sort = Sort::wrap(
+                            ctx.clone(),
+                            Z3_get_quantifier_bound_sort(ctx.z3_ctx, z3_ast, 0),
+                        );
+                        if sort.kind() == SortKind::Uninterpreted {
+                            sorts.push(sort);
+                        }
+                    }
+                    ast::Dynamic::wrap(ctx, Z3_get_quantifier_body(ctx.z3_ctx, z3_ast))
+                };
+            }
+            // The probe is `(= x x)`, for a constant or application `x`.
+            let declared = node
+                .children()
+                .first()
+                .filter(|x| x.kind() == AstKind::App)
+                .map(|x| x.decl())
+                .filter(|d| d.kind() == DeclKind::UNINTERPRETED);
+            decls.extend(declared);
+        }
+        (sorts, decls)
+    }
+
+    /// Parse `source`, skipping the top-level commands that fail to parse.
+    /// Return the assertions, and the index of each skipped command with
+    /// its error, in the order of the commands.
+    ///
+    /// Z3 keeps parsing after an error, and reports every error with its
+    /// position. So the commands it reports are blanked out, which keeps
+    /// the positions of the others, and the rest is parsed again. As Z3
+    /// skips failed commands the same way, this usually takes two passes
+    /// over `source`, instead of one per command.
+    fn parse_skipping_errors(&self, source: &str) -> (Vec<ast::Bool>, Vec<(usize, ParseError)>) {
+        let spans = commands(source);
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let mut text = source.to_owned();
+        let mut skipped: Vec<Option<ParseError>> = vec![None; spans.len()];
+        let assertions = loop {
+            let errors = match self.parse_raw_all(Source::String(&text)) {
+                Ok(assertions) => break assertions,
+                Err(errors) => errors,
+            };
+            let mut progress = false;
+            let mut unplaced = None;
+            for e in errors {
+                // Blame the last command that starts before the error.
+                let at = e
+                    .offset(&line_starts)
+                    .and_then(|offset| spans.iter().rposition(|&(start, _)| start <= offset));
+                match at {
+                    Some(i) if skipped[i].is_none() => {
+                        blank_out(&mut text, spans[i]);
+                        skipped[i] = Some(e);
+                        progress = true;
+                    }
+                    _ => unplaced = unplaced.or(Some(e)),
+                }
+            }
+            if !progress {
+                // Blame an error that Z3 did not place on the last command
+                // that is left.
+                match (skipped.iter().rposition(Option::is_none), unplaced) {
+                    (Some(i), Some(e)) => {
+                        blank_out(&mut text, spans[i]);
+                        skipped[i] = Some(e);
+                    }
+                    _ => break vec![],
+                }
+            }
+        };
+        let skipped = skipped
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, e)| Some((i, e?)))
+            .collect();
+        (assertions, skipped)
+    }
+
+    /// Parse the SMT-LIB2 commands in the file at `path`, like
+    /// [`Parser::parse_string()`] after reading the whole file into memory.
+    pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<Parsed, ParseError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| ParseError::new(format!("{}: {}", path.display(), e)))?;
+        self.parse_string(&source)
+    }
+
+    pub(crate) fn parse_string_raw(&self, source: &str) -> Result<Vec<ast::Bool>, ParseError> {
+        self.parse_raw(Source::String(source))
+    }
+
+    pub(crate) fn parse_file_raw(&self, path: &Path) -> Result<Vec<ast::Bool>, ParseError> {
+        self.parse_raw(Source::File(path))
+    }
+
+    fn parse_raw(&self, source: Source) -> Result<Vec<ast::Bool>, ParseError> {
+        self.parse_raw_all(source)
+            .map_err(|mut errors| errors.remove(0))
+    }
+
+    /// Parse `source` like [`Parser::parse_raw()`], but return all the
+    /// errors Z3 reports.
+    fn parse_raw_all(&self, source: Source) -> Result<Vec<ast::Bool>, Vec<ParseError>> {
+        let input = match source {
+            Source::String(s) => {
+                try_cstring(s).map_err(|_| vec![ParseError::new("input contains a NUL byte")])?
+            }
+            Source::File(p) => p
+                .to_str()
+                .and_then(|p| try_cstring(p).ok())
+                .ok_or_else(|| {
+                    vec![ParseError::new(format!(
+                        "invalid file name {}",
+                        p.display()
+                    ))]
+                })?,
+        };
+        let z3_ctx = self.ctx.z3_ctx;
+        let sort_names: Vec<Z3_symbol> = self
+            .sorts
+            .iter()
+            .map(|s| unsafe { Z3_get_sort_name(z3_ctx, s.z3_sort) })
+            .collect();
+        let sorts: Vec<Z3_sort> = self.sorts.iter().map(|s| s.z3_sort).collect();
+        let decl_names: Vec<Z3_symbol> = self
+            .decls
+            .iter()
+            .map(|d| unsafe { Z3_get_decl_name(z3_ctx, d.z3_func_decl) })
+            .collect();
+        let decls: Vec<Z3_func_decl> = self.decls.iter().map(|d| d.z3_func_decl).collect();
+        unsafe {
+            // The parser does not clear the error code of a previous failed
+            // call, so do it here to not pick up a stale error.
+            Z3_set_error(z3_ctx, ErrorCode::OK);
+            let parse = match source {
+                Source::String(_) => Z3_parse_smtlib2_string,
+                Source::File(_) => Z3_parse_smtlib2_file,
+            };
+            let z3_vec = parse(
+                z3_ctx,
+                input.as_ptr(),
+                c_len(sorts.len()),
+                sort_names.as_ptr(),
+                sorts.as_ptr(),
+                c_len(decls.len()),
+                decl_names.as_ptr(),
+                decls.as_ptr(),
+            );
+            let code = Z3_get_error_code(z3_ctx);
+            if code != ErrorCode::OK || z3_vec.is_null() {
+                let msg = CStr::from_ptr(Z3_get_error_msg(z3_ctx, code));
+                return Err(ParseError::from_z3_messages(&msg.to_string_lossy()));
+            }
+            Z3_ast_vector_inc_ref(z3_ctx, z3_vec);
+            let assertions = (0..Z3_ast_vector_size(z3_ctx, z3_vec))
+                .map(|i| ast::Bool::wrap(self.ctx.clone(), Z3_ast_vector_get(z3_ctx, z3_vec, i)))
+                .collect();
+            Z3_ast_vector_dec_ref(z3_ctx, z3_vec);
+            Ok(assertions)
+        }
+    }
+}
+
+/// The elements of the parenthesized expression `expr`, e.g.
+/// `declare-const`, `x` and `Int` for `(declare-const x Int)`.
+fn elements(expr: &str) -> Vec<&str> {
+    match expr.strip_prefix('(').and_then(|e| e.strip_suffix(')')) {
+        Some(inner) => commands(inner)
+            .into_iter()
+            .map(|(start, stop)| &inner[start..stop])
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Replace the bytes of `text` in `span` with spaces, except for line
+/// breaks, so that the positions of the rest stay the same.
+fn blank_out(text: &mut String, (start, stop): (usize, usize)) {
+    let blank: String = text[start..stop]
+        .chars()
+        .flat_map(|c| {
+            let fill = if c == '\n' { '\n' } else { ' ' };
+            std::iter::repeat(fill).take(c.len_utf8())
+        })
+        .collect();
+    text.replace_range(start..stop, &blank);
+}
+
+/// Split `source` into the byte ranges of its top-level commands.
+///
+/// A top-level command is a balanced parenthesized expression, or a stray
+/// atom outside of any parentheses. Comments, string literals and quoted
+/// symbols are taken into account; an unterminated command extends to the
+/// end of `source`.
+fn commands(source: &str) -> Vec<(usize, usize)> {
+    let bytes = source.as_bytes();
+    let mut spans = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b';' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            c if c.is_ascii_whitespace() => i += 1,
+            b'(' => {
+                let start = i;
+                let mut depth = 0;
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'(' => depth += 1,
+                        b')' => depth -= 1,
+                        b';' => {
+                            while i + 1 < bytes.len() && bytes[i + 1] != b'\n' {
+                                i += 1;
+                            }
+                        }
+                        q @ (b'"' | b'|') => {
+                            i += 1;
+                            while i < bytes.len() && bytes[i] != q {
+                                i += 1;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                spans.push((start, i.min(bytes.len())));
+            }
+            _ => {
+                let start = i;
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'(' {
+                    if let q @ (b'"' | b'|') = bytes[i] {
+                        i += 1;
+                        while i < bytes.len() && bytes[i] != q {
+                            i += 1;
+                        }
+                    }
+                    i += 1;
+                }
+                spans.push((start, i.min(bytes.len())));
+            }
+        }
+    }
+    spans
+}
@@ -1,13 +1,19 @@
-use std::convert::TryInto;
 use std::ffi::CStr;
 use std::fmt;
-use std::rc::Rc;
 
 use z3_sys::*;
 
+use crate::conversion::c_len;
+use crate::Rc;
 use crate::{ast::Ast, Context, Pattern};
 
 impl Pattern {
+    unsafe fn wrap(ctx: Rc<Context>, z3_pattern: Z3_pattern) -> Pattern {
+        Z3_inc_ref(ctx.z3_ctx, z3_pattern as Z3_ast);
+        crate::track_live(ctx.z3_ctx, "Pattern", 1);
+        Pattern { ctx, z3_pattern }
+    }
+
     /// Create a pattern for quantifier instantiation.
     ///
     /// Z3 uses pattern matching to instantiate quantifiers. If a
@@ -31,16 +37,20 @@ impl Pattern {
         assert!(terms.iter().all(|t| t.get_ctx().z3_ctx == ctx.z3_ctx));
 
         let terms: Vec<_> = terms.iter().map(|t| t.get_z3_ast()).collect();
-        let z3_pattern = unsafe {
+        unsafe {
             let p = Z3_mk_pattern(
                 ctx.z3_ctx,
-                terms.len().try_into().unwrap(),
+                c_len(terms.len()),
                 terms.as_ptr() as *const Z3_ast,
             );
-            Z3_inc_ref(ctx.z3_ctx, p as Z3_ast);
-            p
-        };
-        Pattern { ctx, z3_pattern }
+            Self::wrap(ctx, p)
+        }
+    }
+}
+
+impl Clone for Pattern {
+    fn clone(&self) -> Self {
+        unsafe { Self::wrap(self.ctx.clone(), self.z3_pattern) }
     }
 }
 
@@ -65,6 +75,7 @@ impl fmt::Display for Pattern {
 
 impl Drop for Pattern {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "Pattern", -1);
         unsafe {
             Z3_dec_ref(self.ctx.z3_ctx, self.z3_pattern as Z3_ast);
         }
@@ -0,0 +1,155 @@
+use num::rational::BigRational;
+use num::Zero;
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Context};
+
+/// A continuous piecewise-linear function, given by its breakpoints.
+///
+/// Between two consecutive breakpoints `(x_i, y_i)` and `(x_j, y_j)`, the
+/// function interpolates linearly; before the first and after the last
+/// breakpoint, it extends the first and last segment.
+///
+/// [`PiecewiseLinear::apply()`] encodes the function with one `ite` per
+/// segment. Convex and concave functions have cheaper, purely linear
+/// encodings of bounds on their value, which
+/// [`PiecewiseLinear::at_most()`] and [`PiecewiseLinear::at_least()`] use.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, PiecewiseLinear, SatResult, Solver};
+/// # use num::{BigInt, rational::BigRational};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let r = |i: i64| BigRational::from_integer(BigInt::from(i));
+/// // A tariff: 2 per unit up to 100 units, then 3 per unit.
+/// let tariff = PiecewiseLinear::new(&[(r(0), r(0)), (r(100), r(200)), (r(200), r(500))]);
+/// assert!(tariff.is_convex());
+///
+/// let usage = ast::Real::new_const(ctx.clone(), "usage");
+/// let cost = tariff.apply(&usage);
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&usage._eq(&ast::Real::from_real(ctx.clone(), 150, 1)));
+/// assert_eq!(solver.check(), SatResult::Sat);
+/// let model = solver.get_model().unwrap();
+/// assert_eq!(model.eval(&cost, true).unwrap().as_real(), Some((350, 1)));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PiecewiseLinear {
+    points: Vec<(BigRational, BigRational)>,
+}
+
+impl PiecewiseLinear {
+    /// Create the function through `points`, dropping breakpoints that do
+    /// not change the slope.
+    ///
+    /// # Panics
+    ///
+    /// If there are fewer than two points, or their x-coordinates are not
+    /// strictly increasing.
+    pub fn new(points: &[(BigRational, BigRational)]) -> PiecewiseLinear {
+        assert!(points.len() >= 2, "need at least two breakpoints");
+        assert!(
+            points.windows(2).all(|w| w[0].0 < w[1].0),
+            "breakpoints are not strictly increasing"
+        );
+        let mut kept: Vec<(BigRational, BigRational)> = vec![points[0].clone()];
+        for (i, p) in points.iter().enumerate().skip(1) {
+            let collinear =
+                i + 1 < points.len() && slope(&kept[kept.len() - 1], p) == slope(p, &points[i + 1]);
+            if !collinear {
+                kept.push(p.clone());
+            }
+        }
+        PiecewiseLinear { points: kept }
+    }
+
+    /// The breakpoints of this function.
+    pub fn points(&self) -> &[(BigRational, BigRational)] {
+        &self.points
+    }
+
+    /// Whether the slopes of the segments never decrease.
+    pub fn is_convex(&self) -> bool {
+        self.slopes().windows(2).all(|w| w[0] <= w[1])
+    }
+
+    /// Whether the slopes of the segments never increase.
+    pub fn is_concave(&self) -> bool {
+        self.slopes().windows(2).all(|w| w[0] >= w[1])
+    }
+
+    /// The value of this function at `x`.
+    pub fn apply(&self, x: &ast::Real) -> ast::Real {
+        let ctx = x.get_ctx();
+        let segments = self.points.len() - 1;
+        // Select the segment from the last one backwards, so that the
+        // first and last segments extend beyond the breakpoints.
+        (0..segments - 1)
+            .rev()
+            .fold(self.line(&ctx, segments - 1, x), |rest, i| {
+                let end = ast::Real::from_big_rational(ctx.clone(), &self.points[i + 1].0);
+                x.lt(&end).ite(&self.line(&ctx, i, x), &rest)
+            })
+    }
+
+    /// Return the constraint `f(x) <= y`. For a convex function, this is
+    /// the conjunction of `y` bounding every segment's line from above,
+    /// without any `ite`.
+    pub fn at_most(&self, x: &ast::Real, y: &ast::Real) -> ast::Bool {
+        if self.is_convex() {
+            self.lines(x, |line| line.le(y))
+        } else {
+            self.apply(x).le(y)
+        }
+    }
+
+    /// Return the constraint `f(x) >= y`, which is linear for a concave
+    /// function, like [`PiecewiseLinear::at_most()`].
+    pub fn at_least(&self, x: &ast::Real, y: &ast::Real) -> ast::Bool {
+        if self.is_concave() {
+            self.lines(x, |line| line.ge(y))
+        } else {
+            self.apply(x).ge(y)
+        }
+    }
+
+    fn slopes(&self) -> Vec<BigRational> {
+        self.points
+            .windows(2)
+            .map(|w| slope(&w[0], &w[1]))
+            .collect()
+    }
+
+    /// The line through segment `i`, at `x`.
+    fn line(&self, ctx: &Rc<Context>, i: usize, x: &ast::Real) -> ast::Real {
+        let (x0, y0) = &self.points[i];
+        let m = slope(&self.points[i], &self.points[i + 1]);
+        let real = |v: &BigRational| ast::Real::from_big_rational(ctx.clone(), v);
+        if m.is_zero() {
+            real(y0)
+        } else {
+            real(y0) + real(&m) * (x.clone() - real(x0))
+        }
+    }
+
+    fn lines(&self, x: &ast::Real, f: impl Fn(ast::Real) -> ast::Bool) -> ast::Bool {
+        let ctx = x.get_ctx();
+        let constraints: Vec<ast::Bool> = (0..self.points.len() - 1)
+            .map(|i| f(self.line(&ctx, i, x)))
+            .collect();
+        ast::Bool::and(ctx, &constraints)
+    }
+}
+
+/// Return the value at `x` of the piecewise-linear function through
+/// `points`, see [`PiecewiseLinear`].
+pub fn piecewise_linear(x: &ast::Real, points: &[(BigRational, BigRational)]) -> ast::Real {
+    PiecewiseLinear::new(points).apply(x)
+}
+
+fn slope(a: &(BigRational, BigRational), b: &(BigRational, BigRational)) -> BigRational {
+    (&b.1 - &a.1) / (&b.0 - &a.0)
+}
@@ -0,0 +1,171 @@
+use crate::ast::{self, Ast, Bool, Dynamic};
+use crate::{DeclKind, Goal, Model, Tactic};
+
+/// Assertions simplified by [`preprocess_preserving_models()`], with what
+/// it takes to turn their models into models of the original assertions.
+#[derive(Clone, Debug)]
+pub struct Preprocessed {
+    /// The simplified assertions, which are satisfiable exactly when the
+    /// original ones are.
+    pub assertions: Vec<Bool>,
+    /// The eliminated constants, each with the term it equals in every
+    /// model of the original assertions. The terms only mention constants
+    /// that are left in `assertions`, in the order they were eliminated.
+    pub substitutions: Vec<(Dynamic, Dynamic)>,
+}
+
+impl Preprocessed {
+    /// A model of the original assertions, from a `model` of the
+    /// simplified ones: a copy of `model` in which each eliminated constant
+    /// is given the value of its term.
+    pub fn reconstruct_model(&self, model: &Model) -> Model {
+        let model = model.copy();
+        for (var, term) in &self.substitutions {
+            let value = model
+                .eval(term, true)
+                .expect("terms of substitutions can be evaluated");
+            model.add_const_interp(&var.decl(), &value);
+        }
+        model
+    }
+}
+
+/// Simplify `assertions` without losing their models.
+///
+/// Z3's `simplify` and `propagate-values` tactics rewrite the assertions
+/// into equivalent ones. Then, like Z3's `solve-eqs`, every assertion
+/// `x = t`, in which `x` is a constant that does not occur in `t`,
+/// eliminates `x` by substituting `t` for it in the other assertions; so
+/// do `b` and `not b` for a Boolean constant `b`. Unlike `solve-eqs`,
+/// which keeps its substitutions to itself, this returns them, so that
+/// [`Preprocessed::reconstruct_model()`] can rebuild models of the
+/// original assertions.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let y = ast::Int::new_const(ctx.clone(), "y");
+/// let z = ast::Int::new_const(ctx.clone(), "z");
+/// let assertions = [
+///     x._eq(&(&y + 1i64)),
+///     y._eq(&(&z * 2i64)),
+///     x.gt(&ast::Int::from_i64(ctx.clone(), 10)),
+/// ];
+///
+/// let preprocessed = z3::preprocess_preserving_models(&assertions);
+/// assert_eq!(preprocessed.assertions.len(), 1);
+/// assert_eq!(preprocessed.substitutions.len(), 2);
+///
+/// let solver = Solver::new(ctx.clone());
+/// for a in &preprocessed.assertions {
+///     solver.assert(a);
+/// }
+/// assert_eq!(solver.check(), SatResult::Sat);
+/// let model = preprocessed.reconstruct_model(&solver.get_model().unwrap());
+/// for a in &assertions {
+///     assert_eq!(model.eval(a, true).unwrap().as_bool(), Some(true));
+/// }
+/// ```
+pub fn preprocess_preserving_models(assertions: &[Bool]) -> Preprocessed {
+    let ctx = match assertions.first() {
+        Some(a) => a.get_ctx(),
+        None => {
+            return Preprocessed {
+                assertions: vec![],
+                substitutions: vec![],
+            }
+        }
+    };
+    let goal = Goal::new(ctx.clone(), true, false, false);
+    for a in assertions {
+        goal.assert(a);
+    }
+    let tactic = Tactic::new(ctx.clone(), "simplify")
+        .and_then(&Tactic::new(ctx.clone(), "propagate-values"));
+    let result = tactic
+        .apply(&goal, None)
+        .expect("simplify and propagate-values do not fail");
+    let mut assertions: Vec<Bool> = result
+        .list_subgoals()
+        .flat_map(|g| g.iter().collect::<Vec<_>>())
+        .collect();
+
+    let mut substitutions: Vec<(Dynamic, Dynamic)> = vec![];
+    while let Some((i, var, term)) = assertions
+        .iter()
+        .enumerate()
+        .find_map(|(i, a)| definition(a).map(|(var, term)| (i, var, term)))
+    {
+        assertions.remove(i);
+        let substitute = |a: &Dynamic| a.substitute(&[(&var, &term)]).simplify();
+        assertions = assertions
+            .iter()
+            .map(|a| substitute(&Dynamic::from_ast(a)).as_bool().unwrap())
+            .flat_map(|a| conjuncts(&a))
+            .filter(|a| a.as_bool() != Some(true))
+            .collect();
+        for (_, t) in substitutions.iter_mut() {
+            *t = substitute(t);
+        }
+        substitutions.push((var, term));
+    }
+    Preprocessed {
+        assertions,
+        substitutions,
+    }
+}
+
+/// The constant `a` defines and its term, if `a` is `x = t` or `t = x`
+/// for a constant `x` that does not occur in `t`, or a Boolean constant or
+/// its negation.
+fn definition(a: &Bool) -> Option<(Dynamic, Dynamic)> {
+    let ctx = a.get_ctx();
+    if is_uninterpreted_const(a) {
+        return Some((Dynamic::from_ast(a), Bool::from_bool(ctx, true).into()));
+    }
+    if !a.is_app() {
+        return None;
+    }
+    let args = a.children();
+    match a.decl().kind() {
+        DeclKind::NOT if is_uninterpreted_const(&args[0]) => {
+            Some((args[0].clone(), Bool::from_bool(ctx, false).into()))
+        }
+        DeclKind::EQ => [(&args[0], &args[1]), (&args[1], &args[0])]
+            .iter()
+            .find(|(var, term)| is_uninterpreted_const(*var) && !occurs(var, term))
+            .map(|(var, term)| ((*var).clone(), (*term).clone())),
+        _ => None,
+    }
+}
+
+fn is_uninterpreted_const(a: &dyn Ast) -> bool {
+    a.is_const() && a.decl().kind() == DeclKind::UNINTERPRETED
+}
+
+/// Whether `var` occurs in `term`.
+fn occurs(var: &Dynamic, term: &Dynamic) -> bool {
+    let mut found = false;
+    ast::visit(term, |a, _| {
+        found |= a == var;
+        !found
+    });
+    found
+}
+
+/// The conjuncts of `a`, which is not a conjunction if there is only one.
+fn conjuncts(a: &Bool) -> Vec<Bool> {
+    if a.is_app() && a.decl().kind() == DeclKind::AND {
+        a.children()
+            .iter()
+            .flat_map(|c| conjuncts(&c.as_bool().unwrap()))
+            .collect()
+    } else {
+        vec![a.clone()]
+    }
+}
@@ -1,16 +1,18 @@
-use std::ffi::{CStr, CString};
+use std::ffi::CStr;
 use std::fmt;
-use std::rc::Rc;
 use std::result::Result;
 use std::str::Utf8Error;
 
 use z3_sys::*;
 
+use crate::conversion::cstring;
+use crate::Rc;
 use crate::{Context, Goal, Probe};
 
 impl Probe {
     unsafe fn wrap(ctx: Rc<Context>, z3_probe: Z3_probe) -> Probe {
         Z3_probe_inc_ref(ctx.z3_ctx, z3_probe);
+        crate::track_live(ctx.z3_ctx, "Probe", 1);
         Probe { ctx, z3_probe }
     }
 
@@ -39,7 +41,7 @@ impl Probe {
     /// Return a string containing a description of the probe with
     /// the given `name`.
     pub fn describe(ctx: Rc<Context>, name: &str) -> std::result::Result<&str, Utf8Error> {
-        let probe_name = CString::new(name).unwrap();
+        let probe_name = cstring(name);
         unsafe { CStr::from_ptr(Z3_probe_get_descr(ctx.z3_ctx, probe_name.as_ptr())).to_str() }
     }
 
@@ -48,14 +50,19 @@ impl Probe {
     /// # Example
     ///
     /// ```
+    /// use std::rc::Rc;
     /// use z3::{Config, Context, Probe};
     ///
     /// let cfg = Config::new();
-    /// let ctx = Context::new(&cfg);
-    /// let probe = Probe::new(&ctx, "is-qfbv");
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let probe = Probe::new(ctx, "is-qfbv");
     /// ```
+    ///
+    /// # See also
+    ///
+    /// - [`Probe::list_all()`]
     pub fn new(ctx: Rc<Context>, name: &str) -> Probe {
-        let probe_name = CString::new(name).unwrap();
+        let probe_name = cstring(name);
         unsafe {
             let probe = Z3_mk_probe(ctx.z3_ctx, probe_name.as_ptr());
             Self::wrap(ctx, probe)
@@ -66,17 +73,49 @@ impl Probe {
     ///
     /// The probe always produce a double value. "Boolean" probes return
     /// `0.0` for `false`, and a value different from `0.0` for `true`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Goal, Probe, Tactic};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let y = ast::Int::new_const(ctx.clone(), "y");
+    /// let goal = Goal::new(ctx.clone(), false, false, false);
+    /// goal.assert(&x.lt(&y));
+    ///
+    /// let consts = Probe::new(ctx.clone(), "num-consts");
+    /// assert_eq!(consts.apply(&goal), 2.0);
+    ///
+    /// // Linear integer arithmetic with few constants.
+    /// let few = consts.le(&Probe::constant(ctx.clone(), 10.0));
+    /// let small_lia = Probe::new(ctx.clone(), "is-qflia").and(&few);
+    /// assert_ne!(small_lia.apply(&goal), 0.0);
+    /// assert_eq!(small_lia.not().apply(&goal), 0.0);
+    ///
+    /// let tactic = Tactic::cond(
+    ///     ctx.clone(),
+    ///     &small_lia,
+    ///     &Tactic::new(ctx.clone(), "smt"),
+    ///     &Tactic::new(ctx.clone(), "fail"),
+    /// );
+    /// assert!(tactic.apply(&goal, None).is_ok());
+    /// ```
     pub fn apply(&self, goal: &Goal) -> f64 {
         unsafe { Z3_probe_apply(self.ctx.z3_ctx, self.z3_probe, goal.z3_goal) }
     }
 
     /// Return a probe that always evaluates to val.
     /// ```
-    /// use z3::{Config, Context, Probe};
+    /// use std::rc::Rc;
+    /// use z3::{Config, Context, Goal, Probe};
     ///
     /// let cfg = Config::new();
-    /// let ctx = Context::new(&cfg);
-    /// let probe = Probe::constant(&ctx, 1.0);
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let probe = Probe::constant(ctx.clone(), 1.0);
+    /// assert_eq!(probe.apply(&Goal::new(ctx, false, false, false)), 1.0);
     /// ```
     pub fn constant(ctx: Rc<Context>, val: f64) -> Probe {
         unsafe {
@@ -89,7 +128,7 @@ impl Probe {
     /// by `self` is less than the value returned by `p`.
     ///
     /// NOTE: For probes, "true" is any value different from 0.0.
-    pub fn lt(&self, p: Probe) -> Probe {
+    pub fn lt(&self, p: &Probe) -> Probe {
         unsafe {
             Self::wrap(
                 self.ctx.clone(),
@@ -199,6 +238,7 @@ impl fmt::Debug for Probe {
 
 impl Drop for Probe {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "Probe", -1);
         unsafe {
             Z3_probe_dec_ref(self.ctx.z3_ctx, self.z3_probe);
         }
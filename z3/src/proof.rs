@@ -0,0 +1,100 @@
+use std::fmt;
+
+use z3_sys::*;
+
+use crate::{ast, ast::Ast};
+
+/// A proof of unsatisfiability, see [`Solver::get_proof()`].
+///
+/// A proof is a tree of rule applications: each step applies a
+/// [rule](Proof::rule) to the proofs of its [premises](Proof::premises),
+/// and derives its [conclusion](Proof::conclusion). The conclusion of the
+/// root is `false`.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, DeclKind, SatResult, Solver};
+/// let mut cfg = Config::new();
+/// cfg.set_proof_generation(true);
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let a = ast::Bool::new_const(ctx.clone(), "a");
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&a);
+/// solver.assert(&!a.clone());
+/// assert_eq!(solver.check(), SatResult::Unsat);
+///
+/// let proof = solver.get_proof().unwrap();
+/// assert_eq!(proof.conclusion().as_bool(), Some(false));
+/// // Collect the assertions the proof starts from.
+/// let mut todo = vec![proof];
+/// let mut asserted = vec![];
+/// while let Some(p) = todo.pop() {
+///     if p.rule() == DeclKind::PR_ASSERTED {
+///         asserted.push(p.conclusion());
+///     }
+///     todo.extend(p.premises());
+/// }
+/// assert!(asserted.contains(&a));
+/// ```
+///
+/// [`Solver::get_proof()`]: crate::Solver::get_proof
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Proof {
+    ast: ast::Dynamic,
+}
+
+impl Proof {
+    /// # Panics
+    ///
+    /// If `ast` is not an application of a proof rule.
+    pub(crate) fn new(ast: ast::Dynamic) -> Proof {
+        assert!(
+            ast.get_sort().kind() == SortKind::Unknown && ast.is_app(),
+            "not a proof term"
+        );
+        Proof { ast }
+    }
+
+    /// The proof rule of this step, e.g. [`DeclKind::PR_MODUS_PONENS`].
+    pub fn rule(&self) -> DeclKind {
+        self.ast.decl().kind()
+    }
+
+    /// The name of the proof rule of this step, as printed by Z3, e.g.
+    /// `"mp"`.
+    pub fn rule_name(&self) -> String {
+        self.ast.decl().name()
+    }
+
+    /// The proofs of the facts this step derives its conclusion from.
+    pub fn premises(&self) -> Vec<Proof> {
+        let mut args = self.ast.children();
+        args.pop();
+        args.into_iter().map(Proof::new).collect()
+    }
+
+    /// The fact this step proves.
+    pub fn conclusion(&self) -> ast::Bool {
+        let n = self.ast.num_children();
+        self.ast.nth_child(n - 1).unwrap().as_bool().unwrap()
+    }
+
+    /// The term Z3 represents this proof by.
+    pub fn as_ast(&self) -> &ast::Dynamic {
+        &self.ast
+    }
+}
+
+impl fmt::Display for Proof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        <ast::Dynamic as fmt::Display>::fmt(&self.ast, f)
+    }
+}
+
+impl fmt::Debug for Proof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        <ast::Dynamic as fmt::Debug>::fmt(&self.ast, f)
+    }
+}
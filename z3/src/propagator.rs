@@ -0,0 +1,354 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::os::raw::{c_uint, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr::NonNull;
+
+use z3_sys::*;
+
+use crate::conversion::c_len;
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, Solver};
+
+/// A custom theory, plugged into a [`Solver`] with
+/// [`Solver::propagator()`].
+///
+/// The solver notifies the propagator about the terms registered with
+/// [`Solver::propagate_register()`]: when a Boolean or bit-vector term is
+/// assigned a value ([`UserPropagator::fixed()`]), when two terms are found
+/// equal or distinct ([`UserPropagator::eq()`], [`UserPropagator::diseq()`]),
+/// and when the solver is about to report a model
+/// ([`UserPropagator::final_check()`]). In those callbacks, the propagator
+/// can use the given [`PropagatorCallback`] to tell the solver about
+/// consequences of the current assignment.
+///
+/// The propagator must keep track of its own state across the solver's
+/// backtracking points, see [`UserPropagator::push()`] and
+/// [`UserPropagator::pop()`].
+///
+/// All methods have empty default implementations.
+///
+/// There is no callback for terms the solver creates itself
+/// (`Z3_solver_propagate_created`): Z3 added it after 4.8.12, along with
+/// the callback signatures this crate does not bind, see
+/// [`Features::user_propagator`](crate::Features::user_propagator).
+#[allow(unused_variables)]
+pub trait UserPropagator {
+    /// The solver created a new backtracking point.
+    fn push(&mut self) {}
+
+    /// The solver removed the `num_scopes` most recent backtracking points;
+    /// everything learned since then must be forgotten.
+    fn pop(&mut self, num_scopes: u32) {}
+
+    /// The registered term `term` was assigned `value`.
+    fn fixed(&mut self, cb: &PropagatorCallback, term: &ast::Dynamic, value: &ast::Dynamic) {}
+
+    /// The registered terms `x` and `y` were found equal.
+    fn eq(&mut self, cb: &PropagatorCallback, x: &ast::Dynamic, y: &ast::Dynamic) {}
+
+    /// The registered terms `x` and `y` were found distinct.
+    fn diseq(&mut self, cb: &PropagatorCallback, x: &ast::Dynamic, y: &ast::Dynamic) {}
+
+    /// All decision variables have been assigned and the solver is about
+    /// to report the assertions satisfiable. Propagating a conflict here
+    /// makes it keep searching.
+    fn final_check(&mut self, cb: &PropagatorCallback) {}
+}
+
+/// The registered terms of a [`UserPropagator`], by the id Z3 assigned them
+/// and by their AST id.
+#[derive(Debug, Default)]
+struct Registry {
+    terms: HashMap<c_uint, ast::Dynamic>,
+    ids: HashMap<c_uint, c_uint>,
+}
+
+pub(crate) struct PropagatorState {
+    ctx: Rc<Context>,
+    user: Box<dyn UserPropagator>,
+    registry: RefCell<Registry>,
+    // A panic raised by a callback, re-raised once control is back in Rust.
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+impl PropagatorState {
+    fn new(ctx: Rc<Context>, user: Box<dyn UserPropagator>) -> PropagatorState {
+        PropagatorState {
+            ctx,
+            user,
+            registry: RefCell::new(Registry::default()),
+            panic: None,
+        }
+    }
+
+    fn term(&self, id: c_uint) -> ast::Dynamic {
+        self.registry.borrow().terms[&id].clone()
+    }
+
+    /// Run `f` on the state behind `ptr`, unless a previous callback
+    /// panicked. A panic in `f` is caught, since it must not unwind into
+    /// Z3; it is re-raised by [`Solver::resume_propagator_panic()`].
+    unsafe fn with(ptr: *mut c_void, f: impl FnOnce(&mut PropagatorState)) {
+        if ptr.is_null() {
+            return;
+        }
+        let state = &mut *(ptr as *mut PropagatorState);
+        if state.panic.is_some() {
+            return;
+        }
+        if let Err(e) = panic::catch_unwind(AssertUnwindSafe(|| f(state))) {
+            state.panic = Some(e);
+        }
+    }
+}
+
+/// Used by the callbacks of a [`UserPropagator`] to propagate consequences
+/// to the solver.
+#[derive(Debug)]
+pub struct PropagatorCallback<'a> {
+    z3_cb: Z3_solver_callback,
+    ctx: &'a Context,
+    registry: &'a RefCell<Registry>,
+}
+
+impl PropagatorCallback<'_> {
+    /// Tell the solver that `consequence` follows from the current values
+    /// of the registered terms `fixed` and the equalities `eqs` between
+    /// registered terms.
+    ///
+    /// Propagating `false` reports a conflict.
+    ///
+    /// # Panics
+    ///
+    /// If one of the terms in `fixed` or `eqs` was not registered with
+    /// [`Solver::propagate_register()`].
+    pub fn propagate(
+        &self,
+        fixed: &[&dyn Ast],
+        eqs: &[(&dyn Ast, &dyn Ast)],
+        consequence: &ast::Bool,
+    ) {
+        let registry = self.registry.borrow();
+        let id = |t: &dyn Ast| {
+            let ast_id = unsafe { Z3_get_ast_id(self.ctx.z3_ctx, t.get_z3_ast()) };
+            *registry
+                .ids
+                .get(&ast_id)
+                .expect("term was not registered with the propagator")
+        };
+        let fixed: Vec<c_uint> = fixed.iter().map(|t| id(*t)).collect();
+        let lhs: Vec<c_uint> = eqs.iter().map(|(l, _)| id(*l)).collect();
+        let rhs: Vec<c_uint> = eqs.iter().map(|(_, r)| id(*r)).collect();
+        unsafe {
+            Z3_solver_propagate_consequence(
+                self.ctx.z3_ctx,
+                self.z3_cb,
+                c_len(fixed.len()),
+                fixed.as_ptr(),
+                c_len(lhs.len()),
+                lhs.as_ptr(),
+                rhs.as_ptr(),
+                consequence.get_z3_ast(),
+            )
+        };
+    }
+}
+
+impl Solver {
+    /// Plug `propagator` into this solver as a custom theory.
+    ///
+    /// Only the terms registered with [`Solver::propagate_register()`] are
+    /// reported to the propagator. The propagator is not carried over to
+    /// clones or translations of this solver.
+    ///
+    /// Z3 only supports user propagators on its SMT core, so this fails
+    /// for solvers not created with [`Solver::new_simple()`], and returns
+    /// Z3's error message. It also fails unless the Z3 library is one whose
+    /// propagator callbacks this crate binds, see
+    /// [`Features::user_propagator`](crate::Features::user_propagator).
+    /// A solver has at most one propagator, so this fails if it already
+    /// has one.
+    ///
+    /// # Examples
+    ///
+    /// A propagator that forbids more than one of its registered Booleans
+    /// to be true:
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, PropagatorCallback, SatResult, Solver, UserPropagator};
+    /// struct AtMostOne {
+    ///     trail: Vec<Vec<ast::Dynamic>>,
+    ///     set: Vec<ast::Dynamic>,
+    /// }
+    ///
+    /// impl UserPropagator for AtMostOne {
+    ///     fn push(&mut self) {
+    ///         self.trail.push(self.set.clone());
+    ///     }
+    ///     fn pop(&mut self, num_scopes: u32) {
+    ///         for _ in 0..num_scopes {
+    ///             self.set = self.trail.pop().unwrap();
+    ///         }
+    ///     }
+    ///     fn fixed(&mut self, cb: &PropagatorCallback, term: &ast::Dynamic, value: &ast::Dynamic) {
+    ///         if value.as_bool().unwrap().as_bool() == Some(true) {
+    ///             if let Some(other) = self.set.first() {
+    ///                 let conflict = ast::Bool::from_bool(term.get_ctx(), false);
+    ///                 cb.propagate(&[other, term], &[], &conflict);
+    ///             }
+    ///             self.set.push(term.clone());
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// # if !z3::features().user_propagator {
+    /// #     return;
+    /// # }
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let a = ast::Bool::new_const(ctx.clone(), "a");
+    /// let b = ast::Bool::new_const(ctx.clone(), "b");
+    /// let solver = Solver::new_simple(ctx.clone());
+    /// solver.propagator(Box::new(AtMostOne { trail: vec![], set: vec![] })).unwrap();
+    /// solver.propagate_register(&a);
+    /// solver.propagate_register(&b);
+    /// solver.assert(&a);
+    /// solver.assert(&b);
+    /// assert_eq!(solver.check(), SatResult::Unsat);
+    /// ```
+    pub fn propagator(&self, propagator: Box<dyn UserPropagator>) -> Result<(), String> {
+        if self.propagator.get().is_some() {
+            return Err("this solver already has a propagator".to_owned());
+        }
+        if !crate::features().user_propagator {
+            return Err(format!(
+                "user propagators are not supported with Z3 {}",
+                crate::version()
+            ));
+        }
+        let state = Box::into_raw(Box::new(PropagatorState::new(self.ctx.clone(), propagator)));
+        let ptr = state as *mut c_void;
+        unsafe {
+            Z3_set_error(self.ctx.z3_ctx, ErrorCode::OK);
+            Z3_solver_propagate_init(
+                self.ctx.z3_ctx,
+                self.z3_slv,
+                ptr,
+                Some(push_eh),
+                Some(pop_eh),
+                Some(fresh_eh),
+            );
+            let code = Z3_get_error_code(self.ctx.z3_ctx);
+            if code != ErrorCode::OK {
+                drop(Box::from_raw(state));
+                let msg = CStr::from_ptr(Z3_get_error_msg(self.ctx.z3_ctx, code));
+                return Err(msg.to_string_lossy().into_owned());
+            }
+            Z3_solver_propagate_fixed(self.ctx.z3_ctx, self.z3_slv, Some(fixed_eh));
+            Z3_solver_propagate_eq(self.ctx.z3_ctx, self.z3_slv, Some(eq_eh));
+            Z3_solver_propagate_diseq(self.ctx.z3_ctx, self.z3_slv, Some(diseq_eh));
+            Z3_solver_propagate_final(self.ctx.z3_ctx, self.z3_slv, Some(final_eh));
+        }
+        self.propagator.set(NonNull::new(state));
+        Ok(())
+    }
+
+    /// Register `term` with the propagator of this solver, so that the
+    /// propagator is notified about its value.
+    ///
+    /// # Panics
+    ///
+    /// If this solver has no propagator, see [`Solver::propagator()`].
+    pub fn propagate_register(&self, term: &impl Ast) {
+        let state = self
+            .propagator
+            .get()
+            .expect("this solver does not have a propagator");
+        let state = unsafe { state.as_ref() };
+        let id = unsafe {
+            Z3_solver_propagate_register(self.ctx.z3_ctx, self.z3_slv, term.get_z3_ast())
+        };
+        let ast_id = unsafe { Z3_get_ast_id(self.ctx.z3_ctx, term.get_z3_ast()) };
+        let mut registry = state.registry.borrow_mut();
+        registry.terms.insert(id, ast::Dynamic::from_ast(term));
+        registry.ids.insert(ast_id, id);
+    }
+
+    /// Re-raise a panic that happened in a propagator callback during the
+    /// last check.
+    pub(crate) fn resume_propagator_panic(&self) {
+        let panic = match self.propagator.get() {
+            Some(mut state) => unsafe { state.as_mut() }.panic.take(),
+            None => None,
+        };
+        if let Some(e) = panic {
+            panic::resume_unwind(e);
+        }
+    }
+}
+
+unsafe extern "C" fn push_eh(ctx: *mut c_void) {
+    PropagatorState::with(ctx, |s| s.user.push());
+}
+
+unsafe extern "C" fn pop_eh(ctx: *mut c_void, num_scopes: c_uint) {
+    PropagatorState::with(ctx, |s| s.user.pop(num_scopes));
+}
+
+unsafe extern "C" fn fresh_eh(_ctx: *mut c_void, _new_context: Z3_context) -> *mut c_void {
+    // Propagators are not carried over to copies of the solver; a null
+    // user context makes all callbacks of the copy no-ops.
+    std::ptr::null_mut()
+}
+
+unsafe extern "C" fn fixed_eh(ctx: *mut c_void, cb: Z3_solver_callback, id: c_uint, value: Z3_ast) {
+    PropagatorState::with(ctx, |s| {
+        let term = s.term(id);
+        let value = ast::Dynamic::wrap(s.ctx.clone(), value);
+        let cb = PropagatorCallback {
+            z3_cb: cb,
+            ctx: &s.ctx,
+            registry: &s.registry,
+        };
+        s.user.fixed(&cb, &term, &value);
+    });
+}
+
+unsafe extern "C" fn eq_eh(ctx: *mut c_void, cb: Z3_solver_callback, x: c_uint, y: c_uint) {
+    PropagatorState::with(ctx, |s| {
+        let (x, y) = (s.term(x), s.term(y));
+        let cb = PropagatorCallback {
+            z3_cb: cb,
+            ctx: &s.ctx,
+            registry: &s.registry,
+        };
+        s.user.eq(&cb, &x, &y);
+    });
+}
+
+unsafe extern "C" fn diseq_eh(ctx: *mut c_void, cb: Z3_solver_callback, x: c_uint, y: c_uint) {
+    PropagatorState::with(ctx, |s| {
+        let (x, y) = (s.term(x), s.term(y));
+        let cb = PropagatorCallback {
+            z3_cb: cb,
+            ctx: &s.ctx,
+            registry: &s.registry,
+        };
+        s.user.diseq(&cb, &x, &y);
+    });
+}
+
+unsafe extern "C" fn final_eh(ctx: *mut c_void, cb: Z3_solver_callback) {
+    PropagatorState::with(ctx, |s| {
+        let cb = PropagatorCallback {
+            z3_cb: cb,
+            ctx: &s.ctx,
+            registry: &s.registry,
+        };
+        s.user.final_check(&cb);
+    });
+}
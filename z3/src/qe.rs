@@ -0,0 +1,97 @@
+use z3_sys::*;
+
+use crate::conversion::c_len;
+use crate::{ast, ast::Ast, Model};
+
+/// Model-based projection: eliminate `vars` from `formula`, guided by
+/// `model`.
+///
+/// `model` must satisfy `formula`. The result does not mention `vars`, is
+/// satisfied by `model`, and implies `exists vars. formula`; unlike full
+/// quantifier elimination, it only covers the part of the projection that
+/// contains `model`. This is the building block of IC3/PDR-style
+/// algorithms such as Spacer.
+///
+/// Z3 can not eliminate every variable (e.g. ones of uninterpreted sorts);
+/// those are left in the result.
+///
+/// # Panics
+///
+/// If one of `vars` is not a constant.
+///
+/// # See also:
+///
+/// - [`qe_lite()`]
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let y = ast::Int::new_const(ctx.clone(), "y");
+/// let formula = x.lt(&y) & y.lt(&ast::Int::from_i64(ctx.clone(), 5));
+///
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&formula);
+/// assert_eq!(solver.check(), SatResult::Sat);
+/// let model = solver.get_model().unwrap();
+///
+/// // Something equivalent to `x < 4`.
+/// let projected = z3::project(&model, &[&y], &formula);
+/// assert!(!projected.to_string().contains('y'));
+/// ```
+pub fn project(model: &Model, vars: &[&dyn Ast], formula: &ast::Bool) -> ast::Bool {
+    let ctx = formula.get_ctx();
+    let apps: Vec<Z3_app> = vars
+        .iter()
+        .map(|v| unsafe {
+            assert!(
+                Z3_is_app(ctx.z3_ctx, v.get_z3_ast())
+                    && Z3_get_app_num_args(ctx.z3_ctx, Z3_to_app(ctx.z3_ctx, v.get_z3_ast())) == 0,
+                "can only project constants"
+            );
+            Z3_to_app(ctx.z3_ctx, v.get_z3_ast())
+        })
+        .collect();
+    unsafe {
+        let projected = Z3_qe_model_project(
+            ctx.z3_ctx,
+            model.z3_mdl,
+            c_len(apps.len()),
+            apps.as_ptr(),
+            formula.get_z3_ast(),
+        );
+        ast::Bool::wrap(ctx, projected)
+    }
+}
+
+/// Cheaply eliminate as many of `vars` as possible from
+/// `exists vars. formula`, e.g. by solving equations for them.
+///
+/// Return the resulting formula, together with those of `vars` that could
+/// not be eliminated (and are still implicitly existentially quantified in
+/// the result).
+///
+/// # See also:
+///
+/// - [`project()`]
+pub fn qe_lite(vars: &[&dyn Ast], formula: &ast::Bool) -> (ast::Bool, Vec<ast::Dynamic>) {
+    let ctx = formula.get_ctx();
+    unsafe {
+        let z3_vec = Z3_mk_ast_vector(ctx.z3_ctx);
+        Z3_ast_vector_inc_ref(ctx.z3_ctx, z3_vec);
+        for v in vars {
+            Z3_ast_vector_push(ctx.z3_ctx, z3_vec, v.get_z3_ast());
+        }
+        let result = Z3_qe_lite(ctx.z3_ctx, z3_vec, formula.get_z3_ast());
+        let result = ast::Bool::wrap(ctx.clone(), result);
+        let remaining = (0..Z3_ast_vector_size(ctx.z3_ctx, z3_vec))
+            .map(|i| ast::Dynamic::wrap(ctx.clone(), Z3_ast_vector_get(ctx.z3_ctx, z3_vec, i)))
+            .collect();
+        Z3_ast_vector_dec_ref(ctx.z3_ctx, z3_vec);
+        (result, remaining)
+    }
+}
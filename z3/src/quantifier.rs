@@ -0,0 +1,154 @@
+use z3_sys::*;
+
+use crate::ast::{Ast, Bool, Dynamic};
+use crate::conversion::c_len;
+use crate::Rc;
+use crate::{Context, Pattern, Symbol};
+
+/// A builder for quantifiers with instantiation patterns, a weight, and
+/// identifiers, as a more configurable alternative to
+/// [`forall_const()`](crate::ast::forall_const) and
+/// [`exists_const()`](crate::ast::exists_const).
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, FuncDecl, Pattern, SatResult, Solver, Sort};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let int = Sort::int(ctx.clone());
+/// let f = FuncDecl::new(ctx.clone(), "f", &[&int], &int);
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let f_x = f.apply(&[&x]).as_int().unwrap();
+///
+/// // f is monotone, instantiated only for the terms f(t) in the goal.
+/// let axiom = ast::QuantifierBuilder::forall(ctx.clone(), &[&x])
+///     .pattern(Pattern::new(ctx.clone(), &[&f_x]))
+///     .weight(2)
+///     .qid("f-grows")
+///     .build(&f_x.gt(&x));
+/// assert!(axiom.to_string().contains(":qid f-grows"));
+///
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&axiom);
+/// let three = ast::Int::from_i64(ctx.clone(), 3);
+/// solver.assert(&f.apply(&[&three]).as_int().unwrap().le(&three));
+/// assert_eq!(solver.check(), SatResult::Unsat);
+/// ```
+#[derive(Debug)]
+pub struct QuantifierBuilder {
+    ctx: Rc<Context>,
+    is_forall: bool,
+    bounds: Vec<Dynamic>,
+    patterns: Vec<Pattern>,
+    no_patterns: Vec<Dynamic>,
+    weight: u32,
+    qid: Option<Symbol>,
+    skid: Option<Symbol>,
+}
+
+impl QuantifierBuilder {
+    /// Start building a universal quantifier over the constants `bounds`.
+    pub fn forall(ctx: Rc<Context>, bounds: &[&dyn Ast]) -> Self {
+        Self::new(ctx, true, bounds)
+    }
+
+    /// Start building an existential quantifier over the constants
+    /// `bounds`.
+    pub fn exists(ctx: Rc<Context>, bounds: &[&dyn Ast]) -> Self {
+        Self::new(ctx, false, bounds)
+    }
+
+    fn new(ctx: Rc<Context>, is_forall: bool, bounds: &[&dyn Ast]) -> Self {
+        assert!(bounds.iter().all(|a| a.get_ctx() == ctx));
+        assert!(
+            bounds.iter().all(|a| a.is_const()),
+            "bounds must be constants"
+        );
+        QuantifierBuilder {
+            ctx,
+            is_forall,
+            bounds: bounds.iter().map(|a| Dynamic::from_ast(*a)).collect(),
+            patterns: vec![],
+            no_patterns: vec![],
+            weight: 0,
+            qid: None,
+            skid: None,
+        }
+    }
+
+    /// Add an instantiation pattern. Without patterns, Z3 infers them.
+    pub fn pattern(mut self, pattern: Pattern) -> Self {
+        assert_eq!(pattern.ctx, self.ctx);
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// Add a term that must not be used as an instantiation pattern.
+    pub fn no_pattern(mut self, term: &dyn Ast) -> Self {
+        assert_eq!(term.get_ctx(), self.ctx);
+        self.no_patterns.push(Dynamic::from_ast(term));
+        self
+    }
+
+    /// Set the weight, where a higher weight makes Z3 instantiate the
+    /// quantifier less eagerly. The default is 0.
+    pub fn weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Set the identifier of the quantifier, which shows in statistics
+    /// and traces of quantifier instantiation.
+    pub fn qid<S: Into<Symbol>>(mut self, qid: S) -> Self {
+        self.qid = Some(qid.into());
+        self
+    }
+
+    /// Set the prefix of the names of the Skolem constants this quantifier
+    /// is replaced by.
+    pub fn skid<S: Into<Symbol>>(mut self, skid: S) -> Self {
+        self.skid = Some(skid.into());
+        self
+    }
+
+    /// Create the quantifier with the given body. Without bounds, this is
+    /// just `body`.
+    pub fn build(&self, body: &Bool) -> Bool {
+        assert_eq!(body.get_ctx(), self.ctx);
+        if self.bounds.is_empty() {
+            return body.clone();
+        }
+
+        let z3_ctx = self.ctx.z3_ctx;
+        let symbol = |s: &Option<Symbol>| match s {
+            Some(s) => s.as_z3_symbol(&self.ctx),
+            None => std::ptr::null_mut(),
+        };
+        let bounds: Vec<_> = self
+            .bounds
+            .iter()
+            .map(|a| unsafe { Z3_to_app(z3_ctx, a.z3_ast) })
+            .collect();
+        let patterns: Vec<_> = self.patterns.iter().map(|p| p.z3_pattern).collect();
+        let no_patterns: Vec<_> = self.no_patterns.iter().map(|a| a.z3_ast).collect();
+        unsafe {
+            let z3_ast = Z3_mk_quantifier_const_ex(
+                z3_ctx,
+                self.is_forall,
+                self.weight,
+                symbol(&self.qid),
+                symbol(&self.skid),
+                c_len(bounds.len()),
+                bounds.as_ptr(),
+                c_len(patterns.len()),
+                patterns.as_ptr(),
+                c_len(no_patterns.len()),
+                no_patterns.as_ptr(),
+                body.get_z3_ast(),
+            );
+            Bool::wrap(self.ctx.clone(), z3_ast)
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// How often Z3 instantiated the quantifiers with one identifier, as
+/// recorded in a trace written with
+/// [`Config::set_quantifier_trace()`](crate::Config::set_quantifier_trace).
+///
+/// Quantifiers are identified by the `qid` given with
+/// [`QuantifierBuilder::qid()`](crate::ast::QuantifierBuilder::qid). Z3
+/// keeps it on the copies of a quantifier it makes while preprocessing,
+/// and counts those with the original. Quantifiers without a `qid` get
+/// a name like `k!12`, which changes from run to run.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, FuncDecl, QuantifierInstantiations, SatResult, Solver, Sort};
+/// let path = std::env::temp_dir().join("z3-quantifier-trace-example.log");
+/// let mut cfg = Config::new();
+/// cfg.set_quantifier_trace(&path);
+/// {
+///     let ctx = Rc::new(Context::new(&cfg));
+///     let int = Sort::int(ctx.clone());
+///     let f = FuncDecl::new(ctx.clone(), "f", &[&int], &int);
+///     let x = ast::Int::new_const(ctx.clone(), "x");
+///     let f_x = f.apply(&[&x]).as_int().unwrap();
+///     let axiom = ast::QuantifierBuilder::forall(ctx.clone(), &[&x])
+///         .qid("f-grows")
+///         .build(&f_x.gt(&x));
+///
+///     let solver = Solver::new(ctx.clone());
+///     solver.assert(&axiom);
+///     let three = ast::Int::from_i64(ctx.clone(), 3);
+///     solver.assert(&f.apply(&[&three]).as_int().unwrap().le(&three));
+///     assert_eq!(solver.check(), SatResult::Unsat);
+/// }
+///
+/// let counts = QuantifierInstantiations::read_trace(&path).unwrap();
+/// assert_eq!(counts[0].qid, "f-grows");
+/// assert_eq!(counts[0].instances, 1);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct QuantifierInstantiations {
+    pub qid: String,
+    /// The number of matches of the patterns of the quantifiers, and of
+    /// instances found by model-based quantifier instantiation.
+    pub matches: usize,
+    /// The number of instances added to the search, which can be fewer
+    /// than the matches.
+    pub instances: usize,
+}
+
+impl QuantifierInstantiations {
+    /// Read the trace file at `path` with [`QuantifierInstantiations::from_trace()`].
+    pub fn read_trace<P: AsRef<Path>>(path: P) -> io::Result<Vec<QuantifierInstantiations>> {
+        Ok(Self::from_trace(&fs::read_to_string(path)?))
+    }
+
+    /// Count the instantiations of each quantifier in the contents of a
+    /// trace file, from the most to the least instantiated quantifier.
+    /// Quantifiers that were never matched are left out.
+    pub fn from_trace(trace: &str) -> Vec<QuantifierInstantiations> {
+        // The qid of each quantifier and of each match, by their id in the
+        // trace. Ids are reused, so later lines override earlier ones.
+        let mut quantifiers: HashMap<&str, &str> = HashMap::new();
+        let mut matches: HashMap<&str, &str> = HashMap::new();
+        let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+
+        for line in trace.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                ["[mk-quant]", id, qid, ..] => {
+                    quantifiers.insert(id, qid);
+                }
+                // Matches found by E-matching, and by other methods than
+                // theory reasoning, like `MBQI`.
+                ["[new-match]", fingerprint, quantifier, ..]
+                | ["[inst-discovered]", _, fingerprint, quantifier, ..]
+                    if fingerprint != "0" =>
+                {
+                    if let Some(qid) = quantifiers.get(quantifier) {
+                        matches.insert(fingerprint, qid);
+                        counts.entry(qid).or_default().0 += 1;
+                    }
+                }
+                ["[instance]", fingerprint, ..] => {
+                    if let Some(qid) = matches.get(fingerprint) {
+                        counts.entry(qid).or_default().1 += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut counts: Vec<_> = counts
+            .into_iter()
+            .map(|(qid, (matches, instances))| QuantifierInstantiations {
+                qid: qid.to_owned(),
+                matches,
+                instances,
+            })
+            .collect();
+        counts.sort_by(|a, b| {
+            (b.instances, b.matches, &a.qid).cmp(&(a.instances, a.matches, &b.qid))
+        });
+        counts
+    }
+}
@@ -1,16 +1,17 @@
-use std::convert::TryInto;
 use std::ffi::CStr;
 use std::fmt;
 use std::ops::Deref;
-use std::rc::Rc;
 
 use z3_sys::*;
 
+use crate::conversion::c_len;
+use crate::Rc;
 use crate::{ast, ast::Ast, Context, FuncDecl, RecFuncDecl, Sort, Symbol};
 
 impl RecFuncDecl {
     pub(crate) unsafe fn wrap(ctx: Rc<Context>, z3_func_decl: Z3_func_decl) -> Self {
         Z3_inc_ref(ctx.z3_ctx, Z3_func_decl_to_ast(ctx.z3_ctx, z3_func_decl));
+        crate::track_live(ctx.z3_ctx, "RecFuncDecl", 1);
         Self { ctx, z3_func_decl }
     }
 
@@ -24,7 +25,7 @@ impl RecFuncDecl {
             let func_decl = Z3_mk_rec_func_decl(
                 ctx.z3_ctx,
                 name.into().as_z3_symbol(&ctx),
-                domain.len().try_into().unwrap(),
+                c_len(domain.len()),
                 domain.as_ptr(),
                 range.z3_sort,
             );
@@ -109,6 +110,7 @@ impl fmt::Debug for RecFuncDecl {
 
 impl Drop for RecFuncDecl {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "RecFuncDecl", -1);
         unsafe {
             Z3_dec_ref(
                 self.ctx.z3_ctx,
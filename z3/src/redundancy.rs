@@ -0,0 +1,68 @@
+use crate::{ast, ast::Ast, SatResult, Solver};
+
+/// Return the indices of the `assertions` that are implied by the others,
+/// in increasing order.
+///
+/// The assertions are checked one by one, each against the conjunction of
+/// all others that were not found redundant before. The remaining
+/// assertions therefore still imply every assertion in the result, so the
+/// result can be dropped as a whole. If Z3 can not decide whether an
+/// assertion is implied, it is kept.
+///
+/// All checks share one solver, in which every assertion is guarded by a
+/// literal, so what Z3 learns in one check is reused in the next.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let int = |i| ast::Int::from_i64(ctx.clone(), i);
+/// let assertions = [x.gt(&int(5)), x.gt(&int(3)), x.lt(&int(10))];
+///
+/// assert_eq!(z3::find_redundant_assertions(&assertions), [1]);
+/// ```
+pub fn find_redundant_assertions(assertions: &[ast::Bool]) -> Vec<usize> {
+    let ctx = match assertions.first() {
+        Some(a) => a.get_ctx(),
+        None => return vec![],
+    };
+    let solver = Solver::new(ctx.clone());
+    let guards: Vec<ast::Bool> = assertions
+        .iter()
+        .map(|a| {
+            let guard = ast::Bool::fresh_const(ctx.clone(), "redundant");
+            solver.assert(&guard.implies(a));
+            guard
+        })
+        .collect();
+
+    let mut kept = vec![true; assertions.len()];
+    for (i, assertion) in assertions.iter().enumerate() {
+        // Skip the solver for `true`, and for repeated assertions.
+        if assertion.as_bool() == Some(true)
+            || (0..i).any(|j| kept[j] && assertions[j] == *assertion)
+        {
+            kept[i] = false;
+            continue;
+        }
+        let mut assumptions: Vec<ast::Bool> = guards
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i && kept[j])
+            .map(|(_, g)| g.clone())
+            .collect();
+        // Assume the negation through a fresh literal as well, since
+        // assumptions have to be literals.
+        let negated = ast::Bool::fresh_const(ctx.clone(), "negated");
+        solver.assert(&negated.implies(&!assertion.clone()));
+        assumptions.push(negated);
+        if solver.check_assumptions(&assumptions) == SatResult::Unsat {
+            kept[i] = false;
+        }
+    }
+    (0..assertions.len()).filter(|&i| !kept[i]).collect()
+}
@@ -0,0 +1,188 @@
+use std::error::Error;
+use std::fmt;
+
+use regex_syntax::hir::{Class, Hir, HirKind, Look};
+use regex_syntax::ParserBuilder;
+
+use crate::ast::Regexp;
+use crate::char_class::char_range;
+use crate::Context;
+use crate::Rc;
+
+/// An error returned by [`Regexp::from_regex_str()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegexError {
+    /// The pattern is not a valid regular expression.
+    Syntax(String),
+    /// The pattern uses a construct that Z3 regular expressions can not
+    /// express, like a backreference or a word boundary.
+    Unsupported(String),
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            RegexError::Syntax(msg) => write!(f, "invalid regular expression: {}", msg),
+            RegexError::Unsupported(what) => {
+                write!(f, "unsupported in Z3 regular expressions: {}", what)
+            }
+        }
+    }
+}
+
+impl Error for RegexError {}
+
+impl Regexp {
+    /// Translate `pattern`, written in the syntax of the [`regex`] crate,
+    /// into a Z3 regular expression.
+    ///
+    /// Literals, character classes (including Unicode classes like `\d`
+    /// and `\p{Greek}`), repetitions, alternations and groups are
+    /// supported; flags like `(?i)` are applied while translating.
+    ///
+    /// The result matches whole strings, as if `pattern` was surrounded by
+    /// `^` and `$`. Anchors are therefore only accepted at the very start
+    /// and end of `pattern`; other look-around assertions (like `\b`), and
+    /// backreferences, are reported as [`RegexError::Unsupported`].
+    /// Characters beyond the range Z3 supports (`U+2FFFF`) are dropped
+    /// from classes.
+    ///
+    /// This requires the `regex` feature.
+    ///
+    /// [`regex`]: https://docs.rs/regex
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let re = ast::Regexp::from_regex_str(ctx.clone(), r"[a-z]+\d{2}").unwrap();
+    /// let s = ast::String::from_str(ctx.clone(), "abc42").unwrap();
+    ///
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&s.regex_matches(&re));
+    /// assert_eq!(solver.check(), SatResult::Sat);
+    ///
+    /// assert!(ast::Regexp::from_regex_str(ctx, r"(a)\1").is_err());
+    /// ```
+    pub fn from_regex_str(ctx: Rc<Context>, pattern: &str) -> Result<Regexp, RegexError> {
+        let hir = ParserBuilder::new().build().parse(pattern).map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("backreferences are not supported") {
+                RegexError::Unsupported("backreferences".to_owned())
+            } else {
+                RegexError::Syntax(msg)
+            }
+        })?;
+        let hir = strip_anchors(hir);
+        translate(&ctx, &hir)
+    }
+}
+
+/// Remove the anchors at the start and end of `hir`, which are implied by
+/// Z3's whole-string matching.
+fn strip_anchors(hir: Hir) -> Hir {
+    let is_start = |h: &Hir| matches!(h.kind(), HirKind::Look(Look::Start | Look::StartLF));
+    let is_end = |h: &Hir| matches!(h.kind(), HirKind::Look(Look::End | Look::EndLF));
+    if is_start(&hir) || is_end(&hir) {
+        return Hir::empty();
+    }
+    match hir.into_kind() {
+        HirKind::Concat(mut hirs) => {
+            if hirs.first().is_some_and(is_start) {
+                hirs.remove(0);
+            }
+            if hirs.last().is_some_and(is_end) {
+                hirs.pop();
+            }
+            Hir::concat(hirs)
+        }
+        kind => hir_of(kind),
+    }
+}
+
+fn hir_of(kind: HirKind) -> Hir {
+    match kind {
+        HirKind::Empty => Hir::empty(),
+        HirKind::Literal(lit) => Hir::literal(lit.0),
+        HirKind::Class(class) => Hir::class(class),
+        HirKind::Look(look) => Hir::look(look),
+        HirKind::Repetition(rep) => Hir::repetition(rep),
+        HirKind::Capture(cap) => Hir::capture(cap),
+        HirKind::Concat(hirs) => Hir::concat(hirs),
+        HirKind::Alternation(hirs) => Hir::alternation(hirs),
+    }
+}
+
+fn translate(ctx: &Rc<Context>, hir: &Hir) -> Result<Regexp, RegexError> {
+    Ok(match hir.kind() {
+        HirKind::Empty => Regexp::literal(ctx.clone(), ""),
+        HirKind::Literal(lit) => match std::str::from_utf8(&lit.0) {
+            Ok(s) => concat(ctx, s.chars().map(|c| char_range(ctx, c, c)).collect()),
+            Err(_) => concat(
+                ctx,
+                lit.0
+                    .iter()
+                    .map(|&b| char_range(ctx, b as char, b as char))
+                    .collect(),
+            ),
+        },
+        HirKind::Class(Class::Unicode(class)) => {
+            let ranges: Vec<(char, char)> = class.iter().map(|r| (r.start(), r.end())).collect();
+            Regexp::ranges(ctx.clone(), &ranges)
+        }
+        HirKind::Class(Class::Bytes(class)) => {
+            let ranges: Vec<(char, char)> = class
+                .iter()
+                .map(|r| (r.start() as char, r.end() as char))
+                .collect();
+            Regexp::ranges(ctx.clone(), &ranges)
+        }
+        HirKind::Look(look) => {
+            return Err(RegexError::Unsupported(format!(
+                "look-around assertion {:?}",
+                look
+            )))
+        }
+        HirKind::Repetition(rep) => {
+            let sub = translate(ctx, &rep.sub)?;
+            match (rep.min, rep.max) {
+                (0, None) => sub.star(),
+                (1, None) => sub.plus(),
+                (min, None) => Regexp::concat(ctx.clone(), &[sub.r#loop(min, min), sub.star()]),
+                (min, Some(max)) => sub.r#loop(min, max),
+            }
+        }
+        HirKind::Capture(cap) => translate(ctx, &cap.sub)?,
+        HirKind::Concat(hirs) => concat(
+            ctx,
+            hirs.iter()
+                .map(|h| translate(ctx, h))
+                .collect::<Result<_, _>>()?,
+        ),
+        HirKind::Alternation(hirs) => union(
+            ctx,
+            hirs.iter()
+                .map(|h| translate(ctx, h))
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
+fn concat(ctx: &Rc<Context>, mut res: Vec<Regexp>) -> Regexp {
+    match res.len() {
+        0 => Regexp::literal(ctx.clone(), ""),
+        1 => res.pop().unwrap(),
+        _ => Regexp::concat(ctx.clone(), &res),
+    }
+}
+
+fn union(ctx: &Rc<Context>, mut res: Vec<Regexp>) -> Regexp {
+    match res.len() {
+        0 => Regexp::empty(ctx.clone()),
+        1 => res.pop().unwrap(),
+        _ => Regexp::union(ctx.clone(), &res),
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use z3_sys::*;
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, Model, SatResult, Solver};
+
+/// Identifies a constraint added with [`RetractableSolver::add()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConstraintId(u64);
+
+/// Counters of a [`RetractableSolver`], see [`RetractableSolver::stats()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RetractStats {
+    /// How many constraints were added.
+    pub added: u64,
+    /// How many constraints were retracted.
+    pub retracted: u64,
+    /// How many times the solver was re-created.
+    pub rebuilds: u64,
+    /// The time spent re-creating the solver.
+    pub rebuild_time: Duration,
+    /// The number of distinct activation literals created so far. It only
+    /// grows with the number of constraints that are active at the same
+    /// time, since literals are recycled.
+    pub literals: usize,
+    /// The number of retracted constraints whose literals are still in the
+    /// current solver.
+    pub dead: usize,
+}
+
+/// A solver whose constraints can be taken back individually, for long
+/// interactive sessions.
+///
+/// Every constraint `c` is guarded by an activation literal `l`: the
+/// solver holds `l => c`, and `l` is assumed in every check while `c` is
+/// active. Retracting `c` asserts `!l`, which disables it for good, but
+/// leaves the clause, and the literal, in the solver. Once more than
+/// [`RetractableSolver::with_threshold()`] retracted constraints have
+/// accumulated, the solver is re-created with the active constraints only.
+/// The literals of the retracted constraints are then recycled for new
+/// constraints, so the number of literals stays bounded by the number of
+/// constraints active at the same time.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, RetractableSolver, SatResult};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let x = ast::Int::new_const(ctx.clone(), "x");
+/// let int = |i| ast::Int::from_i64(ctx.clone(), i);
+///
+/// let mut solver = RetractableSolver::new(ctx.clone()).with_threshold(2);
+/// solver.assert(&x.ge(&int(0)));
+/// let mut last = solver.add(&x._eq(&int(0)));
+/// for i in 1..10 {
+///     solver.retract(last);
+///     last = solver.add(&x._eq(&int(i)));
+///     assert_eq!(solver.check(), SatResult::Sat);
+/// }
+/// let negative = solver.add(&x.lt(&int(0)));
+/// assert_eq!(solver.check(), SatResult::Unsat);
+/// assert!(solver.unsat_core().contains(&negative));
+///
+/// let stats = solver.stats();
+/// assert_eq!(stats.rebuilds, 3);
+/// assert!(stats.literals <= 4);
+/// ```
+#[derive(Debug)]
+pub struct RetractableSolver {
+    ctx: Rc<Context>,
+    solver: Solver,
+    threshold: usize,
+    hard: Vec<ast::Bool>,
+    // The active constraints, with their activation literals.
+    active: BTreeMap<ConstraintId, (ast::Bool, ast::Bool)>,
+    // Literals that do not occur in the current solver.
+    free: Vec<ast::Bool>,
+    // The literals of retracted constraints that are still in the solver.
+    dead: Vec<ast::Bool>,
+    next_id: u64,
+    stats: RetractStats,
+}
+
+impl RetractableSolver {
+    /// Create a solver that is re-created after 1000 retractions.
+    pub fn new(ctx: Rc<Context>) -> RetractableSolver {
+        RetractableSolver {
+            solver: Solver::new(ctx.clone()),
+            ctx,
+            threshold: 1000,
+            hard: vec![],
+            active: BTreeMap::new(),
+            free: vec![],
+            dead: vec![],
+            next_id: 0,
+            stats: RetractStats::default(),
+        }
+    }
+
+    /// Re-create the solver once more than `threshold` retracted
+    /// constraints are left in it.
+    pub fn with_threshold(mut self, threshold: usize) -> RetractableSolver {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Assert `constraint` permanently.
+    pub fn assert(&mut self, constraint: &ast::Bool) {
+        self.solver.assert(constraint);
+        self.hard.push(constraint.clone());
+    }
+
+    /// Add `constraint` until it is [retracted](RetractableSolver::retract).
+    pub fn add(&mut self, constraint: &ast::Bool) -> ConstraintId {
+        let literal = self.free.pop().unwrap_or_else(|| {
+            self.stats.literals += 1;
+            ast::Bool::new_const(
+                self.ctx.clone(),
+                format!("retract!{}", self.stats.literals - 1),
+            )
+        });
+        self.solver.assert(&literal.implies(constraint));
+        let id = ConstraintId(self.next_id);
+        self.next_id += 1;
+        self.active.insert(id, (literal, constraint.clone()));
+        self.stats.added += 1;
+        id
+    }
+
+    /// Retract the constraint `id`. Return `false` if it is not active.
+    pub fn retract(&mut self, id: ConstraintId) -> bool {
+        let (literal, _) = match self.active.remove(&id) {
+            Some(c) => c,
+            None => return false,
+        };
+        self.solver.assert(&!literal.clone());
+        self.dead.push(literal);
+        self.stats.retracted += 1;
+        if self.dead.len() > self.threshold {
+            self.rebuild();
+        }
+        true
+    }
+
+    /// Whether the constraint `id` is active.
+    pub fn is_active(&self, id: ConstraintId) -> bool {
+        self.active.contains_key(&id)
+    }
+
+    /// Re-create the solver with the permanent and active constraints only,
+    /// freeing the literals of the retracted ones. The parameters set with
+    /// [`Solver::set_params()`] on [`solver()`](Self::solver) carry over.
+    pub fn rebuild(&mut self) {
+        let start = Instant::now();
+        let params = self.solver.saved_params();
+        self.solver = Solver::new(self.ctx.clone());
+        self.solver.set_params(&params);
+        for c in &self.hard {
+            self.solver.assert(c);
+        }
+        for (literal, c) in self.active.values() {
+            self.solver.assert(&literal.implies(c));
+        }
+        self.free.append(&mut self.dead);
+        self.stats.rebuilds += 1;
+        self.stats.rebuild_time += start.elapsed();
+    }
+
+    /// Check the permanent and active constraints.
+    pub fn check(&self) -> SatResult {
+        self.check_assumptions(&[])
+    }
+
+    /// Check the permanent and active constraints, and `assumptions`.
+    pub fn check_assumptions(&self, assumptions: &[ast::Bool]) -> SatResult {
+        let mut all: Vec<ast::Bool> = self.active.values().map(|(l, _)| l.clone()).collect();
+        all.extend_from_slice(assumptions);
+        self.solver.check_assumptions(&all)
+    }
+
+    /// The model of the last satisfiable check.
+    pub fn get_model(&self) -> Option<Model> {
+        self.solver.get_model()
+    }
+
+    /// The active constraints in the unsat core of the last check.
+    pub fn unsat_core(&self) -> Vec<ConstraintId> {
+        let ids: HashMap<Z3_ast, ConstraintId> = self
+            .active
+            .iter()
+            .map(|(id, (l, _))| (l.get_z3_ast(), *id))
+            .collect();
+        let mut core: Vec<ConstraintId> = self
+            .solver
+            .get_unsat_core()
+            .iter()
+            .filter_map(|l| ids.get(&l.get_z3_ast()).copied())
+            .collect();
+        core.sort();
+        core
+    }
+
+    /// The counters of this solver.
+    pub fn stats(&self) -> RetractStats {
+        RetractStats {
+            dead: self.dead.len(),
+            ..self.stats.clone()
+        }
+    }
+
+    /// The underlying solver. It is replaced whenever this solver is
+    /// [rebuilt](RetractableSolver::rebuild).
+    pub fn solver(&self) -> &Solver {
+        &self.solver
+    }
+}
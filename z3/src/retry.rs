@@ -0,0 +1,208 @@
+use std::time::Duration;
+
+use crate::conversion::timeout_ms;
+use crate::{Model, Params, ReasonUnknown, SatResult, Solver, Tactic};
+
+/// One attempt of a [`RetryPolicy`]: a check with a timeout, a random seed
+/// and a tactic, each of which defaults to the solver's own setting.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Attempt {
+    timeout: Option<Duration>,
+    random_seed: Option<u32>,
+    tactic: Option<String>,
+}
+
+impl Attempt {
+    /// An attempt with the solver's own settings.
+    pub fn new() -> Attempt {
+        Attempt::default()
+    }
+
+    /// Give up after `timeout`, rounded down to milliseconds. Timeouts
+    /// longer than Z3 accepts are handled according to the
+    /// [`ConversionPolicy`](crate::ConversionPolicy).
+    pub fn timeout(mut self, timeout: Duration) -> Attempt {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Use `seed` as the `random_seed` parameter.
+    pub fn random_seed(mut self, seed: u32) -> Attempt {
+        self.random_seed = Some(seed);
+        self
+    }
+
+    /// Check the assertions with a solver built from the tactic `name`, see
+    /// [`Tactic::new()`], instead of with the solver itself.
+    pub fn tactic(mut self, name: &str) -> Attempt {
+        self.tactic = Some(name.to_owned());
+        self
+    }
+}
+
+/// A sequence of [`Attempt`]s for [`Solver::check_with_retries()`].
+///
+/// A policy does not belong to a context, so one policy can serve every
+/// solver of an application.
+///
+/// # Examples
+///
+/// ```
+/// # use std::time::Duration;
+/// # use z3::{Attempt, RetryPolicy};
+/// // Three attempts with timeouts of 100ms, 200ms and 400ms and different
+/// // seeds, and then the nlsat procedure without a timeout.
+/// let policy = RetryPolicy::escalating(Duration::from_millis(100), 3)
+///     .then(Attempt::new().tactic("qfnra-nlsat"));
+/// assert_eq!(policy.attempts().len(), 4);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RetryPolicy {
+    attempts: Vec<Attempt>,
+}
+
+impl RetryPolicy {
+    /// A policy without attempts, with which
+    /// [`Solver::check_with_retries()`] checks once with the solver's own
+    /// settings.
+    pub fn new() -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    /// `n` attempts whose timeouts start at `timeout` and double, with the
+    /// random seeds `0..n`.
+    pub fn escalating(timeout: Duration, n: u32) -> RetryPolicy {
+        (0..n).fold(RetryPolicy::new(), |policy, i| {
+            policy.then(
+                Attempt::new()
+                    .timeout(timeout.saturating_mul(2u32.saturating_pow(i)))
+                    .random_seed(i),
+            )
+        })
+    }
+
+    /// Add `attempt` after the attempts so far.
+    pub fn then(mut self, attempt: Attempt) -> RetryPolicy {
+        self.attempts.push(attempt);
+        self
+    }
+
+    /// The attempts, in order.
+    pub fn attempts(&self) -> &[Attempt] {
+        &self.attempts
+    }
+}
+
+/// The result of [`Solver::check_with_retries()`].
+#[derive(Debug)]
+pub struct RetryOutcome {
+    /// The first definite result, or [`SatResult::Unknown`] if every
+    /// attempt gave up.
+    pub result: SatResult,
+    /// The index of the attempt that gave `result`.
+    pub attempt: usize,
+    /// Why each attempt up to it that gave up did so, if Z3 said.
+    pub failures: Vec<Option<ReasonUnknown>>,
+    /// The model, if `result` is [`SatResult::Sat`]. An attempt with a
+    /// [tactic](Attempt::tactic) finds it with another solver, so it is
+    /// not available from [`Solver::get_model()`].
+    pub model: Option<Model>,
+}
+
+impl Solver {
+    /// Check the assertions with each attempt of `policy` in turn, until
+    /// one gives a definite result.
+    ///
+    /// A timeout or random seed only applies to its attempt, as with
+    /// [`Solver::check_with_params()`]. Retrying stops early if an attempt
+    /// is [canceled](ReasonUnknown::Canceled), since a retry would be
+    /// canceled too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use std::time::Duration;
+    /// # use z3::{ast, Attempt, Config, Context, RetryPolicy, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&x.gt(&ast::Int::from_i64(ctx.clone(), 41)));
+    ///
+    /// // A tactic that always gives up, then the solver itself.
+    /// let policy = RetryPolicy::new()
+    ///     .then(Attempt::new().tactic("fail"))
+    ///     .then(Attempt::new().timeout(Duration::from_secs(10)));
+    /// let outcome = solver.check_with_retries(&policy);
+    /// assert_eq!(outcome.result, SatResult::Sat);
+    /// assert_eq!(outcome.attempt, 1);
+    /// assert_eq!(outcome.failures.len(), 1);
+    /// assert!(outcome.model.is_some());
+    /// ```
+    pub fn check_with_retries(&self, policy: &RetryPolicy) -> RetryOutcome {
+        let default = [Attempt::new()];
+        let attempts = match policy.attempts() {
+            [] => &default[..],
+            attempts => attempts,
+        };
+
+        // Z3's defaults, which the parameters are restored to unless they
+        // were set with `Solver::set_params()`.
+        let mut defaults = Params::new(self.ctx.clone());
+        defaults.set_u32("timeout", u32::MAX);
+        defaults.set_u32("random_seed", 0);
+
+        let mut failures = vec![];
+        for (i, attempt) in attempts.iter().enumerate() {
+            let mut params = Params::new(self.ctx.clone());
+            if let Some(timeout) = attempt.timeout {
+                params.set_u32("timeout", timeout_ms(timeout));
+            }
+            if let Some(seed) = attempt.random_seed {
+                params.set_u32("random_seed", seed);
+            }
+
+            let (result, reason, model) = match &attempt.tactic {
+                Some(name) => {
+                    let other = Tactic::new(self.ctx.clone(), name).solver();
+                    other.set_params(&params);
+                    for a in self.get_assertions() {
+                        other.assert(&a);
+                    }
+                    let result = other.check();
+                    answer(&other, result)
+                }
+                None => {
+                    let result = self
+                        .with_params(&params, &defaults, || self.check())
+                        .expect("the defaults cover the timeout and random seed");
+                    answer(self, result)
+                }
+            };
+            if result == SatResult::Unknown {
+                let canceled = reason == Some(ReasonUnknown::Canceled);
+                failures.push(reason);
+                if !canceled && i + 1 < attempts.len() {
+                    continue;
+                }
+            }
+            return RetryOutcome {
+                result,
+                attempt: i,
+                failures,
+                model,
+            };
+        }
+        unreachable!("there is at least one attempt")
+    }
+}
+
+/// What `solver` has to say about its `result`.
+fn answer(solver: &Solver, result: SatResult) -> (SatResult, Option<ReasonUnknown>, Option<Model>) {
+    match result {
+        SatResult::Sat => (result, None, solver.get_model()),
+        SatResult::Unsat => (result, None, None),
+        SatResult::Unknown => (result, solver.get_reason_unknown_kind(), None),
+    }
+}
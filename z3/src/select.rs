@@ -0,0 +1,129 @@
+use crate::ast::{self, Ast, Bool};
+
+/// An element of a list chosen by a symbolic index, built by
+/// [`select_by_index()`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Selection<T> {
+    /// The option at the index, which is unspecified if the index is out
+    /// of bounds.
+    pub value: T,
+    /// Whether the index is in bounds, a side condition to assert along
+    /// with constraints on the value.
+    pub in_bounds: Bool,
+}
+
+/// A symbolic index for [`select_by_index()`].
+pub trait SelectIndex: Ast {
+    /// Build the balanced `ite` tree that selects from `options`, which is
+    /// not empty.
+    fn select_from<T: Ast + Clone>(&self, options: &[T]) -> Selection<T>;
+}
+
+impl SelectIndex for ast::Int {
+    /// Compare the index with the middle of the options at each level:
+    /// `ite(idx < mid, <lower half>, <upper half>)`.
+    fn select_from<T: Ast + Clone>(&self, options: &[T]) -> Selection<T> {
+        let ctx = self.get_ctx();
+        let at = |i: usize| ast::Int::from_u64(ctx.clone(), i as u64);
+        let value = split(options, 0, &|mid: usize| self.lt(&at(mid)));
+        let in_bounds = Bool::and(
+            ctx.clone(),
+            &[&self.ge(&at(0)), &self.lt(&at(options.len()))],
+        );
+        Selection { value, in_bounds }
+    }
+}
+
+impl SelectIndex for ast::BV {
+    /// Test one bit of the index at each level, from the most significant
+    /// bit that can select an option, like a multiplexer: `ite(bit_k, <the
+    /// options with bit k set>, <the others>)`. The index is unsigned, and
+    /// options beyond `2^size` cannot be selected.
+    fn select_from<T: Ast + Clone>(&self, options: &[T]) -> Selection<T> {
+        let ctx = self.get_ctx();
+        let size = self.get_size();
+        let reachable = if size < usize::BITS {
+            options.len().min(1 << size)
+        } else {
+            options.len()
+        };
+        let options = &options[..reachable];
+        let bits = usize::BITS - (options.len() - 1).leading_zeros();
+        let value = mux(self, options, bits);
+        let in_bounds = if bits < size || options.len() < 1 << bits {
+            let len = ast::BV::from_u64(ctx, options.len() as u64, size);
+            self.bvult(&len)
+        } else {
+            Bool::from_bool(ctx, true)
+        };
+        Selection { value, in_bounds }
+    }
+}
+
+/// Select the option at the symbolic index `idx` of `options`, an
+/// [`ast::Int`] or an unsigned [`ast::BV`], with a balanced tree of `ite`s
+/// of depth `log2(options.len())`, and return it with the condition that
+/// `idx` is in bounds.
+///
+/// # Panics
+///
+/// Panics if `options` is empty.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, select_by_index, Config, Context, SatResult, Solver};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let table: Vec<_> = [3, 1, 4, 1, 5]
+///     .iter()
+///     .map(|&v| ast::Int::from_i64(ctx.clone(), v))
+///     .collect();
+/// let idx = ast::BV::new_const(ctx.clone(), "idx", 8);
+/// let entry = select_by_index(&idx, &table);
+///
+/// // The only index of 5 in the table is 4.
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&entry.in_bounds);
+/// solver.assert(&entry.value._eq(&ast::Int::from_i64(ctx.clone(), 5)));
+/// assert_eq!(solver.check(), SatResult::Sat);
+/// let model = solver.get_model().unwrap();
+/// assert_eq!(model.eval(&idx, true).unwrap().as_u64(), Some(4));
+/// ```
+pub fn select_by_index<I, T>(idx: &I, options: &[T]) -> Selection<T>
+where
+    I: SelectIndex,
+    T: Ast + Clone,
+{
+    assert!(!options.is_empty(), "no options to select from");
+    idx.select_from(options)
+}
+
+/// Select from `options`, the first of which is at index `offset`, by
+/// comparisons `below(mid)` of the index with the middle index.
+fn split<T: Ast + Clone>(options: &[T], offset: usize, below: &dyn Fn(usize) -> Bool) -> T {
+    if options.len() == 1 {
+        return options[0].clone();
+    }
+    let half = options.len() / 2;
+    let lower = split(&options[..half], offset, below);
+    let upper = split(&options[half..], offset + half, below);
+    below(offset + half).ite(&lower, &upper)
+}
+
+/// Select from at most `2^bits` `options` by the low `bits` bits of `idx`.
+fn mux<T: Ast + Clone>(idx: &ast::BV, options: &[T], bits: u32) -> T {
+    if options.len() == 1 {
+        return options[0].clone();
+    }
+    let half = 1 << (bits - 1);
+    if options.len() <= half {
+        return mux(idx, options, bits - 1);
+    }
+    let lower = mux(idx, &options[..half], bits - 1);
+    let upper = mux(idx, &options[half..], bits - 1);
+    let bit = idx.extract(bits - 1, bits - 1);
+    let set = bit._eq(&ast::BV::from_u64(idx.get_ctx(), 1, 1));
+    set.ite(&upper, &lower)
+}
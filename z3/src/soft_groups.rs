@@ -0,0 +1,194 @@
+use num::rational::BigRational;
+use num::Zero;
+
+use crate::ast::Bool;
+use crate::optimize::Weight;
+use crate::Rc;
+use crate::{Context, Model, Optimize, Priority, SatResult, Symbol};
+
+/// Weighted soft constraints in named tiers, for `MaxSMT` problems with
+/// priorities.
+///
+/// Each tier is one group of soft constraints of an [`Optimize`]: Z3
+/// minimizes the total weight of the violated constraints of each tier,
+/// and weighs the tiers lexicographically, in the order they were first
+/// used. No violation in a later tier makes up for one in an earlier tier.
+///
+/// The soft constraints are only added to the optimizer for the duration
+/// of [`SoftGroups::check()`], so tiers can get constraints in any order.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use num::rational::BigRational;
+/// # use z3::{ast, Config, Context, SatResult, SoftGroups};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let early = ast::Bool::new_const(ctx.clone(), "early");
+/// let cheap = ast::Bool::new_const(ctx.clone(), "cheap");
+/// let short = ast::Bool::new_const(ctx.clone(), "short");
+///
+/// let mut soft = SoftGroups::new(ctx.clone());
+/// soft.add_tier("must");
+/// soft.add_tier("nice");
+/// // Being cheap and short outweighs being early, but not within a tier.
+/// soft.add("nice", &cheap, 3);
+/// soft.add("nice", &short, 3);
+/// soft.add("must", &early, 1);
+/// soft.optimize().assert(&!(early.clone() & (cheap.clone() | short.clone())));
+///
+/// assert_eq!(soft.check(), SatResult::Sat);
+/// let summary = soft.summary();
+/// assert_eq!(summary[0].name, "must");
+/// assert!(summary[0].violated.is_empty());
+/// assert_eq!(summary[1].violated, [cheap, short]);
+/// assert_eq!(summary[1].violated_weight, BigRational::from_integer(6.into()));
+/// ```
+#[derive(Debug)]
+pub struct SoftGroups {
+    opt: Optimize,
+    tiers: Vec<Tier>,
+    model: Option<Model>,
+}
+
+#[derive(Debug)]
+struct Tier {
+    name: String,
+    constraints: Vec<(Bool, BigRational)>,
+}
+
+/// How the model of the last [`SoftGroups::check()`] satisfies the soft
+/// constraints of a tier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TierSummary {
+    pub name: String,
+    /// The number of satisfied constraints.
+    pub satisfied: usize,
+    /// The violated constraints, in the order they were added.
+    pub violated: Vec<Bool>,
+    /// The total weight of the violated constraints.
+    pub violated_weight: BigRational,
+    /// The total weight of all constraints of the tier.
+    pub total_weight: BigRational,
+}
+
+impl SoftGroups {
+    /// Create soft constraint tiers with an optimizer of their own.
+    pub fn new(ctx: Rc<Context>) -> SoftGroups {
+        let opt = Optimize::new(ctx);
+        opt.set_priority(Priority::Lexicographic);
+        SoftGroups {
+            opt,
+            tiers: vec![],
+            model: None,
+        }
+    }
+
+    /// The optimizer, for hard constraints, objectives and parameters.
+    pub fn optimize(&self) -> &Optimize {
+        &self.opt
+    }
+
+    /// Add the tier `name` after the tiers so far, if there is no such tier
+    /// yet.
+    pub fn add_tier(&mut self, name: &str) {
+        self.tier_index(name);
+    }
+
+    fn tier_index(&mut self, name: &str) -> usize {
+        match self.tiers.iter().position(|t| t.name == name) {
+            Some(i) => i,
+            None => {
+                self.tiers.push(Tier {
+                    name: name.to_owned(),
+                    constraints: vec![],
+                });
+                self.tiers.len() - 1
+            }
+        }
+    }
+
+    /// Add the soft constraint `formula` to the tier `tier`, which is
+    /// added after the tiers so far if there is no such tier yet.
+    ///
+    /// Weights are like for [`Optimize::assert_soft()`].
+    pub fn add(&mut self, tier: &str, formula: &Bool, weight: impl Weight) {
+        let weight = parse_weight(&weight.to_string());
+        let i = self.tier_index(tier);
+        self.tiers[i].constraints.push((formula.clone(), weight));
+    }
+
+    /// The names of the tiers, from the most to the least important.
+    pub fn tiers(&self) -> Vec<&str> {
+        self.tiers.iter().map(|t| &t.name[..]).collect()
+    }
+
+    /// Find a model of the hard constraints that is optimal for the soft
+    /// constraints, see [`Optimize::check()`].
+    pub fn check(&mut self) -> SatResult {
+        self.opt.push();
+        for tier in &self.tiers {
+            for (formula, weight) in &tier.constraints {
+                let group = Symbol::from(&tier.name[..]);
+                self.opt.assert_soft(formula, weight.clone(), Some(group));
+            }
+        }
+        let result = self.opt.check(&[]);
+        self.model = match result {
+            SatResult::Sat => self.opt.get_model(),
+            _ => None,
+        };
+        self.opt.pop();
+        result
+    }
+
+    /// The model of the last [`SoftGroups::check()`], if it was
+    /// satisfiable.
+    pub fn get_model(&self) -> Option<&Model> {
+        self.model.as_ref()
+    }
+
+    /// How the model of the last [`SoftGroups::check()`] satisfies each
+    /// tier, from the most to the least important, or nothing if there is
+    /// no model.
+    pub fn summary(&self) -> Vec<TierSummary> {
+        let model = match &self.model {
+            Some(model) => model,
+            None => return vec![],
+        };
+        self.tiers
+            .iter()
+            .map(|tier| {
+                let mut summary = TierSummary {
+                    name: tier.name.clone(),
+                    satisfied: 0,
+                    violated: vec![],
+                    violated_weight: BigRational::zero(),
+                    total_weight: BigRational::zero(),
+                };
+                for (formula, value) in &tier.constraints {
+                    summary.total_weight += value;
+                    let holds = model.eval(formula, true).and_then(|b| b.as_bool());
+                    if holds == Some(true) {
+                        summary.satisfied += 1;
+                    } else {
+                        summary.violated.push(formula.clone());
+                        summary.violated_weight += value;
+                    }
+                }
+                summary
+            })
+            .collect()
+    }
+}
+
+/// The value of a weight as rendered by [`Weight::to_string()`], an integer
+/// or a fraction `n / d`.
+fn parse_weight(weight: &str) -> BigRational {
+    let parse = |s: &str| s.trim().parse().expect("weight is not a number");
+    match weight.split_once('/') {
+        Some((n, d)) => BigRational::new(parse(n), parse(d)),
+        None => BigRational::from_integer(parse(weight)),
+    }
+}
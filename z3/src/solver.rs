@@ -1,18 +1,33 @@
 use log::debug;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
 use std::ffi::{CStr, CString};
 use std::fmt;
-use std::rc::Rc;
 
 use z3_sys::*;
 
 use std::ops::AddAssign;
 
-use crate::{ast, ast::Ast, Context, Model, Params, SatResult, Solver, Statistics, Symbol};
+use crate::conversion::{c_len, cstring};
+use crate::Rc;
+use crate::{
+    ast, ast::Ast, Context, Model, ParamDescrs, Params, Polarity, Proof, ReasonUnknown, SatResult,
+    Snapshot, Solver, SourceLocation, Statistics, Symbol,
+};
 
 impl Solver {
     pub(crate) unsafe fn wrap(ctx: Rc<Context>, z3_slv: Z3_solver) -> Solver {
         Z3_solver_inc_ref(ctx.z3_ctx, z3_slv);
-        Solver { ctx, z3_slv }
+        crate::track_live(ctx.z3_ctx, "Solver", 1);
+        Solver {
+            ctx,
+            z3_slv,
+            preferences: RefCell::new(vec![]),
+            params: RefCell::new(BTreeMap::new()),
+            #[cfg(feature = "user-propagator")]
+            propagator: std::cell::Cell::new(None),
+        }
     }
 
     /// Create a new solver. This solver is a "combined solver"
@@ -53,10 +68,22 @@ impl Solver {
         }
     }
 
+    /// Create a new solver that uses Z3's SMT core directly, without the
+    /// preprocessing the solver created by [`Solver::new()`] applies.
+    ///
+    /// Some features, like user propagators, are only supported by this
+    /// solver.
+    pub fn new_simple(ctx: Rc<Context>) -> Solver {
+        unsafe {
+            let solver = Z3_mk_simple_solver(ctx.z3_ctx);
+            Self::wrap(ctx, solver)
+        }
+    }
+
     /// Parse an SMT-LIB2 string with assertions, soft constraints and optimization objectives.
     /// Add the parsed constraints and objectives to the solver.
     pub fn from_string<T: Into<Vec<u8>>>(&self, source_string: T) {
-        let source_cstring = CString::new(source_string).unwrap();
+        let source_cstring = cstring(source_string);
         unsafe {
             Z3_solver_from_string(self.ctx.z3_ctx, self.z3_slv, source_cstring.as_ptr());
         }
@@ -75,6 +102,15 @@ impl Solver {
         }
     }
 
+    /// Translate this solver, with its assertions, to context `dest`, like
+    /// [`Model::translate()`].
+    ///
+    /// The [preferences](Solver::prefer) and the user propagator are not
+    /// translated.
+    ///
+    /// # See also:
+    ///
+    /// - [`Solver::detach()`]
     pub fn translate(&self, dest: Rc<Context>) -> Solver {
         unsafe {
             let solver = Z3_solver_translate(self.ctx.z3_ctx, self.z3_slv, dest.z3_ctx);
@@ -125,6 +161,36 @@ impl Solver {
     /// and the Boolean literals provided using
     /// [`Solver::check_assumptions()`].
     ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let int = |i| ast::Int::from_i64(ctx.clone(), i);
+    /// let constraints = [
+    ///     ("positive", x.gt(&int(0))),
+    ///     ("small", x.lt(&int(10))),
+    ///     ("negative", x.lt(&int(0))),
+    /// ];
+    ///
+    /// let solver = Solver::new(ctx.clone());
+    /// for (name, c) in &constraints {
+    ///     solver.assert_and_track(c, &ast::Bool::new_const(ctx.clone(), *name));
+    /// }
+    /// assert_eq!(solver.check(), SatResult::Unsat);
+    /// // Map the core back to the names of the constraints.
+    /// let mut core: Vec<String> = solver
+    ///     .get_unsat_core()
+    ///     .iter()
+    ///     .map(|p| p.to_string())
+    ///     .collect();
+    /// core.sort();
+    /// assert_eq!(core, ["negative", "positive"]);
+    /// ```
+    ///
     /// # See also:
     ///
     /// - [`Solver::assert()`]
@@ -133,7 +199,34 @@ impl Solver {
         unsafe { Z3_solver_assert_and_track(self.ctx.z3_ctx, self.z3_slv, ast.z3_ast, p.z3_ast) };
     }
 
-    /// Remove all assertions from the solver.
+    /// Assert a constraint `a` into the solver, and track it using a
+    /// Boolean constant named after the location of the caller.
+    ///
+    /// When the assertion ends up in an unsat core, or the assertions are
+    /// dumped, the tracking constant points back at the line of Rust code
+    /// that made it; use [`Solver::get_unsat_core_locations()`] or
+    /// [`SourceLocation::of()`] to get the location back.
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, Config, Context, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert_here(&ast::Bool::from_bool(ctx, false));
+    /// assert_eq!(solver.check(), SatResult::Unsat);
+    /// let locations = solver.get_unsat_core_locations();
+    /// assert_eq!(locations[0].line, line!() - 3);
+    /// ```
+    #[track_caller]
+    pub fn assert_here(&self, ast: &ast::Bool) {
+        let caller = std::panic::Location::caller();
+        let name = format!("{}:{}:{}", caller.file(), caller.line(), caller.column());
+        let p = ast::Bool::new_const(self.ctx.clone(), name);
+        self.assert_and_track(ast, &p);
+    }
+
+    /// Remove all assertions, and all backtracking points, from the solver.
     pub fn reset(&self) {
         unsafe { Z3_solver_reset(self.ctx.z3_ctx, self.z3_slv) };
     }
@@ -162,7 +255,13 @@ impl Solver {
     /// [model construction is enabled]: crate::Config::set_model_generation
     /// [proof generation was enabled]: crate::Config::set_proof_generation
     pub fn check(&self) -> SatResult {
-        match unsafe { Z3_solver_check(self.ctx.z3_ctx, self.z3_slv) } {
+        if !self.preferences.borrow().is_empty() {
+            return self.check_preferring(&[]);
+        }
+        let result = unsafe { Z3_solver_check(self.ctx.z3_ctx, self.z3_slv) };
+        #[cfg(feature = "user-propagator")]
+        self.resume_propagator_panic();
+        match result {
             Z3_L_FALSE => SatResult::Unsat,
             Z3_L_UNDEF => SatResult::Unknown,
             Z3_L_TRUE => SatResult::Sat,
@@ -177,14 +276,45 @@ impl Solver {
     /// retrieves the subset of the assumptions used in the
     /// unsatisfiability proof produced by Z3.
     ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let small = ast::Bool::new_const(ctx.clone(), "small");
+    /// let large = ast::Bool::new_const(ctx.clone(), "large");
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&small.implies(&x.lt(&ast::Int::from_i64(ctx.clone(), 10))));
+    /// solver.assert(&large.implies(&x.gt(&ast::Int::from_i64(ctx.clone(), 100))));
+    ///
+    /// assert_eq!(solver.check_assumptions(&[small.clone()]), SatResult::Sat);
+    /// assert_eq!(solver.check_assumptions(&[small.clone(), large.clone()]), SatResult::Unsat);
+    /// assert_eq!(solver.get_unsat_core().len(), 2);
+    /// // Assumptions do not stay asserted.
+    /// assert_eq!(solver.check_assumptions(&[large]), SatResult::Sat);
+    /// ```
+    ///
     /// # See also:
     ///
     /// - [`Solver::check()`]
     pub fn check_assumptions(&self, assumptions: &[ast::Bool]) -> SatResult {
         let a: Vec<Z3_ast> = assumptions.iter().map(|a| a.z3_ast).collect();
-        match unsafe {
-            Z3_solver_check_assumptions(self.ctx.z3_ctx, self.z3_slv, a.len() as u32, a.as_ptr())
-        } {
+        if !self.preferences.borrow().is_empty() {
+            return self.check_preferring(&a);
+        }
+        self.check_raw(&a)
+    }
+
+    fn check_raw(&self, a: &[Z3_ast]) -> SatResult {
+        let result = unsafe {
+            Z3_solver_check_assumptions(self.ctx.z3_ctx, self.z3_slv, c_len(a.len()), a.as_ptr())
+        };
+        #[cfg(feature = "user-propagator")]
+        self.resume_propagator_panic();
+        match result {
             Z3_L_FALSE => SatResult::Unsat,
             Z3_L_UNDEF => SatResult::Unknown,
             Z3_L_TRUE => SatResult::Sat,
@@ -192,16 +322,121 @@ impl Solver {
         }
     }
 
-    // Return a vector of assumptions in the solver.
+    /// Check with the given `assumptions` plus as many of the preferred
+    /// literals as are consistent with them, dropping preferred literals
+    /// that show up in unsat cores until none of them do.
+    fn check_preferring(&self, assumptions: &[Z3_ast]) -> SatResult {
+        let mut preferred: Vec<ast::Bool> = self
+            .preferences
+            .borrow()
+            .iter()
+            .map(|(b, polarity)| match polarity {
+                Polarity::Positive => b.clone(),
+                Polarity::Negative => b.not(),
+            })
+            .collect();
+        loop {
+            let mut a = assumptions.to_vec();
+            a.extend(preferred.iter().map(|p| p.z3_ast));
+            let result = self.check_raw(&a);
+            if result != SatResult::Unsat || preferred.is_empty() {
+                return result;
+            }
+            let core = self.get_unsat_core();
+            let before = preferred.len();
+            preferred.retain(|p| !core.contains(p));
+            if preferred.len() == before {
+                // The core does not depend on any preference, so it is a
+                // core of the assertions and `assumptions` alone.
+                return result;
+            }
+        }
+    }
+
+    /// Prefer assigning `b` the given `polarity`.
+    ///
+    /// Subsequent calls to [`Solver::check()`] and
+    /// [`Solver::check_assumptions()`] first look for models that satisfy
+    /// all preferences, and drop preferences that are inconsistent with
+    /// the assertions (as witnessed by unsat cores) one batch at a time.
+    /// Preferences never change whether the assertions are satisfiable,
+    /// and unsat cores returned after an [`SatResult::Unsat`] answer never
+    /// mention them; they only guide which model is found, and, because Z3
+    /// decides on assumptions first, can cut solving time considerably when
+    /// they are mostly right.
+    ///
+    /// Setting a preference for a `b` that already has one replaces it.
+    /// Preferences are not affected by [`Solver::push()`] and
+    /// [`Solver::pop()`]; use [`Solver::clear_preferences()`] to remove
+    /// them.
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Polarity, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let a = ast::Bool::new_const(ctx.clone(), "a");
+    /// let b = ast::Bool::new_const(ctx.clone(), "b");
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&ast::Bool::or(ctx, &[&a, &b]));
+    /// solver.prefer(&a, Polarity::Negative);
+    /// assert_eq!(solver.check(), SatResult::Sat);
+    /// let model = solver.get_model().unwrap();
+    /// assert_eq!(model.eval(&a, true).unwrap().as_bool(), Some(false));
+    /// assert_eq!(model.eval(&b, true).unwrap().as_bool(), Some(true));
+    /// ```
+    pub fn prefer(&self, b: &ast::Bool, polarity: Polarity) {
+        let mut preferences = self.preferences.borrow_mut();
+        preferences.retain(|(p, _)| p != b);
+        preferences.push((b.clone(), polarity));
+    }
+
+    /// Remove all preferences set with [`Solver::prefer()`].
+    pub fn clear_preferences(&self) {
+        self.preferences.borrow_mut().clear();
+    }
+
+    /// Return the assertions currently in the solver, in the order they
+    /// were added, see [`Solver::assertions()`].
     pub fn get_assertions(&self) -> Vec<ast::Bool> {
-        let z3_vec = unsafe { Z3_solver_get_assertions(self.ctx.z3_ctx, self.z3_slv) };
+        unsafe {
+            let z3_vec = Z3_solver_get_assertions(self.ctx.z3_ctx, self.z3_slv);
+            Z3_ast_vector_inc_ref(self.ctx.z3_ctx, z3_vec);
+            let assertions = (0..Z3_ast_vector_size(self.ctx.z3_ctx, z3_vec))
+                .map(|i| {
+                    let z3_ast = Z3_ast_vector_get(self.ctx.z3_ctx, z3_vec, i);
+                    ast::Bool::wrap(self.ctx.clone(), z3_ast)
+                })
+                .collect();
+            Z3_ast_vector_dec_ref(self.ctx.z3_ctx, z3_vec);
+            assertions
+        }
+    }
 
-        (0..unsafe { Z3_ast_vector_size(self.ctx.z3_ctx, z3_vec) })
-            .map(|i| unsafe {
-                let z3_ast = Z3_ast_vector_get(self.ctx.z3_ctx, z3_vec, i);
-                ast::Bool::wrap(self.ctx.clone(), z3_ast)
-            })
-            .collect()
+    /// Iterate over the assertions currently in the solver, in the order
+    /// they were added.
+    ///
+    /// This includes assertions from every backtracking point, those added
+    /// with [`Solver::from_string()`], and those tracked with
+    /// [`Solver::assert_and_track()`], but not the assumptions of
+    /// [`Solver::check_assumptions()`]. The iterator works on a snapshot:
+    /// asserting while iterating does not affect it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{Config, Context, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.from_string("(declare-const x Int) (assert (> x 0)) (assert (< x 10))");
+    /// assert_eq!(solver.assertions().count(), 2);
+    /// let smtlib: Vec<String> = solver.assertions().map(|a| a.to_string()).collect();
+    /// assert_eq!(smtlib, ["(> x 0)", "(< x 10)"]);
+    /// ```
+    pub fn assertions(&self) -> impl Iterator<Item = ast::Bool> {
+        self.get_assertions().into_iter()
     }
 
     /// Return a subset of the assumptions provided to either the last
@@ -231,6 +466,10 @@ impl Solver {
             return vec![];
         }
 
+        // The vector starts out with a reference count of zero; take a
+        // reference so that it is freed once its elements are wrapped.
+        unsafe { Z3_ast_vector_inc_ref(self.ctx.z3_ctx, z3_unsat_core) };
+
         let len = unsafe { Z3_ast_vector_size(self.ctx.z3_ctx, z3_unsat_core) };
 
         let mut unsat_core = Vec::with_capacity(len as usize);
@@ -241,9 +480,23 @@ impl Solver {
             unsat_core.push(elem);
         }
 
+        unsafe { Z3_ast_vector_dec_ref(self.ctx.z3_ctx, z3_unsat_core) };
         unsat_core
     }
 
+    /// Return the source locations of the assertions made with
+    /// [`Solver::assert_here()`] that are part of the unsat core.
+    ///
+    /// # See also:
+    ///
+    /// - [`Solver::get_unsat_core()`]
+    pub fn get_unsat_core_locations(&self) -> Vec<SourceLocation> {
+        self.get_unsat_core()
+            .iter()
+            .filter_map(SourceLocation::of)
+            .collect()
+    }
+
     /// Create a backtracking point.
     ///
     /// The solver contains a stack of assertions.
@@ -260,10 +513,354 @@ impl Solver {
     /// # See also:
     ///
     /// - [`Solver::push()`]
+    /// - [`Solver::try_pop()`]
     pub fn pop(&self, n: u32) {
         unsafe { Z3_solver_pop(self.ctx.z3_ctx, self.z3_slv, n) };
     }
 
+    /// Return the number of backtracking points, i.e. the number of
+    /// [`Solver::push()`] calls that were not undone by [`Solver::pop()`]
+    /// or [`Solver::reset()`].
+    pub fn num_scopes(&self) -> u32 {
+        unsafe { Z3_solver_get_num_scopes(self.ctx.z3_ctx, self.z3_slv) }
+    }
+
+    /// Backtrack `n` backtracking points, like [`Solver::pop()`], or
+    /// return an error and leave the solver as it is if `n` is larger than
+    /// [`Solver::num_scopes()`].
+    pub fn try_pop(&self, n: u32) -> Result<(), String> {
+        let num_scopes = self.num_scopes();
+        if n > num_scopes {
+            return Err(format!(
+                "can not pop {} of {} backtracking points",
+                n, num_scopes
+            ));
+        }
+        self.pop(n);
+        Ok(())
+    }
+
+    /// Take a snapshot of the current assertions of this solver, which can
+    /// later be restored with [`Solver::rollback()`].
+    ///
+    /// Unlike [`Solver::push()`] and [`Solver::pop()`], snapshots do not
+    /// have to be restored in the reverse order they were taken in, which
+    /// suits search procedures that jump between unrelated states.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, Config, Context, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let a = ast::Bool::new_const(ctx.clone(), "a");
+    /// let solver = Solver::new(ctx.clone());
+    ///
+    /// let empty = solver.snapshot();
+    /// solver.assert(&a);
+    /// let with_a = solver.snapshot();
+    /// solver.assert(&a.not());
+    /// assert_eq!(solver.check(), SatResult::Unsat);
+    ///
+    /// solver.rollback(&empty);
+    /// assert_eq!(solver.get_assertions().len(), 0);
+    /// solver.rollback(&with_a);
+    /// assert_eq!(solver.check(), SatResult::Sat);
+    /// ```
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            assertions: self.get_assertions(),
+        }
+    }
+
+    /// Replace the assertions of this solver with those recorded in
+    /// `snapshot`.
+    ///
+    /// The solver is [reset](Solver::reset) and the recorded assertions are
+    /// asserted again, at the base level: backtracking points created with
+    /// [`Solver::push()`] are not restored, and assertions that were
+    /// tracked with [`Solver::assert_and_track()`] come back untracked.
+    ///
+    /// # Panics
+    ///
+    /// If `snapshot` was taken from a solver of a different context.
+    pub fn rollback(&self, snapshot: &Snapshot) {
+        if let Some(a) = snapshot.assertions.first() {
+            assert!(
+                *a.get_ctx() == *self.ctx,
+                "snapshot was taken in a different context"
+            );
+        }
+        self.reset();
+        for a in &snapshot.assertions {
+            self.assert(a);
+        }
+    }
+
+    /// Split the search space of this solver into cubes: conjunctions of
+    /// literals that together cover all models of the assertions.
+    ///
+    /// The cubes are over `vars`, or over variables Z3 picks if `vars` is
+    /// empty. Solving the assertions under each cube (e.g. with
+    /// [`Solver::check_assumptions()`], possibly on other threads or
+    /// machines) is cube-and-conquer.
+    ///
+    /// `backtrack_level` is the number of decisions the first cube
+    /// backtracks over; pass `u32::MAX` to start from scratch. An empty
+    /// cube means the solver could not split the problem further (or
+    /// found it satisfiable); once the assertions are found unsatisfiable
+    /// under all cubes, the iterator ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, Config, Context, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let a = ast::Bool::new_const(ctx.clone(), "a");
+    /// let b = ast::Bool::new_const(ctx.clone(), "b");
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&(a.clone() | b.clone()));
+    ///
+    /// for cube in solver.cubes(&[a, b], u32::MAX) {
+    ///     let worker = solver.clone();
+    ///     assert_ne!(worker.check_assumptions(&cube), SatResult::Unknown);
+    /// }
+    /// ```
+    pub fn cubes(&self, vars: &[ast::Bool], backtrack_level: u32) -> Cubes<'_> {
+        let z3_vars = unsafe {
+            let z3_vars = Z3_mk_ast_vector(self.ctx.z3_ctx);
+            Z3_ast_vector_inc_ref(self.ctx.z3_ctx, z3_vars);
+            for v in vars {
+                Z3_ast_vector_push(self.ctx.z3_ctx, z3_vars, v.z3_ast);
+            }
+            z3_vars
+        };
+        Cubes {
+            solver: self,
+            z3_vars,
+            backtrack_level,
+            done: false,
+        }
+    }
+
+    /// Enumerate the models of the assertions of this solver (all-SAT).
+    ///
+    /// After each model, a blocking clause is asserted that requires one
+    /// of `vars` to take a different value, so every model is distinct on
+    /// `vars`. If `vars` is empty, the models are distinct on all
+    /// uninterpreted constants occurring in the assertions instead. The iteration ends once no further model
+    /// exists, or when the solver returns [`SatResult::Unknown`].
+    ///
+    /// The blocking clauses are asserted in a new scope (see
+    /// [`Solver::push()`]). When the iterator is dropped, the solver is
+    /// popped back to the scopes it had before that, unless it was reset
+    /// or popped further in the meantime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let y = ast::Int::new_const(ctx.clone(), "y");
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&x.ge(&ast::Int::from_i64(ctx.clone(), 0)));
+    /// solver.assert(&x.lt(&ast::Int::from_i64(ctx.clone(), 3)));
+    ///
+    /// // `y` is unconstrained, so only enumerate the values of `x`.
+    /// let mut xs: Vec<i64> = solver
+    ///     .models(&[&x])
+    ///     .map(|m| m.eval(&x, true).unwrap().as_i64().unwrap())
+    ///     .collect();
+    /// xs.sort();
+    /// assert_eq!(xs, [0, 1, 2]);
+    /// ```
+    pub fn models(&self, vars: &[&dyn Ast]) -> Models<'_> {
+        Models {
+            solver: self,
+            vars: if vars.is_empty() {
+                self.uninterpreted_constants()
+            } else {
+                vars.iter().map(|v| ast::Dynamic::from_ast(*v)).collect()
+            },
+            scopes: None,
+            done: false,
+        }
+    }
+
+    /// The uninterpreted constants occurring in the assertions.
+    fn uninterpreted_constants(&self) -> Vec<ast::Dynamic> {
+        let mut seen = HashSet::new();
+        let mut todo: Vec<ast::Dynamic> = self
+            .get_assertions()
+            .iter()
+            .map(|a| ast::Dynamic::from_ast(a))
+            .collect();
+        let mut consts = vec![];
+        while let Some(a) = todo.pop() {
+            if a.kind() != AstKind::App || !seen.insert(a.get_z3_ast()) {
+                continue;
+            }
+            if a.num_children() == 0 && a.decl().kind() == DeclKind::UNINTERPRETED {
+                consts.push(a);
+            } else {
+                todo.extend(a.children());
+            }
+        }
+        consts
+    }
+
+    /// Return a new solver with the assertions of this one in a
+    /// pseudo-random order determined by `seed`.
+    ///
+    /// The order of the assertions does not change whether they are
+    /// satisfiable, but it can change how long Z3 takes to find out, so
+    /// checking several shuffles measures that variance. The new solver has
+    /// the default parameters, and no tracked assertions or scopes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let solver = Solver::new(ctx.clone());
+    /// for i in 0..10 {
+    ///     let x = ast::Int::new_const(ctx.clone(), format!("x{}", i));
+    ///     solver.assert(&x.gt(&ast::Int::from_i64(ctx.clone(), i)));
+    /// }
+    /// let shuffled = solver.shuffle_assertions(7);
+    /// assert_ne!(shuffled.get_assertions(), solver.get_assertions());
+    /// assert_eq!(shuffled.get_assertions(), solver.shuffle_assertions(7).get_assertions());
+    /// assert_eq!(shuffled.check(), SatResult::Sat);
+    /// ```
+    pub fn shuffle_assertions(&self, seed: u64) -> Solver {
+        let mut assertions = self.get_assertions();
+        let mut rng = SplitMix64(seed);
+        for i in (1..assertions.len()).rev() {
+            assertions.swap(i, rng.below(i + 1));
+        }
+        let solver = Solver::new(self.ctx.clone());
+        for a in &assertions {
+            solver.assert(a);
+        }
+        solver
+    }
+
+    /// Return a new solver with the assertions of this one, where every
+    /// uninterpreted constant is renamed to a pseudo-random name determined
+    /// by `seed`, together with the pairs of original and renamed
+    /// constants.
+    ///
+    /// Like [`Solver::shuffle_assertions()`], this is for measuring how
+    /// much Z3 depends on the presentation of a problem: names decide,
+    /// e.g., the order in which variables are first considered. Constants
+    /// that only occur inside quantifiers, and functions with arguments,
+    /// keep their names.
+    pub fn rename_symbols(&self, seed: u64) -> (Solver, Vec<(ast::Dynamic, ast::Dynamic)>) {
+        let mut rng = SplitMix64(seed);
+        let mut names = HashSet::new();
+        let renaming: Vec<(ast::Dynamic, ast::Dynamic)> = self
+            .uninterpreted_constants()
+            .into_iter()
+            .map(|c| {
+                let name = loop {
+                    let name = format!("v{:016x}", rng.next());
+                    if names.insert(name.clone()) {
+                        break name;
+                    }
+                };
+                let renamed = ast::Dynamic::new_const(self.ctx.clone(), name, &c.get_sort());
+                (c, renamed)
+            })
+            .collect();
+        let substitutions: Vec<(&ast::Dynamic, &ast::Dynamic)> =
+            renaming.iter().map(|(c, r)| (c, r)).collect();
+        let solver = Solver::new(self.ctx.clone());
+        for a in self.get_assertions() {
+            solver.assert(&a.substitute(&substitutions));
+        }
+        (solver, renaming)
+    }
+
+    /// Check the assertions with Z3's solver for `QF_UF`, the theory of
+    /// equality and uninterpreted functions (EUF) without quantifiers,
+    /// which decides it by congruence closure.
+    ///
+    /// Return an error, without checking, if an assertion contains a term
+    /// outside of it: a quantifier, or a term of a sort other than `Bool`
+    /// and uninterpreted sorts, such as an integer or bit-vector.
+    ///
+    /// The check runs on a separate solver, so the models and unsat cores
+    /// of this solver do not change. To use them, create the solver with
+    /// [`Solver::new_for_logic()`] and `"QF_UF"` instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, FuncDecl, SatResult, Solver, Sort, Symbol};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let ptr = Sort::uninterpreted(ctx.clone(), Symbol::from("Ptr"));
+    /// let field = FuncDecl::new(ctx.clone(), "field", &[&ptr], &ptr);
+    /// let p = ast::Dynamic::new_const(ctx.clone(), "p", &ptr);
+    /// let q = ast::Dynamic::new_const(ctx.clone(), "q", &ptr);
+    ///
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&p._eq(&q));
+    /// solver.assert(&!field.apply(&[&p])._eq(&field.apply(&[&q])));
+    /// assert_eq!(solver.check_euf_only(), Ok(SatResult::Unsat));
+    ///
+    /// solver.assert(&ast::Int::new_const(ctx.clone(), "n")._eq(&ast::Int::from_i64(ctx.clone(), 0)));
+    /// assert!(solver.check_euf_only().is_err());
+    /// ```
+    pub fn check_euf_only(&self) -> Result<SatResult, NotEuf> {
+        let assertions = self.get_assertions();
+        let mut seen = HashSet::new();
+        let mut todo: Vec<ast::Dynamic> = assertions
+            .iter()
+            .map(|a| ast::Dynamic::from_ast(a))
+            .collect();
+        while let Some(a) = todo.pop() {
+            if !seen.insert(a.get_z3_ast()) {
+                continue;
+            }
+            let euf = a.kind() == AstKind::App
+                && matches!(a.sort_kind(), SortKind::Bool | SortKind::Uninterpreted)
+                && matches!(
+                    a.decl().kind(),
+                    DeclKind::TRUE
+                        | DeclKind::FALSE
+                        | DeclKind::EQ
+                        | DeclKind::DISTINCT
+                        | DeclKind::ITE
+                        | DeclKind::AND
+                        | DeclKind::OR
+                        | DeclKind::IFF
+                        | DeclKind::XOR
+                        | DeclKind::NOT
+                        | DeclKind::IMPLIES
+                        | DeclKind::UNINTERPRETED
+                );
+            if !euf {
+                return Err(NotEuf { term: a });
+            }
+            todo.extend(a.children());
+        }
+        let solver = Solver::new_for_logic(self.ctx.clone(), "QF_UF").unwrap();
+        for a in &assertions {
+            solver.assert(a);
+        }
+        Ok(solver.check())
+    }
+
     /// Retrieve the model for the last [`Solver::check()`]
     /// or [`Solver::check_assumptions()`].
     ///
@@ -286,13 +883,12 @@ impl Solver {
     /// - [`Config::set_proof_generation()`](crate::Config::set_proof_generation)
     ///
     /// [proof generation is not enabled]: crate::Config::set_proof_generation
-    //
-    // This seems to actually return an Ast with kind `SortKind::Unknown`, which we don't
-    // have an Ast subtype for yet.
-    pub fn get_proof(&self) -> Option<impl Ast> {
+    pub fn get_proof(&self) -> Option<Proof> {
         let m = unsafe { Z3_solver_get_proof(self.ctx.z3_ctx, self.z3_slv) };
         if !m.is_null() {
-            Some(unsafe { ast::Dynamic::wrap(self.ctx.clone(), m) })
+            Some(Proof::new(unsafe {
+                ast::Dynamic::wrap(self.ctx.clone(), m)
+            }))
         } else {
             None
         }
@@ -312,9 +908,165 @@ impl Solver {
             .map(|s| s.to_string())
     }
 
+    /// Like [`Solver::get_reason_unknown()`], but classifies the
+    /// justification, so that callers can react to specific causes (e.g.
+    /// avoid a tactic that keeps failing).
+    ///
+    /// Returns `None` if Z3 did not give a justification.
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, Config, Context, ReasonUnknown, SatResult, Tactic};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let solver = Tactic::new(ctx.clone(), "fail").solver();
+    /// solver.assert(&ast::Bool::new_const(ctx, "b"));
+    /// assert_eq!(solver.check(), SatResult::Unknown);
+    /// match solver.get_reason_unknown_kind() {
+    ///     Some(ReasonUnknown::TacticFailed { tactic, .. }) => assert_eq!(tactic, "fail"),
+    ///     r => panic!("unexpected reason: {:?}", r),
+    /// }
+    /// ```
+    pub fn get_reason_unknown_kind(&self) -> Option<ReasonUnknown> {
+        self.get_reason_unknown()
+            .filter(|r| !r.is_empty())
+            .map(|r| ReasonUnknown::from(r.as_str()))
+    }
+
     /// Set the current solver using the given parameters.
     pub fn set_params(&self, params: &Params) {
         unsafe { Z3_solver_set_params(self.ctx.z3_ctx, self.z3_slv, params.z3_params) };
+        let mut saved = self.params.borrow_mut();
+        for (k, v) in params.values() {
+            saved.insert(k.clone(), v.clone());
+        }
+    }
+
+    /// The parameters set with [`Solver::set_params()`], with their latest
+    /// values.
+    pub(crate) fn saved_params(&self) -> Params {
+        let mut params = Params::new(self.ctx.clone());
+        for (k, v) in self.params.borrow().iter() {
+            params.set(k, v);
+        }
+        params
+    }
+
+    /// Check the assertions like [`Solver::check()`], with `params` set for
+    /// this call only.
+    ///
+    /// Afterwards, every parameter in `params` is restored to the value it
+    /// was last given with [`Solver::set_params()`], or else to Z3's
+    /// default, as listed in the solver's help text, or for a module
+    /// parameter like `smt.random_seed`, to its global value. Z3 does not
+    /// report the current values of solver parameters, so if a parameter
+    /// has neither, this returns an error without checking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, Config, Context, Params, SatResult, Solver};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let solver = Solver::new(ctx.clone());
+    /// solver.assert(&ast::Bool::new_const(ctx.clone(), "a"));
+    ///
+    /// let mut quick = Params::new(ctx.clone());
+    /// quick.set_u32("timeout", 100);
+    /// quick.set_u32("smt.random_seed", 7);
+    /// assert_eq!(solver.check_with_params(&quick), Ok(SatResult::Sat));
+    /// // Later calls run without the timeout and seed again.
+    /// solver.check();
+    /// ```
+    pub fn check_with_params(&self, params: &Params) -> Result<SatResult, String> {
+        self.with_params(params, &Params::new(self.ctx.clone()), || self.check())
+    }
+
+    /// Call `f` with `params` set, and restore them as
+    /// [`Solver::check_with_params()`] does, preferring the values in
+    /// `defaults` over Z3's for the parameters that were never set. The
+    /// parameters are restored even if `f` panics.
+    pub(crate) fn with_params<T>(
+        &self,
+        params: &Params,
+        defaults: &Params,
+        f: impl FnOnce() -> T,
+    ) -> Result<T, String> {
+        let mut restore = Params::new(self.ctx.clone());
+        let mut z3_defaults = None;
+        let descrs = self.get_param_descrs();
+        let mut unknown = vec![];
+        for (k, v) in params.values() {
+            let saved = self.params.borrow();
+            if let Some(value) = saved.get(k).or_else(|| defaults.values().get(k)) {
+                restore.set(k, value);
+                continue;
+            }
+            let z3_defaults = z3_defaults.get_or_insert_with(|| self.param_defaults());
+            let value = z3_defaults.get(k).cloned().or_else(|| {
+                // Module parameters default to their global values.
+                k.contains('.')
+                    .then(|| crate::get_global_param(k))
+                    .flatten()
+            });
+            let kind = descrs.kind(k).unwrap_or_else(|| v.kind());
+            match value.map(|value| restore.set_parsed(kind, k, &value)) {
+                Some(Ok(())) => {}
+                Some(Err(e)) => unknown.push(e),
+                None => unknown.push(format!("no known value for parameter {}", k)),
+            }
+        }
+        if !unknown.is_empty() {
+            return Err(format!(
+                "can not restore the parameters after the call: {}",
+                unknown.join(", ")
+            ));
+        }
+
+        struct Restore<'a> {
+            solver: &'a Solver,
+            params: Params,
+        }
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                let (ctx, slv) = (self.solver.ctx.z3_ctx, self.solver.z3_slv);
+                unsafe { Z3_solver_set_params(ctx, slv, self.params.z3_params) };
+            }
+        }
+
+        unsafe { Z3_solver_set_params(self.ctx.z3_ctx, self.z3_slv, params.z3_params) };
+        let _restore = Restore {
+            solver: self,
+            params: restore,
+        };
+        Ok(f())
+    }
+
+    /// Z3's defaults of the parameters of this solver, by name, in the
+    /// syntax of its help text, where each line reads
+    /// `name (kind) description (default: value)`.
+    fn param_defaults(&self) -> BTreeMap<String, String> {
+        let help = unsafe { CStr::from_ptr(Z3_solver_get_help(self.ctx.z3_ctx, self.z3_slv)) }
+            .to_string_lossy()
+            .into_owned();
+        help.lines()
+            .filter_map(|line| {
+                let name = line.split_whitespace().next()?;
+                let (_, value) = line.rsplit_once(" (default: ")?;
+                Some((name.to_owned(), value.strip_suffix(')')?.to_owned()))
+            })
+            .collect()
+    }
+
+    /// The parameters this solver accepts in [`Solver::set_params()`].
+    pub fn get_param_descrs(&self) -> ParamDescrs {
+        unsafe {
+            ParamDescrs::wrap(
+                self.ctx.clone(),
+                Z3_solver_get_param_descrs(self.ctx.z3_ctx, self.z3_slv),
+            )
+        }
     }
 
     /// Retrieve the statistics for the last [`Solver::check()`].
@@ -365,6 +1117,189 @@ impl Solver {
     }
 }
 
+impl SourceLocation {
+    /// Return the location recorded in a tracking constant created by
+    /// [`Solver::assert_here()`], or `None` if `p` is not one.
+    pub fn of(p: &ast::Bool) -> Option<SourceLocation> {
+        if !p.is_const() {
+            return None;
+        }
+        let name = p.decl().name();
+        let mut parts = name.rsplitn(3, ':');
+        let column = parts.next()?.parse().ok()?;
+        let line = parts.next()?.parse().ok()?;
+        let file = parts.next()?.to_owned();
+        Some(SourceLocation { file, line, column })
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// A term outside of the theory of equality and uninterpreted functions,
+/// see [`Solver::check_euf_only()`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NotEuf {
+    term: ast::Dynamic,
+}
+
+impl NotEuf {
+    /// The first term found outside of the theory.
+    pub fn term(&self) -> &ast::Dynamic {
+        &self.term
+    }
+}
+
+impl fmt::Display for NotEuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "not in the theory of equality and uninterpreted functions: {}",
+            self.term
+        )
+    }
+}
+
+impl Error for NotEuf {}
+
+/// Iterator over the cubes of a [`Solver`], see [`Solver::cubes()`].
+#[derive(Debug)]
+pub struct Cubes<'a> {
+    solver: &'a Solver,
+    z3_vars: Z3_ast_vector,
+    backtrack_level: u32,
+    done: bool,
+}
+
+impl Iterator for Cubes<'_> {
+    type Item = Vec<ast::Bool>;
+
+    fn next(&mut self) -> Option<Vec<ast::Bool>> {
+        if self.done {
+            return None;
+        }
+        let ctx = &self.solver.ctx;
+        let level = std::mem::replace(&mut self.backtrack_level, u32::MAX);
+        unsafe {
+            let z3_cube = Z3_solver_cube(ctx.z3_ctx, self.solver.z3_slv, self.z3_vars, level);
+            if z3_cube.is_null() {
+                self.done = true;
+                return None;
+            }
+            Z3_ast_vector_inc_ref(ctx.z3_ctx, z3_cube);
+            let cube: Vec<ast::Bool> = (0..Z3_ast_vector_size(ctx.z3_ctx, z3_cube))
+                .map(|i| ast::Bool::wrap(ctx.clone(), Z3_ast_vector_get(ctx.z3_ctx, z3_cube, i)))
+                .collect();
+            Z3_ast_vector_dec_ref(ctx.z3_ctx, z3_cube);
+            // Z3 ends the sequence with the cube `false`, after the empty
+            // cube if the problem could not be split.
+            if cube.len() == 1 && cube[0].as_bool() == Some(false) {
+                self.done = true;
+                return None;
+            }
+            self.done = cube.is_empty();
+            Some(cube)
+        }
+    }
+}
+
+impl Drop for Cubes<'_> {
+    fn drop(&mut self) {
+        unsafe { Z3_ast_vector_dec_ref(self.solver.ctx.z3_ctx, self.z3_vars) };
+    }
+}
+
+/// Iterator over the models of a [`Solver`], see [`Solver::models()`].
+#[derive(Debug)]
+pub struct Models<'a> {
+    solver: &'a Solver,
+    vars: Vec<ast::Dynamic>,
+    // The number of scopes of the solver before the iterator pushed its
+    // own, once it has.
+    scopes: Option<u32>,
+    done: bool,
+}
+
+impl Iterator for Models<'_> {
+    type Item = Model;
+
+    fn next(&mut self) -> Option<Model> {
+        if self.done {
+            return None;
+        }
+        if self.scopes.is_none() {
+            self.scopes = Some(self.solver.num_scopes());
+            self.solver.push();
+        }
+        if self.solver.check() != SatResult::Sat {
+            self.done = true;
+            return None;
+        }
+        let model = self.solver.get_model()?;
+        let differs: Vec<ast::Bool> = self
+            .vars
+            .iter()
+            .filter_map(|v| Some(!v._eq(&model.eval(v, true)?)))
+            .collect();
+        // Without any constants, `differs` is empty and the clause `false`.
+        self.solver
+            .assert(&ast::Bool::or(self.solver.ctx.clone(), &differs));
+        Some(model)
+    }
+}
+
+impl Drop for Models<'_> {
+    fn drop(&mut self) {
+        if let Some(scopes) = self.scopes {
+            let num_scopes = self.solver.num_scopes();
+            if num_scopes > scopes {
+                self.solver.pop(num_scopes - scopes);
+            }
+        }
+    }
+}
+
+impl From<&str> for ReasonUnknown {
+    fn from(reason: &str) -> Self {
+        let trimmed = reason.trim();
+        match trimmed {
+            "timeout" => return ReasonUnknown::Timeout,
+            "canceled" | "interrupted" | "interrupted from keyboard" => {
+                return ReasonUnknown::Canceled
+            }
+            _ => {}
+        }
+        if trimmed.contains("memory") {
+            return ReasonUnknown::MemoryLimit;
+        }
+        if trimmed.contains("resource limit") {
+            return ReasonUnknown::ResourceLimit;
+        }
+        // Tactic failures read "<name> tactic ...", e.g. "smt tactic failed
+        // to show goal to be sat/unsat (incomplete quantifiers)".
+        if let Some(i) = trimmed.find(" tactic") {
+            let name = &trimmed[..i];
+            let rest = &trimmed[i + " tactic".len()..];
+            if !name.is_empty()
+                && !name.contains(char::is_whitespace)
+                && (rest.is_empty() || rest.starts_with(|c: char| !c.is_alphanumeric()))
+            {
+                return ReasonUnknown::TacticFailed {
+                    tactic: name.to_owned(),
+                    message: trimmed.to_owned(),
+                };
+            }
+        }
+        if trimmed.trim_start_matches('(').starts_with("incomplete") {
+            return ReasonUnknown::Incomplete(trimmed.to_owned());
+        }
+        ReasonUnknown::Other(trimmed.to_owned())
+    }
+}
+
 impl fmt::Display for Solver {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         let p = unsafe { Z3_solver_to_string(self.ctx.z3_ctx, self.z3_slv) };
@@ -386,7 +1321,12 @@ impl fmt::Debug for Solver {
 
 impl Drop for Solver {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "Solver", -1);
         unsafe { Z3_solver_dec_ref(self.ctx.z3_ctx, self.z3_slv) };
+        #[cfg(feature = "user-propagator")]
+        if let Some(state) = self.propagator.take() {
+            drop(unsafe { Box::from_raw(state.as_ptr()) });
+        }
     }
 }
 
@@ -414,3 +1354,22 @@ impl AddAssign<ast::Bool> for Solver {
         self.assert(&rhs);
     }
 }
+
+/// The `SplitMix64` generator, which is enough to pick reproducible
+/// permutations and names without a dependency on `rand`.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, with a negligible bias for small `n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
@@ -1,18 +1,37 @@
-use std::convert::TryInto;
 use std::ffi::CStr;
 use std::fmt;
-use std::rc::Rc;
 
 use z3_sys::*;
 
-use crate::{Context, FuncDecl, Sort, SortDiffers, Symbol};
+use crate::conversion::c_len;
+use crate::Rc;
+use crate::{
+    ast, ast::Ast, Context, DatatypeVariant, FuncDecl, ListSort, Sort, SortDiffers, Symbol,
+};
 
 impl Sort {
     pub(crate) unsafe fn wrap(ctx: Rc<Context>, z3_sort: Z3_sort) -> Sort {
         Z3_inc_ref(ctx.z3_ctx, Z3_sort_to_ast(ctx.z3_ctx, z3_sort));
+        crate::track_live(ctx.z3_ctx, "Sort", 1);
         Sort { ctx, z3_sort }
     }
 
+    /// Translate this `Sort` into the given [`Context`].
+    ///
+    /// # See also:
+    ///
+    /// - [`Translator`](crate::Translator)
+    pub fn translate(&self, dest: Rc<Context>) -> Sort {
+        unsafe {
+            let ast = Z3_translate(
+                self.ctx.z3_ctx,
+                Z3_sort_to_ast(self.ctx.z3_ctx, self.z3_sort),
+                dest.z3_ctx,
+            );
+            Self::wrap(dest, ast as Z3_sort)
+        }
+    }
+
     pub fn uninterpreted(ctx: Rc<Context>, name: Symbol) -> Sort {
         unsafe {
             let sort = Z3_mk_uninterpreted_sort(ctx.z3_ctx, name.as_z3_symbol(&ctx));
@@ -90,6 +109,15 @@ impl Sort {
         }
     }
 
+    /// Create the sort of sequences of `elt`. Strings are sequences of
+    /// characters.
+    pub fn seq(ctx: Rc<Context>, elt: &Sort) -> Sort {
+        unsafe {
+            let sort = Z3_mk_seq_sort(ctx.z3_ctx, elt.z3_sort);
+            Self::wrap(ctx, sort)
+        }
+    }
+
     /// Create an enumeration sort.
     ///
     /// Creates a Z3 enumeration sort with the given `name`.
@@ -139,7 +167,7 @@ impl Sort {
                 Z3_mk_enumeration_sort(
                     ctx.z3_ctx,
                     name.as_z3_symbol(&ctx),
-                    enum_names.len().try_into().unwrap(),
+                    c_len(enum_names.len()),
                     enum_names.as_ptr(),
                     enum_consts.as_mut_ptr(),
                     enum_testers.as_mut_ptr(),
@@ -147,37 +175,129 @@ impl Sort {
             )
         };
 
-        // increase ref counts
-        for i in &enum_consts {
-            unsafe {
-                Z3_inc_ref(ctx.z3_ctx, *i as Z3_ast);
-            }
-        }
-        for i in &enum_testers {
-            unsafe {
-                Z3_inc_ref(ctx.z3_ctx, *i as Z3_ast);
-            }
-        }
-
         // convert to Rust types
         let enum_consts: Vec<_> = enum_consts
             .iter()
-            .map(|z3_func_decl| FuncDecl {
-                ctx: ctx.clone(),
-                z3_func_decl: *z3_func_decl,
-            })
+            .map(|z3_func_decl| unsafe { FuncDecl::wrap(ctx.clone(), *z3_func_decl) })
             .collect();
         let enum_testers: Vec<_> = enum_testers
             .iter()
-            .map(|z3_func_decl| FuncDecl {
-                ctx: ctx.clone(),
-                z3_func_decl: *z3_func_decl,
-            })
+            .map(|z3_func_decl| unsafe { FuncDecl::wrap(ctx.clone(), *z3_func_decl) })
             .collect();
 
         (sort, enum_consts, enum_testers)
     }
 
+    /// Create a tuple sort, a datatype with the single constructor `name`
+    /// whose fields are `fields`.
+    ///
+    /// Returns the sort, its constructor and the projections onto the
+    /// fields, in order.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver, Sort};
+    /// # let cfg = Config::new();
+    /// # let ctx = Rc::new(Context::new(&cfg));
+    /// let int = Sort::int(ctx.clone());
+    /// let (point, mk_point, fields) = Sort::tuple(
+    ///     ctx.clone(),
+    ///     "Point".into(),
+    ///     &[("x".into(), &int), ("y".into(), &int)],
+    /// );
+    ///
+    /// let one = ast::Int::from_i64(ctx.clone(), 1);
+    /// let two = ast::Int::from_i64(ctx.clone(), 2);
+    /// let p = mk_point.apply(&[&one, &two]);
+    /// assert_eq!(p.get_sort(), point);
+    /// assert_eq!(fields[1].apply(&[&p]).simplify(), two.into());
+    /// ```
+    pub fn tuple(
+        ctx: Rc<Context>,
+        name: Symbol,
+        fields: &[(Symbol, &Sort)],
+    ) -> (Sort, FuncDecl, Vec<FuncDecl>) {
+        assert!(fields.iter().all(|(_, s)| s.ctx.z3_ctx == ctx.z3_ctx));
+        let field_names: Vec<_> = fields.iter().map(|(n, _)| n.as_z3_symbol(&ctx)).collect();
+        let field_sorts: Vec<_> = fields.iter().map(|(_, s)| s.z3_sort).collect();
+        let mut constructor = std::ptr::null_mut();
+        let mut projections = vec![std::ptr::null_mut(); fields.len()];
+
+        unsafe {
+            let sort = Self::wrap(
+                ctx.clone(),
+                Z3_mk_tuple_sort(
+                    ctx.z3_ctx,
+                    name.as_z3_symbol(&ctx),
+                    c_len(fields.len()),
+                    field_names.as_ptr(),
+                    field_sorts.as_ptr(),
+                    &mut constructor,
+                    projections.as_mut_ptr(),
+                ),
+            );
+            let constructor = FuncDecl::wrap(ctx.clone(), constructor);
+            let projections = projections
+                .into_iter()
+                .map(|p| FuncDecl::wrap(ctx.clone(), p))
+                .collect();
+            (sort, constructor, projections)
+        }
+    }
+
+    /// Create the sort `name` of lists of `elem_sort`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver, Sort};
+    /// # let cfg = Config::new();
+    /// # let ctx = Rc::new(Context::new(&cfg));
+    /// let list = Sort::list(ctx.clone(), "IntList".into(), &Sort::int(ctx.clone()));
+    ///
+    /// // The list [1, 2].
+    /// let one = ast::Int::from_i64(ctx.clone(), 1);
+    /// let two = ast::Int::from_i64(ctx.clone(), 2);
+    /// let l = list.cons(&one, &list.cons(&two, &list.nil()));
+    ///
+    /// let rest = list.tail.apply(&[&l]);
+    /// assert_eq!(list.head.apply(&[&rest]).simplify(), two.into());
+    /// let is_nil = list.is_nil.apply(&[&rest]).as_bool().unwrap();
+    /// assert_eq!(is_nil.simplify().as_bool(), Some(false));
+    /// ```
+    pub fn list(ctx: Rc<Context>, name: Symbol, elem_sort: &Sort) -> ListSort {
+        assert_eq!(ctx.z3_ctx, elem_sort.ctx.z3_ctx);
+        let mut decls = [std::ptr::null_mut(); 6];
+        unsafe {
+            let sort = Self::wrap(ctx.clone(), {
+                let [nil, is_nil, cons, is_cons, head, tail] = &mut decls;
+                Z3_mk_list_sort(
+                    ctx.z3_ctx,
+                    name.as_z3_symbol(&ctx),
+                    elem_sort.z3_sort,
+                    nil,
+                    is_nil,
+                    cons,
+                    is_cons,
+                    head,
+                    tail,
+                )
+            });
+            let [nil, is_nil, cons, is_cons, head, tail] =
+                decls.map(|d| FuncDecl::wrap(ctx.clone(), d));
+            ListSort {
+                sort,
+                nil,
+                is_nil,
+                cons,
+                is_cons,
+                head,
+                tail,
+            }
+        }
+    }
+
     pub fn kind(&self) -> SortKind {
         unsafe { Z3_get_sort_kind(self.ctx.z3_ctx, self.z3_sort) }
     }
@@ -202,6 +322,16 @@ impl Sort {
         }
     }
 
+    /// Returns `Some(n)` where `n` is the number of bits if the sort is a
+    /// `BitVector` and `None` otherwise.
+    pub fn bv_size(&self) -> Option<u32> {
+        if self.kind() == SortKind::BV {
+            Some(unsafe { Z3_get_bv_sort_size(self.ctx.z3_ctx, self.z3_sort) })
+        } else {
+            None
+        }
+    }
+
     /// Return if this Sort is for an `Array` or a `Set`.
     ///
     /// # Examples
@@ -288,6 +418,83 @@ impl Sort {
             None
         }
     }
+
+    /// Return the `Sort` of the elements of sequences of this `Sort`.
+    ///
+    /// If this is not a sequence `Sort`, return `None`.
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{Config, Context, Sort};
+    /// # let cfg = Config::new();
+    /// # let ctx = Rc::new(Context::new(&cfg));
+    /// let int_sort = Sort::int(ctx.clone());
+    /// let seq_sort = Sort::seq(ctx.clone(), &int_sort);
+    /// assert_eq!(seq_sort.seq_basis().unwrap(), int_sort);
+    /// assert!(Sort::string(ctx.clone()).seq_basis().is_some());
+    /// assert!(int_sort.seq_basis().is_none());
+    /// ```
+    pub fn seq_basis(&self) -> Option<Sort> {
+        if self.kind() == SortKind::Seq {
+            unsafe {
+                let basis = Z3_get_seq_sort_basis(self.ctx.z3_ctx, self.z3_sort);
+                if basis.is_null() {
+                    None
+                } else {
+                    Some(Self::wrap(self.ctx.clone(), basis))
+                }
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Return the variants of this `Sort`, with their constructors, testers
+    /// and accessors, in order.
+    ///
+    /// If this is not a datatype `Sort`, return `None`. Enumeration, tuple
+    /// and list sorts are datatypes too.
+    /// # Examples
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{Config, Context, Sort};
+    /// # let cfg = Config::new();
+    /// # let ctx = Rc::new(Context::new(&cfg));
+    /// let list = Sort::list(ctx.clone(), "IntList".into(), &Sort::int(ctx.clone()));
+    /// let variants = list.sort.datatype_variants().unwrap();
+    /// assert_eq!(variants[1].constructor, list.cons);
+    /// assert_eq!(variants[1].accessors, [list.head, list.tail]);
+    /// assert!(Sort::int(ctx.clone()).datatype_variants().is_none());
+    /// ```
+    pub fn datatype_variants(&self) -> Option<Vec<DatatypeVariant>> {
+        if self.kind() != SortKind::Datatype {
+            return None;
+        }
+        let z3_ctx = self.ctx.z3_ctx;
+        let decl = |d| unsafe { FuncDecl::wrap(self.ctx.clone(), d) };
+        let n = unsafe { Z3_get_datatype_sort_num_constructors(z3_ctx, self.z3_sort) };
+        let variants = (0..n)
+            .map(|i| unsafe {
+                let constructor = decl(Z3_get_datatype_sort_constructor(z3_ctx, self.z3_sort, i));
+                let accessors = (0..constructor.arity() as u32)
+                    .map(|j| {
+                        decl(Z3_get_datatype_sort_constructor_accessor(
+                            z3_ctx,
+                            self.z3_sort,
+                            i,
+                            j,
+                        ))
+                    })
+                    .collect();
+                DatatypeVariant {
+                    tester: decl(Z3_get_datatype_sort_recognizer(z3_ctx, self.z3_sort, i)),
+                    constructor,
+                    accessors,
+                }
+            })
+            .collect();
+        Some(variants)
+    }
 }
 
 impl Clone for Sort {
@@ -325,6 +532,7 @@ impl Eq for Sort {}
 
 impl Drop for Sort {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "Sort", -1);
         unsafe {
             Z3_dec_ref(
                 self.ctx.z3_ctx,
@@ -357,3 +565,15 @@ impl fmt::Display for SortDiffers {
         )
     }
 }
+
+impl ListSort {
+    /// The empty list.
+    pub fn nil(&self) -> ast::Datatype {
+        self.nil.apply(&[]).as_datatype().unwrap()
+    }
+
+    /// The list with `head` in front of `tail`.
+    pub fn cons(&self, head: &dyn Ast, tail: &ast::Datatype) -> ast::Datatype {
+        self.cons.apply(&[head, tail]).as_datatype().unwrap()
+    }
+}
@@ -1,9 +1,9 @@
 use std::ffi::CStr;
 use std::fmt;
-use std::rc::Rc;
 
 use z3_sys::*;
 
+use crate::Rc;
 use crate::{Context, Statistics};
 
 /// The value for a key in [`Statistics`].
@@ -18,6 +18,17 @@ pub enum StatisticsValue {
     Double(f64),
 }
 
+impl StatisticsValue {
+    /// This value as a floating-point number, whatever its kind, e.g. to
+    /// chart counters and timings alike.
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            StatisticsValue::UInt(v) => v as f64,
+            StatisticsValue::Double(v) => v,
+        }
+    }
+}
+
 /// A key, value entry within [`Statistics`].
 ///
 /// # See also:
@@ -33,6 +44,7 @@ impl Statistics {
     /// Wrap a raw [`Z3_stats`], managing refcounts.
     pub(crate) unsafe fn wrap(ctx: Rc<Context>, z3_stats: Z3_stats) -> Statistics {
         Z3_stats_inc_ref(ctx.z3_ctx, z3_stats);
+        crate::track_live(ctx.z3_ctx, "Statistics", 1);
         Statistics { ctx, z3_stats }
     }
 
@@ -107,6 +119,7 @@ impl fmt::Debug for Statistics {
 
 impl Drop for Statistics {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "Statistics", -1);
         unsafe {
             Z3_stats_dec_ref(self.ctx.z3_ctx, self.z3_stats);
         }
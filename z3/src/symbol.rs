@@ -1,17 +1,51 @@
-use std::ffi::CString;
+use std::ffi::CStr;
 
 use z3_sys::*;
 
+use crate::conversion::{try_cstring, ConversionError};
 use crate::{Context, Symbol};
 
 impl Symbol {
+    /// Convert a raw [`Z3_symbol`].
+    ///
+    /// # Safety
+    ///
+    /// `symbol` must be a valid symbol of `ctx`.
+    pub(crate) unsafe fn from_z3_symbol(ctx: &Context, symbol: Z3_symbol) -> Symbol {
+        match Z3_get_symbol_kind(ctx.z3_ctx, symbol) {
+            SymbolKind::String => Symbol::String(
+                CStr::from_ptr(Z3_get_symbol_string(ctx.z3_ctx, symbol))
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            SymbolKind::Int => Symbol::Int(Z3_get_symbol_int(ctx.z3_ctx, symbol) as u32),
+        }
+    }
+
+    /// Convert this symbol into a [`Z3_symbol`] of `ctx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a string symbol contains a NUL byte; see
+    /// [`Symbol::try_as_z3_symbol()`].
     pub fn as_z3_symbol(&self, ctx: &Context) -> Z3_symbol {
+        match self.try_as_z3_symbol(ctx) {
+            Ok(symbol) => symbol,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Convert this symbol into a [`Z3_symbol`] of `ctx`, or return an
+    /// error if a string symbol contains a NUL byte.
+    pub fn try_as_z3_symbol(&self, ctx: &Context) -> Result<Z3_symbol, ConversionError> {
         match self {
-            Symbol::Int(i) => unsafe { Z3_mk_int_symbol(ctx.z3_ctx, *i as ::std::os::raw::c_int) },
+            Symbol::Int(i) => {
+                Ok(unsafe { Z3_mk_int_symbol(ctx.z3_ctx, *i as ::std::os::raw::c_int) })
+            }
             Symbol::String(s) => {
-                let ss = CString::new(s.clone()).unwrap();
+                let ss = try_cstring(s.clone())?;
                 let p = ss.as_ptr();
-                unsafe { Z3_mk_string_symbol(ctx.z3_ctx, p) }
+                Ok(unsafe { Z3_mk_string_symbol(ctx.z3_ctx, p) })
             }
         }
     }
@@ -0,0 +1,224 @@
+//! Building blocks for symbolic execution of machine code or bytecode.
+//!
+//! A [`State`] is the symbolic state of a small stack/register machine:
+//! named registers, an operand stack, a byte-addressable [`Memory`], and
+//! the path condition under which the state is reached. An interpreter
+//! updates it instruction by instruction, [forks](State::fork) it at
+//! branches, and may [merge](State::merge) the two sides again where the
+//! paths join.
+//!
+//! # Examples
+//!
+//! Execute `if x > 10 { y = x - 10 } else { y = 0 }` and check that `y`
+//! is at most `x`:
+//!
+//! ```
+//! # use std::rc::Rc;
+//! # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver};
+//! use z3::symexec::State;
+//!
+//! let cfg = Config::new();
+//! let ctx = Rc::new(Context::new(&cfg));
+//! let word = |v| ast::BV::from_u64(ctx.clone(), v, 32);
+//! let state = State::new(ctx.clone(), 32);
+//! let x = state.reg("x");
+//!
+//! let (mut then, mut otherwise) = state.fork(&x.bvugt(&word(10)));
+//! then.set_reg("y", x.bvsub(&word(10)));
+//! otherwise.set_reg("y", word(0));
+//! let joined = then.merge(&otherwise).unwrap();
+//!
+//! let solver = Solver::new(ctx.clone());
+//! solver.assert(&joined.path_condition());
+//! solver.assert(&joined.reg("y").bvugt(&x));
+//! assert_eq!(solver.check(), SatResult::Unsat);
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::mem::{Endian, Memory};
+use crate::Rc;
+use crate::{ast, Context, SatResult, Solver};
+
+/// The symbolic state of a machine with `word_bits`-wide registers, stack
+/// slots and addresses. See the [module documentation](self).
+#[derive(Clone, Debug)]
+pub struct State {
+    ctx: Rc<Context>,
+    word_bits: u32,
+    endian: Endian,
+    regs: BTreeMap<String, ast::BV>,
+    stack: Vec<ast::BV>,
+    mem: Memory,
+    path: Vec<ast::Bool>,
+}
+
+impl State {
+    /// Create the initial state: every register and memory byte holds an
+    /// unconstrained value, the stack is empty and the path condition is
+    /// `true`. Multi-byte memory accesses are little endian.
+    pub fn new(ctx: Rc<Context>, word_bits: u32) -> State {
+        State {
+            mem: Memory::new_const(ctx.clone(), "mem", word_bits),
+            ctx,
+            word_bits,
+            endian: Endian::Little,
+            regs: BTreeMap::new(),
+            stack: vec![],
+            path: vec![],
+        }
+    }
+
+    /// Use `endian` for the multi-byte accesses of [`State::load()`] and
+    /// [`State::store()`].
+    pub fn with_endian(mut self, endian: Endian) -> State {
+        self.endian = endian;
+        self
+    }
+
+    pub fn word_bits(&self) -> u32 {
+        self.word_bits
+    }
+
+    /// The value of register `name`. A register that was never set holds
+    /// its initial value, the constant `name`.
+    pub fn reg(&self, name: &str) -> ast::BV {
+        match self.regs.get(name) {
+            Some(v) => v.clone(),
+            None => ast::BV::new_const(self.ctx.clone(), name, self.word_bits),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If `value` is not `word_bits` wide.
+    pub fn set_reg(&mut self, name: &str, value: ast::BV) {
+        assert_eq!(
+            value.get_size(),
+            self.word_bits,
+            "value has the wrong width"
+        );
+        self.regs.insert(name.to_owned(), value);
+    }
+
+    /// # Panics
+    ///
+    /// If `value` is not `word_bits` wide.
+    pub fn push(&mut self, value: ast::BV) {
+        assert_eq!(
+            value.get_size(),
+            self.word_bits,
+            "value has the wrong width"
+        );
+        self.stack.push(value);
+    }
+
+    /// Pop the top of the stack, or return `None` if it is empty.
+    pub fn pop(&mut self) -> Option<ast::BV> {
+        self.stack.pop()
+    }
+
+    /// The operand stack, bottom first.
+    pub fn stack(&self) -> &[ast::BV] {
+        &self.stack
+    }
+
+    pub fn mem(&self) -> &Memory {
+        &self.mem
+    }
+
+    /// Load `size` bytes at `addr`, see [`Memory::load()`].
+    pub fn load(&self, addr: &ast::BV, size: u32) -> ast::BV {
+        self.mem.load(addr, size, self.endian)
+    }
+
+    /// Store `value` at `addr`, see [`Memory::store()`].
+    pub fn store(&mut self, addr: &ast::BV, value: &ast::BV) {
+        self.mem = self.mem.store(addr, value, self.endian);
+    }
+
+    /// Restrict the paths reaching this state to those where `cond` holds.
+    pub fn assume(&mut self, cond: ast::Bool) {
+        self.path.push(cond);
+    }
+
+    /// The conjunction of everything [assumed](State::assume) on the way
+    /// to this state.
+    pub fn path_condition(&self) -> ast::Bool {
+        ast::Bool::and(self.ctx.clone(), &self.path)
+    }
+
+    /// Split this state at a branch on `cond`, into the state where `cond`
+    /// holds and the state where it does not.
+    pub fn fork(&self, cond: &ast::Bool) -> (State, State) {
+        let mut then = self.clone();
+        then.assume(cond.clone());
+        let mut otherwise = self.clone();
+        otherwise.assume(!cond.clone());
+        (then, otherwise)
+    }
+
+    /// Join this state with `other`, where the paths to them meet again.
+    ///
+    /// The path conditions of both states must start with the same
+    /// assumptions, like the two states of a [`State::fork()`] (and the
+    /// states derived from them) do. Every register, stack slot and memory
+    /// byte of the result holds this state's value if this state's
+    /// remaining assumptions hold, and `other`'s value otherwise.
+    ///
+    /// Return `None` if the stacks have different depths.
+    pub fn merge(&self, other: &State) -> Option<State> {
+        if self.stack.len() != other.stack.len() {
+            return None;
+        }
+        let common = self
+            .path
+            .iter()
+            .zip(&other.path)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let ours = ast::Bool::and(self.ctx.clone(), &self.path[common..]);
+        let theirs = ast::Bool::and(self.ctx.clone(), &other.path[common..]);
+
+        let mut path = self.path[..common].to_vec();
+        path.push(ours.clone() | theirs);
+        let regs = self
+            .regs
+            .keys()
+            .chain(other.regs.keys())
+            .map(|name| {
+                let (a, b) = (self.reg(name), other.reg(name));
+                let v = if a == b { a } else { ours.ite(&a, &b) };
+                (name.clone(), v)
+            })
+            .collect();
+        let stack = self
+            .stack
+            .iter()
+            .zip(&other.stack)
+            .map(|(a, b)| if a == b { a.clone() } else { ours.ite(a, b) })
+            .collect();
+        let mem = if self.mem == other.mem {
+            self.mem.clone()
+        } else {
+            Memory::from_array(ours.ite(self.mem.array(), other.mem.array()))
+        };
+        Some(State {
+            ctx: self.ctx.clone(),
+            word_bits: self.word_bits,
+            endian: self.endian,
+            regs,
+            stack,
+            mem,
+            path,
+        })
+    }
+
+    /// Whether this state may be reachable, i.e. whether its path
+    /// condition is satisfiable together with the assertions of `solver`.
+    ///
+    /// Infeasible states can be dropped instead of executed further.
+    pub fn is_feasible(&self, solver: &Solver) -> bool {
+        solver.check_assumptions(&[self.path_condition()]) != SatResult::Unsat
+    }
+}
@@ -0,0 +1,58 @@
+//! With the `sync` feature, objects hold their [`Context`] through an
+//! [`Arc`](std::sync::Arc), and the context and the objects are [`Send`],
+//! so that a solver can be moved to a worker thread or an async task.
+//!
+//! Z3 itself does not synchronize the use of a context: all objects of one
+//! context must only be used by one thread at a time, e.g. by moving them to
+//! another thread together, or by guarding them with a `Mutex`. The compiler
+//! cannot check this, since the objects of a context share it through the
+//! `Arc` without borrowing it. To interrupt a check from another thread, use
+//! a [`ContextHandle`](crate::ContextHandle), which is safe to share.
+//!
+//! A user propagator runs on the thread that checks its solver, so it must
+//! be [`Send`] itself for its solver to move.
+
+use crate::{ast, Context};
+
+// Safety: the users of the `sync` feature take over the synchronization of
+// each context, as documented above.
+unsafe impl Send for Context {}
+unsafe impl Sync for Context {}
+
+macro_rules! impl_send {
+    ($($t:ty),* $(,)?) => {
+        $(unsafe impl Send for $t {})*
+    };
+}
+
+impl_send!(
+    crate::Sort,
+    crate::Solver,
+    crate::Model,
+    crate::Optimize,
+    crate::Fixedpoint,
+    crate::FuncDecl,
+    crate::FuncInterp,
+    crate::FuncEntry,
+    crate::RecFuncDecl,
+    crate::Params,
+    crate::ParamDescrs,
+    crate::Pattern,
+    crate::ApplyResult,
+    crate::Tactic,
+    crate::Goal,
+    crate::Probe,
+    crate::Statistics,
+    ast::Bool,
+    ast::Int,
+    ast::Real,
+    ast::Float,
+    ast::String,
+    ast::Seq,
+    ast::BV,
+    ast::Array,
+    ast::Set,
+    ast::Datatype,
+    ast::Dynamic,
+    ast::Regexp,
+);
@@ -1,22 +1,45 @@
-use std::convert::TryFrom;
-use std::ffi::{CStr, CString};
+use std::ffi::CStr;
 use std::fmt;
-use std::os::raw::c_uint;
-use std::rc::Rc;
 use std::result::Result;
 use std::str::Utf8Error;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use z3_sys::*;
 
-use crate::{ApplyResult, Context, Goal, Params, Probe, Solver, Tactic};
+use crate::conversion::{cstring, timeout_ms};
+use crate::Rc;
+use crate::{ApplyResult, Context, Goal, Model, Params, Probe, Solver, Tactic};
+
+/// The sizes of a goal before and after a tactic was applied to it, see
+/// [`ApplyResult::stats()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ApplyStats {
+    /// The number of subgoals the tactic produced.
+    pub subgoals: u32,
+    /// The number of formulas in the goal, see [`Goal::get_size()`].
+    pub formulas_before: u32,
+    /// The number of formulas in all subgoals together.
+    pub formulas_after: u32,
+    /// The number of subterms in the goal, see [`Goal::get_num_expr()`].
+    pub exprs_before: u32,
+    /// The number of subterms in all subgoals together.
+    pub exprs_after: u32,
+}
 
 impl ApplyResult {
-    unsafe fn wrap(ctx: Rc<Context>, z3_apply_result: Z3_apply_result) -> ApplyResult {
+    unsafe fn wrap(
+        ctx: Rc<Context>,
+        z3_apply_result: Z3_apply_result,
+        elapsed: Duration,
+        stats: ApplyStats,
+    ) -> ApplyResult {
         Z3_apply_result_inc_ref(ctx.z3_ctx, z3_apply_result);
+        crate::track_live(ctx.z3_ctx, "ApplyResult", 1);
         ApplyResult {
             ctx,
             z3_apply_result,
+            elapsed,
+            stats,
         }
     }
 
@@ -30,10 +53,116 @@ impl ApplyResult {
             )
         })
     }
+
+    /// The time [`Tactic::apply()`] took to produce this result.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// How the tactic changed the size of the goal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Goal, Tactic};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let goal = Goal::new(ctx.clone(), false, false, false);
+    /// goal.assert(&x._eq(&ast::Int::from_i64(ctx.clone(), 1)));
+    /// goal.assert(&x.gt(&ast::Int::from_i64(ctx.clone(), 0)));
+    ///
+    /// let result = Tactic::new(ctx.clone(), "solve-eqs").apply(&goal, None).unwrap();
+    /// let stats = result.stats();
+    /// assert_eq!((stats.subgoals, stats.formulas_before, stats.formulas_after), (1, 2, 0));
+    /// assert!(result.elapsed() < std::time::Duration::from_secs(10));
+    /// ```
+    pub fn stats(&self) -> ApplyStats {
+        self.stats
+    }
+
+    /// Convert `model`, a model of the subgoal at `subgoal_index` of
+    /// [`ApplyResult::list_subgoals()`], to a model of the goal the tactic
+    /// was applied to, with the constants the tactic eliminated.
+    ///
+    /// Tactics only keep track of how to convert models of goals that
+    /// were created with model generation, see [`Goal::new()`]. For other
+    /// goals, the model is returned as is.
+    ///
+    /// # Panics
+    ///
+    /// If there is no subgoal at `subgoal_index`, or if `model` is not in
+    /// the context of this result.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use z3::{ast, ast::Ast, Config, Context, Goal, SatResult, Solver, Tactic};
+    /// let cfg = Config::new();
+    /// let ctx = Rc::new(Context::new(&cfg));
+    /// let x = ast::Int::new_const(ctx.clone(), "x");
+    /// let y = ast::Int::new_const(ctx.clone(), "y");
+    /// let goal = Goal::new(ctx.clone(), true, false, false);
+    /// goal.assert(&x._eq(&(&y + 1i64)));
+    /// goal.assert(&y.gt(&ast::Int::from_i64(ctx.clone(), 2)));
+    ///
+    /// // `solve-eqs` eliminates `x`, which models of the subgoal lack.
+    /// let result = Tactic::new(ctx.clone(), "solve-eqs").apply(&goal, None).unwrap();
+    /// let subgoal = result.clone().list_subgoals().next().unwrap();
+    /// let solver = Solver::new(ctx.clone());
+    /// for f in &subgoal {
+    ///     solver.assert(&f);
+    /// }
+    /// assert_eq!(solver.check(), SatResult::Sat);
+    /// let model = solver.get_model().unwrap();
+    /// assert_eq!(model.eval(&x, false), Some(x.clone()));
+    ///
+    /// let model = result.convert_model(0, &model);
+    /// let x = model.eval(&x, false).unwrap().as_i64().unwrap();
+    /// let y = model.eval(&y, false).unwrap().as_i64().unwrap();
+    /// assert_eq!(x, y + 1);
+    /// ```
+    pub fn convert_model(&self, subgoal_index: u32, model: &Model) -> Model {
+        assert_eq!(self.ctx, model.ctx);
+        let num_subgoals =
+            unsafe { Z3_apply_result_get_num_subgoals(self.ctx.z3_ctx, self.z3_apply_result) };
+        assert!(
+            subgoal_index < num_subgoals,
+            "subgoal {} of {}",
+            subgoal_index,
+            num_subgoals
+        );
+        unsafe {
+            let subgoal = Goal::wrap(
+                self.ctx.clone(),
+                Z3_apply_result_get_subgoal(self.ctx.z3_ctx, self.z3_apply_result, subgoal_index),
+            );
+            Model::wrap(
+                self.ctx.clone(),
+                Z3_goal_convert_model(self.ctx.z3_ctx, subgoal.z3_goal, model.z3_mdl),
+            )
+        }
+    }
+}
+
+impl Clone for ApplyResult {
+    fn clone(&self) -> Self {
+        unsafe {
+            Self::wrap(
+                self.ctx.clone(),
+                self.z3_apply_result,
+                self.elapsed,
+                self.stats,
+            )
+        }
+    }
 }
 
 impl Drop for ApplyResult {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "ApplyResult", -1);
         unsafe {
             Z3_apply_result_dec_ref(self.ctx.z3_ctx, self.z3_apply_result);
         }
@@ -65,6 +194,7 @@ impl Tactic {
 
     unsafe fn wrap(ctx: Rc<Context>, z3_tactic: Z3_tactic) -> Tactic {
         Z3_tactic_inc_ref(ctx.z3_ctx, z3_tactic);
+        crate::track_live(ctx.z3_ctx, "Tactic", 1);
         Tactic { ctx, z3_tactic }
     }
 
@@ -84,7 +214,7 @@ impl Tactic {
     ///
     /// - [`Tactic::list_all()`]
     pub fn new(ctx: Rc<Context>, name: &str) -> Tactic {
-        let tactic_name = CString::new(name).unwrap();
+        let tactic_name = cstring(name);
         unsafe {
             let tactic = Z3_mk_tactic(ctx.z3_ctx, tactic_name.as_ptr());
             Self::wrap(ctx, tactic)
@@ -118,12 +248,14 @@ impl Tactic {
 
     /// Return a tactic that applies the current tactic to a given goal, failing
     /// if it doesn't terminate within the period specified by `timeout`.
+    ///
+    /// Timeouts longer than Z3 accepts are handled according to the
+    /// [`ConversionPolicy`](crate::ConversionPolicy).
     pub fn try_for(&self, timeout: Duration) -> Tactic {
-        let timeout_ms = c_uint::try_from(timeout.as_millis()).unwrap_or(c_uint::MAX);
         unsafe {
             Self::wrap(
                 self.ctx.clone(),
-                Z3_tactic_try_for(self.ctx.z3_ctx, self.z3_tactic, timeout_ms),
+                Z3_tactic_try_for(self.ctx.z3_ctx, self.z3_tactic, timeout_ms(timeout)),
             )
         }
     }
@@ -192,8 +324,12 @@ impl Tactic {
     /// Attempts to apply the tactic to `goal`. If the tactic succeeds, returns
     /// `Ok(_)` with a `ApplyResult`. If the tactic fails, returns `Err(_)` with
     /// an error message describing why.
+    ///
+    /// The result records how long the call took and how the goal changed,
+    /// see [`ApplyResult::elapsed()`] and [`ApplyResult::stats()`].
     pub fn apply(&self, goal: &Goal, params: Option<&Params>) -> Result<ApplyResult, String> {
         unsafe {
+            let start = Instant::now();
             let z3_apply_result = match params {
                 None => Z3_tactic_apply(self.ctx.z3_ctx, self.z3_tactic, goal.z3_goal),
                 Some(params) => Z3_tactic_apply_ex(
@@ -210,7 +346,25 @@ impl Tactic {
                     "Couldn't retrieve error message from z3: got invalid UTF-8",
                 )))
             } else {
-                Ok(ApplyResult::wrap(self.ctx.clone(), z3_apply_result))
+                let elapsed = start.elapsed();
+                let mut result = ApplyResult::wrap(
+                    self.ctx.clone(),
+                    z3_apply_result,
+                    elapsed,
+                    ApplyStats::default(),
+                );
+                let mut stats = ApplyStats {
+                    formulas_before: goal.get_size(),
+                    exprs_before: goal.get_num_expr(),
+                    ..ApplyStats::default()
+                };
+                for subgoal in result.clone().list_subgoals() {
+                    stats.subgoals += 1;
+                    stats.formulas_after += subgoal.get_size();
+                    stats.exprs_after += subgoal.get_num_expr();
+                }
+                result.stats = stats;
+                Ok(result)
             }
         }
     }
@@ -265,6 +419,7 @@ impl fmt::Debug for Tactic {
 
 impl Drop for Tactic {
     fn drop(&mut self) {
+        crate::track_live(self.ctx.z3_ctx, "Tactic", -1);
         unsafe {
             Z3_tactic_dec_ref(self.ctx.z3_ctx, self.z3_tactic);
         }
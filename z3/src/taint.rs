@@ -0,0 +1,200 @@
+//! Taint (label) tracking for information-flow analyses.
+//!
+//! Every value of interest is paired with a Boolean label, which holds if
+//! the value may depend on a tainted source. [`Labeled`] pairs a single
+//! term with its label, and [`LabeledArray`] pairs a data array with a
+//! parallel array of labels. Their operations compute the labels of their
+//! results along with the results, so an encoder only has to be written
+//! once, over labeled values.
+//!
+//! # Examples
+//!
+//! ```
+//! # use std::rc::Rc;
+//! # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver, Sort};
+//! use z3::taint::{Labeled, LabeledArray};
+//!
+//! let cfg = Config::new();
+//! let ctx = Rc::new(Context::new(&cfg));
+//! let int = Sort::int(ctx.clone());
+//! let input = Labeled::tainted(ast::Int::new_const(ctx.clone(), "input"));
+//! let zero = Labeled::clean(ast::Int::from_i64(ctx.clone(), 0));
+//! let one = Labeled::clean(ast::Int::from_i64(ctx.clone(), 1));
+//!
+//! let buf = LabeledArray::new_clean(ctx.clone(), "buf", &int, &int);
+//! let buf = buf.store(&zero, &input);
+//!
+//! let solver = Solver::new(ctx.clone());
+//! // `buf[0]` is tainted, `buf[1]` is not.
+//! solver.assert(&!buf.select(&zero).label().clone());
+//! assert_eq!(solver.check(), SatResult::Unsat);
+//! solver.reset();
+//! solver.assert(&buf.select(&one).label().clone());
+//! assert_eq!(solver.check(), SatResult::Unsat);
+//! ```
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, Sort, Symbol};
+
+/// A term together with its taint label.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Labeled<T> {
+    value: T,
+    label: ast::Bool,
+}
+
+impl<T: Ast> Labeled<T> {
+    pub fn new(value: T, label: ast::Bool) -> Labeled<T> {
+        Labeled { value, label }
+    }
+
+    /// A value that is not tainted.
+    pub fn clean(value: T) -> Labeled<T> {
+        let label = ast::Bool::from_bool(value.get_ctx(), false);
+        Labeled::new(value, label)
+    }
+
+    /// A value that is tainted, e.g. an untrusted input.
+    pub fn tainted(value: T) -> Labeled<T> {
+        let label = ast::Bool::from_bool(value.get_ctx(), true);
+        Labeled::new(value, label)
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn label(&self) -> &ast::Bool {
+        &self.label
+    }
+
+    /// Apply `f` to the value, keeping the label.
+    pub fn map<U: Ast>(&self, f: impl FnOnce(&T) -> U) -> Labeled<U> {
+        Labeled::new(f(&self.value), self.label.clone())
+    }
+
+    /// Combine the values of `self` and `other` with `f`. The result is
+    /// tainted if either operand is.
+    pub fn combine<U: Ast, R: Ast>(
+        &self,
+        other: &Labeled<U>,
+        f: impl FnOnce(&T, &U) -> R,
+    ) -> Labeled<R> {
+        Labeled::new(
+            f(&self.value, &other.value),
+            self.label.clone() | other.label.clone(),
+        )
+    }
+}
+
+/// A data array together with a parallel array of taint labels, which maps
+/// every index to the label of the value stored there.
+///
+/// By default, only the stored values carry their labels. With
+/// [`LabeledArray::with_index_taint()`], an access at a tainted index also
+/// taints its result (for loads) or the stored value (for stores), which
+/// tracks implicit flows through addresses.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LabeledArray {
+    data: ast::Array,
+    labels: ast::Array,
+    index_taint: bool,
+}
+
+impl LabeledArray {
+    /// Pair `data` with `labels`, an array from the same domain to `Bool`.
+    pub fn new(data: ast::Array, labels: ast::Array) -> LabeledArray {
+        LabeledArray {
+            data,
+            labels,
+            index_taint: false,
+        }
+    }
+
+    /// Create an array of unconstrained values, none of which is tainted.
+    pub fn new_clean<S: Into<Symbol>>(
+        ctx: Rc<Context>,
+        name: S,
+        domain: &Sort,
+        range: &Sort,
+    ) -> LabeledArray {
+        let data = ast::Array::new_const(ctx.clone(), name, domain, range);
+        let clean = ast::Bool::from_bool(ctx.clone(), false);
+        LabeledArray::new(data, ast::Array::const_array(ctx, domain, &clean))
+    }
+
+    /// Create an array of unconstrained values, all of which are tainted,
+    /// e.g. an input buffer.
+    pub fn new_tainted<S: Into<Symbol>>(
+        ctx: Rc<Context>,
+        name: S,
+        domain: &Sort,
+        range: &Sort,
+    ) -> LabeledArray {
+        let data = ast::Array::new_const(ctx.clone(), name, domain, range);
+        let tainted = ast::Bool::from_bool(ctx.clone(), true);
+        LabeledArray::new(data, ast::Array::const_array(ctx, domain, &tainted))
+    }
+
+    /// Set whether the label of an index taints the accessed value.
+    pub fn with_index_taint(mut self, index_taint: bool) -> LabeledArray {
+        self.index_taint = index_taint;
+        self
+    }
+
+    pub fn data(&self) -> &ast::Array {
+        &self.data
+    }
+
+    pub fn labels(&self) -> &ast::Array {
+        &self.labels
+    }
+
+    /// Read the value at `index`, with the label stored for it.
+    pub fn select<A: Ast>(&self, index: &Labeled<A>) -> Labeled<ast::Dynamic> {
+        let value = self.data.select(index.value());
+        let label = self.labels.select(index.value()).as_bool().unwrap();
+        Labeled::new(value, self.with_index_label(label, index))
+    }
+
+    /// Write `value` at `index`, and return the updated array.
+    pub fn store<A: Ast, V: Ast>(&self, index: &Labeled<A>, value: &Labeled<V>) -> LabeledArray {
+        let label = self.with_index_label(value.label().clone(), index);
+        LabeledArray {
+            data: self.data.store(index.value(), value.value()),
+            labels: self.labels.store(index.value(), &label),
+            index_taint: self.index_taint,
+        }
+    }
+
+    /// Copy this array into fresh arrays named `name` (for the data) and
+    /// `name!labels`, and return them with the constraint that defines
+    /// them, e.g. to give an intermediate array a name in an encoding.
+    pub fn copy(&self, name: &str) -> (LabeledArray, ast::Bool) {
+        let ctx = self.data.get_ctx();
+        let fresh = |array: &ast::Array, name: String| {
+            let sort = array.get_sort();
+            ast::Array::new_const(
+                ctx.clone(),
+                name,
+                &sort.array_domain().unwrap(),
+                &sort.array_range().unwrap(),
+            )
+        };
+        let copy = LabeledArray {
+            data: fresh(&self.data, name.to_owned()),
+            labels: fresh(&self.labels, format!("{}!labels", name)),
+            index_taint: self.index_taint,
+        };
+        let defs = copy.data._eq(&self.data) & copy.labels._eq(&self.labels);
+        (copy, defs)
+    }
+
+    fn with_index_label<A>(&self, label: ast::Bool, index: &Labeled<A>) -> ast::Bool {
+        if self.index_taint {
+            label | index.label.clone()
+        } else {
+            label
+        }
+    }
+}
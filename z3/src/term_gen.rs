@@ -0,0 +1,275 @@
+use crate::Rc;
+use crate::{ast, ast::Ast, Context};
+
+/// A generator of random, well-sorted terms, for fuzzing code that
+/// processes formulas.
+///
+/// Terms are drawn from Boolean logic and, as configured, integer
+/// arithmetic and fixed-width bitvectors, over a few constants of each
+/// sort. The generator is deterministic in its seed, so a property-testing
+/// framework can drive it by generating the seed, and a failing case is
+/// reproduced by its seed alone.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver, TermGenerator};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+///
+/// // Simplification preserves the meaning of formulas.
+/// for seed in 0..20 {
+///     let mut gen = TermGenerator::new(ctx.clone(), seed).bitvectors(Some(8));
+///     let f = gen.gen_bool();
+///     let solver = Solver::new(ctx.clone());
+///     solver.assert(&f._eq(&f.simplify()).not());
+///     assert_eq!(solver.check(), SatResult::Unsat, "seed {}: {}", seed, f);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TermGenerator {
+    ctx: Rc<Context>,
+    state: u64,
+    max_depth: u32,
+    num_vars: u32,
+    ints: bool,
+    nonlinear: bool,
+    bv_width: Option<u32>,
+}
+
+impl TermGenerator {
+    /// Create a generator of Boolean and linear integer terms of depth at
+    /// most 4 over 3 constants of each sort.
+    pub fn new(ctx: Rc<Context>, seed: u64) -> TermGenerator {
+        TermGenerator {
+            ctx,
+            state: seed,
+            max_depth: 4,
+            num_vars: 3,
+            ints: true,
+            nonlinear: false,
+            bv_width: None,
+        }
+    }
+
+    /// Set the maximum depth of generated terms, where constants and
+    /// numerals have depth 0.
+    pub fn max_depth(mut self, max_depth: u32) -> TermGenerator {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set the number of constants of each sort. With 0, the leaves are
+    /// only numerals, so terms are ground and closed.
+    pub fn num_vars(mut self, num_vars: u32) -> TermGenerator {
+        self.num_vars = num_vars;
+        self
+    }
+
+    /// Enable or disable integer terms.
+    pub fn ints(mut self, ints: bool) -> TermGenerator {
+        self.ints = ints;
+        self
+    }
+
+    /// Allow the product of two non-numeral integer terms.
+    pub fn nonlinear(mut self, nonlinear: bool) -> TermGenerator {
+        self.nonlinear = nonlinear;
+        self
+    }
+
+    /// Enable bitvector terms of the given width, or disable them with
+    /// `None`.
+    pub fn bitvectors(mut self, width: Option<u32>) -> TermGenerator {
+        assert_ne!(width, Some(0), "bitvectors must be at least 1 bit wide");
+        self.bv_width = width;
+        self
+    }
+
+    /// The constants generated terms are over: `p0`, `p1`, ... of sort
+    /// `Bool`, `x0`, ... of sort `Int` and `v0`, ... of the bitvector sort,
+    /// for the enabled theories.
+    pub fn variables(&self) -> Vec<ast::Dynamic> {
+        let mut vars = vec![];
+        for i in 0..self.num_vars {
+            vars.push(self.bool_var(i).into());
+            if self.ints {
+                vars.push(self.int_var(i).into());
+            }
+            if let Some(width) = self.bv_width {
+                vars.push(self.bv_var(i, width).into());
+            }
+        }
+        vars
+    }
+
+    /// Generate a random formula.
+    pub fn gen_bool(&mut self) -> ast::Bool {
+        self.bool_term(self.max_depth)
+    }
+
+    /// Generate a random integer term.
+    ///
+    /// # Panics
+    ///
+    /// Panics if integer terms are disabled.
+    pub fn gen_int(&mut self) -> ast::Int {
+        assert!(self.ints, "integer terms are disabled");
+        self.int_term(self.max_depth)
+    }
+
+    /// Generate a random bitvector term.
+    ///
+    /// # Panics
+    ///
+    /// Panics if bitvector terms are disabled.
+    pub fn gen_bv(&mut self) -> ast::BV {
+        let width = self.bv_width.expect("bitvector terms are disabled");
+        self.bv_term(self.max_depth, width)
+    }
+
+    // SplitMix64, which is good enough for fuzzing and needs no state
+    // beyond the seed.
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u32) -> u32 {
+        (self.next() % u64::from(n)) as u32
+    }
+
+    // Whether to stop at a leaf: always at depth 0, and otherwise with
+    // probability 1/4, so that terms are not all of the maximum depth.
+    fn leaf(&mut self, depth: u32) -> bool {
+        depth == 0 || self.below(4) == 0
+    }
+
+    fn bool_var(&self, i: u32) -> ast::Bool {
+        ast::Bool::new_const(self.ctx.clone(), format!("p{}", i))
+    }
+
+    fn int_var(&self, i: u32) -> ast::Int {
+        ast::Int::new_const(self.ctx.clone(), format!("x{}", i))
+    }
+
+    fn bv_var(&self, i: u32, width: u32) -> ast::BV {
+        ast::BV::new_const(self.ctx.clone(), format!("v{}", i), width)
+    }
+
+    fn bool_term(&mut self, depth: u32) -> ast::Bool {
+        if self.leaf(depth) {
+            let choice = self.below(self.num_vars + 2);
+            return match choice.checked_sub(2) {
+                Some(i) => self.bool_var(i),
+                None => ast::Bool::from_bool(self.ctx.clone(), choice == 0),
+            };
+        }
+        let d = depth - 1;
+        let theories = 1 + self.ints as u32 + self.bv_width.is_some() as u32;
+        let mut theory = self.below(theories);
+        if theory > 0 && !self.ints {
+            theory += 1;
+        }
+        match theory {
+            0 => match self.below(7) {
+                0 => self.bool_term(d).not(),
+                1 => ast::Bool::and(self.ctx.clone(), &[self.bool_term(d), self.bool_term(d)]),
+                2 => ast::Bool::or(self.ctx.clone(), &[self.bool_term(d), self.bool_term(d)]),
+                3 => self.bool_term(d).xor(&self.bool_term(d)),
+                4 => self.bool_term(d).implies(&self.bool_term(d)),
+                5 => self.bool_term(d).iff(&self.bool_term(d)),
+                _ => self
+                    .bool_term(d)
+                    .ite(&self.bool_term(d), &self.bool_term(d)),
+            },
+            1 => {
+                let (a, b) = (self.int_term(d), self.int_term(d));
+                match self.below(3) {
+                    0 => a._eq(&b),
+                    1 => a.le(&b),
+                    _ => a.lt(&b),
+                }
+            }
+            _ => {
+                let width = self.bv_width.unwrap();
+                let (a, b) = (self.bv_term(d, width), self.bv_term(d, width));
+                match self.below(3) {
+                    0 => a._eq(&b),
+                    1 => a.bvule(&b),
+                    _ => a.bvslt(&b),
+                }
+            }
+        }
+    }
+
+    fn int_numeral(&mut self) -> ast::Int {
+        let value = i64::from(self.below(11)) - 5;
+        ast::Int::from_i64(self.ctx.clone(), value)
+    }
+
+    fn int_term(&mut self, depth: u32) -> ast::Int {
+        if self.leaf(depth) {
+            let choice = self.below(self.num_vars + 1);
+            return match choice.checked_sub(1) {
+                Some(i) => self.int_var(i),
+                None => self.int_numeral(),
+            };
+        }
+        let d = depth - 1;
+        match self.below(7) {
+            0 => ast::Int::add(self.ctx.clone(), &[self.int_term(d), self.int_term(d)]),
+            1 => ast::Int::sub(self.ctx.clone(), &[self.int_term(d), self.int_term(d)]),
+            2 => self.int_term(d).unary_minus(),
+            3 => {
+                let a = self.int_term(d);
+                let b = if self.nonlinear {
+                    self.int_term(d)
+                } else {
+                    self.int_numeral()
+                };
+                ast::Int::mul(self.ctx.clone(), &[a, b])
+            }
+            // Division by a non-zero numeral stays in linear arithmetic.
+            4 | 5 => {
+                let a = self.int_term(d);
+                let value = i64::from(self.below(5)) + 1;
+                let b = ast::Int::from_i64(self.ctx.clone(), value);
+                if self.below(2) == 0 {
+                    a.div(&b)
+                } else {
+                    a.modulo(&b)
+                }
+            }
+            _ => self.bool_term(d).ite(&self.int_term(d), &self.int_term(d)),
+        }
+    }
+
+    fn bv_term(&mut self, depth: u32, width: u32) -> ast::BV {
+        if self.leaf(depth) {
+            let choice = self.below(self.num_vars + 1);
+            return match choice.checked_sub(1) {
+                Some(i) => self.bv_var(i, width),
+                None => ast::BV::from_u64(self.ctx.clone(), self.next(), width),
+            };
+        }
+        let d = depth - 1;
+        let a = self.bv_term(d, width);
+        match self.below(10) {
+            0 => a.bvnot(),
+            1 => a.bvneg(),
+            2 => a.bvadd(&self.bv_term(d, width)),
+            3 => a.bvsub(&self.bv_term(d, width)),
+            4 => a.bvmul(&self.bv_term(d, width)),
+            5 => a.bvand(&self.bv_term(d, width)),
+            6 => a.bvor(&self.bv_term(d, width)),
+            7 => a.bvxor(&self.bv_term(d, width)),
+            8 => a.bvshl(&self.bv_term(d, width)),
+            _ => self.bool_term(d).ite(&a, &self.bv_term(d, width)),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Assertions for the test suites of crates that use Z3.
+//!
+//! The macros [`assert_sat!`](crate::assert_sat),
+//! [`assert_unsat!`](crate::assert_unsat) and
+//! [`assert_model_eq!`](crate::assert_model_eq) check a [`Solver`] or a
+//! [`Model`] like [`assert!`] checks a condition, and say what went wrong
+//! when they fail: the reason when a solver gives up, the model when it
+//! finds one where none was expected, and the value a model gives instead
+//! of the expected one. Like [`assert!`], they take an optional message
+//! after their arguments.
+//!
+//! # Examples
+//!
+//! ```
+//! # use std::rc::Rc;
+//! # use z3::{ast, ast::Ast, Config, Context, Solver};
+//! use z3::{assert_model_eq, assert_sat, assert_unsat};
+//!
+//! let cfg = Config::new();
+//! let ctx = Rc::new(Context::new(&cfg));
+//! let x = ast::Int::new_const(ctx.clone(), "x");
+//! let solver = Solver::new(ctx.clone());
+//! solver.assert(&x._eq(&ast::Int::from_i64(ctx.clone(), 3)));
+//!
+//! let model = assert_sat!(solver);
+//! assert_model_eq!(model, x, 3);
+//!
+//! solver.assert(&x.gt(&ast::Int::from_i64(ctx.clone(), 5)));
+//! assert_unsat!(solver, "x cannot be {} and greater than 5", 3);
+//! ```
+
+use std::fmt;
+
+use crate::ast::{Ast, Dynamic};
+use crate::{Model, SatResult, Solver, Z3Value};
+
+pub use crate::{assert_model_eq, assert_sat, assert_unsat};
+
+/// Assert that a [`Solver`](crate::Solver) is satisfiable, and return its
+/// model.
+///
+/// On failure, the panic message gives the result of the check and, if
+/// the solver gave up, the reason.
+///
+/// ```should_panic
+/// # use std::rc::Rc;
+/// # use z3::{ast, Config, Context, Solver};
+/// # let cfg = Config::new();
+/// # let ctx = Rc::new(Context::new(&cfg));
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&ast::Bool::from_bool(ctx.clone(), false));
+/// z3::assert_sat!(solver, "false should be satisfiable");
+/// ```
+#[macro_export]
+macro_rules! assert_sat {
+    ($solver:expr $(,)?) => {
+        $crate::testing::__sat(&$solver, stringify!($solver), None)
+    };
+    ($solver:expr, $($arg:tt)+) => {
+        $crate::testing::__sat(&$solver, stringify!($solver), Some(format_args!($($arg)+)))
+    };
+}
+
+/// Assert that a [`Solver`](crate::Solver) is unsatisfiable.
+///
+/// On failure, the panic message gives the result of the check and the
+/// model or, if the solver gave up, the reason.
+#[macro_export]
+macro_rules! assert_unsat {
+    ($solver:expr $(,)?) => {
+        $crate::testing::__unsat(&$solver, stringify!($solver), None)
+    };
+    ($solver:expr, $($arg:tt)+) => {
+        $crate::testing::__unsat(&$solver, stringify!($solver), Some(format_args!($($arg)+)))
+    };
+}
+
+/// Assert that a [`Model`](crate::Model) gives an AST the value of a Rust
+/// [`Z3Value`](crate::Z3Value), like a `bool`, an integer or a
+/// [`Z3Enum`](crate::Z3Enum).
+///
+/// The AST is evaluated with model completion, so constants the model
+/// does not mention get a default value.
+#[macro_export]
+macro_rules! assert_model_eq {
+    ($model:expr, $ast:expr, $value:expr $(,)?) => {
+        $crate::testing::__model_eq(&$model, &$ast, stringify!($ast), $value, None)
+    };
+    ($model:expr, $ast:expr, $value:expr, $($arg:tt)+) => {
+        $crate::testing::__model_eq(
+            &$model,
+            &$ast,
+            stringify!($ast),
+            $value,
+            Some(format_args!($($arg)+)),
+        )
+    };
+}
+
+/// Panic with `what`, followed by the message given to the macro, if any.
+#[track_caller]
+fn fail(what: fmt::Arguments, msg: Option<fmt::Arguments>) -> ! {
+    match msg {
+        Some(msg) => panic!("{}: {}", what, msg),
+        None => panic!("{}", what),
+    }
+}
+
+fn unknown_reason(solver: &Solver) -> String {
+    solver
+        .get_reason_unknown()
+        .unwrap_or_else(|| "no reason given".to_owned())
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn __sat(solver: &Solver, name: &str, msg: Option<fmt::Arguments>) -> Model {
+    match solver.check() {
+        SatResult::Sat => solver
+            .get_model()
+            .expect("a satisfiable solver has a model"),
+        SatResult::Unsat => fail(format_args!("`{}` is unsat, expected sat", name), msg),
+        SatResult::Unknown => fail(
+            format_args!(
+                "`{}` is unknown ({}), expected sat",
+                name,
+                unknown_reason(solver)
+            ),
+            msg,
+        ),
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn __unsat(solver: &Solver, name: &str, msg: Option<fmt::Arguments>) {
+    match solver.check() {
+        SatResult::Unsat => {}
+        SatResult::Sat => {
+            let model = solver
+                .get_model()
+                .map(|m| m.to_string())
+                .unwrap_or_default();
+            fail(
+                format_args!("`{}` is sat, expected unsat, with model\n{}", name, model),
+                msg,
+            )
+        }
+        SatResult::Unknown => fail(
+            format_args!(
+                "`{}` is unknown ({}), expected unsat",
+                name,
+                unknown_reason(solver)
+            ),
+            msg,
+        ),
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn __model_eq<T>(
+    model: &Model,
+    ast: &dyn Ast,
+    name: &str,
+    expected: T,
+    msg: Option<fmt::Arguments>,
+) where
+    T: Z3Value + PartialEq + fmt::Debug,
+{
+    let value = model.eval(&Dynamic::from_ast(ast), true);
+    match value.as_ref().and_then(T::decode) {
+        Some(found) if found == expected => {}
+        Some(found) => fail(
+            format_args!(
+                "`{}` is {:?} in the model, expected {:?}",
+                name, found, expected
+            ),
+            msg,
+        ),
+        None => fail(
+            format_args!(
+                "`{}` is {} in the model, which is not a value like {:?}",
+                name,
+                value.map_or_else(|| "not evaluated".to_owned(), |v| v.to_string()),
+                expected
+            ),
+            msg,
+        ),
+    }
+}
@@ -0,0 +1,131 @@
+use crate::Rc;
+use crate::{ast, ast::AstNode, Context, Params, Solver, SortKind};
+
+/// Parameter presets for workloads that mix theories in ways Z3's default
+/// configuration handles poorly.
+///
+/// The presets only change heuristics, never which formulas are
+/// satisfiable. They are plain solver parameters, listed by
+/// [`TheoryMix::settings()`], so they can be inspected, and combined with
+/// parameters of your own by setting those afterwards.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver, Sort, TheoryMix};
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let bv8 = Sort::bitvector(ctx.clone(), 8);
+/// let mem = ast::Array::new_const(ctx.clone(), "mem", &bv8, &bv8);
+/// let p = ast::BV::new_const(ctx.clone(), "p", 8);
+/// let f = mem.store(&p, &p).select(&p)._eq(&p.clone().into());
+///
+/// let mix = TheoryMix::detect(&[f.clone()]);
+/// assert_eq!(mix, Some(TheoryMix::BvArray));
+///
+/// let solver = Solver::new(ctx.clone());
+/// mix.unwrap().apply(&solver);
+/// solver.assert(&f.not());
+/// assert_eq!(solver.check(), SatResult::Unsat);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TheoryMix {
+    /// Bitvectors and arrays, as in memory models of programs: select and
+    /// store chains are expanded, and bitvector sums and products are
+    /// normalized, so that more of the memory is resolved by rewriting.
+    BvArray,
+    /// Integers, uninterpreted functions and quantifiers, as in program
+    /// verification: quantified definitions are turned into macros, nested
+    /// quantifiers are pulled up, and model-based instantiation is on.
+    IntUfQuantifiers,
+    /// Floating-point arithmetic, which is bit-blasted: the relevancy
+    /// filter, whose bookkeeping outweighs its gain on the large circuits,
+    /// is off.
+    FloatingPoint,
+    /// Strings and sequences: the sequence solver is used, with theory
+    /// aware branching.
+    Strings,
+}
+
+impl TheoryMix {
+    /// All presets.
+    pub const ALL: [TheoryMix; 4] = [
+        TheoryMix::BvArray,
+        TheoryMix::IntUfQuantifiers,
+        TheoryMix::FloatingPoint,
+        TheoryMix::Strings,
+    ];
+
+    /// The solver parameters of this preset, with their values in SMT-LIB
+    /// syntax.
+    pub fn settings(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            TheoryMix::BvArray => &[("expand_select_store", "true"), ("bv_sort_ac", "true")],
+            TheoryMix::IntUfQuantifiers => &[
+                ("macro_finder", "true"),
+                ("pull_nested_quantifiers", "true"),
+                ("mbqi", "true"),
+            ],
+            TheoryMix::FloatingPoint => &[("relevancy", "0")],
+            TheoryMix::Strings => &[("string_solver", "seq"), ("theory_aware_branching", "true")],
+        }
+    }
+
+    /// The parameters of this preset, to set with
+    /// [`Solver::set_params()`].
+    pub fn params(self, ctx: Rc<Context>) -> Params {
+        let descrs = Solver::new(ctx.clone()).get_param_descrs();
+        let mut params = Params::new(ctx);
+        for (k, v) in self.settings() {
+            let kind = descrs
+                .kind(k)
+                .unwrap_or_else(|| panic!("solver parameter {} of {:?} is unknown", k, self));
+            params
+                .set_parsed(kind, k, v)
+                .unwrap_or_else(|e| panic!("{} in {:?}", e, self));
+        }
+        params
+    }
+
+    /// Configure `solver` with this preset.
+    pub fn apply(self, solver: &Solver) {
+        solver.set_params(&self.params(solver.get_context()));
+    }
+
+    /// The preset for the theories `formulas` use, if one applies.
+    ///
+    /// Strings take precedence over floating-point numbers, which take
+    /// precedence over quantifiers. [`TheoryMix::BvArray`] applies only if
+    /// both bitvectors and arrays occur.
+    pub fn detect(formulas: &[ast::Bool]) -> Option<TheoryMix> {
+        let (mut bv, mut array, mut fp, mut strings, mut quantifiers) =
+            (false, false, false, false, false);
+        for f in formulas {
+            ast::visit(f, |a, node| {
+                match a.sort_kind() {
+                    SortKind::BV => bv = true,
+                    SortKind::Array => array = true,
+                    SortKind::FloatingPoint | SortKind::RoundingMode => fp = true,
+                    SortKind::Seq => strings = true,
+                    _ => {}
+                }
+                if let AstNode::Quantifier { .. } = node {
+                    quantifiers = true;
+                }
+                true
+            });
+        }
+        if strings {
+            Some(TheoryMix::Strings)
+        } else if fp {
+            Some(TheoryMix::FloatingPoint)
+        } else if quantifiers {
+            Some(TheoryMix::IntUfQuantifiers)
+        } else if bv && array {
+            Some(TheoryMix::BvArray)
+        } else {
+            None
+        }
+    }
+}
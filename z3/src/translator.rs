@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use z3_sys::*;
+
+use crate::conversion::c_len;
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, FuncDecl, Sort};
+
+/// Translates sorts, declarations and terms from one [`Context`] into
+/// another, memoizing every translated node by its AST id.
+///
+/// [`Ast::translate()`] hands the whole term to Z3, which forgets what it
+/// has translated as soon as the call returns, so translating many terms
+/// that share sub-terms re-walks the shared parts every time. A
+/// `Translator` remembers each node it has seen, so transcribing a large
+/// problem term by term costs time proportional to the number of distinct
+/// nodes.
+///
+/// The `Translator` keeps the source nodes it has translated alive, so
+/// their ids cannot be reused by unrelated terms while it exists.
+///
+/// # Examples
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast, ast::Ast, Config, Context, SatResult, Solver, Translator};
+/// let cfg = Config::new();
+/// let src = Rc::new(Context::new(&cfg));
+/// let dst = Rc::new(Context::new(&cfg));
+///
+/// let x = ast::Int::new_const(src.clone(), "x");
+/// let shared = &x + &x;
+/// let a = shared._eq(&ast::Int::from_i64(src.clone(), 4));
+/// let b = shared.gt(&ast::Int::from_i64(src.clone(), 0));
+///
+/// let mut translator = Translator::new(src, dst.clone());
+/// let solver = Solver::new(dst);
+/// solver.assert(&translator.translate(&a));
+/// solver.assert(&translator.translate(&b));
+/// assert_eq!(solver.check(), SatResult::Sat);
+/// ```
+#[derive(Debug)]
+pub struct Translator {
+    src: Rc<Context>,
+    dst: Rc<Context>,
+    asts: HashMap<u32, (ast::Dynamic, ast::Dynamic)>,
+    sorts: HashMap<u32, (Sort, Sort)>,
+    decls: HashMap<u32, (FuncDecl, FuncDecl)>,
+}
+
+impl Translator {
+    /// Create a `Translator` from `src` into `dst`.
+    pub fn new(src: Rc<Context>, dst: Rc<Context>) -> Translator {
+        Translator {
+            src,
+            dst,
+            asts: HashMap::new(),
+            sorts: HashMap::new(),
+            decls: HashMap::new(),
+        }
+    }
+
+    /// The [`Context`] this `Translator` translates from.
+    pub fn source(&self) -> Rc<Context> {
+        self.src.clone()
+    }
+
+    /// The [`Context`] this `Translator` translates into.
+    pub fn destination(&self) -> Rc<Context> {
+        self.dst.clone()
+    }
+
+    /// Translate `sort` into the destination context.
+    pub fn translate_sort(&mut self, sort: &Sort) -> Sort {
+        assert_eq!(sort.ctx, self.src);
+        let id = unsafe { Z3_get_sort_id(self.src.z3_ctx, sort.z3_sort) };
+        let dst = self.dst.clone();
+        self.sorts
+            .entry(id)
+            .or_insert_with(|| (sort.clone(), sort.translate(dst)))
+            .1
+            .clone()
+    }
+
+    /// Translate `f` into the destination context.
+    pub fn translate_func_decl(&mut self, f: &FuncDecl) -> FuncDecl {
+        assert_eq!(f.ctx, self.src);
+        let id = unsafe { Z3_get_func_decl_id(self.src.z3_ctx, f.z3_func_decl) };
+        let dst = self.dst.clone();
+        self.decls
+            .entry(id)
+            .or_insert_with(|| (f.clone(), f.translate(dst)))
+            .1
+            .clone()
+    }
+
+    /// Translate `ast` into the destination context.
+    ///
+    /// Function applications are rebuilt node by node from their
+    /// translated declarations and arguments; every other node (constants,
+    /// numerals, quantifiers, ...) is translated by Z3 as a whole.
+    pub fn translate<T: Ast>(&mut self, ast: &T) -> T {
+        assert_eq!(ast.get_ctx(), self.src);
+        let translated = self.translate_raw(ast.get_z3_ast());
+        unsafe { T::wrap(self.dst.clone(), translated.get_z3_ast()) }
+    }
+
+    /// The number of distinct terms translated so far.
+    pub fn num_translated(&self) -> usize {
+        self.asts.len()
+    }
+
+    fn translate_raw(&mut self, root: Z3_ast) -> ast::Dynamic {
+        let src = self.src.z3_ctx;
+        // Post-order walk with an explicit stack, so deep terms cannot
+        // overflow the call stack.
+        let mut stack = vec![(root, false)];
+        while let Some((a, expanded)) = stack.pop() {
+            let id = unsafe { Z3_get_ast_id(src, a) };
+            if self.asts.contains_key(&id) {
+                continue;
+            }
+            let source = unsafe { ast::Dynamic::wrap(self.src.clone(), a) };
+            let num_args = if source.is_app() {
+                source.num_children()
+            } else {
+                0
+            };
+            if num_args == 0 {
+                let translated = source.translate(self.dst.clone());
+                self.asts.insert(id, (source, translated));
+                continue;
+            }
+            let children = source.children();
+            if !expanded {
+                stack.push((a, true));
+                for child in children.iter().rev() {
+                    stack.push((child.get_z3_ast(), false));
+                }
+                continue;
+            }
+            let decl = self.translate_func_decl(&source.decl());
+            let args: Vec<Z3_ast> = children
+                .iter()
+                .map(|c| {
+                    let id = unsafe { Z3_get_ast_id(src, c.get_z3_ast()) };
+                    self.asts[&id].1.get_z3_ast()
+                })
+                .collect();
+            let translated = unsafe {
+                let app = Z3_mk_app(
+                    self.dst.z3_ctx,
+                    decl.z3_func_decl,
+                    c_len(args.len()),
+                    args.as_ptr(),
+                );
+                ast::Dynamic::wrap(self.dst.clone(), app)
+            };
+            self.asts.insert(id, (source, translated));
+        }
+        let id = unsafe { Z3_get_ast_id(src, root) };
+        self.asts[&id].1.clone()
+    }
+}
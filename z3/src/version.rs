@@ -0,0 +1,127 @@
+use std::ffi::CStr;
+use std::fmt;
+
+use z3_sys::*;
+
+/// The version of the Z3 library this crate is running against.
+///
+/// `Version`s are ordered, so they can be compared against the release
+/// that introduced an API:
+///
+/// ```
+/// # use z3::Version;
+/// if z3::version() >= Version::new(4, 8, 0, 0) {
+///     // ...
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub build: u32,
+    pub revision: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, build: u32, revision: u32) -> Version {
+        Version {
+            major,
+            minor,
+            build,
+            revision,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.major, self.minor, self.build, self.revision
+        )
+    }
+}
+
+/// Return the version of the Z3 library this crate is running against.
+///
+/// # See also:
+///
+/// - [`full_version()`]
+/// - [`features()`]
+pub fn version() -> Version {
+    let mut v = Version::new(0, 0, 0, 0);
+    unsafe { Z3_get_version(&mut v.major, &mut v.minor, &mut v.build, &mut v.revision) };
+    v
+}
+
+/// Return a string describing the version of the Z3 library this crate is
+/// running against, including the git hash it was built from if known.
+///
+/// # See also:
+///
+/// - [`version()`]
+pub fn full_version() -> String {
+    unsafe { CStr::from_ptr(Z3_get_full_version()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Which of the APIs added in recent Z3 releases are available in the Z3
+/// library this crate is running against.
+///
+/// Downstream crates can use this to fall back to older ways of doing
+/// things instead of failing when linked against an older Z3.
+///
+/// # See also:
+///
+/// - [`features()`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Features {
+    /// Simplifier objects (`Z3_mk_simplifier` and friends), added in Z3
+    /// 4.12.0.
+    pub simplifier: bool,
+    /// The clause inference callback (`Z3_solver_register_on_clause`),
+    /// added in Z3 4.12.0.
+    pub on_clause: bool,
+    /// Solving for variables (`Z3_solver_solve_for`), added in Z3 4.13.1.
+    pub solve_for: bool,
+    /// Folds and maps over sequences (`Z3_mk_seq_foldl` and friends),
+    /// added in Z3 4.12.0.
+    pub seq_fold: bool,
+    /// The user propagator callbacks that refer to registered terms by
+    /// number, which `Solver::propagator()` binds with the
+    /// `user-propagator` feature: those of Z3 4.8.10 to 4.8.12. Later
+    /// releases pass the terms themselves, with different signatures.
+    pub user_propagator: bool,
+}
+
+impl Features {
+    /// The features available in Z3 `version`.
+    pub fn of(version: Version) -> Features {
+        Features {
+            simplifier: version >= Version::new(4, 12, 0, 0),
+            on_clause: version >= Version::new(4, 12, 0, 0),
+            solve_for: version >= Version::new(4, 13, 1, 0),
+            seq_fold: version >= Version::new(4, 12, 0, 0),
+            user_propagator: version >= Version::new(4, 8, 10, 0)
+                && version < Version::new(4, 8, 13, 0),
+        }
+    }
+}
+
+/// Report which of the APIs added in recent Z3 releases are available in
+/// the Z3 library this crate is running against.
+///
+/// # Examples
+///
+/// ```
+/// let features = z3::features();
+/// if !features.seq_fold {
+///     // Unroll folds by hand instead.
+/// }
+/// ```
+pub fn features() -> Features {
+    Features::of(version())
+}
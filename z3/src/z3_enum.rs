@@ -0,0 +1,137 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, FuncDecl, Model, Sort, Symbol};
+
+/// A fieldless Rust enum that can be modelled by an enumeration sort.
+///
+/// With the `derive` feature this trait can be derived, which names the sort
+/// after the enum and its constants after the variants:
+///
+/// ```
+/// # use std::rc::Rc;
+/// # use z3::{ast::Ast, Config, Context, SatResult, Solver, Z3Enum};
+/// #[derive(Debug, PartialEq)]
+/// # #[cfg_attr(feature = "derive", derive(Z3Enum))]
+/// enum Light {
+///     Red,
+///     Yellow,
+///     Green,
+/// }
+/// # #[cfg(not(feature = "derive"))]
+/// # impl Z3Enum for Light {
+/// #     const NAME: &'static str = "Light";
+/// #     const VARIANT_NAMES: &'static [&'static str] = &["Red", "Yellow", "Green"];
+/// #     fn to_index(&self) -> usize {
+/// #         match self { Light::Red => 0, Light::Yellow => 1, Light::Green => 2 }
+/// #     }
+/// #     fn from_index(index: usize) -> Option<Self> {
+/// #         match index {
+/// #             0 => Some(Light::Red),
+/// #             1 => Some(Light::Yellow),
+/// #             2 => Some(Light::Green),
+/// #             _ => None,
+/// #         }
+/// #     }
+/// # }
+///
+/// let cfg = Config::new();
+/// let ctx = Rc::new(Context::new(&cfg));
+/// let light = Light::enum_sort(ctx.clone());
+/// let now = light.new_const("now");
+///
+/// let solver = Solver::new(ctx.clone());
+/// solver.assert(&now._eq(&light.value(&Light::Red)).not());
+/// solver.assert(&light.tester(&Light::Green, &now).not());
+/// assert_eq!(solver.check(), SatResult::Sat);
+///
+/// let model = solver.get_model().unwrap();
+/// assert_eq!(light.from_model(&model, &now), Some(Light::Yellow));
+/// ```
+pub trait Z3Enum: Sized {
+    /// The name of the enumeration sort.
+    const NAME: &'static str;
+    /// The names of the constants, in the order of the variants.
+    const VARIANT_NAMES: &'static [&'static str];
+
+    /// The position of `self` in [`Self::VARIANT_NAMES`](Z3Enum::VARIANT_NAMES).
+    fn to_index(&self) -> usize;
+
+    /// The variant at position `index`, or `None` if there is none.
+    fn from_index(index: usize) -> Option<Self>;
+
+    /// Declare the enumeration sort of this type.
+    fn enum_sort(ctx: Rc<Context>) -> EnumSort<Self> {
+        EnumSort::new(ctx)
+    }
+}
+
+/// The enumeration sort declared for a [`Z3Enum`], together with its
+/// constants and testers.
+pub struct EnumSort<T> {
+    pub sort: Sort,
+    pub consts: Vec<FuncDecl>,
+    pub testers: Vec<FuncDecl>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Z3Enum> EnumSort<T> {
+    /// Declare the enumeration sort of `T`.
+    pub fn new(ctx: Rc<Context>) -> Self {
+        let names: Vec<Symbol> = T::VARIANT_NAMES.iter().map(|&n| n.into()).collect();
+        let (sort, consts, testers) = Sort::enumeration(ctx, T::NAME.into(), &names);
+        EnumSort {
+            sort,
+            consts,
+            testers,
+            marker: PhantomData,
+        }
+    }
+
+    /// Create a constant of this sort.
+    pub fn new_const<S: Into<Symbol>>(&self, name: S) -> ast::Datatype {
+        ast::Datatype::new_const(self.sort.ctx.clone(), name, &self.sort)
+    }
+
+    /// The Z3 value of `value`.
+    pub fn value(&self, value: &T) -> ast::Datatype {
+        self.consts[value.to_index()]
+            .apply(&[])
+            .as_datatype()
+            .unwrap()
+    }
+
+    /// A [`ast::Bool`] which is true exactly when `x` is `value`.
+    pub fn tester(&self, value: &T, x: &ast::Datatype) -> ast::Bool {
+        self.testers[value.to_index()]
+            .apply(&[x])
+            .as_bool()
+            .unwrap()
+    }
+
+    /// The Rust value of a constant of this sort, or `None` if `x` is not
+    /// one of the constants.
+    pub fn decode(&self, x: &ast::Datatype) -> Option<T> {
+        if !x.is_app() {
+            return None;
+        }
+        let decl = x.decl();
+        let index = self.consts.iter().position(|c| *c == decl)?;
+        T::from_index(index)
+    }
+
+    /// The Rust value of `x` in `model`, or `None` if it cannot be decoded.
+    pub fn from_model(&self, model: &Model, x: &ast::Datatype) -> Option<T> {
+        self.decode(&model.eval(x, true)?)
+    }
+}
+
+impl<T> fmt::Debug for EnumSort<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EnumSort")
+            .field("sort", &self.sort)
+            .field("consts", &self.consts)
+            .finish()
+    }
+}
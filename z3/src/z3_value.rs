@@ -0,0 +1,114 @@
+use std::convert::TryFrom;
+
+use crate::Rc;
+use crate::{ast, ast::Ast, Context, Model, Sort, Z3Enum};
+
+/// A Rust type whose values are modelled by the values of one Z3 sort.
+///
+/// It is implemented for `bool`, which is modelled by [`ast::Bool`], for
+/// the primitive integer types, which are modelled by unbounded
+/// [`ast::Int`]s, and for every [`Z3Enum`]. With the `derive` feature,
+/// `#[derive(Z3Struct)]` implements it for structs, so that they can be
+/// nested.
+pub trait Z3Value: Sized {
+    /// The type of symbolic values of this type.
+    type Symbolic: Ast;
+
+    /// The sort of the values of this type.
+    fn sort(ctx: Rc<Context>) -> Sort;
+
+    /// View `ast`, which must be of [`Self::sort()`](Z3Value::sort), as a
+    /// symbolic value.
+    fn symbolic(ast: ast::Dynamic) -> Self::Symbolic;
+
+    /// The Z3 value of `self`.
+    fn to_ast(&self, ctx: Rc<Context>) -> ast::Dynamic;
+
+    /// The Rust value of a Z3 value, such as one obtained from
+    /// [`Model::eval()`], or `None` if `value` is not a value of this
+    /// type.
+    fn decode(value: &ast::Dynamic) -> Option<Self>;
+
+    /// The Rust value of `ast` in `model`, or `None` if it cannot be
+    /// decoded.
+    fn from_model(model: &Model, ast: &dyn Ast) -> Option<Self> {
+        Self::decode(&model.eval(&ast::Dynamic::from_ast(ast), true)?)
+    }
+}
+
+impl Z3Value for bool {
+    type Symbolic = ast::Bool;
+
+    fn sort(ctx: Rc<Context>) -> Sort {
+        Sort::bool(ctx)
+    }
+
+    fn symbolic(ast: ast::Dynamic) -> ast::Bool {
+        ast.as_bool().unwrap()
+    }
+
+    fn to_ast(&self, ctx: Rc<Context>) -> ast::Dynamic {
+        ast::Bool::from_bool(ctx, *self).into()
+    }
+
+    fn decode(value: &ast::Dynamic) -> Option<bool> {
+        value.as_bool()?.as_bool()
+    }
+}
+
+macro_rules! impl_z3_value_int {
+    ($($t:ty, $from:ident, $as:ident;)*) => {
+        $(
+            impl Z3Value for $t {
+                type Symbolic = ast::Int;
+
+                fn sort(ctx: Rc<Context>) -> Sort {
+                    Sort::int(ctx)
+                }
+
+                fn symbolic(ast: ast::Dynamic) -> ast::Int {
+                    ast.as_int().unwrap()
+                }
+
+                fn to_ast(&self, ctx: Rc<Context>) -> ast::Dynamic {
+                    ast::Int::$from(ctx, (*self).into()).into()
+                }
+
+                fn decode(value: &ast::Dynamic) -> Option<$t> {
+                    <$t>::try_from(value.as_int()?.$as()?).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_z3_value_int! {
+    i8, from_i64, as_i64;
+    i16, from_i64, as_i64;
+    i32, from_i64, as_i64;
+    i64, from_i64, as_i64;
+    u8, from_u64, as_u64;
+    u16, from_u64, as_u64;
+    u32, from_u64, as_u64;
+    u64, from_u64, as_u64;
+}
+
+impl<T: Z3Enum> Z3Value for T {
+    type Symbolic = ast::Datatype;
+
+    fn sort(ctx: Rc<Context>) -> Sort {
+        T::enum_sort(ctx).sort
+    }
+
+    fn symbolic(ast: ast::Dynamic) -> ast::Datatype {
+        ast.as_datatype().unwrap()
+    }
+
+    fn to_ast(&self, ctx: Rc<Context>) -> ast::Dynamic {
+        T::enum_sort(ctx).value(self).into()
+    }
+
+    fn decode(value: &ast::Dynamic) -> Option<T> {
+        T::enum_sort(value.get_ctx()).decode(&value.as_datatype()?)
+    }
+}
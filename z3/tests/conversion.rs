@@ -0,0 +1,25 @@
+// The conversion policy is crate-wide, so this test runs in a process of
+// its own, where it cannot affect the other tests.
+
+use std::panic;
+use std::rc::Rc;
+use std::time::Duration;
+
+use z3::*;
+
+#[test]
+fn test_conversion_policy() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let too_long = Duration::from_millis(u64::from(u32::MAX) + 1);
+
+    assert_eq!(conversion_policy(), ConversionPolicy::Saturate);
+    Tactic::new(ctx.clone(), "simplify").try_for(too_long);
+
+    set_conversion_policy(ConversionPolicy::Panic);
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        Tactic::new(ctx.clone(), "simplify").try_for(too_long);
+    }));
+    set_conversion_policy(ConversionPolicy::Saturate);
+    assert!(result.is_err());
+}
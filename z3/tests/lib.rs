@@ -1,8 +1,9 @@
 use log::info;
 use std::convert::TryInto;
 use std::ops::Add;
+use std::rc::Rc;
 use std::time::Duration;
-use z3::ast::{Array, Ast, Bool, Int, BV};
+use z3::ast::{Array, Ast, Bool, Dynamic, Int, BV};
 use z3::*;
 
 use num::{bigint::BigInt, rational::BigRational};
@@ -124,9 +125,9 @@ fn test_cloning_ast() {
     assert_eq!(yv, 0);
 }
 
-fn get_some_solver_assertions(ctx: &Context) -> Vec<ast::Bool> {
-    let s = Solver::new(ctx);
-    let x = ast::Int::new_const(ctx, "x");
+fn get_some_solver_assertions(ctx: Rc<Context>) -> Vec<ast::Bool> {
+    let s = Solver::new(ctx.clone());
+    let x = ast::Int::new_const(ctx.clone(), "x");
     let y = ast::Int::new_const(ctx, "y");
     s.assert(&x.gt(&y));
     s.get_assertions()
@@ -229,16 +230,16 @@ fn test_floating_point_bits() {
 fn test_ast_translate() {
     let cfg = Config::new();
     let source = Rc::new(Context::new(&cfg));
-    let a = ast::Int::new_const(&source, "a");
+    let a = ast::Int::new_const(source.clone(), "a");
 
     let destination = Rc::new(Context::new(&cfg));
-    let translated_a = a.translate(&destination);
+    let translated_a = a.translate(destination.clone());
 
-    let slv = Solver::new(&destination);
-    slv.assert(&translated_a._eq(&ast::Int::from_u64(&destination, 2)));
+    let slv = Solver::new(destination.clone());
+    slv.assert(&translated_a._eq(&ast::Int::from_u64(destination.clone(), 2)));
     assert_eq!(slv.check(), SatResult::Sat);
 
-    slv.assert(&translated_a._eq(&ast::Int::from_u64(&destination, 3)));
+    slv.assert(&translated_a._eq(&ast::Int::from_u64(destination.clone(), 3)));
     assert_eq!(slv.check(), SatResult::Unsat);
 }
 
@@ -276,18 +277,18 @@ fn test_solver_to_smtlib2() {
 fn test_solver_translate() {
     let cfg = Config::new();
     let source = Rc::new(Context::new(&cfg));
-    let a = ast::Int::new_const(&source, "a");
+    let a = ast::Int::new_const(source.clone(), "a");
 
     let destination = Rc::new(Context::new(&cfg));
-    let translated_a = a.translate(&destination);
+    let translated_a = a.translate(destination.clone());
 
-    let slv = Solver::new(&destination);
-    slv.assert(&translated_a._eq(&ast::Int::from_u64(&destination, 2)));
+    let slv = Solver::new(destination.clone());
+    slv.assert(&translated_a._eq(&ast::Int::from_u64(destination.clone(), 2)));
     assert_eq!(slv.check(), SatResult::Sat);
 
-    let translated_slv = slv.translate(&source);
+    let translated_slv = slv.translate(source.clone());
     // Add a new constraint, make the old one unsatisfiable, while the copy remains satisfiable.
-    slv.assert(&translated_a._eq(&ast::Int::from_u64(&destination, 3)));
+    slv.assert(&translated_a._eq(&ast::Int::from_u64(destination.clone(), 3)));
     assert_eq!(slv.check(), SatResult::Unsat);
     assert_eq!(translated_slv.check(), SatResult::Sat);
 }
@@ -296,18 +297,18 @@ fn test_solver_translate() {
 fn test_model_translate() {
     let cfg = Config::new();
     let source = Rc::new(Context::new(&cfg));
-    let a = ast::Int::new_const(&source, "a");
+    let a = ast::Int::new_const(source.clone(), "a");
 
     let destination = Rc::new(Context::new(&cfg));
-    let translated_a = a.translate(&destination);
+    let translated_a = a.translate(destination.clone());
 
-    let slv = Solver::new(&source);
-    slv.assert(&a._eq(&ast::Int::from_u64(&source, 2)));
+    let slv = Solver::new(source.clone());
+    slv.assert(&a._eq(&ast::Int::from_u64(source.clone(), 2)));
     assert_eq!(slv.check(), SatResult::Sat);
 
     let model = slv.get_model().unwrap();
     assert_eq!(2, model.eval(&a, true).unwrap().as_i64().unwrap());
-    let translated_model = model.translate(&destination);
+    let translated_model = model.translate(destination.clone());
     assert_eq!(
         2,
         translated_model
@@ -1403,15 +1404,15 @@ fn test_goal_apply_tactic() {
     let ctx = Rc::new(Context::new(&cfg));
 
     pub fn test_apply_tactic(
-        ctx: &Context,
+        ctx: Rc<Context>,
         goal: Goal,
         before_formulas: Vec<Bool>,
         after_formulas: Vec<Bool>,
     ) {
         assert_eq!(goal.get_formulas::<Bool>(), before_formulas);
-        let params = Params::new(ctx);
+        let params = Params::new(ctx.clone());
 
-        let tactic = Tactic::new(ctx, "sat-preprocess");
+        let tactic = Tactic::new(ctx.clone(), "sat-preprocess");
         let repeat_tactic = Tactic::repeat(ctx, &tactic, 100);
         let apply_results = repeat_tactic.apply(&goal, Some(&params));
         let goal_results = apply_results
@@ -1505,7 +1506,7 @@ fn test_tactic_conditions() {
 fn test_probe_debug() {
     let cfg = Config::new();
     let ctx = Rc::new(Context::new(&cfg));
-    let _v: Vec<&str> = Probe::list_all(ctx).map(|x| x.unwrap()).collect();
+    let _v: Vec<&str> = Probe::list_all(&ctx).map(|x| x.unwrap()).collect();
     assert_eq!(
         "A probe to give an upper bound of Ackermann congruence lemmas that a formula might generate.",
         Probe::describe(ctx.clone(), "ackr-bound-probe").unwrap(),
@@ -1656,13 +1657,13 @@ fn test_issue_94() {
 fn test_ast_safe_eq() {
     let cfg = Config::new();
     let ctx = Rc::new(Context::new(&cfg));
-    let x: ast::Dynamic = ast::Bool::new_const(ctx, "a").into();
-    let y: ast::Dynamic = ast::String::from_str(ctx, "b").unwrap().into();
+    let x: ast::Dynamic = ast::Bool::new_const(ctx.clone(), "a").into();
+    let y: ast::Dynamic = ast::String::from_str(ctx.clone(), "b").unwrap().into();
 
-    let other_bool: ast::Dynamic = ast::Bool::new_const(ctx, "c").into();
-    let other_string: ast::Dynamic = ast::String::from_str(ctx, "d").unwrap().into();
+    let other_bool: ast::Dynamic = ast::Bool::new_const(ctx.clone(), "c").into();
+    let other_string: ast::Dynamic = ast::String::from_str(ctx.clone(), "d").unwrap().into();
 
-    let sd: SortDiffers<'_> = SortDiffers::new(other_bool.get_sort(), other_string.get_sort());
+    let sd: SortDiffers = SortDiffers::new(other_bool.get_sort(), other_string.get_sort());
 
     let result = x._safe_eq(&y);
     assert!(result.is_err());
@@ -1675,15 +1676,20 @@ fn test_ast_safe_eq() {
 fn test_ast_safe_decl() {
     let cfg = Config::new();
     let ctx = Rc::new(Context::new(&cfg));
-    let x: ast::Bool = ast::Bool::new_const(ctx, "x");
+    let x: ast::Bool = ast::Bool::new_const(ctx.clone(), "x");
     let x_not = x.not();
     assert_eq!(x_not.safe_decl().unwrap().kind(), DeclKind::NOT);
 
-    let f = FuncDecl::new(ctx, "f", &[&Sort::int(ctx)], &Sort::int(ctx));
-    let x = ast::Int::new_const(ctx, "x");
+    let f = FuncDecl::new(
+        ctx.clone(),
+        "f",
+        &[&Sort::int(ctx.clone())],
+        &Sort::int(ctx.clone()),
+    );
+    let x = ast::Int::new_const(ctx.clone(), "x");
     let f_x: ast::Int = f.apply(&[&x]).try_into().unwrap();
-    let f_x_pattern: Pattern = Pattern::new(ctx, &[&f_x]);
-    let forall = ast::forall_const(ctx, &[&x], &[&f_x_pattern], &x._eq(&f_x));
+    let f_x_pattern: Pattern = Pattern::new(ctx.clone(), &[&f_x]);
+    let forall = ast::forall_const(ctx.clone(), &[&x], &[&f_x_pattern], &x._eq(&f_x));
     assert!(forall.safe_decl().is_err());
     assert_eq!(
         format!("{}", forall.safe_decl().err().unwrap()),
@@ -1696,20 +1702,20 @@ fn test_ast_safe_decl() {
 fn test_regex_capital_foobar_intersect_az_plus_is_unsat() {
     let cfg = Config::new();
     let ctx = Rc::new(Context::new(&cfg));
-    let solver = Solver::new(ctx);
-    let s = ast::String::new_const(ctx, "s");
+    let solver = Solver::new(ctx.clone());
+    let s = ast::String::new_const(ctx.clone(), "s");
 
     let re = ast::Regexp::intersect(
-        ctx,
+        ctx.clone(),
         &[
             &ast::Regexp::concat(
-                ctx,
+                ctx.clone(),
                 &[
-                    &ast::Regexp::literal(ctx, "FOO"),
-                    &ast::Regexp::literal(ctx, "bar"),
+                    &ast::Regexp::literal(ctx.clone(), "FOO"),
+                    &ast::Regexp::literal(ctx.clone(), "bar"),
                 ],
             ),
-            &ast::Regexp::plus(&ast::Regexp::range(ctx, &'a', &'z')),
+            &ast::Regexp::plus(&ast::Regexp::range(ctx.clone(), &'a', &'z')),
         ],
     );
     solver.assert(&s.regex_matches(&re));
@@ -1722,23 +1728,33 @@ fn test_array_example1() {
     let cfg = Config::new();
     let ctx = Rc::new(Context::new(&cfg));
 
-    let g = Goal::new(ctx, true, false, false);
+    let g = Goal::new(ctx.clone(), true, false, false);
 
-    let aex = Array::new_const(ctx, "MyArray", &Sort::int(ctx), &Sort::bitvector(ctx, 32));
+    let aex = Array::new_const(
+        ctx.clone(),
+        "MyArray",
+        &Sort::int(ctx.clone()),
+        &Sort::bitvector(ctx.clone(), 32),
+    );
 
-    let sel = aex.select(&Int::from_u64(ctx, 0));
+    let sel = aex.select(&Int::from_u64(ctx.clone(), 0));
 
-    g.assert(&sel._eq(&BV::from_u64(ctx, 42, 32).into()));
+    g.assert(&sel._eq(&BV::from_u64(ctx.clone(), 42, 32).into()));
 
-    let xc = Int::new_const(ctx, "x");
+    let xc = Int::new_const(ctx.clone(), "x");
 
-    let fd = FuncDecl::new(ctx, "f", &[&Sort::int(ctx)], &Sort::int(ctx));
+    let fd = FuncDecl::new(
+        ctx.clone(),
+        "f",
+        &[&Sort::int(ctx.clone())],
+        &Sort::int(ctx.clone()),
+    );
 
     let fapp = fd.apply(&[&xc as &dyn Ast]);
 
-    g.assert(&Int::from_u64(ctx, 123)._eq(&xc.clone().add(&fapp.as_int().unwrap())));
+    g.assert(&Int::from_u64(ctx.clone(), 123)._eq(&xc.clone().add(&fapp.as_int().unwrap())));
 
-    let s = &Solver::new(ctx);
+    let s = &Solver::new(ctx.clone());
     for a in g.get_formulas() {
         s.assert(&a);
     }
@@ -1857,7 +1873,10 @@ fn iterate_all_solutions() {
             .collect::<Vec<_>>()
             .join(", ");
         solutions.insert(format!("[{this_solution}]"));
-        solver.assert(&Bool::or(ctx, &modifications.iter().collect::<Vec<_>>()));
+        solver.assert(&Bool::or(
+            ctx.clone(),
+            &modifications.iter().collect::<Vec<_>>(),
+        ));
     }
 
     assert!(
@@ -1874,3 +1893,4469 @@ fn iterate_all_solutions() {
             .collect()
     );
 }
+
+#[test]
+fn test_interner_materializes_in_each_context() {
+    let mut interner = Interner::new();
+    interner.declare("x", &[], SortSignature::Int);
+    interner.declare(
+        "f",
+        &[SortSignature::Int, SortSignature::BV(8)],
+        SortSignature::Bool,
+    );
+    assert!(interner.signature("g").is_none());
+
+    let cfg = Config::new();
+    for _ in 0..2 {
+        let ctx = Rc::new(Context::new(&cfg));
+        let x = interner.get_const(ctx.clone(), "x").unwrap();
+        assert_eq!(x.sort_kind(), SortKind::Int);
+        assert!(interner.get_const(ctx.clone(), "f").is_none());
+
+        let f = interner.get_func_decl(ctx.clone(), "f").unwrap();
+        assert_eq!(f.arity(), 2);
+        let b = BV::from_u64(ctx.clone(), 7, 8);
+        let app = interner.apply("f", &[&x, &b]).unwrap();
+        assert_eq!(app.decl().name(), "f");
+
+        let solver = Solver::new(ctx.clone());
+        solver.assert(&app.as_bool().unwrap());
+        solver.assert(&f.apply(&[&x, &b]).as_bool().unwrap().not());
+        assert_eq!(solver.check(), SatResult::Unsat);
+    }
+}
+
+#[test]
+fn test_goal_and_apply_result_clones_are_independent() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let goal = Goal::new(ctx.clone(), false, false, false);
+    goal.assert(&x.gt(&ast::Int::from_i64(ctx.clone(), 0)));
+
+    let cloned = goal.clone();
+    drop(goal);
+    assert_eq!(cloned.get_size(), 1);
+
+    let result = Tactic::new(ctx.clone(), "simplify")
+        .apply(&cloned, None)
+        .unwrap();
+    let result_clone = result.clone();
+    drop(result);
+    assert_eq!(result_clone.list_subgoals().count(), 1);
+}
+
+#[test]
+fn test_context_handle_outliving_context() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let handle = Context::handle(ctx.clone());
+    assert!(handle.is_alive());
+
+    // A leaked child keeps its context alive instead of dangling.
+    std::mem::forget(ast::Int::new_const(ctx.clone(), "leaked"));
+    #[cfg(feature = "debug")]
+    assert!(z3::debug::live_objects_in(&ctx)
+        .iter()
+        .any(|l| l.type_name == "ast::Int" && l.count == 1));
+    let weak = Rc::downgrade(&ctx);
+    drop(ctx);
+    assert!(weak.upgrade().is_some());
+    assert!(handle.is_alive());
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let handle = Context::handle(ctx.clone());
+    drop(ctx);
+    assert!(!handle.is_alive());
+    std::thread::spawn(move || handle.interrupt())
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn test_model_eval_many() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let solver = Solver::new(ctx.clone());
+    let vars: Vec<Dynamic> = (0..50)
+        .map(|i| Dynamic::from_ast(&ast::Int::new_const(ctx.clone(), format!("v{}", i))))
+        .collect();
+    for (i, v) in vars.iter().enumerate() {
+        let v = v.as_int().unwrap();
+        solver.assert(&v._eq(&ast::Int::from_i64(ctx.clone(), i as i64)));
+    }
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+
+    let values = model.eval_many(&vars, true);
+    assert_eq!(values.len(), vars.len());
+    for (i, value) in values.into_iter().enumerate() {
+        assert_eq!(value.unwrap().as_int().unwrap().as_i64(), Some(i as i64));
+    }
+}
+
+#[test]
+fn test_conversion_errors() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    // Fallible constructors reject interior NULs.
+    assert!(ast::Int::from_str(ctx.clone(), "1\0").is_none());
+    assert!(ast::BV::from_str(ctx.clone(), 8, "1\0").is_none());
+    assert!(ast::String::from_str(ctx.clone(), "1\0").is_err());
+
+    let int = Sort::int(ctx.clone());
+    assert_eq!(
+        ast::Dynamic::try_new_const(ctx.clone(), "x\0y", &int).unwrap_err(),
+        ConversionError::Nul(1)
+    );
+    assert!(ast::Dynamic::try_fresh_const(ctx.clone(), "x\0", &int).is_err());
+    assert!(FuncDecl::try_new(ctx.clone(), "f\0", &[&int], &int).is_err());
+    assert!(ast::Regexp::try_literal(ctx.clone(), "a\0b").is_err());
+
+    let x = ast::Dynamic::try_new_const(ctx.clone(), "x", &int).unwrap();
+    assert_eq!(x.to_string(), "x");
+}
+
+#[test]
+#[should_panic(expected = "string contains a NUL byte at 1")]
+fn test_conversion_panics_on_nul() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    ast::Int::new_const(ctx, "x\0y");
+}
+
+#[test]
+fn test_translator_memoizes_shared_terms() {
+    let cfg = Config::new();
+    let src = Rc::new(Context::new(&cfg));
+    let dst = Rc::new(Context::new(&cfg));
+
+    let x = ast::Int::new_const(src.clone(), "x");
+    let bv = ast::BV::new_const(src.clone(), "b", 16);
+    let arr = ast::Array::new_const(
+        src.clone(),
+        "a",
+        &Sort::int(src.clone()),
+        &Sort::int(src.clone()),
+    );
+    let shared = &x + &ast::Int::from_i64(src.clone(), 1);
+    let terms = vec![
+        shared._eq(&arr.select(&shared).as_int().unwrap()),
+        shared.gt(&x),
+        bv.extract(7, 0)._eq(&ast::BV::from_u64(src.clone(), 3, 8)),
+        ast::forall_const(src.clone(), &[&x], &[], &x.ge(&x)),
+    ];
+
+    let mut translator = Translator::new(src.clone(), dst.clone());
+    let solver = Solver::new(dst.clone());
+    for t in &terms {
+        let translated = translator.translate(t);
+        assert_eq!(translated.get_ctx(), dst);
+        assert_eq!(translated.to_string(), t.translate(dst.clone()).to_string());
+        solver.assert(&translated);
+    }
+    let seen = translator.num_translated();
+    for t in &terms {
+        translator.translate(t);
+    }
+    assert_eq!(translator.num_translated(), seen);
+    assert_eq!(solver.check(), SatResult::Sat);
+
+    let f = FuncDecl::new(
+        src.clone(),
+        "f",
+        &[&Sort::int(src.clone())],
+        &Sort::bool(src.clone()),
+    );
+    let f_dst = translator.translate_func_decl(&f);
+    assert_eq!(f_dst.name(), "f");
+    let s_dst = translator.translate_sort(&Sort::bitvector(src, 16));
+    assert_eq!(s_dst, Sort::bitvector(dst, 16));
+}
+
+#[test]
+fn test_parse_error_location_and_lenient_mode() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let source = "(declare-const x Int)\n\
+                  (assert (> x 1))\n\
+                  (assert (> y 2)) ; y is not declared\n\
+                  (assert (< x \n\
+                  (assert (< x 5))";
+
+    let err = Parser::new(ctx.clone()).parse_string(source).unwrap_err();
+    assert_eq!(err.line(), Some(3));
+    assert_eq!(err.column(), Some(11));
+    assert_eq!(err.message(), "unknown constant y");
+    assert_eq!(err.to_string(), "line 3 column 11: unknown constant y");
+
+    // The unterminated fourth command swallows the fifth one.
+    let parsed = Parser::new(ctx.clone())
+        .lenient(true)
+        .parse_string(source)
+        .unwrap();
+    assert_eq!(parsed.assertions.len(), 1);
+    assert_eq!(parsed.errors.len(), 2);
+    assert_eq!(parsed.errors[0], err);
+    assert_eq!(parsed.errors[1].line(), Some(5));
+
+    // Commands that use a skipped declaration are skipped too.
+    let parsed = Parser::new(ctx.clone())
+        .lenient(true)
+        .parse_string(
+            "(declare-const x Foo)\n\
+             (assert (> x 1))\n\
+             (declare-const y Int)\n\
+             (assert (> y 0))",
+        )
+        .unwrap();
+    assert_eq!(parsed.assertions.len(), 1);
+    let lines: Vec<_> = parsed.errors.iter().map(|e| e.line()).collect();
+    assert_eq!(lines, [Some(1), Some(2)]);
+
+    let parsed = Parser::new(ctx)
+        .parse_string("(declare-const s String)(assert (= s \"(\"))")
+        .unwrap();
+    assert!(parsed.errors.is_empty());
+    assert_eq!(parsed.assertions.len(), 1);
+}
+
+#[test]
+fn test_parser_symbol_tables() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let parsed = Parser::new(ctx.clone())
+        .parse_string(
+            "(declare-sort U 0)\n\
+             (declare-datatypes ((Color 0)) (((red) (green))))\n\
+             (declare-fun f (U) Int)\n\
+             (declare-const c U)\n\
+             (declare-const k Color)\n\
+             (declare-const unused Int)\n\
+             (declare-sort V 0)\n\
+             (declare-fun |g h| (Int U) Bool)\n\
+             (push)\n\
+             (declare-const gone Int)\n\
+             (pop)\n\
+             (assert (forall ((u U)) (> (f u) 0)))\n\
+             (assert (= (f c) 1))\n\
+             (assert (= k red))",
+        )
+        .unwrap();
+    // Declarations are listed whether they are used or not, unless they
+    // are out of scope.
+    let mut names: Vec<_> = parsed.decls.keys().cloned().collect();
+    names.sort();
+    assert_eq!(names, ["c", "f", "g h", "k", "unused"]);
+    assert_eq!(parsed.decls["g h"].arity(), 2);
+    let mut sorts: Vec<_> = parsed.sorts.keys().cloned().collect();
+    sorts.sort();
+    assert_eq!(sorts, ["Color", "U", "V"]);
+
+    // Terms built from the returned declarations are the parsed ones.
+    let f = &parsed.decls["f"];
+    let c = parsed.decls["c"].apply(&[]);
+    let fc = f.apply(&[&c]).as_int().unwrap();
+    assert_eq!(
+        parsed.assertions[1],
+        fc._eq(&ast::Int::from_i64(ctx.clone(), 1))
+    );
+
+    let solver = Solver::new(ctx.clone());
+    for a in &parsed.assertions {
+        solver.assert(a);
+    }
+    solver.assert(&fc._eq(&ast::Int::from_i64(ctx, 2)));
+    assert_eq!(solver.check(), SatResult::Unsat);
+}
+
+#[test]
+fn test_version_and_features() {
+    let v = z3::version();
+    assert!(v >= Version::new(4, 8, 0, 0));
+    assert!(z3::full_version().contains(&format!("{}.{}.{}", v.major, v.minor, v.build)));
+
+    assert_eq!(z3::features(), Features::of(v));
+    let old = Features::of(Version::new(4, 8, 12, 0));
+    assert!(!old.simplifier && !old.on_clause && !old.solve_for && !old.seq_fold);
+    let new = Features::of(Version::new(4, 13, 1, 0));
+    assert!(new.simplifier && new.on_clause && new.solve_for && new.seq_fold);
+    // The id-based user propagator callbacks only exist in 4.8.10 to 4.8.12.
+    assert!(old.user_propagator && !new.user_propagator);
+    assert!(!Features::of(Version::new(4, 8, 13, 0)).user_propagator);
+}
+
+#[test]
+fn test_goal_clone_with() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let goal = Goal::new(ctx.clone(), false, false, false);
+    goal.assert(&x.gt(&ast::Int::from_i64(ctx.clone(), 0)));
+    goal.assert(&x.lt(&ast::Int::from_i64(ctx.clone(), 10)));
+
+    let copy = goal.clone_with(true, true, false);
+    assert_eq!(copy.get_formulas::<Bool>(), goal.get_formulas::<Bool>());
+
+    // The copy is independent of the original.
+    copy.assert(&x._eq(&ast::Int::from_i64(ctx.clone(), 20)));
+    assert_eq!(goal.get_size(), 2);
+    assert_eq!(copy.get_size(), 3);
+    assert_eq!(copy.get_depth(), 0);
+}
+
+#[test]
+fn test_reason_unknown_kind() {
+    assert_eq!(ReasonUnknown::from("timeout"), ReasonUnknown::Timeout);
+    assert_eq!(ReasonUnknown::from("canceled"), ReasonUnknown::Canceled);
+    assert_eq!(
+        ReasonUnknown::from("max. memory exceeded"),
+        ReasonUnknown::MemoryLimit
+    );
+    assert_eq!(
+        ReasonUnknown::from(
+            "smt tactic failed to show goal to be sat/unsat (incomplete quantifiers)"
+        ),
+        ReasonUnknown::TacticFailed {
+            tactic: "smt".to_owned(),
+            message: "smt tactic failed to show goal to be sat/unsat (incomplete quantifiers)"
+                .to_owned(),
+        }
+    );
+    assert_eq!(
+        ReasonUnknown::from("(incomplete (theory arithmetic))"),
+        ReasonUnknown::Incomplete("(incomplete (theory arithmetic))".to_owned())
+    );
+    assert_eq!(
+        ReasonUnknown::from("something else"),
+        ReasonUnknown::Other("something else".to_owned())
+    );
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let b = ast::Bool::new_const(ctx.clone(), "b");
+    let solver = Tactic::new(ctx.clone(), "simplify").solver();
+    solver.assert(&b);
+    assert_eq!(solver.check(), SatResult::Unknown);
+    assert_eq!(
+        solver.get_reason_unknown_kind(),
+        Some(ReasonUnknown::Incomplete("incomplete".to_owned()))
+    );
+
+    let solver = Solver::new(ctx);
+    solver.assert(&b);
+    assert_eq!(solver.check(), SatResult::Sat);
+    assert_eq!(solver.get_reason_unknown_kind(), None);
+}
+
+#[test]
+fn test_assert_here_records_source_locations() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let solver = Solver::new(ctx.clone());
+    solver.assert_here(&x.gt(&ast::Int::from_i64(ctx.clone(), 10)));
+    let line = line!() - 1;
+    solver.assert(&x.lt(&ast::Int::from_i64(ctx.clone(), 20)));
+    solver.assert_here(&x.lt(&ast::Int::from_i64(ctx.clone(), 5)));
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    let mut locations = solver.get_unsat_core_locations();
+    locations.sort_by_key(|l| l.line);
+    assert_eq!(locations.len(), 2);
+    assert!(locations[0].file.ends_with(".rs"));
+    assert_eq!(locations[0].line, line);
+    assert_eq!(locations[1].line, line + 3);
+    assert_eq!(locations[0].column, 12);
+    assert_eq!(
+        locations[0].to_string(),
+        format!("{}:{}:12", locations[0].file, line)
+    );
+    assert!(solver.to_string().contains(&locations[1].to_string()));
+
+    assert_eq!(SourceLocation::of(&ast::Bool::new_const(ctx, "p")), None);
+}
+
+#[test]
+fn test_check_all_parallel() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let formulas: Vec<Bool> = (0..40)
+        .map(|i| {
+            let bound = ast::Int::from_i64(ctx.clone(), i);
+            Bool::and(
+                ctx.clone(),
+                &[&x.gt(&bound), &x.lt(&ast::Int::from_i64(ctx.clone(), 20))],
+            )
+        })
+        .collect();
+    let expected: Vec<SatResult> = (0..40)
+        .map(|i| {
+            if i < 19 {
+                SatResult::Sat
+            } else {
+                SatResult::Unsat
+            }
+        })
+        .collect();
+
+    assert_eq!(z3::check_all_parallel(&cfg, &formulas, 4), expected);
+    assert_eq!(z3::check_all_parallel(&cfg, &formulas, 1), expected);
+    assert_eq!(z3::check_all_parallel(&cfg, &formulas, 100), expected);
+    assert!(z3::check_all_parallel(&cfg, &[], 4).is_empty());
+
+    let results = z3::check_all_parallel_until(&cfg, &formulas, 4, |_, r| r == SatResult::Unsat);
+    assert!(results.contains(&SatResult::Unsat));
+    for (r, e) in results.iter().zip(&expected) {
+        assert!(r == e || *r == SatResult::Unknown);
+    }
+}
+
+#[test]
+fn test_solver_prefer() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let vars: Vec<Bool> = (0..6)
+        .map(|i| Bool::new_const(ctx.clone(), format!("b{}", i)))
+        .collect();
+    let solver = Solver::new(ctx.clone());
+    // Exactly two of the variables are true, and b0 implies b1.
+    let weighted: Vec<(&Bool, i32)> = vars.iter().map(|v| (v, 1)).collect();
+    solver.assert(&Bool::pb_eq(ctx.clone(), &weighted, 2));
+    solver.assert(&vars[0].implies(&vars[1]));
+    for v in &vars {
+        solver.prefer(v, Polarity::Positive);
+    }
+    // Replaces the positive preference.
+    solver.prefer(&vars[5], Polarity::Negative);
+
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    let values: Vec<bool> = vars
+        .iter()
+        .map(|v| model.eval(v, true).unwrap().as_bool().unwrap())
+        .collect();
+    assert_eq!(values.iter().filter(|v| **v).count(), 2);
+    assert!(!values[5]);
+
+    // Preferences never leak into unsat cores.
+    let p = Bool::new_const(ctx.clone(), "p");
+    solver.assert_and_track(&Bool::from_bool(ctx.clone(), false), &p);
+    assert_eq!(solver.check(), SatResult::Unsat);
+    assert_eq!(solver.get_unsat_core(), vec![p.clone()]);
+    assert_eq!(
+        solver.check_assumptions(&[vars[0].clone()]),
+        SatResult::Unsat
+    );
+    assert_eq!(solver.get_unsat_core(), vec![p]);
+
+    solver.clear_preferences();
+    assert_eq!(solver.check(), SatResult::Unsat);
+}
+
+#[cfg(feature = "user-propagator")]
+#[test]
+fn test_user_propagator_requires_simple_solver() {
+    struct Nothing;
+    impl z3::UserPropagator for Nothing {}
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let solver = Solver::new(ctx.clone());
+    assert!(solver.propagator(Box::new(Nothing)).is_err());
+    let solver = Solver::new_simple(ctx);
+    assert_eq!(
+        solver.propagator(Box::new(Nothing)).is_ok(),
+        z3::features().user_propagator
+    );
+    assert!(solver.propagator(Box::new(Nothing)).is_err());
+}
+
+#[cfg(feature = "user-propagator")]
+#[test]
+fn test_user_propagator_final_check() {
+    use std::cell::RefCell;
+    use z3::{PropagatorCallback, UserPropagator};
+
+    // Forbid models in which both registered Booleans are true, but only
+    // once the solver has assigned everything.
+    struct NotBoth {
+        log: Rc<RefCell<Vec<String>>>,
+        trail: Vec<usize>,
+        values: Vec<(ast::Dynamic, bool)>,
+    }
+
+    impl UserPropagator for NotBoth {
+        fn push(&mut self) {
+            self.trail.push(self.values.len());
+        }
+        fn pop(&mut self, num_scopes: u32) {
+            for _ in 0..num_scopes {
+                let len = self.trail.pop().unwrap();
+                self.values.truncate(len);
+            }
+        }
+        fn fixed(&mut self, _: &PropagatorCallback, term: &ast::Dynamic, value: &ast::Dynamic) {
+            let value = value.as_bool().unwrap().as_bool().unwrap();
+            self.values.push((term.clone(), value));
+        }
+        fn final_check(&mut self, cb: &PropagatorCallback) {
+            self.log.borrow_mut().push("final".to_owned());
+            if self.values.len() == 2 && self.values.iter().all(|(_, v)| *v) {
+                let (x, y) = (&self.values[0].0, &self.values[1].0);
+                let conflict = ast::Bool::from_bool(x.get_ctx(), false);
+                cb.propagate(&[x, y], &[], &conflict);
+            }
+        }
+    }
+
+    if !z3::features().user_propagator {
+        return;
+    }
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let a = ast::Bool::new_const(ctx.clone(), "a");
+    let b = ast::Bool::new_const(ctx.clone(), "b");
+    let log = Rc::new(RefCell::new(vec![]));
+    let solver = Solver::new_simple(ctx.clone());
+    solver
+        .propagator(Box::new(NotBoth {
+            log: log.clone(),
+            trail: vec![],
+            values: vec![],
+        }))
+        .unwrap();
+    solver.propagate_register(&a);
+    solver.propagate_register(&b);
+
+    solver.assert(&(a.clone() | b.clone()));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    let a_val = model.eval(&a, true).unwrap().as_bool().unwrap();
+    let b_val = model.eval(&b, true).unwrap().as_bool().unwrap();
+    assert!(!(a_val && b_val));
+    assert!(!log.borrow().is_empty());
+
+    solver.assert(&a);
+    solver.assert(&b);
+    assert_eq!(solver.check(), SatResult::Unsat);
+}
+
+#[cfg(feature = "user-propagator")]
+#[test]
+fn test_user_propagator_panic_is_resumed() {
+    use std::panic::{self, AssertUnwindSafe};
+    use z3::{PropagatorCallback, UserPropagator};
+
+    struct Panics;
+    impl UserPropagator for Panics {
+        fn fixed(&mut self, _: &PropagatorCallback, _: &ast::Dynamic, _: &ast::Dynamic) {
+            panic!("boom");
+        }
+    }
+
+    if !z3::features().user_propagator {
+        return;
+    }
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let a = ast::Bool::new_const(ctx.clone(), "a");
+    let solver = Solver::new_simple(ctx);
+    solver.propagator(Box::new(Panics)).unwrap();
+    solver.propagate_register(&a);
+    solver.assert(&a);
+    let payload = panic::catch_unwind(AssertUnwindSafe(|| solver.check())).unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+}
+
+#[test]
+fn test_parse_smtlib2_file() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let path = std::env::temp_dir().join(format!("z3_parse_{}.smt2", std::process::id()));
+    std::fs::write(
+        &path,
+        "(declare-const y Int)\n(assert (< x y))\n(assert (> x 3))\n",
+    )
+    .unwrap();
+
+    let assertions = Context::parse_smtlib2_file(ctx.clone(), &path, &[], &[x.decl()]).unwrap();
+    assert_eq!(assertions.len(), 2);
+    assert_eq!(assertions[1], x.gt(&ast::Int::from_i64(ctx.clone(), 3)));
+
+    // Without the environment, `x` is unknown.
+    let err = Context::parse_smtlib2_file(ctx.clone(), &path, &[], &[]).unwrap_err();
+    assert_eq!(err.line(), Some(2));
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(Context::parse_smtlib2_file(ctx.clone(), &path, &[], &[]).is_err());
+    assert!(Context::parse_smtlib2_string(ctx, "(assert", &[], &[]).is_err());
+}
+
+#[test]
+fn test_solver_snapshot_rollback_out_of_order() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let int = |i| ast::Int::from_i64(ctx.clone(), i);
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x.gt(&int(0)));
+    let base = solver.snapshot();
+
+    solver.push();
+    solver.assert(&x.lt(&int(5)));
+    let small = solver.snapshot();
+
+    solver.rollback(&base);
+    solver.assert(&x.gt(&int(10)));
+    let large = solver.snapshot();
+
+    // Jump back to a state that is not an ancestor of the current one.
+    solver.rollback(&small);
+    assert_eq!(solver.check(), SatResult::Sat);
+    solver.assert(&x.gt(&int(10)));
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    solver.rollback(&large);
+    assert_eq!(solver.get_assertions().len(), 2);
+    assert_eq!(solver.check(), SatResult::Sat);
+
+    // Snapshots can be restored into another solver of the same context.
+    let other = Solver::new(ctx);
+    other.rollback(&small);
+    assert_eq!(other.get_assertions().len(), 2);
+}
+
+#[test]
+fn test_model_based_projection() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+    let int = |i| ast::Int::from_i64(ctx.clone(), i);
+    let formula = x.lt(&y) & y.lt(&int(5)) & x.ge(&int(0));
+
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&formula);
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    let projected = project(&model, &[&y], &formula);
+
+    assert_eq!(model.eval(&projected, true).unwrap().as_bool(), Some(true));
+    // The projection implies that some `y` exists.
+    let check = Solver::new(ctx.clone());
+    check.assert(&projected);
+    check.assert(&x.ge(&int(4)));
+    assert_eq!(check.check(), SatResult::Unsat);
+}
+
+#[test]
+fn test_qe_lite() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+    let z = ast::Int::new_const(ctx.clone(), "z");
+    let formula = y._eq(&(x.clone() + ast::Int::from_i64(ctx.clone(), 1))) & y.gt(&z);
+
+    let (result, remaining) = qe_lite(&[&y, &z], &formula);
+    assert!(!result.to_string().contains('y'));
+    assert_eq!(remaining, vec![ast::Dynamic::from_ast(&z)]);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_regexp_from_regex_str() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let matches = |pattern: &str, s: &str| {
+        let re = ast::Regexp::from_regex_str(ctx.clone(), pattern).unwrap();
+        // Z3 reads non-ASCII characters byte by byte, unless escaped.
+        let escaped: String = s
+            .chars()
+            .map(|c| match c {
+                ' '..='~' => c.to_string(),
+                _ => format!("\\u{{{:x}}}", c as u32),
+            })
+            .collect();
+        let s = ast::String::from_str(ctx.clone(), &escaped).unwrap();
+        let solver = Solver::new(ctx.clone());
+        solver.assert(&s.regex_matches(&re));
+        solver.check() == SatResult::Sat
+    };
+
+    assert!(matches(r"[a-z]+\d{2}", "abc42"));
+    assert!(!matches(r"[a-z]+\d{2}", "abc4"));
+    assert!(!matches(r"[a-z]+\d{2}", "x42y"));
+    assert!(matches(r"^(foo|bar)*$", "foobarfoo"));
+    assert!(!matches(r"(foo|bar)*", "fob"));
+    assert!(matches(r"a{2,}", "aaaa"));
+    assert!(!matches(r"a{2,}", "a"));
+    assert!(matches(r"a?b", "b"));
+    assert!(matches(r"(?i)hello", "HeLLo"));
+    assert!(matches(r"[^0-9]", "é"));
+    assert!(!matches(r"[^0-9]", "5"));
+    assert!(matches(r"\p{Greek}+", "λόγος"));
+    assert!(matches(r"a.c", "a~c"));
+    assert!(!matches(r"a.c", "a\nc"));
+    assert!(!matches(r"[a-z]", ""));
+
+    let err = |pattern| ast::Regexp::from_regex_str(ctx.clone(), pattern).unwrap_err();
+    assert_eq!(
+        err(r"(a)\1"),
+        RegexError::Unsupported("backreferences".to_owned())
+    );
+    assert!(matches!(err(r"a\bc"), RegexError::Unsupported(_)));
+    assert!(matches!(err(r"a(b"), RegexError::Syntax(_)));
+}
+
+#[test]
+fn test_get_unsat_core_repeated() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let p = Bool::new_const(ctx.clone(), "p");
+    let q = Bool::new_const(ctx.clone(), "q");
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&p.implies(&x.gt(&Int::from_i64(ctx.clone(), 2))));
+    solver.assert(&q.implies(&x.lt(&Int::from_i64(ctx.clone(), 1))));
+
+    assert_eq!(
+        solver.check_assumptions(&[p.clone(), q.clone()]),
+        SatResult::Unsat
+    );
+    // The core can be retrieved as often as needed, and outlives the
+    // vector Z3 returned it in.
+    let core = solver.get_unsat_core();
+    for _ in 0..3 {
+        assert_eq!(solver.get_unsat_core(), core);
+    }
+    assert_eq!(core.len(), 2);
+    assert!(core.contains(&p) && core.contains(&q));
+}
+
+#[test]
+fn test_grammar_depth_bound() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    // parens ::= "" | "(" parens ")"
+    let grammar = Grammar::new(ctx.clone(), "parens")
+        .rule("parens", vec![])
+        .rule(
+            "parens",
+            vec![Term::lit("("), Term::nt("parens"), Term::lit(")")],
+        );
+    let matches = |s: &str, depth| {
+        let solver = Solver::new(ctx.clone());
+        let s = ast::String::from_str(ctx.clone(), s).unwrap();
+        solver.assert(&grammar.matches(&s, depth));
+        solver.check() == SatResult::Sat
+    };
+
+    assert!(matches("", 1));
+    assert!(matches("(())", 3));
+    assert!(!matches("((()))", 3));
+    assert!(!matches("(()", 5));
+    assert!(!matches("", 0));
+}
+
+#[test]
+#[should_panic(expected = "no rules for non-terminal value")]
+fn test_grammar_undefined_non_terminal() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    Grammar::new(ctx, "list")
+        .rule(
+            "list",
+            vec![Term::lit("["), Term::nt("value"), Term::lit("]")],
+        )
+        .to_regexp(3);
+}
+
+#[test]
+fn test_regexp_char_classes() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let matches = |re: &ast::Regexp, s: &str| {
+        let solver = Solver::new(ctx.clone());
+        let s = ast::String::from_str(ctx.clone(), s).unwrap();
+        solver.assert(&s.regex_matches(re));
+        solver.check() == SatResult::Sat
+    };
+
+    let ident = ast::Regexp::concat(
+        ctx.clone(),
+        &[
+            ast::Regexp::union(
+                ctx.clone(),
+                &[
+                    ast::Regexp::ascii_class(ctx.clone(), CharClass::Alpha),
+                    ast::Regexp::literal(ctx.clone(), "_"),
+                ],
+            ),
+            ast::Regexp::ascii_class(ctx.clone(), CharClass::Word).star(),
+        ],
+    );
+    assert!(matches(&ident, "_foo42"));
+    assert!(!matches(&ident, "4foo"));
+
+    let space = ast::Regexp::ascii_class(ctx.clone(), CharClass::Whitespace);
+    assert!(matches(&space, "\\u{b}"));
+    assert!(!matches(&space, "\\u{a0}"));
+
+    let greek = ast::Regexp::ranges(
+        ctx.clone(),
+        &[('\u{391}', '\u{3a9}'), ('\u{3b1}', '\u{3c9}')],
+    );
+    assert!(matches(&greek, "\\u{3bb}"));
+    assert!(!matches(&greek, "l"));
+    assert!(!matches(&ast::Regexp::ranges(ctx.clone(), &[]), ""));
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_regexp_unicode_classes() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let matches = |class, s: &str| {
+        let solver = Solver::new(ctx.clone());
+        let s = ast::String::from_str(ctx.clone(), s).unwrap();
+        solver.assert(&s.regex_matches(&ast::Regexp::unicode_class(ctx.clone(), class)));
+        solver.check() == SatResult::Sat
+    };
+
+    assert!(matches(CharClass::Whitespace, "\\u{a0}"));
+    assert!(matches(CharClass::Alpha, "\\u{3bb}"));
+    assert!(matches(CharClass::Upper, "\\u{39b}"));
+    assert!(!matches(CharClass::Upper, "\\u{3bb}"));
+    assert!(matches(CharClass::Digit, "\\u{966}"));
+    assert!(!matches(CharClass::Alnum, "-"));
+}
+
+#[test]
+fn test_solver_cubes() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let vars: Vec<Bool> = (0..4)
+        .map(|i| Bool::new_const(ctx.clone(), format!("v{}", i)))
+        .collect();
+    let solver = Solver::new(ctx.clone());
+    // Exactly one of the variables is true.
+    solver.assert(&Bool::or(ctx.clone(), &vars));
+    for (i, v) in vars.iter().enumerate() {
+        for w in &vars[i + 1..] {
+            solver.assert(&!(v.clone() & w.clone()));
+        }
+    }
+
+    let cubes: Vec<Vec<Bool>> = solver.cubes(&vars, u32::MAX).collect();
+    assert!(!cubes.is_empty());
+    let sat = cubes
+        .iter()
+        .filter(|cube| solver.check_assumptions(cube) == SatResult::Sat)
+        .count();
+    assert!(sat >= 1);
+
+    let unsat = Solver::new(ctx.clone());
+    unsat.assert(&vars[0]);
+    unsat.assert(&!vars[0].clone());
+    assert_eq!(unsat.cubes(&[], u32::MAX).count(), 0);
+}
+
+#[test]
+fn test_fixed_point_arithmetic() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let d = |s| FixedPointNum::<2>::from_decimal_str(ctx.clone(), s).unwrap();
+    let value = |x: FixedPointNum<2>| {
+        let solver = Solver::new(ctx.clone());
+        assert_eq!(solver.check(), SatResult::Sat);
+        x.eval(&solver.get_model().unwrap())
+            .unwrap()
+            .as_decimal_string()
+            .unwrap()
+    };
+
+    assert_eq!(value(d("1.5") + d("-0.25")), "1.25");
+    assert_eq!(value(d("1.5") * d("2")), "3.00");
+    assert_eq!(value(d("0.01") * d("0.01")), "0.00");
+    assert_eq!(value(d("1") / d("3")), "0.33");
+    // Z3's division is Euclidean.
+    assert_eq!(value(d("-1") / d("3")), "-0.34");
+    assert_eq!(value(d("-1") / d("-3")), "0.34");
+    assert_eq!(value(-d(".5")), "-0.50");
+    assert_eq!(value(FixedPointNum::from_i64(ctx.clone(), -7)), "-7.00");
+    assert_eq!(value(d("12.34").rescale::<1>().rescale::<2>()), "12.30");
+
+    assert!(FixedPointNum::<2>::from_decimal_str(ctx.clone(), "1.234").is_none());
+    assert!(FixedPointNum::<2>::from_decimal_str(ctx.clone(), "1e5").is_none());
+    assert!(FixedPointNum::<2>::from_decimal_str(ctx.clone(), "-").is_none());
+    assert_eq!(
+        FixedPointNum::<0>::from_decimal_str(ctx.clone(), "42")
+            .unwrap()
+            .as_decimal_string()
+            .unwrap(),
+        "42"
+    );
+    assert_eq!(d("0.07").to_string(), "0.07");
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn test_fixed_point_decimal_conversions() {
+    use rust_decimal::Decimal;
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let decimal = |s: &str| s.parse::<Decimal>().unwrap();
+    let x = FixedPointNum::<2>::from_decimal(ctx.clone(), &decimal("-12.50")).unwrap();
+    assert_eq!(x.as_decimal_string().unwrap(), "-12.50");
+    assert_eq!(x.as_decimal(), Some(decimal("-12.50")));
+    // Trailing zeros do not count as digits.
+    assert!(FixedPointNum::<2>::from_decimal(ctx.clone(), &decimal("1.2300")).is_some());
+    assert!(FixedPointNum::<2>::from_decimal(ctx.clone(), &decimal("1.234")).is_none());
+    assert_eq!(
+        FixedPointNum::<2>::new_const(ctx.clone(), "y").as_decimal(),
+        None
+    );
+}
+
+#[test]
+fn test_mach_int_matches_rust_semantics() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let value = |x: MachInt<i8>| {
+        MachInt::<i8>::from_bv(x.bv().simplify())
+            .as_value()
+            .unwrap()
+    };
+    let holds = |b: Bool| b.simplify().as_bool().unwrap();
+    let samples: [i8; 7] = [-128, -7, -1, 0, 1, 3, 127];
+    for &a in &samples {
+        for &b in &samples {
+            let (x, y) = (
+                MachInt::from_value(ctx.clone(), a),
+                MachInt::from_value(ctx.clone(), b),
+            );
+            assert_eq!(value(x.clone() + y.clone()), a.wrapping_add(b));
+            assert_eq!(value(x.clone() - y.clone()), a.wrapping_sub(b));
+            assert_eq!(value(x.clone() * y.clone()), a.wrapping_mul(b));
+            assert_eq!(holds(x.add_overflows(&y)), a.checked_add(b).is_none());
+            assert_eq!(holds(x.sub_overflows(&y)), a.checked_sub(b).is_none());
+            assert_eq!(holds(x.mul_overflows(&y)), a.checked_mul(b).is_none());
+            assert_eq!(holds(x.lt(&y)), a < b);
+            assert_eq!(holds(x.ge(&y)), a >= b);
+            if b != 0 {
+                assert_eq!(value(x.clone() / y.clone()), a.wrapping_div(b));
+                assert_eq!(value(x.clone() % y.clone()), a.wrapping_rem(b));
+                assert_eq!(holds(x.div_overflows(&y)), a.checked_div(b).is_none());
+            }
+        }
+        let x = MachInt::from_value(ctx.clone(), a);
+        assert_eq!(holds(x.neg_overflows()), a.checked_neg().is_none());
+        for amount in [0u32, 1, 7, 8, 9] {
+            let n = MachInt::from_value(ctx.clone(), amount);
+            assert_eq!(value(x.clone() << n.clone()), a.wrapping_shl(amount));
+            assert_eq!(value(x.clone() >> n.clone()), a.wrapping_shr(amount));
+            assert_eq!(
+                holds(MachInt::<i8>::shift_overflows(&n)),
+                a.checked_shl(amount).is_none()
+            );
+        }
+    }
+
+    let u = |v: u64| MachInt::from_value(ctx.clone(), v);
+    let value = |x: MachInt<u64>| {
+        MachInt::<u64>::from_bv(x.bv().simplify())
+            .as_value()
+            .unwrap()
+    };
+    assert_eq!(
+        value(u(u64::MAX) >> MachInt::from_value(ctx.clone(), 60)),
+        15
+    );
+    assert_eq!(value(u(7) / u(2)), 3);
+    assert!(holds(u(0).sub_overflows(&u(1))));
+    assert!(holds(u(u64::MAX).gt(&u(1))));
+    assert!(holds(u(1).neg_overflows()));
+    assert!(!holds(u(0).neg_overflows()));
+}
+
+#[test]
+fn test_solver_models() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let a = ast::Bool::new_const(ctx.clone(), "a");
+    let b = ast::Bool::new_const(ctx.clone(), "b");
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&(a.clone() | b.clone()));
+
+    let mut seen: Vec<(bool, bool)> = solver
+        .models(&[])
+        .map(|m| {
+            (
+                m.eval(&a, true).unwrap().as_bool().unwrap(),
+                m.eval(&b, true).unwrap().as_bool().unwrap(),
+            )
+        })
+        .collect();
+    seen.sort();
+    assert_eq!(seen, [(false, true), (true, false), (true, true)]);
+
+    // Blocking only on `a` yields one model per value of `a`.
+    assert_eq!(solver.models(&[&a]).count(), 2);
+
+    // The blocking clauses are gone once the iterator is dropped.
+    assert_eq!(solver.get_assertions().len(), 1);
+    assert_eq!(solver.check(), SatResult::Sat);
+
+    // Resetting the solver while iterating leaves nothing to pop.
+    let mut models = solver.models(&[]);
+    assert!(models.next().is_some());
+    solver.reset();
+    drop(models);
+    assert_eq!(solver.num_scopes(), 0);
+
+    solver.assert(&(a.clone() | b.clone()));
+    solver.assert(&!a.clone());
+    solver.assert(&!b.clone());
+    assert_eq!(solver.models(&[]).count(), 0);
+}
+
+#[test]
+fn test_context_handle_interrupts_check() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    // x^3 + y^3 = z^3 has no positive solutions, which Z3 can not prove.
+    let [x, y, z] = ["x", "y", "z"].map(|n| ast::Int::new_const(ctx.clone(), n));
+    let zero = ast::Int::from_i64(ctx.clone(), 0);
+    let cube = |v: &ast::Int| v.clone() * v.clone() * v.clone();
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x.gt(&zero));
+    solver.assert(&y.gt(&zero));
+    solver.assert(&z.gt(&zero));
+    solver.assert(&(cube(&x) + cube(&y))._eq(&cube(&z)));
+
+    let handle = Context::handle(ctx.clone());
+    let done = Arc::new(AtomicBool::new(false));
+    let watchdog = {
+        let done = done.clone();
+        std::thread::spawn(move || {
+            // Keep interrupting, in case the first one arrives before the
+            // check started.
+            while !done.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(50));
+                handle.interrupt();
+            }
+        })
+    };
+    assert_eq!(solver.check(), SatResult::Unknown);
+    done.store(true, Ordering::SeqCst);
+    watchdog.join().unwrap();
+    assert_eq!(
+        solver.get_reason_unknown_kind(),
+        Some(ReasonUnknown::Canceled)
+    );
+}
+
+#[test]
+fn test_mem_load_store() {
+    use z3::mem::{self, Endian, Memory};
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let bv = |v: u64, bits: u32| BV::from_u64(ctx.clone(), v, bits);
+    let addr = bv(0x100, 16);
+    let value = bv(0x1122_3344, 32);
+    let solver = Solver::new(ctx.clone());
+    let value_of = |b: &BV| {
+        assert_eq!(solver.check(), SatResult::Sat);
+        let model = solver.get_model().unwrap();
+        model.eval(b, true).unwrap().as_u64().unwrap()
+    };
+
+    let little = Memory::zeroed(ctx.clone(), 16).store(&addr, &value, Endian::Little);
+    assert_eq!(
+        value_of(&little.load(&addr, 4, Endian::Little)),
+        0x1122_3344
+    );
+    assert_eq!(value_of(&little.load(&addr, 4, Endian::Big)), 0x4433_2211);
+    assert_eq!(value_of(&little.load(&addr, 1, Endian::Big)), 0x44);
+    assert_eq!(
+        value_of(&little.load(&bv(0x102, 16), 2, Endian::Little)),
+        0x1122
+    );
+    assert_eq!(value_of(&little.load(&bv(0x104, 16), 1, Endian::Little)), 0);
+
+    let big = Memory::zeroed(ctx.clone(), 16).store(&addr, &value, Endian::Big);
+    assert_eq!(value_of(&big.load(&addr, 1, Endian::Big)), 0x11);
+    assert_eq!(value_of(&big.load(&addr, 4, Endian::Big)), 0x1122_3344);
+
+    // Accesses wrap around at the end of the address space.
+    let wrapped =
+        Memory::zeroed(ctx.clone(), 16).store(&bv(0xffff, 16), &bv(0xabcd, 16), Endian::Big);
+    assert_eq!(value_of(&wrapped.load(&bv(0, 16), 1, Endian::Big)), 0xcd);
+
+    let sym = Memory::from_array(little.array().clone());
+    assert_eq!(sym.addr_bits(), 16);
+    assert_eq!(sym, little);
+
+    let ptr = BV::new_const(ctx.clone(), "ptr", 16);
+    solver.push();
+    solver.assert(&mem::is_aligned(&ptr, 4));
+    solver.assert(&ptr._eq(&bv(0x102, 16)));
+    assert_eq!(solver.check(), SatResult::Unsat);
+    solver.pop(1);
+    solver.assert(&mem::is_aligned(&ptr, 4));
+    solver.assert(&ptr.bvugt(&bv(0x100, 16)));
+    solver.assert(&ptr.bvult(&bv(0x108, 16)));
+    assert_eq!(value_of(&ptr), 0x104);
+}
+
+#[test]
+fn test_detached_solver_on_worker_thread() {
+    fn assert_send<T: Send>(_: &T) {}
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+    let solver = Solver::new(ctx.clone());
+    // Nonlinear integer arithmetic Z3 does not solve quickly.
+    solver.assert(
+        &(x.clone() * x.clone() * x.clone())
+            ._eq(&(y.clone() * y.clone() + Int::from_i64(ctx.clone(), 7))),
+    );
+    solver.assert(&x.gt(&Int::from_i64(ctx.clone(), 1_000_000)));
+
+    let detached = solver.detach(&cfg);
+    assert_send(&detached);
+    let handle = detached.handle();
+    let worker = std::thread::spawn(move || {
+        let solver = detached.attach();
+        let result = solver.check();
+        (result, solver.get_assertions().len())
+    });
+    while !worker.is_finished() {
+        std::thread::sleep(Duration::from_millis(50));
+        handle.interrupt();
+    }
+    let (result, num_assertions) = worker.join().unwrap();
+    assert_ne!(result, SatResult::Sat);
+    assert_eq!(num_assertions, 2);
+    // The original solver is unaffected.
+    assert_eq!(solver.get_assertions().len(), 2);
+}
+
+#[test]
+fn test_symexec_stack_machine() {
+    use z3::symexec::State;
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let word = |v: u64| BV::from_u64(ctx.clone(), v, 16);
+    let solver = Solver::new(ctx.clone());
+
+    // push a; push b; add; store [0x10]; load [0x10]
+    let mut state = State::new(ctx.clone(), 16);
+    state.push(state.reg("a"));
+    state.push(state.reg("b"));
+    let (b, a) = (state.pop().unwrap(), state.pop().unwrap());
+    state.push(a.bvadd(&b));
+    let sum = state.pop().unwrap();
+    state.store(&word(0x10), &sum);
+    let loaded = state.load(&word(0x10), 2);
+    solver.push();
+    solver.assert(&!loaded._eq(&state.reg("a").bvadd(&state.reg("b"))));
+    assert_eq!(solver.check(), SatResult::Unsat);
+    solver.pop(1);
+
+    // if a == 0 { push 1 } else { push a }; on both sides, the value is
+    // non-zero.
+    let cond = state.reg("a")._eq(&word(0));
+    let (mut then, mut otherwise) = state.fork(&cond);
+    then.push(word(1));
+    otherwise.push(otherwise.reg("a"));
+    otherwise.store(&word(0x20), &word(0xffff));
+    assert!(then.is_feasible(&solver));
+    let mut infeasible = then.clone();
+    infeasible.assume(!cond.clone());
+    assert!(!infeasible.is_feasible(&solver));
+
+    let joined = then.merge(&otherwise).unwrap();
+    assert_eq!(joined.stack().len(), 1);
+    let top = joined.stack()[0].clone();
+    solver.push();
+    solver.assert(&joined.path_condition());
+    solver.assert(&top._eq(&word(0)));
+    assert_eq!(solver.check(), SatResult::Unsat);
+    solver.pop(1);
+
+    // The merged memory holds both the earlier store, and the store of
+    // the `else` side where that side is taken.
+    solver.assert(&joined.path_condition());
+    solver.push();
+    solver.assert(&!joined.load(&word(0x10), 2)._eq(&sum));
+    assert_eq!(solver.check(), SatResult::Unsat);
+    solver.pop(1);
+    solver.assert(&!cond.clone());
+    solver.assert(&!joined.load(&word(0x20), 2)._eq(&word(0xffff)));
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    let mut deeper = otherwise.clone();
+    deeper.push(word(2));
+    assert!(then.merge(&deeper).is_none());
+}
+
+#[test]
+fn test_taint_labeled_array() {
+    use z3::taint::{Labeled, LabeledArray};
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let solver = Solver::new(ctx.clone());
+    let must = |b: &Bool| {
+        solver.push();
+        solver.assert(&!b.clone());
+        let r = solver.check() == SatResult::Unsat;
+        solver.pop(1);
+        r
+    };
+    let i = |v: i64| Labeled::clean(Int::from_i64(ctx.clone(), v));
+    let secret = Labeled::tainted(Int::new_const(ctx.clone(), "secret"));
+
+    // Labels propagate through arithmetic.
+    let sum = secret.combine(&i(1), |a, b| a.clone() + b.clone());
+    assert!(must(sum.label()));
+    assert!(must(
+        &!i(2)
+            .combine(&i(3), |a, b| a.clone() * b.clone())
+            .label()
+            .clone()
+    ));
+
+    // ... and through stores, selects and copies.
+    let buf = LabeledArray::new_clean(ctx.clone(), "buf", &int, &int).store(&i(0), &sum);
+    assert!(must(buf.select(&i(0)).label()));
+    assert!(must(&!buf.select(&i(1)).label().clone()));
+    let (copy, defs) = buf.copy("copy");
+    solver.assert(&defs);
+    assert!(must(copy.select(&i(0)).label()));
+    assert!(must(
+        &copy.select(&i(0)).value()._eq(&sum.value().clone().into())
+    ));
+
+    // Accesses at a tainted index only taint with index taint.
+    let index = secret.map(|s| s.clone() + Int::from_i64(ctx.clone(), 1));
+    assert!(!must(buf.select(&index).label()));
+    assert!(must(
+        &!buf.store(&index, &i(7)).select(&index).label().clone()
+    ));
+    let strict = buf.clone().with_index_taint(true);
+    assert!(must(strict.select(&index).label()));
+    assert!(must(strict.store(&index, &i(7)).select(&index).label()));
+
+    let input = LabeledArray::new_tainted(ctx.clone(), "input", &int, &int);
+    assert!(must(input.select(&i(42)).label()));
+}
+
+#[test]
+fn test_find_redundant_assertions() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let y = Int::new_const(ctx.clone(), "y");
+    let int = |i| Int::from_i64(ctx.clone(), i);
+
+    assert!(find_redundant_assertions(&[]).is_empty());
+
+    let assertions = [
+        x.gt(&int(0)),
+        y.gt(&x),
+        y.gt(&int(0)),
+        Bool::from_bool(ctx.clone(), true),
+        x.gt(&int(0)),
+        x.lt(&int(10)),
+    ];
+    // The first `x > 0` is implied by its repetition.
+    let redundant = find_redundant_assertions(&assertions);
+    assert_eq!(redundant, [0, 2, 3]);
+
+    // The remaining assertions imply the removed ones.
+    let kept: Vec<Bool> = (0..assertions.len())
+        .filter(|i| !redundant.contains(i))
+        .map(|i| assertions[i].clone())
+        .collect();
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&Bool::and(ctx.clone(), &kept));
+    solver.assert(&!Bool::and(ctx.clone(), &assertions));
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    // Of two equivalent assertions, only one is dropped.
+    let a = x.ge(&int(1));
+    let b = x.gt(&int(0));
+    assert_eq!(find_redundant_assertions(&[a, b]), [0]);
+}
+
+#[test]
+fn test_solver_get_proof_structure() {
+    let mut cfg = Config::new();
+    cfg.set_proof_generation(true);
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let lower = x.gt(&Int::from_i64(ctx.clone(), 2));
+    let upper = x.lt(&Int::from_i64(ctx.clone(), 1));
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&lower);
+    solver.assert(&upper);
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    let proof = solver.get_proof().unwrap();
+    assert_eq!(proof.conclusion().as_bool(), Some(false));
+    assert!(!proof.premises().is_empty());
+
+    let mut asserted = vec![];
+    let mut todo = vec![proof.clone()];
+    while let Some(p) = todo.pop() {
+        assert!(!p.rule_name().is_empty());
+        assert_eq!(p.premises().len() + 1, p.as_ast().num_children());
+        if p.rule() == DeclKind::PR_ASSERTED {
+            assert!(p.premises().is_empty());
+            asserted.push(p.conclusion());
+        }
+        todo.extend(p.premises());
+    }
+    assert!(asserted.contains(&lower));
+    assert!(asserted.contains(&upper));
+    assert!(format!("{}", proof).contains("asserted"));
+}
+
+#[test]
+fn test_infer_bounds() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let y = Int::new_const(ctx.clone(), "y");
+    let int = |i| Int::from_i64(ctx.clone(), i);
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x.ge(&int(-5)));
+    solver.assert(&y.lt(&x));
+
+    let bounds = infer_bounds(&solver, &[x.clone(), y.clone(), x.clone() - y.clone()]).unwrap();
+    assert_eq!(bounds[0].as_i64(), (Some(-5), None));
+    assert_eq!(bounds[1].as_i64(), (None, None));
+    assert_eq!(bounds[2].as_i64(), (Some(1), None));
+    assert_eq!(
+        bounds[0].lower.as_ref().unwrap().get_sort(),
+        Sort::int(ctx.clone())
+    );
+
+    // The solver itself is left alone.
+    assert_eq!(solver.get_assertions().len(), 2);
+
+    solver.assert(&x.lt(&int(-5)));
+    assert!(infer_bounds(&solver, &[x]).is_none());
+}
+
+#[test]
+fn test_solver_statistics() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x.gt(&Int::from_i64(ctx.clone(), 2)));
+    assert_eq!(solver.check(), SatResult::Sat);
+
+    let stats = solver.get_statistics();
+    let entries: Vec<StatisticsEntry> = stats.entries().collect();
+    assert!(!entries.is_empty());
+    for entry in &entries {
+        let found = stats.value(&entry.key).unwrap();
+        assert_eq!(found.as_f64(), entry.value.as_f64());
+    }
+    assert!(matches!(stats.value("memory"), Some(StatisticsValue::Double(m)) if m > 0.0));
+    assert!(stats.value("no such statistic").is_none());
+}
+
+#[test]
+fn test_optimize_prime_with() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = BV::new_const(ctx.clone(), "x", 8);
+    let y = BV::new_const(ctx.clone(), "y", 8);
+    let constraint = x.bvadd(&y)._eq(&BV::from_u64(ctx.clone(), 50, 8));
+    let model_with = |assertions: &[Bool]| {
+        let solver = Solver::new(ctx.clone());
+        assertions.iter().for_each(|a| solver.assert(a));
+        assert_eq!(solver.check(), SatResult::Sat);
+        solver.get_model().unwrap()
+    };
+    let heuristic = model_with(&[constraint.clone(), x._eq(&BV::from_u64(ctx.clone(), 30, 8))]);
+
+    let opt = Optimize::new(ctx.clone());
+    opt.assert(&constraint);
+    // An objective of another optimizer.
+    let other = Optimize::new(ctx.clone());
+    assert!(!opt.prime_with(&other.minimize(&x), &heuristic));
+    let objective = opt.minimize(&x);
+    opt.assert_soft(&y.bvult(&BV::from_u64(ctx.clone(), 10, 8)), 1, None);
+    assert_eq!(opt.get_assertions().len(), 1);
+    assert!(opt.prime_with(&objective, &heuristic));
+    assert_eq!(opt.get_assertions().len(), 2);
+    assert_eq!(opt.check(&[]), SatResult::Sat);
+    let model = opt.get_model().unwrap();
+    assert_eq!(model.eval(&x, true).unwrap().as_u64(), Some(0));
+
+    // A model that violates the hard constraints is rejected.
+    let wrong = model_with(&[x._eq(&BV::from_u64(ctx.clone(), 1, 8)), y._eq(&x)]);
+    assert!(!opt.prime_with(&objective, &wrong));
+    assert_eq!(opt.get_assertions().len(), 2);
+
+    // Priming a maximized bit-vector with its worst value still finds the
+    // optimum.
+    let worst = model_with(&[constraint.clone(), x._eq(&BV::from_u64(ctx.clone(), 0, 8))]);
+    let opt = Optimize::new(ctx.clone());
+    opt.assert(&constraint);
+    let objective = opt.maximize(&x);
+    assert!(opt.prime_with(&objective, &worst));
+    assert_eq!(opt.check(&[]), SatResult::Sat);
+    let model = opt.get_model().unwrap();
+    assert_eq!(model.eval(&x, true).unwrap().as_u64(), Some(255));
+}
+
+#[test]
+fn test_linear_big_m() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Real::new_const(ctx.clone(), "x");
+    let y = ast::Real::new_const(ctx.clone(), "y");
+    let on = Bool::new_const(ctx.clone(), "on");
+    let real = |v: i32| ast::Real::from_real(ctx.clone(), v, 1);
+
+    // x - 3y + 4, with -10 <= x <= 10 and 0 <= y <= 5.
+    let expr = Linear::new(ctx.clone())
+        .term(1, &x, -10, 10)
+        .term(-3, &y, 0, 5)
+        .constant(4);
+    assert_eq!(expr.bounds(), (-21, 14));
+
+    // Within the bounds, each big-M constraint is equivalent to its
+    // indicator constraint.
+    let value = x.clone() - real(3) * y.clone() + real(4);
+    for (big_m, constraint) in [
+        (expr.big_m_le(&on, -2), value.le(&real(-2))),
+        (expr.big_m_ge(&on, 7), value.ge(&real(7))),
+        (expr.big_m_eq(&on, 0), value._eq(&real(0))),
+        // The bounds already imply the constraint, so M is zero.
+        (expr.big_m_le(&on, 20), value.le(&real(20))),
+    ] {
+        let solver = Solver::new(ctx.clone());
+        solver.assert(&expr.bound_constraints());
+        solver.assert(&!big_m._eq(&indicator(&on, &constraint)));
+        assert_eq!(solver.check(), SatResult::Unsat);
+    }
+
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&!expr.to_real()._eq(&value));
+    assert_eq!(solver.check(), SatResult::Unsat);
+}
+
+#[test]
+fn test_solver_nested_scopes() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let int = |i| Int::from_i64(ctx.clone(), i);
+    let solver = Solver::new(ctx.clone());
+    assert_eq!(solver.num_scopes(), 0);
+
+    solver.assert(&x.gt(&int(0)));
+    solver.push();
+    solver.assert(&x.lt(&int(10)));
+    solver.push();
+    solver.assert(&x.gt(&int(20)));
+    assert_eq!(solver.num_scopes(), 2);
+    assert_eq!(solver.get_assertions().len(), 3);
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    solver.pop(1);
+    assert_eq!(solver.num_scopes(), 1);
+    assert_eq!(solver.get_assertions().len(), 2);
+    assert_eq!(solver.check(), SatResult::Sat);
+
+    solver.push();
+    solver.push();
+    solver.assert(&x._eq(&int(0)));
+    assert_eq!(solver.check(), SatResult::Unsat);
+    solver.pop(3);
+    assert_eq!(solver.num_scopes(), 0);
+    assert_eq!(solver.get_assertions().len(), 1);
+    solver.assert(&x._eq(&int(20)));
+    assert_eq!(solver.check(), SatResult::Sat);
+
+    solver.push();
+    solver.reset();
+    assert_eq!(solver.num_scopes(), 0);
+    assert!(solver.get_assertions().is_empty());
+}
+
+#[test]
+fn test_solver_try_pop() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let solver = Solver::new(ctx);
+    solver.push();
+    assert_eq!(
+        solver.try_pop(2),
+        Err("can not pop 2 of 1 backtracking points".to_string())
+    );
+    assert_eq!(solver.num_scopes(), 1);
+    assert_eq!(solver.try_pop(1), Ok(()));
+    assert_eq!(solver.num_scopes(), 0);
+}
+
+#[test]
+fn test_piecewise_linear() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let r = |n: i64, d: i64| BigRational::new(BigInt::from(n), BigInt::from(d));
+    let real = |n: i32, d: i32| ast::Real::from_real(ctx.clone(), n, d);
+    let x = ast::Real::new_const(ctx.clone(), "x");
+    let y = ast::Real::new_const(ctx.clone(), "y");
+    let value_at = |f: &ast::Real, at: ast::Real| {
+        let solver = Solver::new(ctx.clone());
+        solver.assert(&x._eq(&at));
+        assert_eq!(solver.check(), SatResult::Sat);
+        let model = solver.get_model().unwrap();
+        model.eval(f, true).unwrap().as_real().unwrap()
+    };
+
+    // A concave function with a redundant breakpoint at x = 1.
+    let points = [
+        (r(0, 1), r(0, 1)),
+        (r(1, 1), r(2, 1)),
+        (r(2, 1), r(4, 1)),
+        (r(4, 1), r(5, 1)),
+    ];
+    let f = PiecewiseLinear::new(&points);
+    assert_eq!(f.points().len(), 3);
+    assert!(f.is_concave() && !f.is_convex());
+
+    let fx = piecewise_linear(&x, &points);
+    assert_eq!(value_at(&fx, real(3, 2)), (3, 1));
+    assert_eq!(value_at(&fx, real(3, 1)), (9, 2));
+    // Extrapolated beyond the breakpoints.
+    assert_eq!(value_at(&fx, real(-1, 1)), (-2, 1));
+    assert_eq!(value_at(&fx, real(6, 1)), (6, 1));
+
+    // The linear encoding of `f(x) >= y` agrees with the `ite` encoding.
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&!f.at_least(&x, &y)._eq(&fx.ge(&y)));
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    // As does that of `g(x) <= y`, for the convex `g = -f`.
+    let negated: Vec<_> = points
+        .iter()
+        .map(|(a, b)| (a.clone(), -b.clone()))
+        .collect();
+    let g = PiecewiseLinear::new(&negated);
+    assert!(g.is_convex());
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&!g.at_most(&x, &y)._eq(&g.apply(&x).le(&y)));
+    assert_eq!(solver.check(), SatResult::Unsat);
+}
+
+#[test]
+fn test_sym_matrix() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let a = SymMatrix::<ast::Real>::from_i64(ctx.clone(), &[&[1, 2], &[3, 4]]);
+    let b = SymMatrix::<ast::Real>::new_const(ctx.clone(), "b", 2, 2);
+    assert_eq!(b.get(1, 0).to_string(), "b_1_0");
+    assert_eq!(
+        a.transpose().row(0),
+        &SymVector::from_i64(ctx.clone(), &[1, 3])
+    );
+
+    // Find the inverse of `a`.
+    let id = SymMatrix::from_i64(ctx.clone(), &[&[1, 0], &[0, 1]]);
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&a.mul(&b)._eq(&id));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    let inv: Vec<Option<(i64, i64)>> = (0..4)
+        .map(|i| model.eval(b.get(i / 2, i % 2), true).unwrap().as_real())
+        .collect();
+    assert_eq!(
+        inv,
+        [Some((-2, 1)), Some((1, 1)), Some((3, 2)), Some((-1, 2))]
+    );
+
+    let u = SymVector::<ast::Int>::new_const(ctx.clone(), "u", 3);
+    let v = SymVector::from_i64(ctx.clone(), &[1, -1, 2]);
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&u.dot(&v)._eq(&ast::Int::from_i64(ctx.clone(), 0)));
+    solver.assert(&(&u - &v)._eq(&v.scale(&ast::Int::from_i64(ctx.clone(), 2))));
+    assert_eq!(solver.check(), SatResult::Unsat);
+}
+
+#[test]
+fn test_solver_assertions() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let solver = Solver::new(ctx.clone());
+    assert_eq!(solver.assertions().count(), 0);
+
+    let x = Int::new_const(ctx.clone(), "x");
+    solver.assert(&x.gt(&Int::from_i64(ctx.clone(), 0)));
+    solver.push();
+    solver.from_string("(declare-const y Int) (assert (= y 2))");
+    let p = Bool::new_const(ctx.clone(), "p");
+    solver.assert_and_track(&x._eq(&Int::from_i64(ctx.clone(), 1)), &p);
+    let assertions: Vec<String> = solver.assertions().map(|a| a.to_string()).collect();
+    assert_eq!(assertions, ["(> x 0)", "(= y 2)", "(=> p (= x 1))"]);
+
+    // The Bool wrappers outlive the solver.
+    let rest: Vec<Bool> = solver.assertions().collect();
+    solver.pop(1);
+    drop(solver);
+    assert_eq!(rest[0], x.gt(&Int::from_i64(ctx.clone(), 0)));
+}
+
+#[test]
+fn test_nnverify_two_layer_network() {
+    use z3::nnverify::{Activation, Layer, Network, Robustness};
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    // h = relu(x - 0.5), out = [h, 0.25 - h]
+    let net = Network::new(vec![
+        Layer::new(&[vec![1.0]], &[-0.5], Activation::Relu),
+        Layer::new(&[vec![1.0], vec![-1.0]], &[0.0, 0.25], Activation::Identity),
+    ]);
+    assert_eq!((net.num_inputs(), net.num_outputs()), (1, 2));
+
+    let x = ast::Real::new_const(ctx.clone(), "x");
+    let out = net.encode(std::slice::from_ref(&x));
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x._eq(&ast::Real::from_real(ctx.clone(), 2, 1)));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    assert_eq!(model.eval(&out[0], true).unwrap().as_real(), Some((3, 2)));
+    assert_eq!(model.eval(&out[1], true).unwrap().as_real(), Some((-5, 4)));
+
+    // Label 0 wins iff x > 0.625.
+    assert_eq!(
+        net.check_robust(ctx.clone(), &[1.0], 0.25, 0),
+        Robustness::Robust
+    );
+    assert_eq!(
+        net.check_robust(ctx.clone(), &[0.0], 0.5, 1),
+        Robustness::Robust
+    );
+    match net.check_robust(ctx.clone(), &[1.0], 0.5, 0) {
+        Robustness::Counterexample(xs) => {
+            let (n, d) = xs[0].as_real().unwrap();
+            assert!(n * 8 <= 5 * d && 2 * n >= d);
+        }
+        r => panic!("expected a counterexample, got {:?}", r),
+    }
+}
+
+#[test]
+fn test_solver_check_with_params() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let solver = Solver::new(ctx.clone());
+    let x = Int::new_const(ctx.clone(), "x");
+    let y = Int::new_const(ctx.clone(), "y");
+    solver.assert(
+        &(x.clone() * x.clone() + y.clone() * y.clone())._eq(&Int::from_i64(ctx.clone(), 25)),
+    );
+    solver.assert(&x.gt(&Int::from_i64(ctx.clone(), 0)));
+    solver.assert(&y.gt(&Int::from_i64(ctx.clone(), 0)));
+
+    let mut unlimited = Params::new(ctx.clone());
+    unlimited.set_u32("rlimit", 0);
+    solver.set_params(&unlimited);
+    let mut starved = Params::new(ctx.clone());
+    starved.set_u32("rlimit", 1);
+    assert_eq!(solver.check_with_params(&starved), Ok(SatResult::Unknown));
+    assert_eq!(solver.check(), SatResult::Sat);
+
+    // Persistent parameters come back after the call.
+    solver.set_params(&starved);
+    assert_eq!(solver.check(), SatResult::Unknown);
+    assert_eq!(solver.check_with_params(&unlimited), Ok(SatResult::Sat));
+    assert_eq!(solver.check(), SatResult::Unknown);
+}
+
+#[test]
+fn test_solver_check_with_unset_params() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&Bool::new_const(ctx.clone(), "a"));
+    let mut starved = Params::new(ctx.clone());
+    starved.set_u32("rlimit", 1);
+    starved.set_symbol("string_solver", "seq");
+    assert_eq!(solver.check_with_params(&starved), Ok(SatResult::Unknown));
+    // Both are back at Z3's defaults.
+    assert_eq!(solver.check(), SatResult::Sat);
+
+    // Module parameters are back at their global values.
+    let mut module = Params::new(ctx.clone());
+    module.set_u32("smt.random_seed", 3);
+    assert_eq!(solver.check_with_params(&module), Ok(SatResult::Sat));
+
+    // Parameters whose value Z3 does not report are not set at all.
+    let mut unknown = Params::new(ctx.clone());
+    unknown.set_u32("no_such_parameter", 1);
+    assert!(solver.check_with_params(&unknown).is_err());
+    assert_eq!(solver.check(), SatResult::Sat);
+}
+
+#[test]
+fn test_optimize_objective_handles() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let opt = Optimize::new(ctx.clone());
+    let int = |i| Int::from_i64(ctx.clone(), i);
+    // Two jobs of length 3 and 5 on one machine, both starting at 0 or later.
+    let a = Int::new_const(ctx.clone(), "a");
+    let b = Int::new_const(ctx.clone(), "b");
+    let end = Int::new_const(ctx.clone(), "end");
+    for (start, len) in [(&a, 3), (&b, 5)] {
+        opt.assert(&start.ge(&int(0)));
+        opt.assert(&end.ge(&(start.clone() + int(len))));
+    }
+    opt.assert(&Bool::or(
+        ctx.clone(),
+        &[a.ge(&(b.clone() + int(5))), b.ge(&(a.clone() + int(3)))],
+    ));
+    let makespan = opt.minimize(&end);
+    let early_a = opt.minimize(&a);
+    assert_eq!((makespan.index(), early_a.index()), (0, 1));
+    assert_eq!(makespan.term(), &end);
+
+    assert_eq!(opt.check(&[]), SatResult::Sat);
+    assert_eq!(
+        opt.lower(&makespan).unwrap().as_int().unwrap().as_i64(),
+        Some(8)
+    );
+    assert_eq!(
+        opt.upper(&makespan).unwrap().as_int().unwrap().as_i64(),
+        Some(8)
+    );
+    assert_eq!(
+        opt.lower(&early_a).unwrap().as_int().unwrap().as_i64(),
+        Some(0)
+    );
+    let model = opt.get_model().unwrap();
+    assert_eq!(makespan.value(&model).unwrap().as_i64(), Some(8));
+    assert_eq!(early_a.value(&model).unwrap().as_i64(), Some(0));
+    assert_eq!(model.eval(&b, true).unwrap().as_i64(), Some(3));
+
+    // `b` is fixed by the makespan, but nothing bounds `c`.
+    let c = Int::new_const(ctx.clone(), "c");
+    opt.assert(&c.ge(&int(0)));
+    assert_eq!(opt.maximize(&b).index(), 2);
+    let unbounded = opt.maximize(&c);
+    assert_eq!(opt.check(&[]), SatResult::Sat);
+    assert!(opt.upper(&unbounded).unwrap().to_string().contains("oo"));
+    assert_eq!(
+        opt.lower(&makespan).unwrap().as_int().unwrap().as_i64(),
+        Some(8)
+    );
+
+    // Objectives belong to their optimizer, and to the scope they were
+    // added in.
+    let other = Optimize::new(ctx.clone());
+    other.minimize(&a);
+    assert_eq!(other.lower(&makespan), None);
+    opt.push();
+    let scoped = opt.minimize(&c);
+    assert_eq!(opt.lower(&scoped), None);
+    assert_eq!(opt.check(&[]), SatResult::Sat);
+    assert!(opt.lower(&scoped).is_some());
+    opt.pop();
+    assert_eq!(opt.lower(&scoped), None);
+    assert_eq!(opt.upper_value(&scoped), None);
+    assert!(opt.lower(&makespan).is_some());
+
+    // An objective added at the index of a popped one is a different one.
+    let replacement = opt.minimize(&b);
+    assert_eq!(replacement.index(), scoped.index());
+    assert_eq!(opt.check(&[]), SatResult::Sat);
+    assert!(opt.lower(&replacement).is_some());
+    assert_eq!(opt.lower(&scoped), None);
+
+    // So is an objective of an optimizer that replaces a dropped one.
+    let dropped = Optimize::new(ctx.clone());
+    let stale = dropped.minimize(&a);
+    drop(dropped);
+    let fresh = Optimize::new(ctx.clone());
+    fresh.minimize(&a);
+    assert_eq!(fresh.check(&[]), SatResult::Sat);
+    assert_eq!(fresh.lower(&stale), None);
+}
+
+#[test]
+fn test_cnf_projected_count() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let [a, b, c, d] = ["a", "b", "c", "d"].map(|n| Bool::new_const(ctx.clone(), n));
+    let x = Int::new_const(ctx.clone(), "x");
+    let small = x.lt(&Int::from_i64(ctx.clone(), 3));
+    let formula = a.iff(&(b.clone() & !c.clone())) & (small | a.clone());
+
+    // `d` does not occur in the formula, so it doubles the count.
+    let projection = [a.clone(), b.clone(), c.clone(), d.clone()];
+    let cnf = Cnf::new(&[formula], &projection).unwrap();
+    assert_eq!(cnf.projection(), &projection[..]);
+    assert_eq!(cnf.var_index(&d), Some(4));
+    assert_eq!(cnf.var_term(1), Some(&a));
+    assert_eq!(cnf.var_term(0), None);
+    // Z3 normalizes `x < 3` to `!(3 <= x)`.
+    let atom = Int::from_i64(ctx.clone(), 3).le(&x);
+    let atom_var = cnf.var_index(&atom).unwrap();
+    assert_eq!(cnf.var_term(atom_var), Some(&atom));
+
+    // Count the assignments of the projection that extend to a model.
+    let n = cnf.num_vars();
+    assert!(n <= 16);
+    let mut projected = std::collections::HashSet::new();
+    for bits in 0u32..1 << n {
+        let value = |l: i32| (bits >> (l.abs() - 1) & 1 == 1) == (l > 0);
+        if cnf.clauses().iter().all(|c| c.iter().any(|&l| value(l))) {
+            projected.insert(bits & 0b1111);
+        }
+    }
+    // a <-> (b & !c) has 4 models over a, b, c, all of which extend to
+    // `x < 3`; times 2 for `d`.
+    assert_eq!(projected.len(), 8);
+
+    let dimacs = cnf.to_dimacs();
+    let mut lines = dimacs.lines();
+    assert_eq!(
+        lines.next(),
+        Some(format!("p cnf {} {}", n, cnf.clauses().len()).as_str())
+    );
+    assert_eq!(lines.next(), Some("c ind 1 2 3 4 0"));
+    assert!(dimacs.contains(&format!("c {} {}\n", atom_var, atom)));
+    assert!(!dimacs.contains("k!"));
+    assert_eq!(
+        dimacs
+            .lines()
+            .filter(|l| l.ends_with(" 0") && !l.starts_with('c'))
+            .count(),
+        cnf.clauses().len()
+    );
+}
+
+#[test]
+fn test_cnf_dimacs_comments() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    // A user constant named like Z3's fresh constants.
+    let k = Bool::new_const(ctx.clone(), "k!0");
+    let xs: Vec<Int> = (0..30)
+        .map(|i| Int::new_const(ctx.clone(), format!("some_long_name_{}", i)))
+        .collect();
+    let sum = Int::add(ctx.clone(), &xs.iter().collect::<Vec<_>>());
+    let big = sum.lt(&Int::from_i64(ctx.clone(), 3));
+    let formula = (k.clone() & big.clone()) | (!k.clone() & !big);
+
+    let cnf = Cnf::new(&[formula], &[]).unwrap();
+    let dimacs = cnf.to_dimacs();
+    let k_var = cnf.var_index(&k).unwrap();
+    assert!(dimacs.contains(&format!("c {} k!0\n", k_var)));
+    // Every line is a comment, the problem line or a clause.
+    for line in dimacs.lines() {
+        assert!(
+            line.starts_with("c ") || line.starts_with("p ") || line.ends_with(" 0"),
+            "{}",
+            line
+        );
+    }
+}
+
+#[test]
+fn test_fof_prove() {
+    use z3::fof::{Fof, Status};
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let fof = Fof::new(ctx.clone());
+    let (x, y, z) = (fof.var("X"), fof.var("Y"), fof.var("Z"));
+    let le = |a: &ast::Dynamic, b: &ast::Dynamic| fof.pred("le", &[a, b]);
+    let transitive = fof.forall(
+        &[&x, &y, &z],
+        &fof.implication_chain(&[&le(&x, &y), &le(&y, &z), &le(&x, &z)]),
+    );
+    assert_eq!(
+        fof.implication_chain(&[&le(&x, &y), &le(&y, &z), &le(&x, &z)]),
+        le(&x, &y).implies(&le(&y, &z).implies(&le(&x, &z)))
+    );
+
+    // Every element is below its successor.
+    let succ = fof.forall(&[&x], &le(&x, &fof.func("s", &[&x])));
+    let a = fof.constant("a");
+    let ssa = fof.func("s", &[&fof.func("s", &[&a])]);
+    assert!(matches!(
+        fof.prove(&[transitive.clone(), succ.clone()], &le(&a, &ssa)),
+        Status::Theorem
+    ));
+    assert_eq!(a.get_sort(), *fof.universe());
+
+    // Without transitivity, the conjecture does not follow.
+    match fof.prove(&[succ], &le(&a, &ssa)) {
+        Status::CounterSatisfiable(model) => {
+            assert_eq!(
+                model.eval(&le(&a, &ssa), true).unwrap().as_bool(),
+                Some(false)
+            );
+        }
+        s => panic!("expected a counter-model, got {:?}", s),
+    }
+    let exists_bigger = fof.exists(&[&y], &le(&a, &y));
+    assert!(matches!(
+        fof.prove(&[transitive], &exists_bigger),
+        Status::CounterSatisfiable(_)
+    ));
+}
+
+#[test]
+#[should_panic(expected = "le was used as a predicate of arity 2")]
+fn test_fof_arity_mismatch() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let fof = z3::fof::Fof::new(ctx);
+    let a = fof.constant("a");
+    fof.pred("le", &[&a, &a]);
+    fof.pred("le", &[&a]);
+}
+
+#[test]
+fn test_optimize_weighted_soft_groups() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let [a, b, c] = ["a", "b", "c"].map(|n| Bool::new_const(ctx.clone(), n));
+    let opt = Optimize::new(ctx.clone());
+    // At most one of a, b, c.
+    opt.assert(&!(a.clone() & b.clone()));
+    opt.assert(&!(a.clone() & c.clone()));
+    opt.assert(&!(b.clone() & c.clone()));
+
+    // The "first" group is optimized before "second": it prefers `b` or
+    // `c`, whose weights add up to more than that of `a`.
+    let half = BigRational::new(BigInt::from(1), BigInt::from(2));
+    opt.assert_soft(&a, 1, Some(Symbol::from("first")));
+    opt.assert_soft(&!a.clone(), (3, 2), Some(Symbol::from("first")));
+    opt.assert_soft(&c, half.clone(), Some(Symbol::from("second")));
+    opt.assert_soft(&b, half, Some(Symbol::from("second")));
+    opt.assert_soft(&b, 2u64, Some(Symbol::from("second")));
+    assert_eq!(opt.get_objectives().len(), 2);
+
+    assert_eq!(opt.check(&[]), SatResult::Sat);
+    let model = opt.get_model().unwrap();
+    let value = |v: &Bool| model.eval(v, true).unwrap().as_bool().unwrap();
+    assert_eq!((value(&a), value(&b), value(&c)), (false, true, false));
+}
+
+#[test]
+fn test_solver_check_euf_only() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let u = Sort::uninterpreted(ctx.clone(), Symbol::from("U"));
+    let f = FuncDecl::new(ctx.clone(), "f", &[&u, &u], &u);
+    let p = FuncDecl::new(ctx.clone(), "p", &[&u], &Sort::bool(ctx.clone()));
+    let [a, b, c] = ["a", "b", "c"].map(|n| ast::Dynamic::new_const(ctx.clone(), n, &u));
+    let flag = Bool::new_const(ctx.clone(), "flag");
+
+    let solver = Solver::new(ctx.clone());
+    assert_eq!(solver.check_euf_only(), Ok(SatResult::Sat));
+    solver.assert(&flag.ite(&a, &b)._eq(&c));
+    solver.assert(&p.apply(&[&f.apply(&[&a, &c])]).as_bool().unwrap());
+    solver.assert(&ast::Dynamic::distinct(ctx.clone(), &[&a, &b]));
+    assert_eq!(solver.check_euf_only(), Ok(SatResult::Sat));
+
+    solver.push();
+    solver.assert(&a._eq(&c));
+    solver.assert(&!p.apply(&[&f.apply(&[&c, &c])]).as_bool().unwrap());
+    assert_eq!(solver.check_euf_only(), Ok(SatResult::Unsat));
+    solver.pop(1);
+
+    let x = ast::Dynamic::new_const(ctx.clone(), "x", &u);
+    let all = ast::forall_const(ctx.clone(), &[&x], &[], &p.apply(&[&x]).as_bool().unwrap());
+    solver.push();
+    solver.assert(&all);
+    let err = solver.check_euf_only().unwrap_err();
+    assert_eq!(err.term(), &ast::Dynamic::from_ast(&all));
+    solver.pop(1);
+
+    let bv = BV::new_const(ctx.clone(), "bv", 8);
+    solver.assert(&bv.bvugt(&BV::from_u64(ctx.clone(), 1, 8)));
+    let err = solver.check_euf_only().unwrap_err();
+    assert!(err.to_string().starts_with("not in the theory of equality"));
+    assert_eq!(err.term().sort_kind(), SortKind::Bool);
+}
+
+#[test]
+fn test_optimize_priorities() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let y = Int::new_const(ctx.clone(), "y");
+    let int = |i| Int::from_i64(ctx.clone(), i);
+    let problem = || {
+        let opt = Optimize::new(ctx.clone());
+        opt.assert(&x.ge(&int(0)));
+        opt.assert(&y.ge(&int(0)));
+        opt.assert(&(x.clone() * int(2) + y.clone()).le(&int(4)));
+        let objectives = (opt.maximize(&y), opt.maximize(&x));
+        (opt, objectives)
+    };
+    let upper =
+        |opt: &Optimize, o: &Objective<Int>| opt.upper(o).unwrap().as_int().unwrap().as_i64();
+
+    // `y` comes first, which leaves no room for `x`.
+    let (opt, (oy, ox)) = problem();
+    opt.set_priority(Priority::Lexicographic);
+    assert_eq!(opt.check(&[]), SatResult::Sat);
+    assert_eq!((upper(&opt, &oy), upper(&opt, &ox)), (Some(4), Some(0)));
+
+    let (opt, (oy, ox)) = problem();
+    opt.set_priority(Priority::Box);
+    assert_eq!(opt.check(&[]), SatResult::Sat);
+    assert_eq!((upper(&opt, &oy), upper(&opt, &ox)), (Some(4), Some(2)));
+
+    let (opt, _) = problem();
+    let front: std::collections::BTreeSet<(i64, i64)> = opt
+        .pareto_models()
+        .map(|m| {
+            (
+                m.eval(&x, true).unwrap().as_i64().unwrap(),
+                m.eval(&y, true).unwrap().as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        front.into_iter().collect::<Vec<_>>(),
+        [(0, 4), (1, 2), (2, 0)]
+    );
+}
+
+#[test]
+fn test_retractable_solver_recycles_literals() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let int = |i| ast::Int::from_i64(ctx.clone(), i);
+
+    let mut solver = RetractableSolver::new(ctx.clone()).with_threshold(4);
+    let pos = solver.add(&x.gt(&int(0)));
+    let mut ids = vec![];
+    for i in 0..20 {
+        ids.push(solver.add(&x.le(&int(i))));
+        if ids.len() > 2 {
+            assert!(solver.retract(ids.remove(0)));
+        }
+    }
+    assert!(solver.retract(pos));
+    assert!(!solver.is_active(pos));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    assert!(model.eval(&x, true).unwrap().as_i64().unwrap() <= 18);
+
+    let zero = solver.add(&x._eq(&int(0)));
+    let pos = solver.add(&x.gt(&int(0)));
+    assert_eq!(solver.check(), SatResult::Unsat);
+    assert_eq!(solver.unsat_core(), [zero, pos]);
+    assert!(solver.retract(zero));
+    assert!(!solver.retract(zero));
+    assert_eq!(solver.check(), SatResult::Sat);
+
+    let stats = solver.stats();
+    assert_eq!(stats.added, 23);
+    assert_eq!(stats.retracted, 20);
+    assert_eq!(stats.rebuilds, 4);
+    assert_eq!(stats.dead, 0);
+    assert!(stats.literals <= 9, "{:?}", stats);
+}
+
+#[test]
+fn test_retractable_solver_rebuild_keeps_params() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+    let int = |i| ast::Int::from_i64(ctx.clone(), i);
+
+    let mut solver = RetractableSolver::new(ctx.clone());
+    solver.assert(&(x.clone() * x.clone() + y.clone() * y.clone())._eq(&int(25)));
+    solver.assert(&x.gt(&int(0)));
+    solver.assert(&y.gt(&int(0)));
+    let mut starved = Params::new(ctx.clone());
+    starved.set_u32("rlimit", 1);
+    solver.solver().set_params(&starved);
+    assert_eq!(solver.check(), SatResult::Unknown);
+    solver.rebuild();
+    assert_eq!(solver.check(), SatResult::Unknown);
+}
+
+#[test]
+fn test_optimize_smtlib2_round_trip() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let optimize = Optimize::new(ctx.clone());
+    optimize.from_string(
+        "(declare-const x Int) (declare-const y Int)
+         (assert (<= (+ x y) 10)) (assert (>= x 0)) (assert (>= y 0))
+         (assert-soft (> y 7) :weight 2)
+         (maximize x)",
+    );
+
+    let dir = std::env::temp_dir().join(format!("z3-optimize-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("problem.smt2");
+    std::fs::write(&path, optimize.to_string()).unwrap();
+    let copy = Optimize::new(ctx.clone());
+    copy.from_file(&path).unwrap();
+    assert_eq!(copy.to_string(), optimize.to_string());
+
+    assert_eq!(copy.check(&[]), SatResult::Sat);
+    let model = copy.get_model().unwrap();
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+    assert_eq!(model.eval(&x, true).unwrap().as_i64(), Some(2));
+    assert_eq!(model.eval(&y, true).unwrap().as_i64(), Some(8));
+
+    assert!(copy.from_file(dir.join("missing.smt2")).is_err());
+    std::fs::write(&path, "(assert (> z 0))").unwrap();
+    assert!(copy.from_file(&path).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_fixedpoint_from_string() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let fp = Fixedpoint::new(ctx.clone());
+    let mut params = Params::new(ctx.clone());
+    params.set_symbol("engine", "spacer");
+    fp.set_params(&params);
+    let queries = fp
+        .from_string(
+            "(declare-rel inv (Int))
+             (declare-rel err ())
+             (declare-var x Int)
+             (rule (inv 0))
+             (rule (=> (and (inv x) (< x 10)) (inv (+ x 1))))
+             (rule (=> (and (inv x) (> x 10)) err))
+             (query err)",
+        )
+        .unwrap();
+    assert_eq!(queries.len(), 1);
+    assert_eq!(fp.get_rules().len(), 3);
+    assert_eq!(fp.query(&queries[0]), SatResult::Unsat);
+
+    let err = queries[0].decl();
+    assert_eq!(fp.query_relations(&[&err]), SatResult::Unsat);
+
+    let copy = Fixedpoint::new(ctx.clone());
+    let reparsed = copy.from_string(&fp.to_smtlib2(&queries)).unwrap();
+    assert_eq!(copy.get_rules().len(), 3);
+    assert_eq!(reparsed, queries);
+
+    let background = ast::Int::new_const(ctx.clone(), "n").gt(&ast::Int::from_i64(ctx.clone(), 0));
+    copy.assert(&background);
+    assert_eq!(copy.get_assertions(), [background]);
+
+    assert!(fp.from_string("(rule (undeclared 0))").is_err());
+}
+
+#[test]
+fn test_solver_shuffle_and_rename() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let f = FuncDecl::new(
+        ctx.clone(),
+        "f",
+        &[&Sort::int(ctx.clone())],
+        &Sort::int(ctx.clone()),
+    );
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x.lt(&y));
+    solver.assert(&f.apply(&[&x])._eq(&ast::Dynamic::from_ast(&y)));
+    solver.assert(&y.lt(&ast::Int::from_i64(ctx.clone(), 5)));
+
+    let original: std::collections::HashSet<String> = solver
+        .get_assertions()
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    let mut orders = std::collections::HashSet::new();
+    for seed in 0..20 {
+        let shuffled = solver.shuffle_assertions(seed);
+        let order: Vec<String> = shuffled
+            .get_assertions()
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert_eq!(
+            order
+                .iter()
+                .cloned()
+                .collect::<std::collections::HashSet<_>>(),
+            original
+        );
+        orders.insert(order);
+    }
+    assert!(orders.len() > 1);
+
+    let (renamed, pairs) = solver.rename_symbols(3);
+    assert_eq!(pairs.len(), 2);
+    assert!(pairs
+        .iter()
+        .all(|(c, r)| c != r && r.to_string().starts_with('v')));
+    let text: String = renamed
+        .get_assertions()
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    assert!(!text.contains(" x") && !text.contains(" y") && text.contains("(f "));
+    assert_eq!(renamed.check(), SatResult::Sat);
+    assert_eq!(
+        solver
+            .rename_symbols(3)
+            .1
+            .iter()
+            .map(|(_, r)| r.to_string())
+            .collect::<Vec<_>>(),
+        pairs.iter().map(|(_, r)| r.to_string()).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_fixedpoint_answers() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let source = |bound| {
+        format!(
+            "(declare-rel inv (Int)) (declare-rel err ()) (declare-var x Int)
+             (rule (inv 0))
+             (rule (=> (and (inv x) (< x 10)) (inv (+ x 1))))
+             (rule (=> (and (inv x) (> x {})) err))
+             (query err)",
+            bound
+        )
+    };
+    let inv = FuncDecl::new(
+        ctx.clone(),
+        "inv",
+        &[&Sort::int(ctx.clone())],
+        &Sort::bool(ctx.clone()),
+    );
+
+    assert!(Fixedpoint::new(ctx.clone()).get_answer().is_none());
+    let fp = Fixedpoint::new(ctx.clone());
+    fp.set_engine(FixedpointEngine::Spacer);
+    let queries = fp.from_string(&source(10)).unwrap();
+    assert_eq!(fp.query(&queries[0]), SatResult::Unsat);
+    // The answer defines `inv` by an invariant that excludes the error states.
+    let answer = fp.get_answer().unwrap().as_bool().unwrap();
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&answer);
+    solver.assert(&inv.apply(&[&x]).as_bool().unwrap());
+    solver.assert(&x.gt(&ast::Int::from_i64(ctx.clone(), 10)));
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    assert!(fp.get_cover_delta(None, &inv).is_some());
+    assert!(fp.get_num_levels(&inv) > 0);
+    assert!(fp.get_cover_delta(Some(0), &inv).is_some());
+
+    let fp = Fixedpoint::new(ctx.clone());
+    fp.set_engine(FixedpointEngine::Spacer);
+    let queries = fp.from_string(&source(5)).unwrap();
+    assert_eq!(fp.query(&queries[0]), SatResult::Sat);
+    assert!(fp.get_answer().is_some());
+}
+
+#[test]
+fn test_optimize_objective_values() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Real::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+    let rational = |n: i64, d: i64| BigRational::new(n.into(), d.into());
+    let bounded = || {
+        let opt = Optimize::new(ctx.clone());
+        opt.assert(&x.le(&ast::Real::from_real(ctx.clone(), 5, 2)));
+        opt.assert(&x.ge(&ast::Real::from_real(ctx.clone(), -1, 3)));
+        opt
+    };
+
+    let opt = bounded();
+    let max_x = opt.maximize(&x);
+    let max_y = opt.maximize(&y);
+    assert_eq!(opt.check(&[]), SatResult::Sat);
+    assert_eq!(
+        opt.lower_value(&max_x),
+        Some(ObjectiveValue::Finite(rational(5, 2)))
+    );
+    assert_eq!(opt.upper_value(&max_x), opt.lower_value(&max_x));
+    assert_eq!(
+        opt.upper_value(&max_y),
+        Some(ObjectiveValue::Infinite(num::bigint::Sign::Plus))
+    );
+    assert_eq!(opt.upper(&max_y).unwrap().to_string(), "oo");
+
+    let opt = bounded();
+    let min_x = opt.minimize(&x);
+    assert_eq!(opt.check(&[]), SatResult::Sat);
+    assert_eq!(
+        opt.lower_value(&min_x),
+        Some(ObjectiveValue::Finite(rational(-1, 3)))
+    );
+    // `max_y` has no counterpart among the objectives of `opt`.
+    assert_eq!(opt.upper_value(&max_y), None);
+}
+
+#[test]
+fn test_apply_result_stats() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+    let goal = Goal::new(ctx.clone(), false, false, false);
+    goal.assert(&x._eq(&(y.clone() + ast::Int::from_i64(ctx.clone(), 1))));
+    goal.assert(&(x.gt(&ast::Int::from_i64(ctx.clone(), 0)) | y.lt(&x)));
+    goal.assert(&y.gt(&ast::Int::from_i64(ctx.clone(), 3)));
+
+    let split = Tactic::new(ctx.clone(), "split-clause")
+        .apply(&goal, None)
+        .unwrap();
+    let stats = split.stats();
+    assert_eq!(stats.subgoals, 2);
+    assert_eq!(stats.formulas_before, 3);
+    assert_eq!(stats.formulas_after, 6);
+    assert!(stats.exprs_before > 0 && stats.exprs_after > stats.exprs_before);
+
+    let copy = split.clone();
+    assert_eq!(copy.stats(), stats);
+    assert_eq!(copy.elapsed(), split.elapsed());
+    assert_eq!(split.list_subgoals().count(), 2);
+
+    let solved = Tactic::new(ctx.clone(), "solve-eqs")
+        .apply(&goal, None)
+        .unwrap();
+    assert_eq!(solved.stats().subgoals, 1);
+    assert!(solved.stats().formulas_after < solved.stats().formulas_before);
+}
+
+#[test]
+fn test_simplify_params() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let a = ast::Bool::new_const(ctx.clone(), "a");
+    let b = ast::Bool::new_const(ctx.clone(), "b");
+    let conjunction = ast::Bool::and(ctx.clone(), &[a, b]);
+
+    let mut params = Params::new(ctx.clone());
+    params.set_bool("elim_and", true);
+    let eliminated = conjunction.simplify_params(&params);
+    assert_eq!(eliminated.decl().kind(), DeclKind::NOT);
+    assert_eq!(eliminated.to_string(), "(not (or (not a) (not b)))");
+    assert_eq!(conjunction.simplify().decl().kind(), DeclKind::AND);
+
+    let descrs = ast::simplify_param_descrs(ctx.clone());
+    assert!(!descrs.is_empty());
+    assert_eq!(descrs.names().len(), descrs.len());
+    assert_eq!(descrs.kind("som"), Some(ParamKind::Bool));
+    assert_eq!(descrs.kind("no_such_param"), None);
+    assert!(descrs.documentation("som").unwrap().contains("monomial"));
+    assert!(descrs.documentation("no_such_param").is_none());
+    assert!(descrs.to_string().contains("pull_cheap_ite"));
+}
+
+#[test]
+fn test_substitute_vars_and_funs() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+
+    let v0 = ast::Dynamic::new_bound(ctx.clone(), 0, &int)
+        .as_int()
+        .unwrap();
+    let v1 = ast::Dynamic::new_bound(ctx.clone(), 1, &int)
+        .as_int()
+        .unwrap();
+    let open = v0.clone() + v1.clone();
+    assert_eq!(open.substitute_vars(&[&x, &y]).to_string(), "(+ x y)");
+
+    // Constants are functions without arguments.
+    let c = FuncDecl::new(ctx.clone(), "c", &[], &int);
+    let g = FuncDecl::new(ctx.clone(), "g", &[&int], &int);
+    let term = g.apply(&[&c.apply(&[])]).as_int().unwrap() + c.apply(&[]).as_int().unwrap();
+    let seven = ast::Int::from_i64(ctx.clone(), 7);
+    let doubled = v0.clone() * ast::Int::from_i64(ctx.clone(), 2);
+    let replaced = term.substitute_funs(&[(&c, &seven), (&g, &doubled)]);
+    assert_eq!(replaced.simplify().as_i64(), Some(21));
+
+    // Applications under a quantifier may mention its bound variables.
+    let body = g.apply(&[&x]).as_int().unwrap()._eq(&x);
+    let forall = ast::forall_const(ctx.clone(), &[&x], &[], &body);
+    let replaced = forall.substitute_funs(&[(&g, &v0)]);
+    assert_eq!(replaced.simplify().as_bool(), Some(true));
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&replaced);
+    assert_eq!(solver.check(), SatResult::Sat);
+}
+
+#[test]
+#[should_panic(expected = "replacement of c has the wrong sort")]
+fn test_substitute_funs_wrong_sort() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let c = FuncDecl::new(ctx.clone(), "c", &[], &int);
+    let t = ast::Bool::from_bool(ctx.clone(), true);
+    c.apply(&[]).substitute_funs(&[(&c, &t)]);
+}
+
+#[test]
+fn test_seq_of_datatype_values() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let event = DatatypeBuilder::new(ctx.clone(), "Event")
+        .variant("Open", vec![])
+        .variant(
+            "Write",
+            vec![("bytes", DatatypeAccessor::Sort(Sort::int(ctx.clone())))],
+        )
+        .variant("Close", vec![])
+        .finish();
+    let open = event.variants[0].constructor.apply(&[]);
+    let close = event.variants[2].constructor.apply(&[]);
+    let int = |n| ast::Int::from_i64(ctx.clone(), n);
+
+    let trace = ast::Seq::new_const(ctx.clone(), "trace", &event.sort);
+    assert_eq!(trace.element_sort(), event.sort);
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&trace.length()._eq(&int(3)));
+    solver.assert(&ast::Seq::unit(&open).prefix(&trace));
+    solver.assert(&ast::Seq::unit(&close).suffix(&trace));
+    let middle = trace.nth(&int(1));
+    solver.assert(
+        &event.variants[1]
+            .tester
+            .apply(&[&middle])
+            .as_bool()
+            .unwrap(),
+    );
+    solver.assert(
+        &event.variants[1].accessors[0]
+            .apply(&[&middle])
+            .as_int()
+            .unwrap()
+            ._eq(&int(42)),
+    );
+    assert_eq!(solver.check(), SatResult::Sat);
+
+    let model = solver.get_model().unwrap();
+    let value = model.eval(&trace, true).unwrap();
+    let elements = value.elements().unwrap();
+    assert_eq!(elements.len(), 3);
+    assert_eq!(elements[0], open);
+    assert_eq!(elements[1].to_string(), "(Write 42)");
+    assert_eq!(elements[2], close);
+
+    // Rebuilding the value from its elements gives the same sequence.
+    let refs: Vec<&dyn Ast> = elements.iter().map(|e| e as &dyn Ast).collect();
+    let rebuilt = ast::Seq::from_elements(ctx.clone(), &event.sort, &refs);
+    assert_eq!(rebuilt._eq(&value).simplify().as_bool(), Some(true));
+    let tail = value.extract(&int(1), &int(2));
+    assert_eq!(tail.simplify().elements().unwrap().len(), 2);
+    assert_eq!(value.at(&int(5)).simplify().elements(), Some(vec![]));
+
+    let empty = ast::Seq::empty(ctx.clone(), &event.sort);
+    assert_eq!(empty.elements(), Some(vec![]));
+    assert!(ast::Dynamic::from_ast(&empty).as_seq().is_some());
+    assert!(trace.elements().is_none());
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn test_derive_z3_enum() {
+    use z3::Z3Enum;
+
+    #[derive(Debug, PartialEq, Z3Enum)]
+    enum Door {
+        Open,
+        Closed,
+        Locked,
+    }
+
+    assert_eq!(Door::NAME, "Door");
+    assert_eq!(Door::VARIANT_NAMES, ["Open", "Closed", "Locked"]);
+    assert_eq!(Door::Locked.to_index(), 2);
+    assert_eq!(Door::from_index(1), Some(Door::Closed));
+    assert_eq!(Door::from_index(3), None);
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let door = Door::enum_sort(ctx.clone());
+    assert_eq!(door.sort.to_string(), "Door");
+    assert_eq!(door.value(&Door::Open).to_string(), "Open");
+    assert_eq!(door.decode(&door.value(&Door::Locked)), Some(Door::Locked));
+
+    // Every transition unlocks at most one step: Locked -> Closed -> Open.
+    let before = door.new_const("before");
+    let after = door.new_const("after");
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&door.tester(&Door::Locked, &before));
+    solver.assert(&after._eq(&before).not());
+    solver.assert(&door.tester(&Door::Open, &after).not());
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    assert_eq!(door.from_model(&model, &before), Some(Door::Locked));
+    assert_eq!(door.from_model(&model, &after), Some(Door::Closed));
+    assert_eq!(door.decode(&before), None);
+}
+
+#[test]
+fn test_ast_node_fold_and_visit() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+
+    match ast::Real::from_real(ctx.clone(), 1, 3).node() {
+        ast::AstNode::Numeral { value, sort } => {
+            assert_eq!(value, "1/3");
+            assert_eq!(sort, Sort::real(ctx.clone()));
+        }
+        node => panic!("unexpected {:?}", node),
+    }
+    match ast::BV::from_u64(ctx.clone(), 200, 8).node() {
+        ast::AstNode::Numeral { value, .. } => assert_eq!(value, "200"),
+        node => panic!("unexpected {:?}", node),
+    }
+    let exists = ast::exists_const(ctx.clone(), &[&x, &y], &[], &x.lt(&y));
+    match exists.node() {
+        ast::AstNode::Quantifier { kind, bound, .. } => {
+            assert_eq!(kind, ast::QuantifierKind::Exists);
+            let names: Vec<_> = bound.iter().map(|(n, _)| n.clone()).collect();
+            assert_eq!(names, ["x".into(), "y".into()] as [Symbol; 2]);
+        }
+        node => panic!("unexpected {:?}", node),
+    }
+
+    // Rebuild a term with fold, swapping the operands of every `-`.
+    let term = (&x - &y) * (&y - ast::Int::from_i64(ctx.clone(), 1));
+    let swapped = ast::fold(&term, |a, node, children: Vec<ast::Dynamic>| match node {
+        ast::AstNode::App { decl, .. } if decl.kind() == DeclKind::SUB => {
+            decl.apply(&[&children[1], &children[0]])
+        }
+        ast::AstNode::App { decl, .. } if !children.is_empty() => {
+            let args: Vec<&dyn Ast> = children.iter().map(|c| c as &dyn Ast).collect();
+            decl.apply(&args)
+        }
+        _ => a.clone(),
+    });
+    assert_eq!(swapped.to_string(), "(* (- y x) (- 1 y))");
+
+    // Deep terms do not overflow the stack.
+    let mut deep = x.clone();
+    for _ in 0..100_000 {
+        deep += &y;
+    }
+    let size = ast::fold(&deep, |_, _, children: Vec<u64>| {
+        1 + children.iter().sum::<u64>()
+    });
+    assert_eq!(size, 200_001);
+
+    // visit can prune: nothing below the quantifier is reached.
+    let formula = ast::Bool::and(ctx.clone(), &[exists.clone(), x._eq(&y)]);
+    let mut reached = vec![];
+    ast::visit(&formula, |a, node| {
+        reached.push(a.to_string());
+        !matches!(node, ast::AstNode::Quantifier { .. })
+    });
+    assert_eq!(reached.len(), 5);
+    assert!(!reached.iter().any(|s| s.starts_with("(<")));
+}
+
+#[test]
+fn test_ast_structure_accessors() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let two = ast::Int::from_i64(ctx.clone(), 2);
+    let product = &x * &two;
+
+    assert!(two.is_numeral() && two.is_const() && two.is_app());
+    assert!(!x.is_numeral() && x.is_const());
+    assert!(!product.is_numeral() && !product.is_const());
+    assert!(ast::BV::from_u64(ctx.clone(), 3, 4).is_numeral());
+    assert_eq!(product.kind(), AstKind::App);
+    assert_eq!(product.decl().kind(), DeclKind::MUL);
+    assert_eq!(product.num_children(), 2);
+    assert_eq!(product.children()[1].as_int().unwrap().as_i64(), Some(2));
+
+    // Quantifiers and bound variables are not applications.
+    let forall = ast::forall_const(ctx.clone(), &[&x], &[], &product._eq(&two));
+    assert_eq!(forall.kind(), AstKind::Quantifier);
+    assert!(!forall.is_app() && !forall.is_const() && !forall.is_numeral());
+    assert_eq!(forall.num_children(), 0);
+    assert!(forall.children().is_empty());
+    assert!(forall.nth_child(0).is_none());
+    assert!(forall.safe_decl().is_err());
+    let var = ast::Dynamic::new_bound(ctx.clone(), 0, &Sort::int(ctx.clone()));
+    assert_eq!(var.kind(), AstKind::Var);
+    assert_eq!(var.num_children(), 0);
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn test_derive_z3_struct() {
+    use z3::{Z3Enum, Z3Struct, Z3Value};
+
+    #[derive(Debug, PartialEq, Z3Enum)]
+    enum Color {
+        Red,
+        Blue,
+    }
+
+    #[derive(Debug, PartialEq, Z3Struct)]
+    struct Point {
+        x: i64,
+        y: i64,
+    }
+
+    #[derive(Debug, PartialEq, Z3Struct)]
+    struct Pixel {
+        at: Point,
+        color: Color,
+        lit: bool,
+    }
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let p = SymPoint::new_const(ctx.clone(), "p");
+    let q = SymPoint::new_const(ctx.clone(), "q");
+    assert_eq!(p.get_sort().to_string(), "Point");
+    assert_eq!(p.x().to_string(), "(Point.x p)");
+
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&p.x()._eq(&ast::Int::from_i64(ctx.clone(), 3)));
+    solver.assert(&p.y()._eq(&(p.x() * 2i64)));
+    solver.assert(&q._eq(&SymPoint::new(ctx.clone(), &p.y(), &p.x())));
+    let pixel = SymPixel::fresh_const(ctx.clone(), "pixel");
+    solver.assert(&pixel.at()._eq(&q));
+    solver.assert(&pixel.lit());
+    let blue = Color::Blue.to_ast(ctx.clone()).as_datatype().unwrap();
+    solver.assert(&pixel.color()._eq(&blue));
+    assert_eq!(solver.check(), SatResult::Sat);
+
+    let model = solver.get_model().unwrap();
+    assert_eq!(p.from_model(&model), Some(Point { x: 3, y: 6 }));
+    assert_eq!(
+        pixel.from_model(&model),
+        Some(Pixel {
+            at: Point { x: 6, y: 3 },
+            color: Color::Blue,
+            lit: true,
+        })
+    );
+
+    // Concrete values round-trip through Z3.
+    let value = Pixel {
+        at: Point { x: -1, y: 0 },
+        color: Color::Red,
+        lit: false,
+    };
+    let ast = value.to_ast(ctx.clone());
+    assert_eq!(ast.to_string(), "(Pixel (Point (- 1) 0) Red false)");
+    assert_eq!(Pixel::decode(&ast), Some(value));
+    assert_eq!(Point::decode(&ast), None);
+    assert_eq!(<u8 as Z3Value>::decode(&300u64.to_ast(ctx.clone())), None);
+}
+
+#[test]
+fn test_flat_term_round_trip() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let r = ast::Real::new_const(ctx.clone(), "r");
+    let b = ast::BV::new_const(ctx.clone(), "b", 8);
+    let f = FuncDecl::new(ctx.clone(), "f", &[&int, &int], &int);
+    let decls = [x.decl(), r.decl(), b.decl(), f.clone()];
+
+    let fx = f
+        .apply(&[&x, &ast::Int::from_i64(ctx.clone(), 7)])
+        .as_int()
+        .unwrap();
+    let real = ast::Real::from_int(&fx).gt(&(&r * ast::Real::from_real(ctx.clone(), -1, 3)));
+    let bv = b.bvadd(&ast::BV::from_u64(ctx.clone(), 15, 8)).bvult(&b);
+    let term = ast::Bool::and(
+        ctx.clone(),
+        &[real, bv, fx.modulo(&x.clone())._eq(&x).not()],
+    );
+
+    let flat = FlatTerm::from_ast(&term).unwrap();
+    assert_eq!(flat.nodes().last().unwrap().op, "and");
+    let text = flat.to_string();
+    assert!(text.contains("(f x 7)") && text.contains("-1/3") && text.contains("#x0f"));
+    let parsed: FlatTerm = text.parse().unwrap();
+    assert_eq!(parsed, flat);
+    let back = parsed.to_ast(ctx.clone(), &decls).unwrap();
+    assert_eq!(back, ast::Dynamic::from_ast(&term));
+
+    // Egg-style input: 2.5 is a real, #b101 a 3-bit vector.
+    let parsed: FlatTerm = "(ite (<= r 2.5) (bvnot #b101) (concat #b1 #b00))"
+        .parse()
+        .unwrap();
+    let built = parsed.to_ast(ctx.clone(), &decls).unwrap();
+    assert_eq!(built.get_sort(), Sort::bitvector(ctx.clone(), 3));
+
+    // Indexed operators keep their indices.
+    let wide = b.extract(3, 0).zero_ext(4).bvadd(&b.sign_ext(0));
+    let int2bv = ast::BV::from_int(&x, 8);
+    let indexed = ast::Bool::and(ctx.clone(), &[wide._eq(&b), int2bv._eq(&b)]);
+    let flat = FlatTerm::from_ast(&indexed).unwrap();
+    let text = flat.to_string();
+    assert!(text.contains("((_ extract 3 0) b)"), "{}", text);
+    assert!(text.contains("((_ zero_extend 4)"), "{}", text);
+    let parsed: FlatTerm = text.parse().unwrap();
+    assert_eq!(parsed, flat);
+    let back = parsed.to_ast(ctx.clone(), &decls).unwrap();
+    assert_eq!(back, ast::Dynamic::from_ast(&indexed));
+    let text = "(= ((_ rotate_left 2) b) ((_ repeat 1) b))";
+    let rotated = text.parse::<FlatTerm>().unwrap();
+    let built = rotated.to_ast(ctx.clone(), &decls).unwrap();
+    assert_eq!(FlatTerm::from_ast(&built).unwrap().to_string(), text);
+    assert!("((_ extract 3) b)"
+        .parse::<FlatTerm>()
+        .unwrap()
+        .to_ast(ctx.clone(), &decls)
+        .is_err());
+    assert!("((extract 3 0) b)".parse::<FlatTerm>().is_err());
+    assert!("((_ extract 3 0 b)".parse::<FlatTerm>().is_err());
+    let zeros = ast::Array::const_array(ctx.clone(), &int, &x);
+    assert!(FlatTerm::from_ast(&zeros).is_err());
+
+    // Nodes can be added directly, children first.
+    let mut manual = FlatTerm::new();
+    let leaf = manual.add("x", &[]);
+    let one = manual.add("1", &[]);
+    let sum = manual.add("+", &[leaf, one]);
+    assert_eq!(manual.add("x", &[]), leaf);
+    manual.add("*", &[sum, sum]);
+    assert_eq!(manual.to_string(), "(* (+ x 1) (+ x 1))");
+
+    // Errors.
+    let forall = ast::forall_const(ctx.clone(), &[&x], &[], &x._eq(&x));
+    assert!(FlatTerm::from_ast(&forall).is_err());
+    assert!("(+ x".parse::<FlatTerm>().is_err());
+    assert!("x)".parse::<FlatTerm>().is_err());
+    assert!("x y".parse::<FlatTerm>().is_err());
+    assert!("(+ x y)"
+        .parse::<FlatTerm>()
+        .unwrap()
+        .to_ast(ctx.clone(), &decls)
+        .is_err());
+    assert!("(+ x r b)"
+        .parse::<FlatTerm>()
+        .unwrap()
+        .to_ast(ctx.clone(), &decls)
+        .is_err());
+    assert!("(not x x)"
+        .parse::<FlatTerm>()
+        .unwrap()
+        .to_ast(ctx.clone(), &decls)
+        .is_err());
+    assert!(FlatTerm::new().to_ast(ctx.clone(), &decls).is_err());
+}
+
+#[cfg(feature = "egg")]
+#[test]
+fn test_flat_term_egg_conversions() {
+    use egg::{RecExpr, SymbolLang};
+    use std::convert::TryFrom;
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let b = ast::BV::new_const(ctx.clone(), "b", 8);
+    let decls = [x.decl(), b.decl()];
+    let sum = &x + ast::Int::from_i64(ctx.clone(), -3);
+    let term = ast::Bool::and(
+        ctx.clone(),
+        &[
+            (&sum * &sum).gt(&x),
+            b.extract(3, 0)._eq(&ast::BV::from_u64(ctx.clone(), 5, 4)),
+        ],
+    );
+
+    let flat = FlatTerm::from_ast(&term).unwrap();
+    let expr = RecExpr::<SymbolLang>::try_from(&flat).unwrap();
+    assert_eq!(expr.as_ref().len(), flat.nodes().len());
+    assert_eq!(FlatTerm::from(&expr), flat);
+    let back = FlatTerm::from(&expr).to_ast(ctx.clone(), &decls).unwrap();
+    assert_eq!(back, ast::Dynamic::from_ast(&term));
+
+    // Terms built in egg convert too, with repeated nodes stored once.
+    let mut built = RecExpr::<SymbolLang>::default();
+    let x1 = built.add(SymbolLang::leaf("x"));
+    let x2 = built.add(SymbolLang::leaf("x"));
+    built.add(SymbolLang::new("+", vec![x1, x2]));
+    let flat = FlatTerm::from(&built);
+    assert_eq!(flat.nodes().len(), 2);
+    assert_eq!(
+        flat.to_ast(ctx.clone(), &decls).unwrap(),
+        ast::Dynamic::from_ast(&(&x + &x))
+    );
+}
+
+#[test]
+fn test_quantifier_builder() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let f = FuncDecl::new(ctx.clone(), "f", &[&int], &int);
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let f_x = f.apply(&[&x]).as_int().unwrap();
+
+    let q = ast::QuantifierBuilder::forall(ctx.clone(), &[&x])
+        .pattern(Pattern::new(ctx.clone(), &[&f_x]))
+        .weight(3)
+        .qid("pos")
+        .skid("sk")
+        .build(&f_x.ge(&ast::Int::from_i64(ctx.clone(), 0)));
+    let printed = q.to_string();
+    assert!(printed.contains(":qid pos"), "{}", printed);
+    assert!(printed.contains(":weight 3"), "{}", printed);
+    assert!(printed.contains(":pattern"), "{}", printed);
+    match q.node() {
+        ast::AstNode::Quantifier {
+            kind,
+            weight,
+            bound,
+            ..
+        } => {
+            assert_eq!(kind, ast::QuantifierKind::Forall);
+            assert_eq!(weight, 3);
+            assert_eq!(bound.len(), 1);
+        }
+        node => panic!("not a quantifier: {:?}", node),
+    }
+
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&q);
+    let five = ast::Int::from_i64(ctx.clone(), 5);
+    solver.assert(
+        &f.apply(&[&five])
+            .as_int()
+            .unwrap()
+            .lt(&ast::Int::from_i64(ctx.clone(), 0)),
+    );
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    let e = ast::QuantifierBuilder::exists(ctx.clone(), &[&x])
+        .no_pattern(&f_x)
+        .build(&x._eq(&five));
+    assert!(matches!(
+        e.node(),
+        ast::AstNode::Quantifier {
+            kind: ast::QuantifierKind::Exists,
+            ..
+        }
+    ));
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&e);
+    assert_eq!(solver.check(), SatResult::Sat);
+}
+
+#[test]
+fn test_array_lambda_const() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let bv32 = Sort::bitvector(ctx.clone(), 32);
+    let addr = ast::BV::new_const(ctx.clone(), "addr", 32);
+    let base = ast::BV::new_const(ctx.clone(), "base", 32);
+
+    // A memory initialized so that each word holds its address plus `base`.
+    let mem = ast::Array::lambda_const(ctx.clone(), &[&addr], &addr.bvadd(&base));
+    assert_eq!(mem.get_sort(), Sort::array(ctx.clone(), &bv32, &bv32));
+    assert!(matches!(
+        mem.node(),
+        ast::AstNode::Quantifier {
+            kind: ast::QuantifierKind::Lambda,
+            ..
+        }
+    ));
+
+    let solver = Solver::new(ctx.clone());
+    let at = ast::BV::from_u64(ctx.clone(), 16, 32);
+    solver.assert(&base._eq(&ast::BV::from_u64(ctx.clone(), 1, 32)));
+    let word = mem.select(&at).as_bv().unwrap();
+    solver.assert(&word._eq(&ast::BV::from_u64(ctx.clone(), 17, 32)).not());
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    // Stores on top of a lambda behave like on any other array.
+    let stored = mem.store(&at, &ast::BV::from_u64(ctx.clone(), 0, 32));
+    let solver = Solver::new(ctx.clone());
+    solver.assert(
+        &stored
+            .select(&at)
+            ._eq(&ast::BV::from_u64(ctx.clone(), 0, 32).into())
+            .not(),
+    );
+    assert_eq!(solver.check(), SatResult::Unsat);
+}
+
+#[test]
+#[should_panic(expected = "a lambda needs at least one bound")]
+fn test_array_lambda_const_without_bounds() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let zero = ast::Int::from_i64(ctx.clone(), 0);
+    ast::Array::lambda_const(ctx.clone(), &[], &zero);
+}
+
+#[cfg(feature = "term-gen")]
+#[test]
+fn test_term_generator() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+
+    // The same seed gives the same term.
+    let gen = |seed| TermGenerator::new(ctx.clone(), seed).bitvectors(Some(4));
+    assert_eq!(gen(7).gen_bool(), gen(7).gen_bool());
+
+    let vars = gen(0).variables();
+    assert_eq!(vars.len(), 9);
+    for seed in 0..50 {
+        let mut g = gen(seed).nonlinear(true).max_depth(5);
+        let f = g.gen_bool();
+        assert_eq!(f.get_sort(), Sort::bool(ctx.clone()));
+        assert_eq!(g.gen_int().get_sort(), Sort::int(ctx.clone()));
+        assert_eq!(g.gen_bv().get_sort(), Sort::bitvector(ctx.clone(), 4));
+
+        // Only the advertised constants occur.
+        ast::visit(&f, |a, node| {
+            if let ast::AstNode::App { args, decl } = node {
+                if args.is_empty() && decl.kind() == DeclKind::UNINTERPRETED {
+                    assert!(vars.contains(a), "{} is not a variable", a);
+                }
+            }
+            true
+        });
+    }
+
+    // Without variables, formulas are ground and simplify to a value.
+    for seed in 0..20 {
+        let f = TermGenerator::new(ctx.clone(), seed).num_vars(0).gen_bool();
+        assert!(f.simplify().as_bool().is_some(), "{}", f);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_diffcheck() {
+    use z3::diffcheck::{self, ExternalSolver};
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::BV::new_const(ctx.clone(), "x", 8);
+    let sat = [x
+        .bvmul(&ast::BV::from_u64(ctx.clone(), 3, 8))
+        ._eq(&ast::BV::from_u64(ctx.clone(), 1, 8))];
+    let unsat = [x.bvult(&x)];
+
+    // The exported script parses back to the same formulas.
+    let script = diffcheck::to_smtlib(&sat);
+    let parsed = Context::parse_smtlib2_string(ctx.clone(), &script, &[], &[]).unwrap();
+    assert_eq!(parsed, sat);
+
+    let echo = |answer: &str| {
+        ExternalSolver::new("sh")
+            .arg("-c")
+            .arg(format!("cat > /dev/null; echo {}", answer))
+    };
+    assert_eq!(diffcheck::compare(&sat, &echo("sat")).unwrap(), None);
+    assert_eq!(diffcheck::compare(&unsat, &echo("unknown")).unwrap(), None);
+    let d = diffcheck::compare(&unsat, &echo("sat")).unwrap().unwrap();
+    assert_eq!((d.z3, d.other), (SatResult::Unsat, SatResult::Sat));
+    assert!(d.smtlib.contains("bvult"));
+
+    assert!(echo("oops").check(&sat).is_err());
+
+    // A solver that prints more than a pipe holds before it reads the
+    // script, which is also more than a pipe holds.
+    let chatty = ExternalSolver::new("sh")
+        .arg("-c")
+        .arg("yes sat | head -n 100000; cat > /dev/null");
+    let script = "(check-sat)\n".repeat(100_000);
+    assert_eq!(chatty.check_smtlib(&script), Ok(SatResult::Sat));
+    assert!(ExternalSolver::new("/nonexistent/solver")
+        .check(&sat)
+        .is_err());
+
+    let goal = Goal::new(ctx.clone(), false, false, false);
+    goal.assert(&unsat[0]);
+    assert!(diffcheck::compare_goal(&goal, &echo("sat"))
+        .unwrap()
+        .is_some());
+}
+
+#[test]
+#[should_panic(expected = "invalid datatype declaration")]
+fn test_datatype_without_finite_values() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    // Every stream has a tail, so there are no streams.
+    DatatypeBuilder::new(ctx.clone(), "Stream")
+        .variant(
+            "cons",
+            vec![
+                ("head", DatatypeAccessor::Sort(Sort::int(ctx.clone()))),
+                ("tail", DatatypeAccessor::Datatype("Stream".into())),
+            ],
+        )
+        .finish();
+}
+
+#[test]
+#[should_panic(expected = "\"Forest\"")]
+fn test_datatype_reference_to_undeclared_datatype() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    DatatypeBuilder::new(ctx.clone(), "Tree")
+        .variant("leaf", vec![])
+        .variant(
+            "node",
+            vec![("children", DatatypeAccessor::Datatype("Forest".into()))],
+        )
+        .finish();
+}
+
+#[test]
+fn test_check_with_retries() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&(&x * &x)._eq(&ast::Int::from_i64(ctx.clone(), 49)));
+
+    let fail = Attempt::new().tactic("fail");
+    let outcome =
+        solver.check_with_retries(&RetryPolicy::new().then(fail.clone()).then(fail.clone()));
+    assert_eq!(outcome.result, SatResult::Unknown);
+    assert_eq!(outcome.attempt, 1);
+    assert_eq!(outcome.failures.len(), 2);
+    assert!(matches!(
+        &outcome.failures[0],
+        Some(ReasonUnknown::TacticFailed { tactic, .. }) if tactic == "fail"
+    ));
+    assert!(outcome.model.is_none());
+
+    // The model of a tactic attempt comes with the outcome.
+    let policy = RetryPolicy::new().then(fail).then(
+        Attempt::new()
+            .tactic("qfnia")
+            .timeout(Duration::from_secs(10)),
+    );
+    let outcome = solver.check_with_retries(&policy);
+    assert_eq!((outcome.result, outcome.attempt), (SatResult::Sat, 1));
+    let model = outcome.model.unwrap();
+    let v = model.eval(&x, true).unwrap().as_i64().unwrap();
+    assert_eq!(v * v, 49);
+
+    // Escalating attempts use the solver itself and leave it unchanged.
+    let outcome =
+        solver.check_with_retries(&RetryPolicy::escalating(Duration::from_millis(500), 3));
+    assert_eq!((outcome.result, outcome.attempt), (SatResult::Sat, 0));
+    assert!(outcome.failures.is_empty());
+    solver.assert(&x.lt(&ast::Int::from_i64(ctx.clone(), -7)));
+    assert_eq!(
+        solver.check_with_retries(&RetryPolicy::new()).result,
+        SatResult::Unsat
+    );
+    assert_eq!(solver.check(), SatResult::Unsat);
+}
+
+#[test]
+fn test_linearizer() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Real::new_const(ctx.clone(), "x");
+    let y = ast::Real::new_const(ctx.clone(), "y");
+    let real = |i| ast::Real::from_real(ctx.clone(), i, 1);
+
+    // x * y > 0 with x, y of opposite signs is unsatisfiable.
+    let mut lin = Linearizer::new(ctx.clone());
+    lin.assert(&(&x * &y).gt(&real(0)));
+    lin.assert(&x.gt(&real(1)));
+    lin.assert(&y.lt(&real(-1)));
+    assert_eq!(lin.check(10), SatResult::Unsat);
+    assert_eq!(lin.products().len(), 1);
+
+    // Without lemmas, a spurious model cannot be refined.
+    let mut lin = Linearizer::new(ctx.clone());
+    lin.set_schemata(LemmaSchemata {
+        zero: false,
+        sign: false,
+        tangent: false,
+    });
+    lin.assert(&(&x * &x)._eq(&real(4)));
+    lin.assert(&x.gt(&real(0)));
+    assert_eq!(lin.check(10), SatResult::Unknown);
+
+    // A hook supplying the lemma that pins down x.
+    let mut lin = Linearizer::new(ctx.clone());
+    lin.set_schemata(LemmaSchemata {
+        zero: false,
+        sign: false,
+        tangent: false,
+    });
+    let calls = Rc::new(std::cell::Cell::new(0));
+    let counter = calls.clone();
+    let (xc, hook_ctx) = (x.clone(), ctx.clone());
+    lin.lemma_hook(move |p, _| {
+        counter.set(counter.get() + 1);
+        let two = ast::Real::from_real(hook_ctx.clone(), 2, 1);
+        let four = ast::Real::from_real(hook_ctx.clone(), 4, 1);
+        vec![p.m._eq(&four.into()).implies(&xc._eq(&two))]
+    });
+    lin.assert(&(&x * &x)._eq(&real(4)));
+    lin.assert(&x.gt(&real(0)));
+    assert_eq!(lin.check(10), SatResult::Sat);
+    assert!(calls.get() >= 1);
+    let model = lin.get_model().unwrap();
+    assert_eq!(model.eval(&x, true).unwrap(), real(2));
+
+    // Products of three factors, with a coefficient, over the integers.
+    let a = ast::Int::new_const(ctx.clone(), "a");
+    let b = ast::Int::new_const(ctx.clone(), "b");
+    let c = ast::Int::new_const(ctx.clone(), "c");
+    let int = |i| ast::Int::from_i64(ctx.clone(), i);
+    let mut lin = Linearizer::new(ctx.clone());
+    let abc = ast::Int::mul(ctx.clone(), &[int(2), a.clone(), b.clone(), c.clone()]);
+    lin.assert(&abc._eq(&int(60)));
+    for v in [&a, &b, &c].iter() {
+        lin.assert(&v.gt(&int(1)));
+        lin.assert(&v.lt(&int(6)));
+    }
+    lin.assert(&a.lt(&b));
+    lin.assert(&b.lt(&c));
+    assert_eq!(lin.check(50), SatResult::Sat);
+    assert_eq!(lin.products().len(), 2);
+    let model = lin.get_model().unwrap();
+    let v = |t: &Int| model.eval(t, true).unwrap().as_i64().unwrap();
+    assert_eq!((v(&a), v(&b), v(&c)), (2, 3, 5));
+}
+
+#[test]
+fn test_theory_mix_presets() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+
+    // Every preset names parameters this version of Z3 knows, with values
+    // it accepts, and leaves the solver working.
+    let descrs = Solver::new(ctx.clone()).get_param_descrs();
+    for mix in TheoryMix::ALL.iter() {
+        for (k, _) in mix.settings() {
+            assert!(
+                descrs.kind(k).is_some(),
+                "{:?}: unknown parameter {}",
+                mix,
+                k
+            );
+        }
+        let solver = Solver::new(ctx.clone());
+        mix.apply(&solver);
+        let b = ast::Bool::new_const(ctx.clone(), "b");
+        solver.assert(&b);
+        assert_eq!(solver.check(), SatResult::Sat, "{:?}", mix);
+        solver.assert(&b.not());
+        assert_eq!(solver.check(), SatResult::Unsat, "{:?}", mix);
+    }
+
+    let s = ast::String::new_const(ctx.clone(), "s");
+    let strings = s._eq(&ast::String::from_str(ctx.clone(), "ab").unwrap());
+    let float = ast::Float::new_const_float32(ctx.clone(), "f");
+    let fp = float._eq(&float);
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let q = ast::forall_const(ctx.clone(), &[&x], &[], &x._eq(&x));
+    assert_eq!(
+        TheoryMix::detect(&[strings.clone(), fp.clone()]),
+        Some(TheoryMix::Strings)
+    );
+    assert_eq!(
+        TheoryMix::detect(&[fp, q.clone()]),
+        Some(TheoryMix::FloatingPoint)
+    );
+    assert_eq!(TheoryMix::detect(&[q]), Some(TheoryMix::IntUfQuantifiers));
+    let v = ast::BV::new_const(ctx.clone(), "v", 8);
+    assert_eq!(TheoryMix::detect(&[v._eq(&v)]), None);
+    assert_eq!(TheoryMix::detect(&[]), None);
+
+    let solver = Solver::new(ctx.clone());
+    TheoryMix::Strings.apply(&solver);
+    solver.assert(&strings);
+    assert_eq!(solver.check(), SatResult::Sat);
+}
+
+#[test]
+fn test_tuple_sort() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let bool_sort = Sort::bool(ctx.clone());
+    let (pair, mk_pair, proj) = Sort::tuple(
+        ctx.clone(),
+        "Pair".into(),
+        &[("count".into(), &int), ("valid".into(), &bool_sort)],
+    );
+    assert_eq!(pair.kind(), SortKind::Datatype);
+    assert_eq!(mk_pair.arity(), 2);
+    assert_eq!(proj.len(), 2);
+    assert_eq!(proj[0].name(), "count");
+    assert_eq!(proj[1].range(), bool_sort);
+
+    let p = ast::Datatype::new_const(ctx.clone(), "p", &pair);
+    let q = ast::Datatype::new_const(ctx.clone(), "q", &pair);
+    let solver = Solver::new(ctx.clone());
+    // Tuples with equal fields are equal.
+    solver.assert(&proj[0].apply(&[&p])._eq(&proj[0].apply(&[&q])));
+    solver.assert(&proj[1].apply(&[&p])._eq(&proj[1].apply(&[&q])));
+    solver.assert(&p._eq(&q).not());
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    // A tuple without fields has exactly one value.
+    let (unit, mk_unit, none) = Sort::tuple(ctx.clone(), "Unit".into(), &[]);
+    assert!(none.is_empty());
+    let u = ast::Datatype::new_const(ctx.clone(), "u", &unit);
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&u._eq(&mk_unit.apply(&[]).as_datatype().unwrap()).not());
+    assert_eq!(solver.check(), SatResult::Unsat);
+}
+
+#[test]
+fn test_list_sort() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let list = Sort::list(ctx.clone(), "IntList".into(), &int);
+    assert_eq!(list.sort.kind(), SortKind::Datatype);
+    assert_eq!(list.head.range(), int);
+    assert_eq!(list.tail.range(), list.sort);
+
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let l = ast::Datatype::new_const(ctx.clone(), "l", &list.sort);
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&l._eq(&list.cons(&x, &list.nil())));
+    solver.assert(&x._eq(&ast::Int::from_i64(ctx.clone(), 7)));
+    solver.assert(&list.is_cons.apply(&[&l]).as_bool().unwrap());
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    let head = model.eval(&list.head.apply(&[&l]), true).unwrap();
+    assert_eq!(head.as_int().unwrap().as_i64(), Some(7));
+
+    solver.assert(&list.is_nil.apply(&[&l]).as_bool().unwrap());
+    assert_eq!(solver.check(), SatResult::Unsat);
+}
+
+#[test]
+fn test_model_diff() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let f = FuncDecl::new(ctx.clone(), "f", &[&int], &int);
+    let c = ast::Int::new_const(ctx.clone(), "c");
+    let d = ast::Int::new_const(ctx.clone(), "d");
+    let n = |v: i64| ast::Int::from_i64(ctx.clone(), v);
+    let model_with = |f1: i64, c: Option<i64>| {
+        let solver = Solver::new(ctx.clone());
+        solver.assert(&f.apply(&[&n(1)])._eq(&n(f1).into()));
+        solver.assert(&f.apply(&[&n(2)])._eq(&n(5).into()));
+        solver.assert(&d._eq(&n(3)));
+        if let Some(v) = c {
+            solver.assert(&ast::Int::new_const(ctx.clone(), "c")._eq(&n(v)));
+        }
+        assert_eq!(solver.check(), SatResult::Sat);
+        solver.get_model().unwrap()
+    };
+
+    let a = model_with(10, Some(0));
+    let b = model_with(20, None);
+    let symbols = [f.clone(), c.decl(), d.decl()];
+    assert!(a.diff(&a, &symbols).is_empty());
+
+    let diff = a.diff(&b, &symbols);
+    assert_eq!(diff.len(), 2);
+    match &diff[0] {
+        // Z3 makes f(1) the default value, and lists only f(2).
+        ModelDifference::Func {
+            decl,
+            points,
+            else_values: Some((first, second)),
+        } => {
+            assert_eq!(decl, &f);
+            assert!(points.is_empty());
+            assert_eq!(first.as_int().unwrap().as_i64(), Some(10));
+            assert_eq!(second.as_int().unwrap().as_i64(), Some(20));
+        }
+        d => panic!("unexpected difference {:?}", d),
+    }
+    assert!(matches!(&diff[1], ModelDifference::OnlyInFirst(decl) if decl.name() == "c"));
+    assert_eq!(diff[1].decl(), &c.decl());
+    assert!(matches!(
+        &b.diff(&a, &symbols)[1],
+        ModelDifference::OnlyInSecond(_)
+    ));
+}
+
+#[test]
+fn test_model_diff_points() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let f = FuncDecl::new(ctx.clone(), "f", &[&int], &int);
+    let n = |v: i64| ast::Int::from_i64(ctx.clone(), v);
+    let model_with = |values: &[i64]| {
+        let solver = Solver::new(ctx.clone());
+        for (i, v) in values.iter().enumerate() {
+            solver.assert(&f.apply(&[&n(i as i64)])._eq(&n(*v).into()));
+        }
+        assert_eq!(solver.check(), SatResult::Sat);
+        solver.get_model().unwrap()
+    };
+
+    let diff = model_with(&[0, 1, 2]).diff(&model_with(&[0, 7, 2]), &[f]);
+    assert_eq!(diff.len(), 1);
+    match &diff[0] {
+        ModelDifference::Func {
+            points,
+            else_values: None,
+            ..
+        } => {
+            assert_eq!(points.len(), 1);
+            assert_eq!(points[0].args, vec![ast::Dynamic::from_ast(&n(1))]);
+            assert_eq!(points[0].first.as_int().unwrap().as_i64(), Some(1));
+            assert_eq!(points[0].second.as_int().unwrap().as_i64(), Some(7));
+        }
+        d => panic!("unexpected difference {:?}", d),
+    }
+}
+
+#[test]
+fn test_goal_to_dimacs_with_vars() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::BV::new_const(ctx.clone(), "x", 4);
+    let y = ast::BV::new_const(ctx.clone(), "y", 4);
+    let goal = Goal::new(ctx.clone(), false, false, false);
+    goal.assert(&x.bvadd(&y)._eq(&ast::BV::from_u64(ctx.clone(), 5, 4)));
+    goal.assert(&x.bvugt(&y));
+    let tactic = Tactic::new(ctx.clone(), "simplify")
+        .and_then(&Tactic::new(ctx.clone(), "bit-blast"))
+        .and_then(&Tactic::new(ctx.clone(), "tseitin-cnf"));
+    let cnf = tactic
+        .apply(&goal, None)
+        .unwrap()
+        .list_subgoals()
+        .next()
+        .unwrap();
+
+    let (dimacs, vars) = cnf.to_dimacs_with_vars().unwrap();
+    let mut lines = dimacs.lines();
+    let header = format!("p cnf {} {}", vars.len(), cnf.get_size());
+    assert_eq!(lines.next(), Some(&header[..]));
+
+    // Every clause is the formula of the goal, in terms of the variables.
+    for (f, clause) in cnf.get_formulas::<Bool>().into_iter().zip(lines) {
+        let literals: Vec<Bool> = clause
+            .split_whitespace()
+            .map(|n| n.parse::<i64>().unwrap())
+            .take_while(|&n| n != 0)
+            .map(|n| {
+                let v = vars[n.unsigned_abs() as usize - 1].clone();
+                if n > 0 {
+                    v
+                } else {
+                    v.not()
+                }
+            })
+            .collect();
+        let expected = match &literals[..] {
+            [l] => l.clone(),
+            ls => Bool::or(ctx.clone(), &ls.iter().collect::<Vec<_>>()),
+        };
+        assert_eq!(f, expected);
+    }
+
+    // An equivalence of Booleans is not a clause.
+    cnf.assert(&vars[0]._eq(&vars[1]));
+    assert!(cnf.to_dimacs_with_vars().is_err());
+}
+
+#[test]
+fn test_datatype_value_helpers() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let tree = DatatypeBuilder::new(ctx.clone(), "Tree")
+        .variant("Leaf", vec![])
+        .variant(
+            "Node",
+            vec![
+                ("value", DatatypeAccessor::Sort(int.clone())),
+                ("left", DatatypeAccessor::Datatype("Tree".into())),
+                ("right", DatatypeAccessor::Datatype("Tree".into())),
+            ],
+        )
+        .finish();
+    let variants = tree.sort.datatype_variants().unwrap();
+    assert_eq!(variants.len(), 2);
+    assert_eq!(variants[1].constructor, tree.variants[1].constructor);
+    assert_eq!(variants[1].accessors, tree.variants[1].accessors);
+    assert_eq!(tree.variants[1].constructor.domain()[0], int);
+
+    let leaf = ast::Datatype::construct(&tree.sort, "Leaf", &[]).unwrap();
+    let seven = Int::from_i64(ctx.clone(), 7);
+    let node = ast::Datatype::construct(&tree.sort, "Node", &[&seven, &leaf, &leaf]).unwrap();
+    let t = ast::Datatype::new_const(ctx.clone(), "t", &tree.sort);
+
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&t.is_variant("Node").unwrap());
+    solver.assert(&t.field("left").unwrap()._eq(&node.clone().into()));
+    solver.assert(&t.field("value").unwrap()._eq(&seven.clone().into()));
+    assert_eq!(solver.check(), SatResult::Sat);
+    solver.assert(
+        &t.field("left")
+            .unwrap()
+            .as_datatype()
+            .unwrap()
+            .is_variant("Leaf")
+            .unwrap(),
+    );
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    assert_eq!(
+        ast::Datatype::construct(&tree.sort, "Node", &[&seven]),
+        Err(DatatypeError::Arity {
+            constructor: "Node".to_owned(),
+            expected: 3,
+            found: 1
+        })
+    );
+    let err = ast::Datatype::construct(&tree.sort, "Node", &[&leaf, &leaf, &leaf]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "field value of constructor Node is of sort Int, not Tree"
+    );
+    assert_eq!(
+        node.field("size").unwrap_err().to_string(),
+        "datatype Tree has no field size; its fields are value, left, right"
+    );
+    assert_eq!(
+        node.is_variant("Empty").unwrap_err(),
+        DatatypeError::UnknownConstructor {
+            sort: tree.sort.clone(),
+            name: "Empty".to_owned()
+        }
+    );
+    assert_eq!(
+        ast::Datatype::construct(&int, "Leaf", &[])
+            .unwrap_err()
+            .to_string(),
+        "Int is not a datatype"
+    );
+}
+
+#[cfg(feature = "debug")]
+#[test]
+fn test_debug_live_objects() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let count = |type_name: &str| {
+        debug::live_objects_in(&ctx)
+            .into_iter()
+            .find(|l| l.type_name == type_name)
+            .map_or(0, |l| l.count)
+    };
+
+    let x = Int::new_const(ctx.clone(), "x");
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x.gt(&Int::from_i64(ctx.clone(), 0)));
+    let mut models = vec![];
+    for _ in 0..3 {
+        assert_eq!(solver.check(), SatResult::Sat);
+        models.push(solver.get_model().unwrap());
+    }
+    assert_eq!(count("Solver"), 1);
+    assert_eq!(count("Model"), 3);
+    assert_eq!(count("ast::Int"), 1);
+
+    let id = debug::context_id(&ctx);
+    assert!(debug::live_objects()
+        .iter()
+        .any(|l| l.context == id && l.type_name == "Model"));
+
+    drop(models);
+    drop(solver);
+    drop(x);
+    assert_eq!(debug::live_objects_in(&ctx), vec![]);
+}
+
+#[test]
+fn test_soft_groups_tiers() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let int = |i| Int::from_i64(ctx.clone(), i);
+
+    let mut soft = SoftGroups::new(ctx.clone());
+    // The second tier is used first, but stays less important.
+    soft.add("low", &x.ge(&int(10)), 100);
+    soft.add("high", &x.le(&int(3)), (1, 2));
+    soft.add("high", &x.le(&int(5)), 1);
+    soft.add("low", &x._eq(&int(5)), 1);
+    assert_eq!(soft.tiers(), ["low", "high"]);
+    assert!(soft.summary().is_empty());
+
+    let mut soft2 = SoftGroups::new(ctx.clone());
+    soft2.add_tier("high");
+    soft2.add_tier("low");
+    soft2.add("low", &x.ge(&int(10)), 100);
+    soft2.add("high", &x.le(&int(3)), (1, 2));
+    soft2.add("high", &x.le(&int(5)), 1);
+    soft2.add("low", &x._eq(&int(5)), 1);
+    assert_eq!(soft2.tiers(), ["high", "low"]);
+    assert_eq!(soft2.check(), SatResult::Sat);
+    let summary = soft2.summary();
+    assert_eq!(summary[0].satisfied, 2);
+    assert_eq!(
+        summary[0].total_weight,
+        BigRational::new(3.into(), 2.into())
+    );
+    assert_eq!(summary[1].satisfied, 0);
+    assert_eq!(summary[1].violated.len(), 2);
+    assert_eq!(
+        summary[1].violated_weight,
+        BigRational::from_integer(101.into())
+    );
+    let x_value = soft2
+        .get_model()
+        .unwrap()
+        .eval(&x, true)
+        .unwrap()
+        .as_i64()
+        .unwrap();
+    assert!(x_value <= 3);
+
+    // With "low" first, x >= 10 wins.
+    assert_eq!(soft.check(), SatResult::Sat);
+    let summary = soft.summary();
+    assert_eq!(summary[0].name, "low");
+    assert_eq!(
+        summary[0].violated_weight,
+        BigRational::from_integer(1.into())
+    );
+    assert_eq!(summary[1].satisfied, 0);
+
+    // The soft constraints are gone between checks.
+    soft.optimize().assert(&x.lt(&int(0)));
+    assert_eq!(soft.optimize().get_assertions().len(), 1);
+    assert_eq!(soft.check(), SatResult::Sat);
+    assert_eq!(soft.summary()[0].satisfied, 0);
+    soft.optimize().assert(&x.gt(&int(0)));
+    assert_eq!(soft.check(), SatResult::Unsat);
+    assert!(soft.get_model().is_none());
+}
+
+#[test]
+fn test_func_interp_entries() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let f = FuncDecl::new(ctx.clone(), "f", &[&int], &int);
+    let n = |i: i64| ast::Dynamic::from_ast(&Int::from_i64(ctx.clone(), i));
+
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&f.apply(&[&n(1)])._eq(&n(10)));
+    solver.assert(&f.apply(&[&n(2)])._eq(&n(20)));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let interp = solver.get_model().unwrap().get_func_interp(&f).unwrap();
+    assert_eq!(interp.get_arity(), 1);
+    let before = interp.get_num_entries();
+
+    interp.add_entry(&[n(7)], &n(70));
+    interp.set_else(&n(0));
+    let entries: Vec<_> = interp.entries().collect();
+    assert_eq!(entries.len() as u32, before + 1);
+    assert_eq!(entries.last(), Some(&(vec![n(7)], n(70))));
+    assert_eq!(interp.else_value(), Some(n(0)));
+    assert_eq!(interp.get_else(), n(0));
+    assert!(interp.to_string().ends_with("7 -> 70, else -> 0]"));
+}
+
+#[test]
+fn test_model_construction() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let x = Int::new_const(ctx.clone(), "x");
+    let y = Int::new_const(ctx.clone(), "y");
+    let f = FuncDecl::new(ctx.clone(), "f", &[&int], &int);
+    let n = |i: i64| Int::from_i64(ctx.clone(), i);
+
+    let model = Model::new(ctx.clone());
+    assert_eq!(model.iter().count(), 0);
+    model.add_const_interp(&x.decl(), &n(2));
+    let interp = model.add_func_interp(&f, &n(0));
+    interp.add_entry(&[n(2).into()], &n(5).into());
+    assert_eq!(model.iter().count(), 2);
+
+    let f_x = f.apply(&[&x]).as_int().unwrap();
+    assert_eq!(model.eval(&f_x, false).unwrap().as_i64(), Some(5));
+    assert_eq!(model.eval(&f.apply(&[&n(3)]), false).unwrap(), n(0).into());
+    // Without model completion, y has no value.
+    assert_eq!(model.eval(&y, false), Some(y.clone()));
+
+    // A copy of a solver model can be changed without changing the model.
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x._eq(&n(1)));
+    solver.assert(&y._eq(&n(1)));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let original = solver.get_model().unwrap();
+    let candidate = original.copy();
+    candidate.add_const_interp(&y.decl(), &n(4));
+    let inv = x.lt(&y);
+    assert_eq!(original.eval(&inv, true).unwrap().as_bool(), Some(false));
+    assert_eq!(candidate.eval(&inv, true).unwrap().as_bool(), Some(true));
+}
+
+#[test]
+#[should_panic(expected = "value of x has the wrong sort")]
+fn test_model_add_const_interp_sort_mismatch() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let model = Model::new(ctx.clone());
+    model.add_const_interp(&x.decl(), &Bool::from_bool(ctx.clone(), true));
+}
+
+#[test]
+fn test_testing_macros() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let b = Bool::new_const(ctx.clone(), "b");
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x._eq(&Int::from_i64(ctx.clone(), -4)));
+    solver.assert(&b);
+
+    let model = assert_sat!(solver);
+    assert_model_eq!(model, x, -4i64);
+    assert_model_eq!(model, b, true, "b was asserted");
+    assert_model_eq!(model, Int::new_const(ctx.clone(), "y"), 0);
+
+    solver.assert(&x.gt(&Int::from_i64(ctx.clone(), 0)));
+    assert_unsat!(solver);
+}
+
+#[test]
+#[should_panic(expected = "`solver` is sat, expected unsat, with model")]
+fn test_testing_assert_unsat_fails() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&Bool::new_const(ctx.clone(), "b"));
+    assert_unsat!(solver);
+}
+
+#[test]
+#[should_panic(expected = "`x` is 2 in the model, expected 3: x should be three")]
+fn test_testing_assert_model_eq_fails() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x._eq(&Int::from_i64(ctx.clone(), 2)));
+    let model = assert_sat!(solver);
+    assert_model_eq!(model, x, 3, "x should be {}", "three");
+}
+
+#[test]
+fn test_model_eval_many_dyn() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let b = Bool::new_const(ctx.clone(), "b");
+    let v = BV::new_const(ctx.clone(), "v", 8);
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x._eq(&Int::from_i64(ctx.clone(), 3)));
+    solver.assert(&v._eq(&BV::from_u64(ctx.clone(), 200, 8)));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+
+    let sum = x.clone() + Int::from_i64(ctx.clone(), 1);
+    let terms: [&dyn Ast; 4] = [&x, &sum, &b, &v];
+    let values = model.eval_many_dyn(&terms, true);
+    assert_eq!(values.len(), 4);
+    let values: Vec<Dynamic> = values.into_iter().map(Option::unwrap).collect();
+    assert_eq!(values[0].as_int().unwrap().as_i64(), Some(3));
+    assert_eq!(values[1].as_int().unwrap().as_i64(), Some(4));
+    assert_eq!(values[2].as_bool().unwrap().as_bool(), Some(false));
+    assert_eq!(values[3].as_bv().unwrap().as_u64(), Some(200));
+
+    // Without completion, constants without a value evaluate to themselves.
+    let c = Bool::new_const(ctx.clone(), "c");
+    let values = model.eval_many_dyn(&[&c, &sum], false);
+    assert_eq!(values[0], Some(Dynamic::from_ast(&c)));
+    assert_eq!(
+        values[1].as_ref().unwrap().as_int().unwrap().as_i64(),
+        Some(4)
+    );
+    assert_eq!(model.eval(&c, false), Some(c));
+}
+
+#[test]
+fn test_quantifier_trace() {
+    let path = std::env::temp_dir().join(format!("z3-qi-trace-{}.log", std::process::id()));
+    let mut cfg = Config::new();
+    cfg.set_quantifier_trace(&path);
+    {
+        let ctx = Rc::new(Context::new(&cfg));
+        let int = Sort::int(ctx.clone());
+        let f = FuncDecl::new(ctx.clone(), "f", &[&int], &int);
+        let g = FuncDecl::new(ctx.clone(), "g", &[&int], &int);
+        let x = Int::new_const(ctx.clone(), "x");
+        let n = |i: i64| Int::from_i64(ctx.clone(), i);
+        let f_x = f.apply(&[&x]).as_int().unwrap();
+        let g_x = g.apply(&[&x]).as_int().unwrap();
+        let f_grows = ast::QuantifierBuilder::forall(ctx.clone(), &[&x])
+            .pattern(Pattern::new(ctx.clone(), &[&f_x]))
+            .qid("f-grows")
+            .build(&f_x.gt(&x));
+        let g_pos = ast::QuantifierBuilder::forall(ctx.clone(), &[&x])
+            .pattern(Pattern::new(ctx.clone(), &[&g_x]))
+            .qid("g-pos")
+            .build(&g_x.gt(&n(0)));
+
+        let solver = Solver::new(ctx.clone());
+        solver.assert(&f_grows);
+        solver.assert(&g_pos);
+        let f_at = |i| f.apply(&[&n(i)]).as_int().unwrap();
+        let sum = f_at(4) + f_at(5) + g.apply(&[&n(1)]).as_int().unwrap();
+        solver.assert(&sum._eq(&n(9)));
+        assert_eq!(solver.check(), SatResult::Unsat);
+    }
+
+    let counts = QuantifierInstantiations::read_trace(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let by_qid = |qid: &str| counts.iter().find(|c| c.qid == qid).unwrap();
+    assert_eq!(counts[0].qid, "f-grows");
+    assert_eq!(by_qid("f-grows").instances, 2);
+    assert_eq!(by_qid("g-pos").instances, 1);
+    assert!(counts.iter().all(|c| c.matches >= c.instances));
+}
+
+#[test]
+fn test_quantifier_instantiations_from_trace() {
+    let trace = "\
+[mk-quant] #13 ax 1 #11 #12
+[mk-quant] #20 other 1 #18 #19
+[new-match] 0x10 #13 #11 #14 ; #15
+[new-match] 0x20 #13 #11 #16 ; #17
+[inst-discovered] MBQI 0x30 #20 #21 ; #22
+[inst-discovered] theory-solving 0 arith# ; #12
+[instance] 0x10 ; 1
+[instance] 0 #23
+[instance] 0x30 ; 2
+[mk-quant] #13 reused 1 #24
+[new-match] 0x40 #13 #24 #25 ; #26
+";
+    let counts = QuantifierInstantiations::from_trace(trace);
+    let expected = |qid: &str, matches, instances| QuantifierInstantiations {
+        qid: qid.to_owned(),
+        matches,
+        instances,
+    };
+    assert_eq!(
+        counts,
+        [
+            expected("ax", 2, 1),
+            expected("other", 1, 1),
+            expected("reused", 1, 0)
+        ]
+    );
+}
+
+#[test]
+fn test_big_numerals() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let big = BigInt::from_str("340282366920938463463374607431768211457").unwrap();
+
+    let x = Int::new_const(ctx.clone(), "x");
+    let bv = BV::new_const(ctx.clone(), "bv", 256);
+    let r = ast::Real::new_const(ctx.clone(), "r");
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x._eq(&Int::from_big_int(ctx.clone(), &-big.clone())));
+    solver.assert(&bv._eq(&BV::from_big_int(ctx.clone(), &big, 256)));
+    let third = BigRational::new(1.into(), big.clone() * 3);
+    solver.assert(&r._eq(&ast::Real::from_big_rational(ctx.clone(), &third)));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+
+    let x = model.eval(&x, true).unwrap();
+    assert_eq!(x.as_i64(), None);
+    assert_eq!(x.as_big_int(), Some(-big.clone()));
+    let bv = model.eval(&bv, true).unwrap();
+    assert_eq!(bv.as_u64(), None);
+    assert_eq!(bv.as_big_uint(), big.to_biguint());
+    assert_eq!(bv.as_big_int(), Some(big.clone()));
+    let r = model.eval(&r, true).unwrap();
+    assert_eq!(r.as_real(), None);
+    assert_eq!(r.as_big_rational(), Some(third));
+
+    // Negative values wrap around, and read back in two's complement.
+    let minus_two = BV::from_big_int(ctx.clone(), &BigInt::from(-2), 8);
+    assert_eq!(minus_two.as_u64(), Some(254));
+    assert_eq!(minus_two.as_big_int(), Some(BigInt::from(-2)));
+    let wrapped = BV::from_big_int(ctx.clone(), &BigInt::from(300), 8);
+    assert_eq!(wrapped.as_u64(), Some(44));
+
+    // Only numerals have values.
+    let y = Int::new_const(ctx.clone(), "y");
+    assert_eq!(y.as_big_int(), None);
+    assert_eq!(BV::new_const(ctx.clone(), "c", 8).as_big_uint(), None);
+    assert_eq!(
+        ast::Real::from_real(ctx.clone(), -3, 4).as_big_rational(),
+        Some(BigRational::new((-3).into(), 4.into()))
+    );
+}
+
+#[test]
+fn test_select_by_index() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    for n in 1..=9 {
+        let options: Vec<Int> = (0..n).map(|i| Int::from_i64(ctx.clone(), 10 + i)).collect();
+        for i in 0..16u64 {
+            let by_int = select_by_index(&Int::from_u64(ctx.clone(), i), &options);
+            let by_bv = select_by_index(&BV::from_u64(ctx.clone(), i, 4), &options);
+            let in_bounds = i < n as u64;
+            assert_eq!(by_int.in_bounds.simplify().as_bool(), Some(in_bounds));
+            assert_eq!(by_bv.in_bounds.simplify().as_bool(), Some(in_bounds));
+            if in_bounds {
+                assert_eq!(by_int.value.simplify().as_i64(), Some(10 + i as i64));
+                assert_eq!(by_bv.value.simplify().as_i64(), Some(10 + i as i64));
+            }
+        }
+        let by_int = select_by_index(&Int::from_i64(ctx.clone(), -1), &options);
+        assert_eq!(by_int.in_bounds.simplify().as_bool(), Some(false));
+    }
+
+    // A 2-bit index selects from the first four options only, and is
+    // always in bounds for them.
+    let options: Vec<Bool> = (0..6)
+        .map(|i| Bool::new_const(ctx.clone(), format!("b{}", i)))
+        .collect();
+    let idx = BV::new_const(ctx.clone(), "idx", 2);
+    let entry = select_by_index(&idx, &options);
+    assert_eq!(entry.in_bounds.simplify().as_bool(), Some(true));
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&entry.value);
+    for b in &options[..4] {
+        solver.assert(&!b.clone());
+    }
+    assert_eq!(solver.check(), SatResult::Unsat);
+}
+
+#[test]
+#[should_panic(expected = "no options to select from")]
+fn test_select_by_index_empty() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let idx = Int::new_const(ctx.clone(), "idx");
+    select_by_index::<_, Int>(&idx, &[]);
+}
+
+#[test]
+fn test_string_bv_bytes() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+
+    // A 16-bit length field parsed from a string header.
+    let header = ast::String::new_const(ctx.clone(), "header");
+    let (len_field, exact) = header.bytes_to_bv(2);
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&exact);
+    solver.assert(&len_field._eq(&BV::from_u64(ctx.clone(), 0x4f4b, 16)));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    let value = model.eval(&header, true).unwrap();
+    assert_eq!(value.as_string().as_deref(), Some("OK"));
+
+    // Bit-level constraints on the bytes of a string.
+    let word = BV::new_const(ctx.clone(), "word", 24);
+    let solver = Solver::new(ctx.clone());
+    let s = word.to_byte_string();
+    solver.assert(&s._eq(&ast::String::from_str(ctx.clone(), "abc").unwrap()));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    assert_eq!(model.eval(&word, true).unwrap().as_u64(), Some(0x61_6263));
+
+    // Characters beyond a byte, and strings of another length, have no bytes.
+    let wide = ast::String::from_str(ctx.clone(), "\\u{100}").unwrap();
+    assert_eq!(wide.bytes_to_bv(1).1.simplify().as_bool(), Some(false));
+    let short = ast::String::from_str(ctx.clone(), "a").unwrap();
+    assert_eq!(short.bytes_to_bv(2).1.simplify().as_bool(), Some(false));
+
+    // Sequences of bytes round-trip.
+    let bytes = BV::from_u64(ctx.clone(), 0x0102_0304, 32).to_byte_seq();
+    assert_eq!(bytes.length().simplify().as_i64(), Some(4));
+    let first = bytes.nth(&Int::from_i64(ctx.clone(), 0)).simplify();
+    assert_eq!(first.as_bv().unwrap().as_u64(), Some(1));
+    let (bv, exact) = bytes.bytes_to_bv(4);
+    assert_eq!(bv.simplify().as_u64(), Some(0x0102_0304));
+    assert_eq!(exact.simplify().as_bool(), Some(true));
+}
+
+#[test]
+#[should_panic(expected = "not made of bytes")]
+fn test_to_byte_seq_not_bytes() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    BV::new_const(ctx.clone(), "x", 12).to_byte_seq();
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn test_derive_from_model() {
+    use z3::{FromModel, Z3Enum, Z3Struct};
+
+    #[derive(Debug, PartialEq, Z3Enum)]
+    enum Mode {
+        Walk,
+        Drive,
+    }
+
+    #[derive(Debug, PartialEq, Z3Struct)]
+    struct Leg {
+        from: u32,
+        to: u32,
+    }
+
+    #[derive(Debug, PartialEq, FromModel)]
+    struct Trip {
+        #[z3(name = "trip.cost")]
+        cost: i64,
+        mode: Mode,
+        first: Leg,
+        direct: bool,
+    }
+
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let cost = Int::new_const(ctx.clone(), "trip.cost");
+    let mode = Mode::enum_sort(ctx.clone());
+    let first = SymLeg::new_const(ctx.clone(), "first");
+    let direct = Bool::new_const(ctx.clone(), "direct");
+
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&cost._eq(&Int::from_i64(ctx.clone(), -5)));
+    solver.assert(&mode.new_const("mode")._eq(&mode.value(&Mode::Drive)));
+    solver.assert(&first.from()._eq(&Int::from_i64(ctx.clone(), 1)));
+    solver.assert(&first.to()._eq(&Int::from_i64(ctx.clone(), 4)));
+    solver.assert(&direct);
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    assert_eq!(
+        Trip::from_model(&model),
+        Some(Trip {
+            cost: -5,
+            mode: Mode::Drive,
+            first: Leg { from: 1, to: 4 },
+            direct: true,
+        })
+    );
+
+    // A value that does not fit its field cannot be decoded.
+    solver.reset();
+    solver.assert(&first.from()._eq(&Int::from_i64(ctx.clone(), -1)));
+    assert_eq!(solver.check(), SatResult::Sat);
+    assert_eq!(Trip::from_model(&solver.get_model().unwrap()), None);
+}
+
+#[test]
+fn test_model_get_const_value() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let b = Bool::new_const(ctx.clone(), "b");
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x._eq(&Int::from_i64(ctx.clone(), -7)));
+    solver.assert(&b);
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+
+    assert_eq!(model.get_const_value::<i64, _>("x"), Some(-7));
+    assert_eq!(model.get_const_value::<u64, _>("x"), None);
+    assert_eq!(model.get_const_value::<bool, _>("b"), Some(true));
+    // Constants the model does not mention get a default value.
+    assert_eq!(model.get_const_value::<i32, _>("unused"), Some(0));
+}
+
+#[test]
+fn test_goal_api() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
+    let goal = Goal::new(ctx.clone(), true, false, false);
+    let pos = x.gt(&Int::from_i64(ctx.clone(), 0));
+    let small = x.lt(&Int::from_i64(ctx.clone(), 10));
+    goal.assert(&pos);
+    goal.assert(&small);
+    assert_eq!(goal.get_size(), 2);
+    assert_eq!(goal.iter().len(), 2);
+    assert_eq!(
+        goal.iter().collect::<Vec<_>>(),
+        [pos.clone(), small.clone()]
+    );
+    assert!(goal.get_num_expr() >= 4);
+    assert_eq!(goal.get_depth(), 0);
+    assert_eq!(goal.get_precision(), GoalPrec::Precise);
+    assert!(!goal.is_inconsistent());
+    assert!(!goal.is_decided_sat() && !goal.is_decided_unsat());
+
+    // Translating borrows the goal, which stays usable.
+    let other = Rc::new(Context::new(&cfg));
+    let copy = goal.translate(other.clone());
+    assert_eq!(copy.get_size(), 2);
+    assert_eq!(
+        copy.iter().map(|f| f.to_string()).collect::<Vec<_>>(),
+        ["(> x 0)", "(< x 10)"]
+    );
+    goal.assert(&Bool::from_bool(ctx.clone(), false));
+    assert!(goal.is_inconsistent() && goal.is_decided_unsat());
+    assert_eq!(copy.get_size(), 2);
+
+    goal.reset();
+    assert_eq!(goal.get_size(), 0);
+    assert!(goal.is_decided_sat());
+    assert_eq!((&goal).into_iter().next(), None);
+}
+
+#[test]
+fn test_cegar() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let square = FuncDecl::new(ctx.clone(), "square", &[&int], &int);
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+    let oracle = |args: &[Dynamic]| -> Dynamic {
+        let v = args[0].as_int().unwrap().as_i64().unwrap();
+        ast::Int::from_i64(args[0].get_ctx(), v * v).into()
+    };
+    let square_of = |a: &ast::Int| square.apply(&[a]).as_int().unwrap();
+    let zero = ast::Int::from_i64(ctx.clone(), 0);
+    let five = ast::Int::from_i64(ctx.clone(), 5);
+
+    // Two distinct numbers in 0..5 whose squares sum to 25.
+    let solver = Solver::new(ctx.clone());
+    for v in &[&x, &y] {
+        solver.assert(&v.ge(&zero));
+        solver.assert(&v.le(&five));
+    }
+    solver.assert(&x.lt(&y));
+    solver.assert(&(square_of(&x) + square_of(&y))._eq(&ast::Int::from_i64(ctx.clone(), 25)));
+    let outcome = cegar(&solver, &square, 50, oracle);
+    assert_eq!(outcome.result, SatResult::Sat);
+    let model = outcome.model.unwrap();
+    let (x_v, y_v) = (
+        model.eval(&x, true).unwrap().as_i64().unwrap(),
+        model.eval(&y, true).unwrap().as_i64().unwrap(),
+    );
+    assert_eq!(x_v * x_v + y_v * y_v, 25);
+
+    // No number in 0..5 has a square of 3; the lemmas make it unsat.
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&x.ge(&zero));
+    solver.assert(&x.le(&five));
+    solver.assert(&square_of(&x)._eq(&ast::Int::from_i64(ctx.clone(), 3)));
+    let outcome = cegar(&solver, &square, 50, oracle);
+    assert_eq!(outcome.result, SatResult::Unsat);
+    assert_eq!(outcome.rounds, outcome.lemmas.len() + 1);
+    assert!(outcome.model.is_none());
+    // The lemmas stay asserted.
+    assert_eq!(solver.get_assertions().len(), 3 + outcome.lemmas.len());
+
+    // One round only finds a spurious model.
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&square_of(&x)._eq(&ast::Int::from_i64(ctx.clone(), -1)));
+    let outcome = cegar(&solver, &square, 1, oracle);
+    assert_eq!(outcome.result, SatResult::Unknown);
+    assert_eq!(outcome.rounds, 1);
+    assert_eq!(outcome.lemmas.len(), 1);
+}
+
+#[test]
+fn test_check_assumptions_with_core_options() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let v: Vec<_> = (0..8)
+        .map(|i| ast::Bool::new_const(ctx.clone(), format!("v{}", i)))
+        .collect();
+    let a: Vec<_> = (0..8)
+        .map(|i| ast::Bool::new_const(ctx.clone(), format!("a{}", i)))
+        .collect();
+    let solver = Solver::new(ctx.clone());
+    for (a, v) in a.iter().zip(&v) {
+        solver.assert(&a.implies(v));
+    }
+    // v6 alone is contradictory, but the solver reaches the conflict
+    // through v0 and v5 as well.
+    let clauses: &[&[(usize, bool)]] = &[
+        &[(6, false), (1, false)],
+        &[(4, false), (0, false), (6, true)],
+        &[(5, true), (0, false)],
+        &[(0, false), (6, false), (5, false)],
+        &[(1, false), (4, true), (5, false)],
+        &[(0, true), (4, true)],
+        &[(2, true), (4, true), (5, true)],
+        &[(2, false), (0, true)],
+        &[(4, false), (0, true), (1, true)],
+    ];
+    for clause in clauses {
+        let lits: Vec<_> = clause
+            .iter()
+            .map(|&(i, positive)| if positive { v[i].clone() } else { v[i].not() })
+            .collect();
+        let lits: Vec<_> = lits.iter().collect();
+        solver.assert(&ast::Bool::or(ctx.clone(), &lits));
+    }
+
+    let (result, core) = solver.check_assumptions_with(&a, &CoreOptions::new());
+    assert_eq!(result, SatResult::Unsat);
+    assert_eq!(core, [a[0].clone(), a[5].clone(), a[6].clone()]);
+    let (result, minimal) = solver.check_assumptions_with(&a, &CoreOptions::minimal());
+    assert_eq!(result, SatResult::Unsat);
+    assert_eq!(minimal, [a[6].clone()]);
+
+    // The options only apply to their call.
+    assert_eq!(solver.check_assumptions(&a), SatResult::Unsat);
+    assert_eq!(solver.get_unsat_core(), core);
+
+    let partial = CoreOptions::new().minimize_partial(true);
+    let (_, core) = solver.check_assumptions_with(&a, &partial);
+    assert!(core.contains(&a[6]));
+    let (result, core) = solver.check_assumptions_with(&a[..1], &CoreOptions::minimal());
+    assert_eq!(result, SatResult::Sat);
+    assert!(core.is_empty());
+}
+
+#[test]
+fn test_goal_to_dimacs() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::BV::new_const(ctx.clone(), "x", 2);
+    let goal = Goal::new(ctx.clone(), false, false, false);
+    goal.assert(&x._eq(&ast::BV::from_u64(ctx.clone(), 2, 2)));
+    let tactic =
+        Tactic::new(ctx.clone(), "bit-blast").and_then(&Tactic::new(ctx.clone(), "tseitin-cnf"));
+    let cnf = tactic
+        .apply(&goal, None)
+        .unwrap()
+        .list_subgoals()
+        .next()
+        .unwrap();
+
+    let plain = cnf.to_dimacs(false).unwrap();
+    assert!(plain.starts_with("p cnf "));
+    assert!(!plain.lines().any(|l| l.starts_with('c')));
+    let named = cnf.to_dimacs(true).unwrap();
+    assert!(named.lines().any(|l| l.starts_with("c ")));
+    // Without the comments, the clauses are the same.
+    let stripped: Vec<&str> = named.lines().filter(|l| !l.starts_with("c ")).collect();
+    assert_eq!(stripped, plain.lines().collect::<Vec<_>>());
+
+    // Goals that are not in CNF are rejected.
+    assert!(goal
+        .to_dimacs(false)
+        .unwrap_err()
+        .starts_with("goal is not in CNF"));
+}
+
+#[test]
+fn test_apply_result_convert_model() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+    let z = ast::Int::new_const(ctx.clone(), "z");
+    let goal = Goal::new(ctx.clone(), true, false, false);
+    goal.assert(&x._eq(&(&y * 2i64)));
+    goal.assert(&z._eq(&(&x + 1i64)));
+    goal.assert(&y.gt(&ast::Int::from_i64(ctx.clone(), 4)));
+
+    let tactic =
+        Tactic::new(ctx.clone(), "simplify").and_then(&Tactic::new(ctx.clone(), "solve-eqs"));
+    let result = tactic.apply(&goal, None).unwrap();
+    let subgoal = result.clone().list_subgoals().next().unwrap();
+    let solver = Solver::new(ctx.clone());
+    for f in &subgoal {
+        solver.assert(&f);
+    }
+    assert_eq!(solver.check(), SatResult::Sat);
+    let sub_model = solver.get_model().unwrap();
+
+    let model = result.convert_model(0, &sub_model);
+    let value = |m: &Model, v: &ast::Int| m.eval(v, false).unwrap().as_i64();
+    let (x, y, z) = (
+        value(&model, &x).unwrap(),
+        value(&model, &y).unwrap(),
+        value(&model, &z).unwrap(),
+    );
+    assert_eq!((x, z), (y * 2, x + 1));
+    assert!(y > 4);
+    // The model of the subgoal is left as is.
+    assert!(sub_model.iter().count() < model.iter().count());
+}
+
+#[test]
+#[should_panic(expected = "subgoal 1 of 1")]
+fn test_apply_result_convert_model_out_of_range() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let goal = Goal::new(ctx.clone(), true, false, false);
+    goal.assert(&ast::Bool::new_const(ctx.clone(), "a"));
+    let result = Tactic::new(ctx.clone(), "skip").apply(&goal, None).unwrap();
+    result.convert_model(1, &Model::new(ctx.clone()));
+}
+
+#[test]
+fn test_preprocess_preserving_models() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = |i| ast::Int::from_i64(ctx.clone(), i);
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let y = ast::Int::new_const(ctx.clone(), "y");
+    let z = ast::Int::new_const(ctx.clone(), "z");
+    let b = ast::Bool::new_const(ctx.clone(), "b");
+    let c = ast::Bool::new_const(ctx.clone(), "c");
+    let assertions = [
+        b.clone(),
+        b.implies(&x._eq(&y)),
+        y._eq(&x),
+        c.not(),
+        c.ite(&z.lt(&int(0)), &z.gt(&(&y * 3i64))),
+        y.gt(&int(2)),
+    ];
+    let preprocessed = preprocess_preserving_models(&assertions);
+    let eliminated: Vec<String> = preprocessed
+        .substitutions
+        .iter()
+        .map(|(var, _)| var.to_string())
+        .collect();
+    for v in &["b", "c"] {
+        assert!(eliminated.contains(&v.to_string()));
+    }
+    // One of `x` and `y` is left, and `z` is not defined by an equation.
+    assert_eq!(eliminated.len(), 3);
+    assert!(!eliminated.contains(&"z".to_string()));
+    for a in &preprocessed.assertions {
+        ast::visit(a, |t, _| {
+            let eliminated = preprocessed.substitutions.iter().any(|(var, _)| t == var);
+            assert!(!eliminated, "{} is left in {}", t, a);
+            true
+        });
+    }
+
+    let solver = Solver::new(ctx.clone());
+    for a in &preprocessed.assertions {
+        solver.assert(a);
+    }
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = preprocessed.reconstruct_model(&solver.get_model().unwrap());
+    for a in &assertions {
+        assert_eq!(model.eval(a, true).unwrap().as_bool(), Some(true), "{}", a);
+    }
+
+    // Unsatisfiable assertions stay so.
+    let preprocessed = preprocess_preserving_models(&[x._eq(&int(1)), x._eq(&int(2))]);
+    let solver = Solver::new(ctx.clone());
+    for a in &preprocessed.assertions {
+        solver.assert(a);
+    }
+    assert_eq!(solver.check(), SatResult::Unsat);
+
+    let preprocessed = preprocess_preserving_models(&[]);
+    assert!(preprocessed.assertions.is_empty() && preprocessed.substitutions.is_empty());
+}
+
+#[test]
+fn test_probe_combinators() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = ast::Int::new_const(ctx.clone(), "x");
+    let goal = Goal::new(ctx.clone(), false, false, false);
+    goal.assert(&x.gt(&ast::Int::from_i64(ctx.clone(), 0)));
+    goal.assert(&x.lt(&ast::Int::from_i64(ctx.clone(), 10)));
+
+    let size = Probe::new(ctx.clone(), "size");
+    let two = Probe::constant(ctx.clone(), 2.0);
+    let three = Probe::constant(ctx.clone(), 3.0);
+    let holds = |p: Probe| p.apply(&goal) != 0.0;
+    assert_eq!(size.apply(&goal), 2.0);
+    assert!(holds(size.eq(&two)));
+    assert!(!holds(size.ne(&two)));
+    assert!(holds(size.lt(&three)));
+    assert!(holds(size.le(&two)));
+    assert!(!holds(size.gt(&two)));
+    assert!(holds(size.ge(&two)));
+    assert!(holds(size.eq(&two).and(&size.lt(&three))));
+    assert!(!holds(size.eq(&three).and(&size.lt(&three))));
+    assert!(holds(size.eq(&three).or(&size.lt(&three))));
+    assert!(holds(size.gt(&two).not()));
+
+    let names: Vec<&str> = Probe::list_all(&ctx).map(|n| n.unwrap()).collect();
+    assert!(names.contains(&"size") && names.contains(&"is-qflia"));
+}
+
+#[test]
+fn test_check_maxsat() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let vars: Vec<_> = (0..6)
+        .map(|i| ast::Bool::new_const(ctx.clone(), format!("v{}", i)))
+        .collect();
+    let mut seed = 7u64;
+    let mut next = |n: u64| {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 33) % n
+    };
+    let mut total_cores = 0;
+    for _ in 0..10 {
+        let solver = Solver::new(ctx.clone());
+        let opt = Optimize::new(ctx.clone());
+        for _ in 0..10 {
+            let i = next(6) as usize;
+            let j = next(6) as usize;
+            let hard = !(vars[i].clone() & vars[j].clone());
+            solver.assert(&hard);
+            opt.assert(&hard);
+        }
+        let soft: Vec<(ast::Bool, u64)> = vars
+            .iter()
+            .map(|v| {
+                let w = next(4);
+                if next(2) == 0 {
+                    (v.clone(), w)
+                } else {
+                    (v.clone() | !vars[next(6) as usize].clone(), w)
+                }
+            })
+            .collect();
+        for (f, w) in &soft {
+            opt.assert_soft(f, *w as u32, None);
+        }
+        let soft: Vec<(&ast::Bool, u64)> = soft.iter().map(|(f, w)| (f, *w)).collect();
+
+        let mut bounds = vec![];
+        let outcome = solver.check_maxsat_with(&soft, |core| {
+            bounds.push(core.lower_bound);
+            assert_eq!(core.iteration, bounds.len());
+            assert!(!core.core.is_empty());
+            true
+        });
+        assert_eq!(outcome.result, SatResult::Sat);
+        assert_eq!(outcome.cores, bounds.len());
+        total_cores += outcome.cores;
+        assert!(bounds.windows(2).all(|w| w[0] < w[1]));
+        assert!(bounds.iter().all(|&b| b <= outcome.cost));
+
+        assert_eq!(opt.check(&[]), SatResult::Sat);
+        let model = opt.get_model().unwrap();
+        let optimum: u64 = soft
+            .iter()
+            .filter(|(f, _)| model.eval(*f, true).unwrap().as_bool() == Some(false))
+            .map(|(_, w)| w)
+            .sum();
+        assert_eq!(outcome.cost, optimum);
+        let model = outcome.model.unwrap();
+        for (i, (f, _)) in soft.iter().enumerate() {
+            let holds = model.eval(*f, true).unwrap().as_bool().unwrap();
+            assert_eq!(holds, !outcome.violated.contains(&i));
+        }
+        assert_eq!(solver.get_assertions().len(), 10);
+    }
+    assert!(total_cores > 10);
+
+    // Unsatisfiable hard constraints, and a callback that stops the search.
+    let a = &vars[0];
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&!a.clone());
+    let outcome = solver.check_maxsat_with(&[(a, 1), (&!a.clone(), 1)], |_| false);
+    assert_eq!(outcome.result, SatResult::Unknown);
+    assert_eq!((outcome.cores, outcome.cost), (1, 1));
+    solver.assert(a);
+    assert_eq!(solver.check_maxsat(&[(a, 1)]).result, SatResult::Unsat);
+}
+
+#[test]
+fn test_array_counterexample_index() {
+    let cfg = Config::new();
+    let ctx = Rc::new(Context::new(&cfg));
+    let int = Sort::int(ctx.clone());
+    let a = ast::Array::new_const(ctx.clone(), "a", &int, &int);
+    let b = ast::Array::new_const(ctx.clone(), "b", &int, &int);
+    let zero = ast::Int::from_i64(ctx.clone(), 0);
+
+    // Two unrelated arrays that must differ somewhere.
+    let solver = Solver::new(ctx.clone());
+    solver.assert(&a._eq(&b).not());
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    let index = ast::Array::counterexample_index(&a, &b, &model).unwrap();
+    assert_ne!(
+        model.eval(&a.select(&index), true),
+        model.eval(&b.select(&index), true)
+    );
+
+    // Arrays that only differ through a store, without an extensionality
+    // argument, and arrays that are equal.
+    let solver = Solver::new(ctx.clone());
+    let c = a.store(&zero, &ast::Int::from_i64(ctx.clone(), 1));
+    solver.assert(&a.select(&zero).as_int().unwrap()._eq(&zero));
+    solver.assert(&b._eq(&a));
+    assert_eq!(solver.check(), SatResult::Sat);
+    let model = solver.get_model().unwrap();
+    let index = ast::Array::counterexample_index(&a, &c, &model).unwrap();
+    assert_eq!(index.as_int().unwrap().as_i64(), Some(0));
+    assert_eq!(ast::Array::counterexample_index(&a, &b, &model), None);
+    assert_eq!(ast::Array::counterexample_index(&c, &c, &model), None);
+}
@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use z3::ast::Ast;
 use z3::*;
 
@@ -12,37 +14,37 @@ fn test_optimize_assert_soft_and_get_objectives() {
     const COUNT: u64 = 10;
 
     let cfg = Config::new();
-    let ctx = Context::new(&cfg);
-    let opt = Optimize::new(&ctx);
+    let ctx = Rc::new(Context::new(&cfg));
+    let opt = Optimize::new(ctx.clone());
 
-    let int = Sort::int(&ctx);
-    let well_ordered_fn = FuncDecl::new(&ctx, "well_ordered_fn", &[&int], &int);
+    let int = Sort::int(ctx.clone());
+    let well_ordered_fn = FuncDecl::new(ctx.clone(), "well_ordered_fn", &[&int], &int);
 
     // i < j in the order
     for i in 0..COUNT {
         opt.assert(&ast::Bool::and(
-            &ctx,
+            ctx.clone(),
             &[
                 &well_ordered_fn
-                    .apply(&[&ast::Int::from_u64(&ctx, i)])
+                    .apply(&[&ast::Int::from_u64(ctx.clone(), i)])
                     .as_int()
                     .unwrap()
-                    .lt(&ast::Int::from_u64(&ctx, COUNT)),
+                    .lt(&ast::Int::from_u64(ctx.clone(), COUNT)),
                 &well_ordered_fn
-                    .apply(&[&ast::Int::from_u64(&ctx, i)])
+                    .apply(&[&ast::Int::from_u64(ctx.clone(), i)])
                     .as_int()
                     .unwrap()
-                    .ge(&ast::Int::from_u64(&ctx, 0)),
+                    .ge(&ast::Int::from_u64(ctx.clone(), 0)),
             ],
         ));
         for j in 0..i {
             opt.assert_soft(
                 &well_ordered_fn
-                    .apply(&[&ast::Int::from_u64(&ctx, i)])
+                    .apply(&[&ast::Int::from_u64(ctx.clone(), i)])
                     .as_int()
                     .unwrap()
                     .lt(&well_ordered_fn
-                        .apply(&[&ast::Int::from_u64(&ctx, j)])
+                        .apply(&[&ast::Int::from_u64(ctx.clone(), j)])
                         .as_int()
                         .unwrap()),
                 1,
@@ -54,11 +56,11 @@ fn test_optimize_assert_soft_and_get_objectives() {
     // incorrect assertion: COUNT-1 > 0
     opt.assert_soft(
         &well_ordered_fn
-            .apply(&[&ast::Int::from_u64(&ctx, 0)])
+            .apply(&[&ast::Int::from_u64(ctx.clone(), 0)])
             .as_int()
             .unwrap()
             .lt(&well_ordered_fn
-                .apply(&[&ast::Int::from_u64(&ctx, COUNT - 1)])
+                .apply(&[&ast::Int::from_u64(ctx.clone(), COUNT - 1)])
                 .as_int()
                 .unwrap()),
         1,
@@ -72,7 +74,7 @@ fn test_optimize_assert_soft_and_get_objectives() {
     for i in 0..COUNT {
         let i_new_pos = model
             .eval(
-                &well_ordered_fn.apply(&[&ast::Int::from_u64(&ctx, i)]),
+                &well_ordered_fn.apply(&[&ast::Int::from_u64(ctx.clone(), i)]),
                 true,
             )
             .unwrap()
@@ -83,7 +85,7 @@ fn test_optimize_assert_soft_and_get_objectives() {
         for j in 0..i {
             let j_new_pos = model
                 .eval(
-                    &well_ordered_fn.apply(&[&ast::Int::from_u64(&ctx, j)]),
+                    &well_ordered_fn.apply(&[&ast::Int::from_u64(ctx.clone(), j)]),
                     true,
                 )
                 .unwrap()
@@ -100,7 +102,7 @@ fn test_optimize_assert_soft_and_get_objectives() {
     assert!(
         model
             .eval(
-                &well_ordered_fn.apply(&[&ast::Int::from_u64(&ctx, 0)]),
+                &well_ordered_fn.apply(&[&ast::Int::from_u64(ctx.clone(), 0)]),
                 true
             )
             .unwrap()
@@ -110,7 +112,7 @@ fn test_optimize_assert_soft_and_get_objectives() {
             .unwrap()
             > model
                 .eval(
-                    &well_ordered_fn.apply(&[&ast::Int::from_u64(&ctx, COUNT - 1)]),
+                    &well_ordered_fn.apply(&[&ast::Int::from_u64(ctx.clone(), COUNT - 1)]),
                     true
                 )
                 .unwrap()
@@ -133,21 +135,21 @@ fn test_optimize_assert_soft_and_get_objectives() {
     assert_eq!(objectives.len(), 1);
     let objective = &objectives[0];
     dbg!(objective);
-    assert_eq!(objective.get_sort(), Sort::real(&ctx));
+    assert_eq!(objective.get_sort(), Sort::real(ctx.clone()));
     assert_eq!(
         objective.num_children(),
         (0..COUNT).fold(0, |acc, i| acc + (0..i).count()) + 1
     );
 
     for ite in objective.children() {
-        assert_eq!(ite.get_sort(), Sort::real(&ctx));
+        assert_eq!(ite.get_sort(), Sort::real(ctx.clone()));
         assert_eq!(ite.num_children(), 3);
         let ite_children = ite.children();
         let r#bool = &ite_children[0];
 
         assert_eq!(r#bool.num_children(), 2);
         for child in r#bool.children() {
-            assert_eq!(child.get_sort(), Sort::int(&ctx));
+            assert_eq!(child.get_sort(), Sort::int(ctx.clone()));
         }
 
         assert_eq!(
@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use z3::{
     ast,
     ast::{Array, Ast, AstKind, Bool, Dynamic, Float, Int, Real, BV},
@@ -7,20 +9,20 @@ use z3::{
 #[test]
 fn test_bv_ops() {
     let cfg = Config::default();
-    let ctx = Context::new(&cfg);
+    let ctx = Rc::new(Context::new(&cfg));
 
     macro_rules! test_binary_op {
         ($op:tt) => {
-            let a = BV::new_const(&ctx, "a", 5);
-            let b = BV::new_const(&ctx, "b", 5);
+            let a = BV::new_const(ctx.clone(), "a", 5);
+            let b = BV::new_const(ctx.clone(), "b", 5);
             let _ = a $op b $op 2u64 $op 2i64;
         };
     }
     macro_rules! test_op_assign {
         ($op:tt, $assign:tt) => {
             test_binary_op!($op);
-            let mut a = BV::new_const(&ctx, "a", 5);
-            let b = BV::new_const(&ctx, "b", 5);
+            let mut a = BV::new_const(ctx.clone(), "a", 5);
+            let b = BV::new_const(ctx.clone(), "b", 5);
             a $assign b;
             a $assign 2u64;
             a $assign 2i64;
@@ -28,7 +30,7 @@ fn test_bv_ops() {
     }
     macro_rules! test_unary_op {
         ($op:tt) => {
-            let a = BV::new_const(&ctx, "a", 5);
+            let a = BV::new_const(ctx.clone(), "a", 5);
             let _ = $op a;
         };
     }
@@ -47,20 +49,20 @@ fn test_bv_ops() {
 #[test]
 fn test_int_ops() {
     let cfg = Config::default();
-    let ctx = Context::new(&cfg);
+    let ctx = Rc::new(Context::new(&cfg));
 
     macro_rules! test_binary_op {
         ($op:tt) => {
-            let a = Int::new_const(&ctx, "a");
-            let b = Int::new_const(&ctx, "b");
+            let a = Int::new_const(ctx.clone(), "a");
+            let b = Int::new_const(ctx.clone(), "b");
             let _ = a $op b $op 2u64 $op 2i64;
         };
     }
     macro_rules! test_op_assign {
         ($op:tt, $assign:tt) => {
             test_binary_op!($op);
-            let mut a = Int::new_const(&ctx, "a");
-            let b = Int::new_const(&ctx, "b");
+            let mut a = Int::new_const(ctx.clone(), "a");
+            let b = Int::new_const(ctx.clone(), "b");
             a $assign b;
             a $assign 2u64;
             a $assign 2i64;
@@ -68,7 +70,7 @@ fn test_int_ops() {
     }
     macro_rules! test_unary_op {
         ($op:tt) => {
-            let a = Int::new_const(&ctx, "a");
+            let a = Int::new_const(ctx.clone(), "a");
             let _ = $op a;
         };
     }
@@ -84,35 +86,35 @@ fn test_int_ops() {
 #[test]
 fn test_pow_ret_real() {
     let cfg = Config::default();
-    let ctx = Context::new(&cfg);
-    let x = Int::new_const(&ctx, "x");
+    let ctx = Rc::new(Context::new(&cfg));
+    let x = Int::new_const(ctx.clone(), "x");
     let y = x.power(&x);
-    assert!(y.get_sort() == Sort::real(&ctx));
+    assert!(y.get_sort() == Sort::real(ctx.clone()));
 }
 
 #[test]
 fn test_real_ops() {
     let cfg = Config::default();
-    let ctx = Context::new(&cfg);
+    let ctx = Rc::new(Context::new(&cfg));
 
     macro_rules! test_binary_op {
         ($op:tt) => {
-            let a = Real::new_const(&ctx, "a");
-            let b = Real::new_const(&ctx, "b");
+            let a = Real::new_const(ctx.clone(), "a");
+            let b = Real::new_const(ctx.clone(), "b");
             let _ = a $op b;
         };
     }
     macro_rules! test_op_assign {
         ($op:tt, $assign:tt) => {
             test_binary_op!($op);
-            let mut a = Real::new_const(&ctx, "a");
-            let b = Real::new_const(&ctx, "b");
+            let mut a = Real::new_const(ctx.clone(), "a");
+            let b = Real::new_const(ctx.clone(), "b");
             a $assign b;
         };
     }
     macro_rules! test_unary_op {
         ($op:tt) => {
-            let a = Real::new_const(&ctx, "a");
+            let a = Real::new_const(ctx.clone(), "a");
             let _ = $op a;
         };
     }
@@ -127,11 +129,11 @@ fn test_real_ops() {
 #[test]
 fn test_float32_ops() {
     let cfg = Config::default();
-    let ctx = Context::new(&cfg);
+    let ctx = Rc::new(Context::new(&cfg));
 
     macro_rules! test_unary_op {
         ($op:tt) => {
-            let a = Float::new_const_float32(&ctx, "a");
+            let a = Float::new_const_float32(ctx.clone(), "a");
             let _ = $op a;
         };
     }
@@ -141,11 +143,11 @@ fn test_float32_ops() {
 #[test]
 fn test_double_ops() {
     let cfg = Config::default();
-    let ctx = Context::new(&cfg);
+    let ctx = Rc::new(Context::new(&cfg));
 
     macro_rules! test_unary_op {
         ($op:tt) => {
-            let a = Float::new_const_double(&ctx, "a");
+            let a = Float::new_const_double(ctx.clone(), "a");
             let _ = $op a;
         };
     }
@@ -155,20 +157,20 @@ fn test_double_ops() {
 #[test]
 fn test_bool_ops() {
     let cfg = Config::default();
-    let ctx = Context::new(&cfg);
+    let ctx = Rc::new(Context::new(&cfg));
 
     macro_rules! test_binary_op {
         ($op:tt) => {
-            let a = Bool::new_const(&ctx, "a");
-            let b = Bool::new_const(&ctx, "b");
+            let a = Bool::new_const(ctx.clone(), "a");
+            let b = Bool::new_const(ctx.clone(), "b");
             let _ = a $op b $op true $op false;
         };
     }
     macro_rules! test_op_assign {
         ($op:tt, $assign:tt) => {
             test_binary_op!($op);
-            let mut a = Bool::new_const(&ctx, "a");
-            let b = Bool::new_const(&ctx, "b");
+            let mut a = Bool::new_const(ctx.clone(), "a");
+            let b = Bool::new_const(ctx.clone(), "b");
             a $assign b;
             a $assign true;
             a $assign false;
@@ -176,7 +178,7 @@ fn test_bool_ops() {
     }
     macro_rules! test_unary_op {
         ($op:tt) => {
-            let a = Bool::new_const(&ctx, "a");
+            let a = Bool::new_const(ctx.clone(), "a");
             let _ = $op a;
         };
     }
@@ -187,16 +189,16 @@ fn test_bool_ops() {
     test_unary_op!(!);
 }
 
-fn assert_bool_child<'c>(node: &impl Ast<'c>, idx: usize, expected: &Bool<'c>) {
+fn assert_bool_child(node: &impl Ast, idx: usize, expected: &Bool) {
     assert_eq!(&node.nth_child(idx).unwrap().as_bool().unwrap(), expected);
 }
 
 #[test]
 fn test_ast_children() {
     let cfg = Config::default();
-    let ctx = Context::new(&cfg);
+    let ctx = Rc::new(Context::new(&cfg));
 
-    let a = Bool::new_const(&ctx, "a");
+    let a = Bool::new_const(ctx.clone(), "a");
     assert_eq!(a.num_children(), 0);
     assert_eq!(a.nth_child(0), None);
     assert_eq!(a.children(), vec![]);
@@ -206,9 +208,9 @@ fn test_ast_children() {
     assert_bool_child(&not_a, 0, &a);
     assert_eq!(not_a.nth_child(1), None);
 
-    let b = Bool::new_const(&ctx, "b");
+    let b = Bool::new_const(ctx.clone(), "b");
     // This is specifically testing for an array of values, not an array of slices
-    let a_or_b = Bool::or(&ctx, &[a.clone(), b.clone()]);
+    let a_or_b = Bool::or(ctx.clone(), &[a.clone(), b.clone()]);
     assert_eq!(a_or_b.num_children(), 2);
     assert_bool_child(&a_or_b, 0, &a);
     assert_bool_child(&a_or_b, 1, &b);
@@ -218,8 +220,8 @@ fn test_ast_children() {
     assert_eq!(children[0].as_bool().unwrap(), a);
     assert_eq!(children[1].as_bool().unwrap(), b);
 
-    let c = Bool::new_const(&ctx, "c");
-    let a_and_b_and_c = Bool::and(&ctx, &[&a, &b, &c]);
+    let c = Bool::new_const(ctx.clone(), "c");
+    let a_and_b_and_c = Bool::and(ctx.clone(), &[&a, &b, &c]);
     assert_eq!(a_and_b_and_c.num_children(), 3);
     assert_bool_child(&a_and_b_and_c, 0, &a);
     assert_bool_child(&a_and_b_and_c, 1, &b);
@@ -231,7 +233,7 @@ fn test_ast_children() {
     assert_eq!(children[2].as_bool().unwrap(), c);
 }
 
-fn assert_ast_attributes<'c, T: Ast<'c>>(expr: &T, is_const: bool) {
+fn assert_ast_attributes<T: Ast>(expr: &T, is_const: bool) {
     assert_eq!(expr.kind(), AstKind::App);
     assert!(expr.is_app());
     assert_eq!(expr.is_const(), is_const);
@@ -240,12 +242,12 @@ fn assert_ast_attributes<'c, T: Ast<'c>>(expr: &T, is_const: bool) {
 #[test]
 fn test_ast_attributes() {
     let cfg = Config::default();
-    let ctx = Context::new(&cfg);
+    let ctx = Rc::new(Context::new(&cfg));
 
-    let a = Bool::new_const(&ctx, "a");
-    let b = Bool::from_bool(&ctx, false);
+    let a = Bool::new_const(ctx.clone(), "a");
+    let b = Bool::from_bool(ctx.clone(), false);
     let not_a = a.not();
-    let a_or_b = &Bool::or(&ctx, &[&a, &b]);
+    let a_or_b = &Bool::or(ctx.clone(), &[&a, &b]);
     assert_eq!(b.decl().kind(), DeclKind::FALSE);
     assert_eq!(not_a.decl().kind(), DeclKind::NOT);
     assert_eq!(a_or_b.decl().kind(), DeclKind::OR);
@@ -257,15 +259,20 @@ fn test_ast_attributes() {
     assert_ast_attributes(a_or_b, false);
 
     assert_ast_attributes(
-        &Array::new_const(&ctx, "arr", &Sort::int(&ctx), &Sort::bool(&ctx)),
+        &Array::new_const(
+            ctx.clone(),
+            "arr",
+            &Sort::int(ctx.clone()),
+            &Sort::bool(ctx.clone()),
+        ),
         true,
     );
-    assert_ast_attributes(&BV::new_const(&ctx, "bv", 512), true);
-    assert_ast_attributes(&Real::new_const(&ctx, "r"), true);
-    assert_ast_attributes(&ast::String::new_const(&ctx, "st"), true);
+    assert_ast_attributes(&BV::new_const(ctx.clone(), "bv", 512), true);
+    assert_ast_attributes(&Real::new_const(ctx.clone(), "r"), true);
+    assert_ast_attributes(&ast::String::new_const(ctx.clone(), "st"), true);
 
-    let int_expr = Int::new_const(&ctx, "i");
-    let set_expr = ast::Set::new_const(&ctx, "set", &Sort::int(&ctx));
+    let int_expr = Int::new_const(ctx.clone(), "i");
+    let set_expr = ast::Set::new_const(ctx.clone(), "set", &Sort::int(ctx.clone()));
     assert_ast_attributes(&int_expr, true);
     assert_ast_attributes(&set_expr, true);
     assert_ast_attributes(&set_expr.add(&Dynamic::from_ast(&int_expr)), false);
@@ -274,23 +281,28 @@ fn test_ast_attributes() {
 #[test]
 fn test_func_decl_attributes() {
     let cfg = Config::default();
-    let ctx = Context::new(&cfg);
+    let ctx = Rc::new(Context::new(&cfg));
 
-    let const_decl = FuncDecl::new(&ctx, "c", &[], &Sort::bool(&ctx));
+    let const_decl = FuncDecl::new(ctx.clone(), "c", &[], &Sort::bool(ctx.clone()));
     assert_eq!(const_decl.kind(), DeclKind::UNINTERPRETED);
     assert_eq!(const_decl.name(), "c");
     assert_eq!(const_decl.arity(), 0);
 
-    let unary_decl = FuncDecl::new(&ctx, "unary", &[&Sort::bool(&ctx)], &Sort::bool(&ctx));
+    let unary_decl = FuncDecl::new(
+        ctx.clone(),
+        "unary",
+        &[&Sort::bool(ctx.clone())],
+        &Sort::bool(ctx.clone()),
+    );
     assert_eq!(unary_decl.kind(), DeclKind::UNINTERPRETED);
     assert_eq!(unary_decl.name(), "unary");
     assert_eq!(unary_decl.arity(), 1);
 
     let binary_decl = FuncDecl::new(
-        &ctx,
+        ctx.clone(),
         "binary",
-        &[&Sort::bool(&ctx), &Sort::bool(&ctx)],
-        &Sort::bool(&ctx),
+        &[&Sort::bool(ctx.clone()), &Sort::bool(ctx.clone())],
+        &Sort::bool(ctx.clone()),
     );
     assert_eq!(binary_decl.kind(), DeclKind::UNINTERPRETED);
     assert_eq!(binary_decl.name(), "binary");
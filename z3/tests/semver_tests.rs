@@ -1,6 +1,7 @@
 use log::info;
 use semver::{Version, VersionReq};
 use std::collections::HashMap;
+use std::rc::Rc;
 use z3::ast::Ast;
 use z3::*;
 
@@ -141,8 +142,8 @@ fn test_solve_simple_semver_example() {
     );
 
     let cfg = Config::new();
-    let ctx = Context::new(&cfg);
-    let opt = Optimize::new(&ctx);
+    let ctx = Rc::new(Context::new(&cfg));
+    let opt = Optimize::new(ctx.clone());
 
     let mut root: HashMap<String, VersionReq> = HashMap::new();
     let mut asts: HashMap<String, ast::Int> = HashMap::new();
@@ -156,21 +157,21 @@ fn test_solve_simple_semver_example() {
 
     // Make a root Z3 Int constant for each pkg we're trying to solve for.
     for (k, v) in &root {
-        let ast = ast::Int::fresh_const(&ctx, "root-pkg");
+        let ast = ast::Int::fresh_const(ctx.clone(), "root-pkg");
         info!("new AST for root {}", k);
 
         match first_version_req_index(&smap, k, v) {
             None => (),
             Some(low) => {
                 info!("Asserting: {} >= #{} (root)", k, low);
-                opt.assert(&ast.ge(&ast::Int::from_u64(&ctx, low as u64)));
+                opt.assert(&ast.ge(&ast::Int::from_u64(ctx.clone(), low as u64)));
             }
         }
         match last_version_req_index(&smap, k, v) {
             None => (),
             Some(high) => {
                 info!("Asserting: {} <= #{} (root)", k, high);
-                opt.assert(&ast.le(&ast::Int::from_u64(&ctx, high as u64)));
+                opt.assert(&ast.le(&ast::Int::from_u64(ctx.clone(), high as u64)));
             }
         }
         asts.insert(k.clone(), ast);
@@ -178,7 +179,7 @@ fn test_solve_simple_semver_example() {
 
     // Tell the optimizer to maximizes the sum of the root constants.
     opt.maximize(&ast::Int::add(
-        &ctx,
+        ctx.clone(),
         &asts.values().collect::<Vec<&ast::Int>>()[..],
     ));
 
@@ -186,7 +187,7 @@ fn test_solve_simple_semver_example() {
     for k in (smap).keys() {
         asts.entry(k.clone()).or_insert_with(|| {
             info!("new AST for {}", k);
-            ast::Int::fresh_const(&ctx, "pkg")
+            ast::Int::fresh_const(ctx.clone(), "pkg")
         });
     }
     for specs in smap.values() {
@@ -194,7 +195,7 @@ fn test_solve_simple_semver_example() {
             for r in (spec).reqs.keys() {
                 asts.entry(r.clone()).or_insert_with(|| {
                     info!("new AST for {}", r);
-                    ast::Int::fresh_const(&ctx, "dep-pkg")
+                    ast::Int::fresh_const(ctx.clone(), "dep-pkg")
                 });
             }
         }
@@ -222,8 +223,8 @@ fn test_solve_simple_semver_example() {
                         );
                         opt.assert(
                             &k_ast
-                                ._eq(&ast::Int::from_u64(&ctx, n as u64))
-                                .implies(&r_ast.ge(&ast::Int::from_u64(&ctx, low as u64))),
+                                ._eq(&ast::Int::from_u64(ctx.clone(), n as u64))
+                                .implies(&r_ast.ge(&ast::Int::from_u64(ctx.clone(), low as u64))),
                         );
                     }
                 }
@@ -241,8 +242,8 @@ fn test_solve_simple_semver_example() {
                         );
                         opt.assert(
                             &k_ast
-                                ._eq(&ast::Int::from_u64(&ctx, n as u64))
-                                .implies(&r_ast.le(&ast::Int::from_u64(&ctx, high as u64))),
+                                ._eq(&ast::Int::from_u64(ctx.clone(), n as u64))
+                                .implies(&r_ast.le(&ast::Int::from_u64(ctx.clone(), high as u64))),
                         );
                     }
                 }